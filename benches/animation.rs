@@ -0,0 +1,274 @@
+use core::time::Duration;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use glam::{Affine3A, Mat4, Quat, Vec3, Vec3A};
+use misc_benches::{kernels::animation::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Samples a track at random times and at monotonically increasing times. The
+// monotonic case is what an animation player normally does, and is where the
+// cursor should win.
+pub fn keyframe(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("keyframe");
+    let mut group = c.benchmark_group("keyframe");
+
+    const COUNT: usize = 4 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    for key_count in [16, 64, 256, 1024, 4096] {
+        let track = random_track(&mut rng, key_count);
+
+        let random_times = (0..COUNT)
+            .map(|_| rng.gen_range(0.0..track.duration()))
+            .collect::<CacheAlignedVec<f32>>();
+
+        let monotonic_times = (0..COUNT)
+            .map(|i| track.duration() * (i as f32 / COUNT as f32))
+            .collect::<CacheAlignedVec<f32>>();
+
+        let dst_array = &mut CacheAlignedVec::from_elem(0.0, COUNT);
+
+        for (order, src_array) in [("random", &random_times), ("monotonic", &monotonic_times)] {
+            let mut params = KeyframeParams {
+                dst_array,
+                src_array,
+                track: &track,
+            };
+
+            for (name, f) in [
+                ("linear", keyframe_linear as fn(&mut KeyframeParams)),
+                ("binary", keyframe_binary),
+                ("cursor", keyframe_cursor),
+            ] {
+                group.bench_function(format!("keys = {key_count}, {order}, {name}"), |b| {
+                    b.iter(|| f(&mut params))
+                });
+            }
+        }
+    }
+}
+
+// Linear blend skinning with matrix and affine palettes, against dual
+// quaternion skinning. Each vertex has four joint influences.
+pub fn skin(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("skin");
+    let mut group = c.benchmark_group("skin");
+
+    const COUNT: usize = 32 * 1024;
+    const JOINT_COUNT: usize = 64;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let joints = random_joint_array(&mut rng, JOINT_COUNT);
+    let src_array = random_skin_vertex_array(&mut rng, COUNT, JOINT_COUNT)
+        .into_iter()
+        .collect::<CacheAlignedVec<_>>();
+    let dst_array = &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT);
+
+    let mat4_palette = joints
+        .iter()
+        .map(|&(r, t)| Mat4::from_rotation_translation(r, t))
+        .collect::<CacheAlignedVec<_>>();
+
+    let affine_palette = joints
+        .iter()
+        .map(|&(r, t)| Affine3A::from_rotation_translation(r, t))
+        .collect::<CacheAlignedVec<_>>();
+
+    let dual_quat_palette = joints
+        .iter()
+        .map(|&(r, t)| DualQuat::from_rotation_translation(r, t))
+        .collect::<CacheAlignedVec<_>>();
+
+    group.bench_function(format!("count = {COUNT}, mat4"), |b| {
+        let mut params = SkinParams {
+            dst_array,
+            src_array: &src_array,
+            palette: &mat4_palette,
+        };
+
+        b.iter(|| skin_mat4(&mut params))
+    });
+
+    group.bench_function(format!("count = {COUNT}, affine"), |b| {
+        let mut params = SkinParams {
+            dst_array,
+            src_array: &src_array,
+            palette: &affine_palette,
+        };
+
+        b.iter(|| skin_affine(&mut params))
+    });
+
+    group.bench_function(format!("count = {COUNT}, dual quat"), |b| {
+        let mut params = SkinParams {
+            dst_array,
+            src_array: &src_array,
+            palette: &dual_quat_palette,
+        };
+
+        b.iter(|| skin_dual_quat(&mut params))
+    });
+}
+
+// Applies weighted morph target deltas to a base mesh of positions and
+// normals. With many targets this is limited by memory bandwidth rather than
+// arithmetic, so the loop order and layout matter.
+pub fn morph(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("morph");
+    let mut group = c.benchmark_group("morph");
+
+    const COUNT: usize = 16 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let base_array = random_morph_vertex_array(&mut rng, COUNT)
+        .into_iter()
+        .collect::<CacheAlignedVec<_>>();
+    let soa_base_array = morph_vertex_array_to_soa(&base_array)
+        .into_iter()
+        .collect::<CacheAlignedVec<_>>();
+
+    for target_count in [4, 16, 64] {
+        let targets = (0..target_count)
+            .map(|_| {
+                random_morph_vertex_array(&mut rng, COUNT)
+                    .into_iter()
+                    .collect::<CacheAlignedVec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let soa_targets = targets
+            .iter()
+            .map(|t| {
+                morph_vertex_array_to_soa(t)
+                    .into_iter()
+                    .collect::<CacheAlignedVec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let target_arrays = targets.iter().map(|t| t.as_slice()).collect::<Vec<_>>();
+        let soa_target_arrays = soa_targets.iter().map(|t| t.as_slice()).collect::<Vec<_>>();
+        let weights = (0..target_count).map(|_| rng.gen()).collect::<Vec<f32>>();
+
+        let mut params = MorphParams {
+            dst_array: &mut CacheAlignedVec::from_elem(MorphVertex::default(), COUNT),
+            base_array: &base_array,
+            target_arrays: &target_arrays,
+            weights: &weights,
+        };
+
+        group.bench_function(
+            format!("count = {COUNT}, targets = {target_count}, per vertex"),
+            |b| b.iter(|| morph_per_vertex(&mut params)),
+        );
+
+        group.bench_function(
+            format!("count = {COUNT}, targets = {target_count}, per target"),
+            |b| b.iter(|| morph_per_target(&mut params)),
+        );
+
+        let mut soa_params = MorphParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0.0, COUNT * 6),
+            base_array: &soa_base_array,
+            target_arrays: &soa_target_arrays,
+            weights: &weights,
+        };
+
+        group.bench_function(
+            format!("count = {COUNT}, targets = {target_count}, soa"),
+            |b| b.iter(|| morph_soa(&mut soa_params)),
+        );
+    }
+}
+
+// Solves chains towards random targets. Two bones use the analytic solver,
+// and longer chains use FABRIK, where the count of joints and the tolerance
+// decide how many iterations it takes. Throughput is chains per second.
+pub fn ik(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("ik");
+    let mut group = c.benchmark_group("ik");
+
+    const COUNT: usize = 4 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let chain_array = random_two_bone_chain_array(&mut rng, COUNT)
+        .into_iter()
+        .collect::<CacheAlignedVec<_>>();
+    let target_array = chain_array
+        .iter()
+        .map(|c| {
+            random_ik_target(
+                &mut rng,
+                c.root,
+                c.root.distance(c.mid) + c.mid.distance(c.end),
+            )
+        })
+        .collect::<CacheAlignedVec<_>>();
+
+    let mut params = TwoBoneParams {
+        dst_array: &mut CacheAlignedVec::from_elem([Quat::IDENTITY; 2], COUNT),
+        chain_array: &chain_array,
+        target_array: &target_array,
+    };
+
+    group.bench_function(format!("count = {COUNT}, joints = 3, two bone"), |b| {
+        b.iter(|| two_bone_ik(&mut params))
+    });
+
+    for joint_count in [3, 4, 8] {
+        let src_array = random_ik_chain_array(&mut rng, COUNT, joint_count)
+            .into_iter()
+            .collect::<CacheAlignedVec<_>>();
+        let target_array = src_array
+            .chunks_exact(joint_count)
+            .map(|joints| random_ik_target(&mut rng, joints[0], chain_length(joints)).position)
+            .collect::<CacheAlignedVec<_>>();
+
+        let dst_array = &mut CacheAlignedVec::from_elem(Vec3A::ZERO, COUNT * joint_count);
+
+        for tolerance in [1e-2, 1e-4] {
+            let mut params = FabrikParams {
+                dst_array,
+                src_array: &src_array,
+                target_array: &target_array,
+                joint_count,
+                max_iterations: 16,
+                tolerance,
+            };
+
+            group.bench_function(
+                format!("count = {COUNT}, joints = {joint_count}, fabrik, tolerance = {tolerance}"),
+                |b| b.iter(|| fabrik(&mut params)),
+            );
+        }
+    }
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+criterion_group!(animation, pin_thread, keyframe, skin, morph, ik);
+
+criterion_main!(animation);
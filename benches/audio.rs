@@ -0,0 +1,142 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::audio::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Frames per buffer, at the larger end of what a game's audio thread would
+// mix at once.
+const FRAMES: usize = 1024;
+
+// Mixes voices into one buffer, with a gain per voice and channel. Throughput
+// is input samples per second, so it's comparable across voice counts.
+fn mix_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    for voices in [1, 8, 32, 128] {
+        group.throughput(Throughput::Elements((voices * FRAMES * CHANNELS) as u64));
+
+        let streams = (0..voices)
+            .map(|_| random_audio_stream(&mut rng, FRAMES))
+            .collect::<Vec<_>>();
+
+        let src_arrays = streams.iter().map(|s| s.as_slice()).collect::<Vec<_>>();
+        let gains = random_voice_gains(&mut rng, voices);
+
+        let mut params = MixParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0.0f32, FRAMES * CHANNELS),
+            src_arrays: &src_arrays,
+            gains: &gains,
+        };
+
+        for (name, f) in [
+            ("scalar", mix_scalar as fn(&mut MixParams)),
+            ("unrolled", mix_unrolled),
+            ("x4", mix_x4),
+        ] {
+            group.bench_function(format!("voices = {voices}, impl = {name}"), |b| {
+                b.iter(|| f(&mut params))
+            });
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            use misc_benches::kernels::x86::*;
+
+            if let Some(avx2) = Avx2::detect() {
+                group.bench_function(format!("voices = {voices}, impl = avx2"), |b| {
+                    b.iter(|| mix_avx2(avx2, &mut params))
+                });
+            }
+        }
+    }
+}
+
+pub fn mix(c: &mut Criterion) {
+    mix_with(c, "mix");
+}
+
+pub fn mix_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("mix", mix_with);
+}
+
+// Filters a mono buffer through a chain of biquads, either a sample at a time
+// through the whole chain, or a block at a time through each filter. The
+// filters run one after another, so the per sample version can overlap each
+// filter's work with the next. Throughput is samples per second.
+fn biquad_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.throughput(Throughput::Elements(FRAMES as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    // A stereo stream has the right number of samples, and they're just as
+    // good as mono samples here.
+    let src_array = random_audio_stream(&mut rng, FRAMES / CHANNELS);
+    let dst_array = &mut CacheAlignedVec::from_elem(0.0f32, FRAMES);
+
+    for filters in [2, 8] {
+        let chain = random_biquad_chain(&mut rng, filters);
+
+        fn bench<S: BiquadState, M: Measurement>(
+            group: &mut criterion::BenchmarkGroup<M>,
+            name: String,
+            params: &mut BiquadParams<S>,
+        ) {
+            group.bench_function(format!("{name}, per sample"), |b| {
+                b.iter(|| biquad_per_sample(params))
+            });
+
+            for block_len in [16, 64, FRAMES] {
+                group.bench_function(format!("{name}, block = {block_len}"), |b| {
+                    b.iter(|| biquad_blocks(params, block_len))
+                });
+            }
+        }
+
+        bench(
+            &mut group,
+            format!("filters = {filters}, form = df1"),
+            &mut BiquadParams {
+                dst_array,
+                src_array: &src_array,
+                chain: &chain,
+                states: &mut vec![DirectForm1::default(); filters],
+            },
+        );
+
+        bench(
+            &mut group,
+            format!("filters = {filters}, form = tdf2"),
+            &mut BiquadParams {
+                dst_array,
+                src_array: &src_array,
+                chain: &chain,
+                states: &mut vec![TransposedDirectForm2::default(); filters],
+            },
+        );
+    }
+}
+
+pub fn biquad(c: &mut Criterion) {
+    biquad_with(c, "biquad");
+}
+
+pub fn biquad_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("biquad", biquad_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(audio, pin_thread, mix, biquad, mix_perf, biquad_perf);
+
+criterion_main!(audio);
@@ -0,0 +1,82 @@
+use criterion::{criterion_group, criterion_main, Criterion, SamplingMode, Throughput};
+use misc_benches::{kernels::bandwidth::*, sysreport::FrequencyCapture, util::*};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::{num::NonZero, thread};
+
+// Elements per array. Big enough that even the f32 arrays are several times
+// larger than any last level cache, so every access goes to memory.
+const COUNT: usize = 16 * 1024 * 1024;
+
+fn stream_with<T: StreamElement>(c: &mut Criterion, type_name: &str, one: T) {
+    let group_name = format!("stream_{type_name}");
+
+    let _frequency = FrequencyCapture::start(&group_name);
+    let mut group = c.benchmark_group(&group_name);
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    let a = CacheAlignedVec::from_elem(one, COUNT);
+    let b = CacheAlignedVec::from_elem(one + one, COUNT);
+    let dst_array = &mut CacheAlignedVec::from_elem(one, COUNT);
+
+    let max_thread_count = thread::available_parallelism()
+        .map(NonZero::<usize>::get)
+        .unwrap_or(1);
+
+    let pools = (1..=max_thread_count)
+        .map(|thread_count| {
+            ThreadPoolBuilder::new()
+                .num_threads(thread_count)
+                .build()
+                .unwrap()
+        })
+        .collect::<Vec<ThreadPool>>();
+
+    let mut params = StreamParams {
+        dst_array,
+        src_arrays: [&a, &b],
+        scalar: one + one + one,
+    };
+
+    for (name, arrays, f) in [
+        (
+            "copy",
+            STREAM_COPY_ARRAYS,
+            stream_copy as fn(&mut StreamParams<T>),
+        ),
+        ("scale", STREAM_SCALE_ARRAYS, stream_scale),
+        ("add", STREAM_ADD_ARRAYS, stream_add),
+        ("triad", STREAM_TRIAD_ARRAYS, stream_triad),
+    ] {
+        group.throughput(Throughput::Bytes((arrays * COUNT * size_of::<T>()) as u64));
+
+        group.bench_function(format!("kernel = {name}, serial"), |b| {
+            b.iter(|| f(&mut params))
+        });
+
+        for pool in &pools {
+            group.bench_function(
+                format!(
+                    "kernel = {name}, parallel, threads = {}",
+                    pool.current_num_threads()
+                ),
+                |b| b.iter(|| pool.install(|| stream_parallel(&mut params, f))),
+            );
+        }
+    }
+}
+
+// The four STREAM kernels over arrays of each type, on the current thread and
+// then split across pools of 1..=N threads. Throughput is bytes read plus
+// bytes written, as STREAM counts them.
+pub fn stream(c: &mut Criterion) {
+    stream_with(c, "f32", 1.0f32);
+    stream_with(c, "f64", 1.0f64);
+}
+
+// This doesn't pin the thread, since the pools' threads would inherit the
+// affinity and every pool would run on one core.
+criterion_group!(bandwidth, stream);
+
+criterion_main!(bandwidth);
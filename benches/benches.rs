@@ -1,82 +1,509 @@
-use criterion::{criterion_group, criterion_main, Criterion, SamplingMode, Throughput};
-use rand::{rngs::StdRng, RngCore, SeedableRng};
+#[cfg(target_os = "linux")]
+use criterion::measurement::Measurement;
+use criterion::{
+    criterion_group, criterion_main, measurement::WallTime, BatchSize, BenchmarkGroup, Criterion,
+    SamplingMode, Throughput,
+};
+use misc_benches::{
+    kernels::{
+        memory::{
+            gather_sum, memcpy_inner, memcpy_parallel, memset_inner, pointer_chase, random_cycle,
+            strided_sum, vec_memset, vec_zeroed, vec_zeroed_touched,
+        },
+        random::WyRand,
+        threads::BarrierPool,
+    },
+    sysreport::{FrequencyCapture, SystemReport},
+    util::{
+        flush_cache, physical_and_smt_cores, pin_to_core, random_array, CacheAlignedVec,
+        CACHE_LINE_SIZE,
+    },
+};
+#[cfg(target_os = "linux")]
+use misc_benches::{
+    measure::for_each_perf_counter,
+    util::{
+        numa_node_cores, numa_nodes_with_memory, opt_in_from_env, transparent_huge_page_bytes,
+        AffinityGuard, PageKind, PageVec,
+    },
+};
+use rand::{
+    rngs::{SmallRng, StdRng},
+    Rng, RngCore, SeedableRng,
+};
+use rayon::ThreadPoolBuilder;
 use std::{iter::repeat_with, num::NonZero, thread, time::Duration};
-use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
 
 pub fn system(_: &mut Criterion) {
-    let sys = System::new_with_specifics(
-        RefreshKind::new()
-            .with_cpu(CpuRefreshKind::new())
-            .with_memory(MemoryRefreshKind::new().with_ram()),
-    );
+    let report = SystemReport::collect();
 
     println!(
         "os: {} / {} / {}",
-        System::long_os_version().unwrap_or("not available".to_string()),
-        System::kernel_version().unwrap_or("not available".to_string()),
-        System::cpu_arch().unwrap_or("not available".to_string()),
+        report.os.long_version.as_deref().unwrap_or("not available"),
+        report
+            .os
+            .kernel_version
+            .as_deref()
+            .unwrap_or("not available"),
+        report.os.arch.as_deref().unwrap_or("not available"),
     );
 
     println!(
         "cpu: {}",
-        sys.cpus()
-            .first()
-            .map(|cpu| cpu.brand().trim().to_string())
-            .unwrap_or("not available".to_string())
+        report.cpu.brand.as_deref().unwrap_or("not available")
     );
 
     println!(
         "cores: {}",
-        sys.physical_core_count()
+        report
+            .cpu
+            .physical_cores
             .map(|cores| cores.to_string())
             .unwrap_or("not available".to_string()),
     );
 
     println!(
         "mem: {:.1} GB",
-        sys.total_memory() as f64 * (1.0 / (1024.0 * 1024.0 * 1024.0))
+        report.memory_bytes as f64 * (1.0 / (1024.0 * 1024.0 * 1024.0))
     );
-}
 
-#[inline(never)]
-fn memcpy_inner(dst: &mut [u8], src: &[u8]) {
-    dst.clone_from_slice(src);
+    println!(
+        "rustc: {} / {}",
+        report.build.rustc_version, report.build.target
+    );
+
+    match report.write_to_criterion_dir() {
+        Ok(path) => println!("system report: {}", path.display()),
+        Err(error) => eprintln!("failed to write system report: {error}"),
+    }
 }
 
+// Working set sizes that should fit in each level of the memory hierarchy.
+const MEMORY_SIZES: [(&str, usize); 4] = [
+    ("L1", 16 * 1024),
+    ("L2", 512 * 1024),
+    ("L3", 16 * 1024 * 1024),
+    ("RAM", 512 * 1024 * 1024),
+];
+
 pub fn memcpy(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("memcpy");
     let mut group = c.benchmark_group("memcpy");
 
-    let sizes = [
-        ("L1", 16 * 1024),
-        ("L2", 512 * 1024),
-        ("L3", 16 * 1024 * 1024),
-        ("RAM", 512 * 1024 * 1024),
-    ];
-
-    for (name, size) in sizes {
+    for (name, size) in MEMORY_SIZES {
         group.throughput(Throughput::Bytes(size as u64));
 
-        let mut v1 = vec![0u8; size / 2];
-        let v2 = vec![0u8; size / 2];
+        let mut v1 = CacheAlignedVec::from_elem(0u8, size / 2);
+        let v2 = CacheAlignedVec::from_elem(0u8, size / 2);
 
         group.bench_function(format!("memcpy = {name}"), |b| {
             b.iter(|| memcpy_inner(&mut v1, &v2))
         });
+
+        group.bench_function(format!("memcpy = {name}, cold"), |b| {
+            b.iter_batched(
+                || flush_cache(&v2),
+                |_| memcpy_inner(&mut v1, &v2),
+                BatchSize::PerIteration,
+            )
+        });
+
+        // Offset the source and destination from the cache line alignment by
+        // different amounts, so the copy can't be aligned for both.
+
+        let mut v1_misaligned = CacheAlignedVec::from_elem(0u8, (size / 2) + CACHE_LINE_SIZE);
+        let v2_misaligned = CacheAlignedVec::from_elem(0u8, (size / 2) + CACHE_LINE_SIZE);
+
+        let dst_misaligned = &mut v1_misaligned[3..(size / 2) + 3];
+        let src_misaligned = &v2_misaligned[1..(size / 2) + 1];
+
+        group.bench_function(format!("memcpy = {name}, misaligned"), |b| {
+            b.iter(|| memcpy_inner(dst_misaligned, src_misaligned))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            use misc_benches::kernels::x86::*;
+
+            if let Some(avx2) = Avx2::detect() {
+                group.bench_function(format!("memcpy = {name}, avx2"), |b| {
+                    b.iter(|| memcpy_avx2(avx2, &mut v1, &v2))
+                });
+
+                // Streaming stores bypass the cache, so they're only worth it
+                // when the copy wouldn't fit anyway.
+                if matches!(name, "L3" | "RAM") {
+                    group.bench_function(format!("memcpy = {name}, avx2 stream"), |b| {
+                        b.iter(|| memcpy_stream_avx2(avx2, &mut v1, &v2))
+                    });
+                }
+            }
+
+            if let Some(avx512) = Avx512::detect() {
+                group.bench_function(format!("memcpy = {name}, avx512"), |b| {
+                    b.iter(|| memcpy_avx512(avx512, &mut v1, &v2))
+                });
+            }
+
+            group.bench_function(format!("memcpy = {name}, dispatch"), |b| {
+                b.iter(|| memcpy_dispatch(&mut v1, &v2))
+            });
+        }
     }
 }
 
-#[inline(never)]
-fn rand_inner(iterations: u64) {
+// The RAM sized memcpy split across pools of 1..=N threads, to show how many
+// threads it takes to saturate the memory controllers. The stream benches in
+// `bandwidth.rs` do the same for the STREAM kernels.
+pub fn memcpy_threads(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("memcpy_threads");
+    let mut group = c.benchmark_group("memcpy_threads");
+
+    let (_, size) = MEMORY_SIZES[MEMORY_SIZES.len() - 1];
+
+    group.throughput(Throughput::Bytes(size as u64));
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    let mut v1 = CacheAlignedVec::from_elem(0u8, size / 2);
+    let v2 = CacheAlignedVec::from_elem(0u8, size / 2);
+
+    let max_thread_count = thread::available_parallelism()
+        .map(NonZero::<usize>::get)
+        .unwrap_or(1);
+
+    group.bench_function("serial", |b| b.iter(|| memcpy_inner(&mut v1, &v2)));
+
+    for thread_count in 1..=max_thread_count {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(thread_count)
+            .build()
+            .unwrap();
+
+        group.bench_function(format!("threads = {thread_count}"), |b| {
+            b.iter(|| pool.install(|| memcpy_parallel(&mut v1, &v2)))
+        });
+    }
+}
+
+// Fills a buffer with zero and with a nonzero byte. Some CPUs have faster
+// paths for zeroing.
+pub fn memset(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("memset");
+    let mut group = c.benchmark_group("memset");
+
+    for (name, size) in MEMORY_SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+
+        let mut v = CacheAlignedVec::from_elem(0u8, size);
+
+        for (pattern, value) in [("zero", 0u8), ("nonzero", 0xab)] {
+            group.bench_function(format!("memset = {name}, {pattern}"), |b| {
+                b.iter(|| memset_inner(&mut v, value))
+            });
+        }
+    }
+}
+
+// Allocates zeroed buffers and frees them. `vec![0; n]` asks the allocator for
+// zeroed memory, which for large sizes is fresh pages from the OS that don't
+// need writing, so it looks free until the pages are touched. The memset
+// version writes every byte up front.
+pub fn alloc_zeroed(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("alloc_zeroed");
+    let mut group = c.benchmark_group("alloc_zeroed");
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    for (name, size) in MEMORY_SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_function(format!("size = {name}, vec zeroed"), |b| {
+            b.iter(|| vec_zeroed(size))
+        });
+
+        group.bench_function(format!("size = {name}, vec zeroed, touched"), |b| {
+            b.iter(|| vec_zeroed_touched(size))
+        });
+
+        group.bench_function(format!("size = {name}, vec memset"), |b| {
+            b.iter(|| vec_memset(size))
+        });
+    }
+}
+
+// Reads a RAM sized array at increasing strides, then in a random order.
+// Throughput is elements read, so it shows how much each element costs once
+// the prefetcher can't keep up. Large strides only touch a few lines, so each
+// iteration starts a line further on, or the lines would still be cached
+// from the previous iteration.
+pub fn access_pattern(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("access_pattern");
+    let mut group = c.benchmark_group("access_pattern");
+
+    const LEN: usize = 64 * 1024 * 1024;
+    const LINE_ELEMENTS: usize = CACHE_LINE_SIZE / size_of::<u32>();
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let src = random_array::<u32>(&mut rng, LEN);
+
+    for stride in (0..=12).map(|i| 1 << i) {
+        group.throughput(Throughput::Elements((LEN / stride) as u64));
+
+        let mut offset = 0;
+
+        group.bench_function(format!("stride = {stride}"), |b| {
+            b.iter(|| {
+                offset = (offset + LINE_ELEMENTS) % stride.max(LINE_ELEMENTS);
+
+                strided_sum(&src[offset..], stride)
+            })
+        });
+    }
+
+    const GATHER_COUNT: usize = 1024 * 1024;
+
+    group.throughput(Throughput::Elements(GATHER_COUNT as u64));
+
+    // The sequential gather reads the same indices as the random one, so the
+    // difference is just the order of the elements.
+
+    let sequential = (0..GATHER_COUNT).collect::<CacheAlignedVec<_>>();
+    let random = (0..GATHER_COUNT)
+        .map(|_| rng.gen_range(0..LEN))
+        .collect::<CacheAlignedVec<_>>();
+
+    group.bench_function("gather = sequential", |b| {
+        b.iter(|| gather_sum(&src, &sequential))
+    });
+
+    group.bench_function("gather = random", |b| b.iter(|| gather_sum(&src, &random)));
+
+    #[cfg(target_arch = "x86_64")]
+    for distance in [1, 4, 16, 64, 256] {
+        use misc_benches::kernels::x86::gather_sum_prefetch;
+
+        group.bench_function(format!("gather = random, prefetch = {distance}"), |b| {
+            b.iter(|| gather_sum_prefetch(&src, &random, distance))
+        });
+    }
+}
+
+// Compare the RAM sized memcpy and a pointer chase across page sizes. Huge
+// pages cover the same memory with far fewer TLB entries, so the difference is
+// mostly TLB misses - the "dtlb_misses" perf counter shows how many. Opt-in
+// with `MISC_BENCHES_HUGE_PAGES=1`, since it maps over a gigabyte and explicit
+// huge pages have to be reserved first.
+#[cfg(target_os = "linux")]
+fn huge_pages_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    if !opt_in_from_env("MISC_BENCHES_HUGE_PAGES") {
+        return;
+    }
+
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const SIZE: usize = 512 * 1024 * 1024;
+    const CHASE_STEPS: usize = 1024 * 1024;
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let next = random_cycle(&mut rng, (SIZE / 2) / size_of::<usize>());
+
+    for kind in PageKind::ALL {
+        let before = transparent_huge_page_bytes();
+
+        let buffers = PageVec::from_elem(0u8, SIZE / 2, kind).and_then(|v1| {
+            let v2 = PageVec::from_elem(0u8, SIZE / 2, kind)?;
+            let chase = PageVec::from_elem(0usize, next.len(), kind)?;
+
+            Ok((v1, v2, chase))
+        });
+
+        let (mut v1, v2, mut chase) = match buffers {
+            Ok(buffers) => buffers,
+            Err(error) => {
+                eprintln!("skipping {group_name}, pages = {}: {error}", kind.name());
+                continue;
+            }
+        };
+
+        chase.copy_from_slice(&next);
+
+        // Transparent huge pages are only a request, so report how much the
+        // kernel actually gave us.
+        if let (Some(before), Some(after)) = (before, transparent_huge_page_bytes()) {
+            println!(
+                "{group_name}, pages = {}: {} of {} MiB in transparent huge pages",
+                kind.name(),
+                after.saturating_sub(before) / (1024 * 1024),
+                (SIZE + (SIZE / 2)) / (1024 * 1024),
+            );
+        }
+
+        group.throughput(Throughput::Bytes(SIZE as u64));
+
+        group.bench_function(format!("memcpy, pages = {}", kind.name()), |b| {
+            b.iter(|| memcpy_inner(&mut v1, &v2))
+        });
+
+        group.throughput(Throughput::Elements(CHASE_STEPS as u64));
+
+        group.bench_function(format!("pointer chase, pages = {}", kind.name()), |b| {
+            b.iter(|| pointer_chase(&chase, CHASE_STEPS))
+        });
+    }
+}
+
+pub fn huge_pages(_c: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    huge_pages_with(_c, "huge_pages");
+}
+
+pub fn huge_pages_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("huge_pages", huge_pages_with);
+}
+
+// Compare the RAM sized memcpy and a pointer chase with the memory on the same
+// NUMA node as the thread and on another node. The thread is pinned to the
+// first core of the local node for the duration. Opt-in with
+// `MISC_BENCHES_NUMA=1`, and skipped if there's only one node with memory.
+#[cfg(target_os = "linux")]
+fn numa_inner(c: &mut Criterion) {
+    if !opt_in_from_env("MISC_BENCHES_NUMA") {
+        return;
+    }
+
+    let nodes = numa_nodes_with_memory();
+
+    let Some((local, core)) = nodes
+        .iter()
+        .find_map(|&node| Some((node, *numa_node_cores(node).first()?)))
+    else {
+        println!("numa: skipping, no nodes with both memory and cores");
+        return;
+    };
+
+    let Some(&remote) = nodes.iter().find(|&&node| node != local) else {
+        println!("numa: skipping, only one node with memory");
+        return;
+    };
+
+    let Some(_affinity) = AffinityGuard::save() else {
+        eprintln!("numa: skipping, failed to read the thread affinity");
+        return;
+    };
+
+    if !pin_to_core(core) {
+        eprintln!("numa: skipping, failed to pin to core {core}");
+        return;
+    }
+
+    println!("numa: pinned to core {core}, local node = {local}, remote node = {remote}");
+
+    let _frequency = FrequencyCapture::start("numa");
+    let mut group = c.benchmark_group("numa");
+
+    const SIZE: usize = 512 * 1024 * 1024;
+    const CHASE_STEPS: usize = 1024 * 1024;
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
     let mut rng = StdRng::seed_from_u64(1234);
 
+    let next = random_cycle(&mut rng, (SIZE / 2) / size_of::<usize>());
+
+    for (name, node) in [("local", local), ("remote", remote)] {
+        let buffers =
+            PageVec::from_elem_on_node(0u8, SIZE / 2, PageKind::Small, node).and_then(|v1| {
+                let v2 = PageVec::from_elem_on_node(0u8, SIZE / 2, PageKind::Small, node)?;
+                let chase = PageVec::from_elem_on_node(0usize, next.len(), PageKind::Small, node)?;
+
+                Ok((v1, v2, chase))
+            });
+
+        let (mut v1, v2, mut chase) = match buffers {
+            Ok(buffers) => buffers,
+            Err(error) => {
+                eprintln!("skipping numa, memory = {name}: {error}");
+                continue;
+            }
+        };
+
+        chase.copy_from_slice(&next);
+
+        group.throughput(Throughput::Bytes(SIZE as u64));
+
+        group.bench_function(format!("memcpy, memory = {name}"), |b| {
+            b.iter(|| memcpy_inner(&mut v1, &v2))
+        });
+
+        group.throughput(Throughput::Elements(CHASE_STEPS as u64));
+
+        group.bench_function(format!("pointer chase, memory = {name}"), |b| {
+            b.iter(|| pointer_chase(&chase, CHASE_STEPS))
+        });
+    }
+}
+
+pub fn numa(_c: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    numa_inner(_c);
+}
+
+// The outputs are combined, since otherwise the compiler can skip the output
+// function of simple generators like wyrand and only advance the state.
+#[inline(never)]
+fn rand_inner<R: RngCore + SeedableRng>(iterations: u64) {
+    let mut rng = R::seed_from_u64(1234);
+    let mut combined = 0;
+
     for _ in 0..iterations {
-        rng.next_u64();
+        combined ^= rng.next_u64();
     }
 
-    criterion::black_box(rng.next_u64());
+    criterion::black_box(combined);
+}
+
+#[inline(never)]
+fn rand_fill_inner<R: RngCore>(rng: &mut R, dst: &mut [u8]) {
+    rng.fill_bytes(dst);
+}
+
+// Compare a generator to the others on one thread, for single values and for
+// filling a buffer that fits in L1.
+fn rand_variant<R: RngCore + SeedableRng>(group: &mut BenchmarkGroup<WallTime>, name: &str) {
+    const ITERATIONS: u64 = 10_000_000;
+    const FILL_SIZE: usize = 16 * 1024;
+
+    group.throughput(Throughput::Elements(ITERATIONS));
+
+    group.bench_function(format!("rng = {name}, next_u64"), |b| {
+        b.iter(|| rand_inner::<R>(ITERATIONS))
+    });
+
+    let mut rng = R::seed_from_u64(1234);
+    let mut dst = CacheAlignedVec::from_elem(0u8, FILL_SIZE);
+
+    group.throughput(Throughput::Bytes(FILL_SIZE as u64));
+
+    group.bench_function(format!("rng = {name}, fill_bytes"), |b| {
+        b.iter(|| rand_fill_inner(&mut rng, &mut dst))
+    });
 }
 
 pub fn rand(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("rand");
     let mut group = c.benchmark_group("rand");
 
     const ITERATIONS: u64 = 100_000_000;
@@ -94,16 +521,133 @@ pub fn rand(c: &mut Criterion) {
     for thread_count in 1..=max_thread_count {
         group.bench_function(format!("threads = {}", thread_count), |b| {
             b.iter(|| {
-                let threads = repeat_with(|| thread::spawn(|| rand_inner(ITERATIONS)))
+                let threads = repeat_with(|| thread::spawn(|| rand_inner::<StdRng>(ITERATIONS)))
                     .take(thread_count)
                     .collect::<Vec<_>>();
 
                 threads.into_iter().for_each(|t| t.join().unwrap());
             })
         });
+
+        // The threads are already running, so this leaves out the spawn and
+        // join costs measured by the thread_spawn group.
+        let pool = BarrierPool::new(thread_count, |_| rand_inner::<StdRng>(ITERATIONS));
+
+        group.bench_function(format!("threads = {}, pool", thread_count), |b| {
+            b.iter(|| pool.run())
+        });
+    }
+
+    // Sweep again with each thread pinned, first to one thread of each physical
+    // core and then to the SMT siblings, so the names show where hyperthreads
+    // start sharing a core.
+
+    let report = SystemReport::collect();
+    let (physical_cores, smt_cores) = physical_and_smt_cores();
+
+    println!(
+        "rand: {} physical cores, {} SMT siblings",
+        physical_cores.len(),
+        smt_cores.len()
+    );
+
+    if let Some(count) = report
+        .cpu
+        .physical_cores
+        .filter(|&count| count != physical_cores.len())
+    {
+        eprintln!(
+            "rand: sysinfo reports {count} physical cores, but the topology has {}",
+            physical_cores.len()
+        );
+    }
+
+    let pinned_cores = [&physical_cores[..], &smt_cores[..]].concat();
+
+    for thread_count in 1..=pinned_cores.len() {
+        let cores = if thread_count <= physical_cores.len() {
+            "physical"
+        } else {
+            "smt"
+        };
+
+        group.bench_function(format!("threads = {thread_count}, pinned, {cores}"), |b| {
+            b.iter(|| {
+                let threads = pinned_cores[..thread_count]
+                    .iter()
+                    .map(|&core_id| {
+                        thread::spawn(move || {
+                            pin_to_core(core_id);
+                            rand_inner::<StdRng>(ITERATIONS)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                threads.into_iter().for_each(|t| t.join().unwrap());
+            })
+        });
+    }
+
+    // StdRng is ChaCha12, which is cryptographically secure. In rand 0.8,
+    // SmallRng is Xoshiro256++ on 64-bit platforms, so it should match the
+    // rand_xoshiro version.
+
+    rand_variant::<StdRng>(&mut group, "StdRng");
+    rand_variant::<SmallRng>(&mut group, "SmallRng");
+    rand_variant::<WyRand>(&mut group, "wyrand");
+
+    #[cfg(feature = "rand_pcg")]
+    rand_variant::<rand_pcg::Pcg64>(&mut group, "Pcg64");
+
+    #[cfg(feature = "rand_xoshiro")]
+    rand_variant::<rand_xoshiro::Xoshiro256PlusPlus>(&mut group, "Xoshiro256++");
+}
+
+// The cost of starting and finishing threads with no work to do, either by
+// spawning and joining them each time, or by waking a pool of threads that were
+// spawned up front. Throughput is per thread.
+pub fn thread_spawn(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("thread_spawn");
+    let mut group = c.benchmark_group("thread_spawn");
+
+    let max_thread_count = thread::available_parallelism()
+        .map(NonZero::<usize>::get)
+        .unwrap_or(1);
+
+    for thread_count in 1..=max_thread_count {
+        group.throughput(Throughput::Elements(thread_count as u64));
+
+        group.bench_function(format!("spawn, threads = {}", thread_count), |b| {
+            b.iter(|| {
+                let threads = repeat_with(|| thread::spawn(|| ()))
+                    .take(thread_count)
+                    .collect::<Vec<_>>();
+
+                threads.into_iter().for_each(|t| t.join().unwrap());
+            })
+        });
+
+        let pool = BarrierPool::new(thread_count, |_| ());
+
+        group.bench_function(format!("pool, threads = {}", thread_count), |b| {
+            b.iter(|| pool.run())
+        });
     }
 }
 
-criterion_group!(benches, system, memcpy, rand,);
+criterion_group!(
+    benches,
+    system,
+    memcpy,
+    memcpy_threads,
+    memset,
+    alloc_zeroed,
+    access_pattern,
+    huge_pages,
+    numa,
+    rand,
+    thread_spawn,
+    huge_pages_perf,
+);
 
 criterion_main!(benches);
@@ -0,0 +1,71 @@
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, Criterion, SamplingMode, Throughput,
+};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{
+        boids::*,
+        spatial::{DenseGrid, HashMapGrid},
+    },
+    sysreport::FrequencyCapture,
+    util::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Updates a flock for one step, with the world scaled so the number of
+// neighbors per boid stays roughly the same. The parallel variant uses rayon's
+// global thread pool. Throughput is boids per second.
+fn boids_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    // The naive version is quadratic, so keep the sample count down.
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    for count in [1000, 2000, 4000, 8000] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let settings = BoidSettings::with_count(count);
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src_array = random_boid_array(&mut rng, count, &settings);
+
+        let mut params = BoidParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Boid::default(), count),
+            src_array: &src_array,
+            settings: &settings,
+        };
+
+        for (name, f) in [
+            ("naive", boids_update_naive as fn(&mut BoidParams)),
+            ("hash map grid", boids_update_grid::<HashMapGrid>),
+            ("dense grid", boids_update_grid::<DenseGrid>),
+            (
+                "dense grid, parallel",
+                boids_update_grid_parallel::<DenseGrid>,
+            ),
+        ] {
+            group.bench_function(format!("count = {count}, {name}"), |b| {
+                b.iter(|| f(&mut params))
+            });
+        }
+    }
+}
+
+pub fn boids(c: &mut Criterion) {
+    boids_with(c, "boids");
+}
+
+pub fn boids_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("boids", boids_with);
+}
+
+// This doesn't pin the thread, since rayon's threads would inherit the affinity
+// and the parallel variant would run on one core.
+criterion_group!(boids_benches, boids, boids_perf);
+
+criterion_main!(boids_benches);
@@ -0,0 +1,62 @@
+use bevy_math::bounding::BoundingSphere;
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::Vec3A;
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::bounding::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Computes a bounding sphere for a random point cloud. Throughput is points
+// per second.
+fn bounding_sphere_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    for count in [100, 1_000, 10_000, 100_000] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let point_array = random_point_cloud(&mut rng, count);
+
+        let exact_radius = bounding_sphere_welzl(&point_array).radius();
+
+        for (name, f) in [
+            (
+                "ritter",
+                bounding_sphere_ritter as fn(&[Vec3A]) -> BoundingSphere,
+            ),
+            ("welzl", bounding_sphere_welzl),
+            ("bevy", bounding_sphere_bevy),
+        ] {
+            // Report how much larger each variant's radius is than the
+            // smallest sphere.
+            let ratio = f(&point_array).radius() / exact_radius;
+
+            bench_function_with_metric(
+                &mut group,
+                group_name,
+                &format!("count = {count}, {name}"),
+                format_args!("radius / smallest radius = {ratio:.4}"),
+                || f(&point_array),
+            );
+        }
+    }
+}
+
+pub fn bounding_sphere(c: &mut Criterion) {
+    bounding_sphere_with(c, "bounding_sphere");
+}
+
+pub fn bounding_sphere_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("bounding_sphere", bounding_sphere_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(bounding, pin_thread, bounding_sphere, bounding_sphere_perf);
+
+criterion_main!(bounding);
@@ -0,0 +1,145 @@
+use bevy_math::bounding::Aabb3d;
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, BatchSize, BenchmarkGroup,
+    Criterion, SamplingMode, Throughput,
+};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{bvh::*, culling::random_culling_aabb_array},
+    sysreport::FrequencyCapture,
+    util::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const COUNTS: [usize; 2] = [10_000, 100_000];
+
+// Builds a tree from boxes in a random order. Each iteration starts from a
+// fresh copy of the boxes, since the build reorders them.
+fn bvh_build_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    for count in COUNTS {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let aabbs = random_culling_aabb_array(&mut rng, count);
+
+        group.bench_function(format!("count = {count}, flat"), |b| {
+            b.iter_batched(
+                || aabbs.clone(),
+                |mut aabbs| FlatBvh::build(&mut aabbs),
+                BatchSize::LargeInput,
+            )
+        });
+
+        group.bench_function(format!("count = {count}, pointer"), |b| {
+            b.iter_batched(
+                || aabbs.clone(),
+                |mut aabbs| PointerBvh::build(&mut aabbs),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+pub fn bvh_build(c: &mut Criterion) {
+    bvh_build_with(c, "bvh_build");
+}
+
+pub fn bvh_build_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("bvh_build", bvh_build_with);
+}
+
+fn bvh_query_variants<B: Bvh, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    name: &str,
+    count: usize,
+    bvh: &B,
+    aabb_array: &[Aabb3d],
+) {
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    const RAY_COUNT: usize = 1024;
+
+    let ray_array = random_bvh_ray_array(&mut rng, RAY_COUNT);
+
+    let mut params = BvhRayParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0, RAY_COUNT),
+        ray_array: &ray_array,
+        bvh,
+        aabb_array,
+    };
+
+    group.throughput(Throughput::Elements(RAY_COUNT as u64));
+
+    group.bench_function(format!("count = {count}, ray, {name}"), |b| {
+        b.iter(|| bvh_ray(&mut params))
+    });
+
+    const FRUSTUM_COUNT: usize = 64;
+
+    let frustum_array = random_query_frustum_array(&mut rng, FRUSTUM_COUNT);
+
+    let mut params = BvhFrustumParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0, FRUSTUM_COUNT),
+        frustum_array: &frustum_array,
+        bvh,
+        aabb_array,
+    };
+
+    group.throughput(Throughput::Elements(FRUSTUM_COUNT as u64));
+
+    group.bench_function(format!("count = {count}, frustum, {name}"), |b| {
+        b.iter(|| bvh_frustum(&mut params))
+    });
+}
+
+// Finds the nearest box along each ray, and counts the boxes visible to each
+// frustum. Throughput is queries per second.
+fn bvh_query_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    for count in COUNTS {
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        // Both builds give the same order, so the boxes are shared.
+        let mut aabbs = random_culling_aabb_array(&mut rng, count);
+        let flat = FlatBvh::build(&mut aabbs);
+        let pointer = PointerBvh::build(&mut aabbs.clone());
+
+        bvh_query_variants(&mut group, "flat", count, &flat, &aabbs);
+        bvh_query_variants(&mut group, "pointer", count, &pointer, &aabbs);
+    }
+}
+
+pub fn bvh_query(c: &mut Criterion) {
+    bvh_query_with(c, "bvh_query");
+}
+
+pub fn bvh_query_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("bvh_query", bvh_query_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    bvh,
+    pin_thread,
+    bvh_build,
+    bvh_query,
+    bvh_build_perf,
+    bvh_query_perf,
+);
+
+criterion_main!(bvh);
@@ -0,0 +1,140 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::{Mat4, Quat, Vec3};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::camera::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Builds look rotations from eye/target pairs, including degenerate pairs that
+// take the kernels' fallback paths - see `LookKind`.
+fn look_at_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Quat, Vec3, Vec3)>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    for kind in LookKind::ALL {
+        let name = kind.name();
+
+        let (eye_array, target_array) = random_look_arrays(&mut rng, COUNT, kind);
+
+        let mut params = LookParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Quat::IDENTITY, COUNT),
+            eye_array: &eye_array,
+            target_array: &target_array,
+        };
+
+        group.bench_function(format!("count = {COUNT}, input = {name}, transform"), |b| {
+            b.iter(|| look_transform(&mut params))
+        });
+
+        group.bench_function(
+            format!("count = {COUNT}, input = {name}, rotation arc"),
+            |b| b.iter(|| look_rotation_arc(&mut params)),
+        );
+
+        group.bench_function(format!("count = {COUNT}, input = {name}, basis"), |b| {
+            b.iter(|| look_basis(&mut params))
+        });
+    }
+}
+
+pub fn look_at(c: &mut Criterion) {
+    look_at_with(c, "look_at");
+}
+
+pub fn look_at_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("look_at", look_at_with);
+}
+
+// Builds projection matrices and extracts frustum corners, as when rebuilding
+// shadow cascades every frame.
+fn projection_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Mat4, ProjectionInput)>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let src_array = (0..COUNT)
+        .map(|_| random_projection_input(&mut rng))
+        .collect::<CacheAlignedVec<_>>();
+
+    // The frustum corner variants read perspective matrices, so build them
+    // up front rather than relying on the perspective bench having run.
+    let mut projection_array = CacheAlignedVec::from_elem(Mat4::IDENTITY, COUNT);
+
+    projection_perspective(&mut ProjectionParams {
+        dst_array: &mut projection_array,
+        src_array: &src_array,
+    });
+
+    let mut params = ProjectionParams {
+        dst_array: &mut CacheAlignedVec::from_elem(Mat4::IDENTITY, COUNT),
+        src_array: &src_array,
+    };
+
+    group.bench_function(format!("count = {COUNT}, perspective"), |b| {
+        b.iter(|| projection_perspective(&mut params))
+    });
+
+    group.bench_function(format!("count = {COUNT}, orthographic"), |b| {
+        b.iter(|| projection_orthographic(&mut params))
+    });
+
+    group.bench_function(format!("count = {COUNT}, infinite reverse z"), |b| {
+        b.iter(|| projection_infinite_reverse(&mut params))
+    });
+
+    let dst_array = &mut CacheAlignedVec::from_elem([Vec3::ZERO; 8], COUNT);
+
+    group.bench_function(format!("count = {COUNT}, frustum corners, inverse"), |b| {
+        b.iter(|| {
+            frustum_corners_inverse(&mut FrustumCornersParams {
+                dst_array,
+                src_array: &projection_array,
+            })
+        })
+    });
+
+    group.bench_function(format!("count = {COUNT}, frustum corners, analytic"), |b| {
+        b.iter(|| {
+            frustum_corners_analytic(&mut FrustumCornersParams {
+                dst_array,
+                src_array: &src_array,
+            })
+        })
+    });
+}
+
+pub fn projection(c: &mut Criterion) {
+    projection_with(c, "projection");
+}
+
+pub fn projection_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("projection", projection_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    camera,
+    pin_thread,
+    look_at,
+    projection,
+    look_at_perf,
+    projection_perf,
+);
+
+criterion_main!(camera);
@@ -0,0 +1,359 @@
+use bevy_color::{ColorToComponents, Hsla, LinearRgba, Oklaba, Oklcha, Srgba};
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, BenchmarkGroup, Criterion,
+    Throughput,
+};
+use glam::Vec4;
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{color::*, quantize::ConvertParams},
+    sysreport::FrequencyCapture,
+    util::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+type SrgbFn = fn(&mut ConvertParams<Vec4, Vec4>);
+type PackFn = fn(&mut ConvertParams<Vec4, u32>);
+
+trait ToVec4: Copy {
+    fn to_vec4(self) -> Vec4;
+}
+
+impl ToVec4 for Vec4 {
+    fn to_vec4(self) -> Vec4 {
+        self
+    }
+}
+
+impl ToVec4 for [u8; 4] {
+    fn to_vec4(self) -> Vec4 {
+        rgba8_to_vec4(self)
+    }
+}
+
+fn srgb_variant<S, D: ToVec4, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    group_name: &str,
+    name: &str,
+    params: &mut ConvertParams<S, D>,
+    expected: &[Vec4],
+    f: impl Fn(&mut ConvertParams<S, D>),
+) {
+    f(params);
+
+    let actual = params
+        .dst_array
+        .iter()
+        .map(|&c| c.to_vec4())
+        .collect::<Vec<_>>();
+
+    let max_error = color_max_error(&actual, expected);
+
+    bench_function_with_metric(
+        group,
+        group_name,
+        name,
+        format_args!("max error = {max_error:e}"),
+        || f(params),
+    );
+}
+
+// Converts image rows between sRGB and linear colors.
+fn srgb_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let lut = SrgbLut::new();
+
+    for count in [1920, 3840] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let rgba8 = random_array::<[u8; 4]>(&mut rng, count);
+        let srgb = rgba8
+            .iter()
+            .copied()
+            .map(rgba8_to_vec4)
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec4::ZERO, count),
+            src_array: &srgb,
+        };
+
+        srgb_to_linear_exact(&mut params);
+
+        let linear = params.dst_array.to_vec();
+
+        for (name, f) in [
+            ("exact", srgb_to_linear_exact as SrgbFn),
+            ("piecewise", srgb_to_linear_piecewise),
+            ("gamma 2.2", srgb_to_linear_gamma22),
+        ] {
+            srgb_variant(
+                &mut group,
+                group_name,
+                &format!("count = {count}, to linear, {name}"),
+                &mut params,
+                &linear,
+                f,
+            );
+        }
+
+        srgb_variant(
+            &mut group,
+            group_name,
+            &format!("count = {count}, to linear, lut"),
+            &mut ConvertParams {
+                dst_array: &mut CacheAlignedVec::from_elem(Vec4::ZERO, count),
+                src_array: &rgba8,
+            },
+            &linear,
+            |params| srgb_to_linear_lut(params, &lut),
+        );
+
+        // Linear colors usually come from rendering rather than from 8-bit
+        // values, so use random inputs to get the error of the LUT.
+
+        let linear = random_array::<Vec4>(&mut rng, count);
+
+        let mut params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec4::ZERO, count),
+            src_array: &linear,
+        };
+
+        linear_to_srgb_exact(&mut params);
+
+        let srgb = params.dst_array.to_vec();
+
+        for (name, f) in [
+            ("exact", linear_to_srgb_exact as SrgbFn),
+            ("piecewise", linear_to_srgb_piecewise),
+            ("gamma 2.2", linear_to_srgb_gamma22),
+        ] {
+            srgb_variant(
+                &mut group,
+                group_name,
+                &format!("count = {count}, to srgb, {name}"),
+                &mut params,
+                &srgb,
+                f,
+            );
+        }
+
+        srgb_variant(
+            &mut group,
+            group_name,
+            &format!("count = {count}, to srgb, lut"),
+            &mut ConvertParams {
+                dst_array: &mut CacheAlignedVec::from_elem([0u8; 4], count),
+                src_array: &linear,
+            },
+            &srgb,
+            |params| linear_to_srgb_lut(params, &lut),
+        );
+    }
+}
+
+pub fn srgb(c: &mut Criterion) {
+    srgb_with(c, "srgb");
+}
+
+pub fn srgb_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("srgb", srgb_with);
+}
+
+fn convert_array<S: Copy, D: From<S>>(src_array: &[S]) -> CacheAlignedVec<D> {
+    src_array.iter().copied().map(D::from).collect()
+}
+
+fn color_space_variant<S: Copy, D: Copy + From<S>, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    name: &str,
+    src_array: &[S],
+) {
+    let count = src_array.len();
+
+    let mut params = ConvertParams {
+        dst_array: &mut CacheAlignedVec::from_elem(D::from(src_array[0]), count),
+        src_array,
+    };
+
+    group.bench_function(format!("count = {count}, {name}"), |b| {
+        b.iter(|| color_convert(&mut params))
+    });
+}
+
+// Converts arrays of colors between `bevy_color` types, as UI theming and
+// palette interpolation do per element per frame.
+fn color_space_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l2 = l2_sized_count::<(Srgba, Srgba)>();
+    let ram = ram_sized_count::<(Srgba, Srgba)>();
+
+    for count in [l2, ram] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let srgba = random_array::<Vec4>(&mut rng, count)
+            .iter()
+            .map(|&c| Srgba::from_vec4(c))
+            .collect::<CacheAlignedVec<_>>();
+
+        let linear = convert_array::<_, LinearRgba>(&srgba);
+        let oklaba = convert_array::<_, Oklaba>(&srgba);
+        let oklcha = convert_array::<_, Oklcha>(&srgba);
+        let hsla = convert_array::<_, Hsla>(&srgba);
+
+        color_space_variant::<_, LinearRgba, _>(&mut group, "srgba to linear", &srgba);
+        color_space_variant::<_, Srgba, _>(&mut group, "linear to srgba", &linear);
+        color_space_variant::<_, Oklaba, _>(&mut group, "linear to oklaba", &linear);
+        color_space_variant::<_, LinearRgba, _>(&mut group, "oklaba to linear", &oklaba);
+        color_space_variant::<_, Oklaba, _>(&mut group, "srgba to oklaba", &srgba);
+        color_space_variant::<_, Oklcha, _>(&mut group, "oklaba to oklcha", &oklaba);
+        color_space_variant::<_, Oklaba, _>(&mut group, "oklcha to oklaba", &oklcha);
+        color_space_variant::<_, Hsla, _>(&mut group, "srgba to hsla", &srgba);
+        color_space_variant::<_, Srgba, _>(&mut group, "hsla to srgba", &hsla);
+        color_space_variant::<_, Hsla, _>(&mut group, "oklaba to hsla", &oklaba);
+    }
+}
+
+pub fn color_space(c: &mut Criterion) {
+    color_space_with(c, "color_space");
+}
+
+pub fn color_space_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("color_space", color_space_with);
+}
+
+// Packs colors into RGBA8 and unpacks them again, as when writing vertex
+// colors or reading textures. The inputs include values outside [0, 1] so the
+// clamping isn't free.
+fn rgba8_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l1 = l1_sized_count::<(Vec4, u32)>();
+    let l2 = l2_sized_count::<(Vec4, u32)>();
+
+    let lut = Rgba8Lut::new();
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src_array = random_array::<Vec4>(&mut rng, count)
+            .iter()
+            .map(|c| (*c * 1.2) - 0.1)
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut pack_params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0u32, count),
+            src_array: &src_array,
+        };
+
+        for (name, f) in [
+            ("truncate", pack_rgba8_truncate as PackFn),
+            ("round", pack_rgba8_round),
+            ("round, clamp", pack_rgba8_clamp),
+            #[cfg(target_arch = "x86_64")]
+            ("sse2", misc_benches::kernels::x86::pack_rgba8_sse2),
+        ] {
+            group.bench_function(format!("count = {count}, pack, {name}"), |b| {
+                b.iter(|| f(&mut pack_params))
+            });
+        }
+
+        let mut unpack_params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec4::ZERO, count),
+            src_array: pack_params.dst_array,
+        };
+
+        group.bench_function(format!("count = {count}, unpack, scalar"), |b| {
+            b.iter(|| unpack_rgba8(&mut unpack_params))
+        });
+
+        group.bench_function(format!("count = {count}, unpack, lut"), |b| {
+            b.iter(|| unpack_rgba8_lut(&mut unpack_params, &lut))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        group.bench_function(format!("count = {count}, unpack, sse2"), |b| {
+            b.iter(|| misc_benches::kernels::x86::unpack_rgba8_sse2(&mut unpack_params))
+        });
+    }
+}
+
+pub fn rgba8(c: &mut Criterion) {
+    rgba8_with(c, "rgba8");
+}
+
+pub fn rgba8_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("rgba8", rgba8_with);
+}
+
+// Tonemaps HDR pixels for a thumbnail and a full 1080p frame.
+fn tonemap_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    for count in [256 * 256, 1920 * 1080] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let mut params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec4::ZERO, count),
+            src_array: &random_hdr_array(&mut rng, count),
+        };
+
+        group.bench_function(format!("count = {count}, reinhard"), |b| {
+            b.iter(|| tonemap_reinhard(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, aces fitted"), |b| {
+            b.iter(|| tonemap_aces_fitted(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, agx"), |b| {
+            b.iter(|| tonemap_agx(&mut params))
+        });
+    }
+}
+
+pub fn tonemap(c: &mut Criterion) {
+    tonemap_with(c, "tonemap");
+}
+
+pub fn tonemap_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("tonemap", tonemap_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    color,
+    pin_thread,
+    srgb,
+    color_space,
+    rgba8,
+    tonemap,
+    srgb_perf,
+    color_space_perf,
+    rgba8_perf,
+    tonemap_perf,
+);
+
+criterion_main!(color);
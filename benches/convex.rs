@@ -0,0 +1,98 @@
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, Criterion, SamplingMode, Throughput,
+};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::convex::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Tests pairs of randomly rotated prisms for overlap, where roughly half the
+// pairs overlap. Four sides is a box. SAT's cost grows with the product of the
+// edge counts, and GJK's with the vertex count.
+fn convex_overlap_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = 1000;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    for sides in [4, 8, 16, 32] {
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src = [
+            random_prism_array(&mut rng, COUNT, sides),
+            random_prism_array(&mut rng, COUNT, sides),
+        ];
+
+        let vertices = sides * 2;
+
+        let mut params = ConvexOverlapParams {
+            dst_array: &mut CacheAlignedVec::from_elem(false, COUNT),
+            src_array: [&src[0], &src[1]],
+        };
+
+        group.bench_function(format!("vertices = {vertices}, sat"), |b| {
+            b.iter(|| convex_overlap_sat(&mut params))
+        });
+
+        group.bench_function(format!("vertices = {vertices}, gjk"), |b| {
+            b.iter(|| convex_overlap_gjk(&mut params))
+        });
+    }
+}
+
+pub fn convex_overlap(c: &mut Criterion) {
+    convex_overlap_with(c, "convex_overlap");
+}
+
+pub fn convex_overlap_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("convex_overlap", convex_overlap_with);
+}
+
+// Computes the convex hull of points inside a ball. Throughput is input points
+// per second.
+fn convex_hull_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    for count in [1_000, 10_000, 100_000, 1_000_000] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let point_array = random_hull_point_array(&mut rng, count);
+
+        group.bench_function(format!("count = {count}, quickhull"), |b| {
+            b.iter(|| quickhull(&point_array))
+        });
+    }
+}
+
+pub fn convex_hull(c: &mut Criterion) {
+    convex_hull_with(c, "convex_hull");
+}
+
+pub fn convex_hull_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("convex_hull", convex_hull_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    convex,
+    pin_thread,
+    convex_overlap,
+    convex_hull,
+    convex_overlap_perf,
+    convex_hull_perf
+);
+
+criterion_main!(convex);
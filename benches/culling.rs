@@ -0,0 +1,140 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{bvh::FlatBvh, culling::*},
+    sysreport::FrequencyCapture,
+    util::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const COUNT: usize = 100_000;
+
+// Tests bounding spheres against a frustum. Roughly a tenth are visible.
+fn cull_sphere_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let frustum = culling_frustum();
+    let spheres = random_culling_sphere_array(&mut rng, COUNT);
+    let sphere_soa = SphereSoa::from_spheres(&spheres);
+
+    let mut params = CullParams {
+        dst_array: &mut CacheAlignedVec::from_elem(false, COUNT),
+        volumes: spheres.as_slice(),
+        frustum: &frustum,
+    };
+
+    group.bench_function(format!("count = {COUNT}, scalar"), |b| {
+        b.iter(|| cull_spheres_scalar(&mut params))
+    });
+
+    let mut params = CullParams {
+        dst_array: &mut CacheAlignedVec::from_elem(false, COUNT),
+        volumes: &sphere_soa,
+        frustum: &frustum,
+    };
+
+    group.bench_function(format!("count = {COUNT}, x4"), |b| {
+        b.iter(|| cull_spheres_x4(&mut params))
+    });
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        use misc_benches::kernels::x86::*;
+
+        if let Some(avx2) = Avx2::detect() {
+            group.bench_function(format!("count = {COUNT}, avx2"), |b| {
+                b.iter(|| cull_spheres_avx2(avx2, &mut params))
+            });
+        }
+    }
+}
+
+pub fn cull_sphere(c: &mut Criterion) {
+    cull_sphere_with(c, "cull_sphere");
+}
+
+pub fn cull_sphere_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("cull_sphere", cull_sphere_with);
+}
+
+// Tests boxes against a frustum. Every variant uses the order given by the
+// BVH, so spatially close boxes are also close in memory.
+fn cull_aabb_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let frustum = culling_frustum();
+    let mut aabbs = random_culling_aabb_array(&mut rng, COUNT);
+    let bvh = FlatBvh::build(&mut aabbs);
+    let aabb_soa = AabbSoa::from_aabbs(&aabbs);
+
+    let mut params = CullParams {
+        dst_array: &mut CacheAlignedVec::from_elem(false, COUNT),
+        volumes: aabbs.as_slice(),
+        frustum: &frustum,
+    };
+
+    group.bench_function(format!("count = {COUNT}, scalar"), |b| {
+        b.iter(|| cull_aabbs_scalar(&mut params))
+    });
+
+    group.bench_function(format!("count = {COUNT}, bvh"), |b| {
+        b.iter(|| cull_aabbs_bvh(&mut params, &bvh))
+    });
+
+    let mut params = CullParams {
+        dst_array: &mut CacheAlignedVec::from_elem(false, COUNT),
+        volumes: &aabb_soa,
+        frustum: &frustum,
+    };
+
+    group.bench_function(format!("count = {COUNT}, x4"), |b| {
+        b.iter(|| cull_aabbs_x4(&mut params))
+    });
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        use misc_benches::kernels::x86::*;
+
+        if let Some(avx2) = Avx2::detect() {
+            group.bench_function(format!("count = {COUNT}, avx2"), |b| {
+                b.iter(|| cull_aabbs_avx2(avx2, &mut params))
+            });
+        }
+    }
+}
+
+pub fn cull_aabb(c: &mut Criterion) {
+    cull_aabb_with(c, "cull_aabb");
+}
+
+pub fn cull_aabb_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("cull_aabb", cull_aabb_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    culling,
+    pin_thread,
+    cull_sphere,
+    cull_aabb,
+    cull_sphere_perf,
+    cull_aabb_perf,
+);
+
+criterion_main!(culling);
@@ -0,0 +1,108 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::{Affine3A, Mat4, Quat, Vec3};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::decompose::*, sysreport::FrequencyCapture, util::*};
+use rand::prelude::*;
+
+// Decomposes matrices into scale, rotation and translation, then recomposes
+// them. The parts are the same for both matrix types, so they're decomposed
+// once up front for the recompose variants.
+fn decompose_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Mat4, (Vec3, Quat, Vec3))>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    for kind in MatrixKind::ALL {
+        let name = kind.name();
+
+        let affine_array = random_affine_array(&mut rng, COUNT, kind);
+        let mat4_array = affine_array
+            .iter()
+            .copied()
+            .map(Mat4::from)
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut parts = CacheAlignedVec::from_elem((Vec3::ZERO, Quat::IDENTITY, Vec3::ZERO), COUNT);
+
+        decompose_affine3a(&mut DecomposeParams {
+            dst_array: &mut parts,
+            src_array: &affine_array,
+        });
+
+        let mut decompose_dst = parts.clone();
+
+        group.bench_function(
+            format!("count = {COUNT}, input = {name}, mat4, decompose"),
+            |b| {
+                b.iter(|| {
+                    decompose_mat4(&mut DecomposeParams {
+                        dst_array: &mut decompose_dst,
+                        src_array: &mat4_array,
+                    })
+                })
+            },
+        );
+
+        group.bench_function(
+            format!("count = {COUNT}, input = {name}, affine3a, decompose"),
+            |b| {
+                b.iter(|| {
+                    decompose_affine3a(&mut DecomposeParams {
+                        dst_array: &mut decompose_dst,
+                        src_array: &affine_array,
+                    })
+                })
+            },
+        );
+
+        let mut mat4_result = CacheAlignedVec::from_elem(Mat4::IDENTITY, COUNT);
+        let mut affine_result = CacheAlignedVec::from_elem(Affine3A::IDENTITY, COUNT);
+
+        group.bench_function(
+            format!("count = {COUNT}, input = {name}, mat4, recompose"),
+            |b| {
+                b.iter(|| {
+                    recompose_mat4(&mut RecomposeParams {
+                        dst_array: &mut mat4_result,
+                        src_array: &parts,
+                    })
+                })
+            },
+        );
+
+        group.bench_function(
+            format!("count = {COUNT}, input = {name}, affine3a, recompose"),
+            |b| {
+                b.iter(|| {
+                    recompose_affine3a(&mut RecomposeParams {
+                        dst_array: &mut affine_result,
+                        src_array: &parts,
+                    })
+                })
+            },
+        );
+    }
+}
+
+pub fn decompose(c: &mut Criterion) {
+    decompose_with(c, "decompose");
+}
+
+pub fn decompose_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("decompose", decompose_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(decompose_benches, pin_thread, decompose, decompose_perf);
+
+criterion_main!(decompose_benches);
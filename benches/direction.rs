@@ -0,0 +1,131 @@
+use bevy_math::Dir3;
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::Vec3;
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::direction::*, sysreport::FrequencyCapture, util::*};
+use rand::prelude::*;
+
+// Constructs directions from random vectors. In the near zero input a quarter
+// of the vectors are too short to normalize.
+fn dir3_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Dir3, Vec3)>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let random_src = random_array::<Vec3>(&mut rng, COUNT)
+        .iter()
+        .map(|&v| v - 0.5)
+        .collect::<CacheAlignedVec<_>>();
+
+    let near_zero_src = random_src
+        .iter()
+        .map(|&v| if rng.gen_ratio(1, 4) { v * 1e-25 } else { v })
+        .collect::<CacheAlignedVec<_>>();
+
+    for (name, src_array) in [("random", &random_src), ("near zero", &near_zero_src)] {
+        let mut params = DirParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Dir3::Y, COUNT),
+            src_array,
+        };
+
+        group.bench_function(format!("count = {COUNT}, input = {name}, new"), |b| {
+            b.iter(|| dir_new(&mut params))
+        });
+
+        group.bench_function(
+            format!("count = {COUNT}, input = {name}, new unchecked"),
+            |b| b.iter(|| dir_new_unchecked(&mut params)),
+        );
+
+        group.bench_function(
+            format!("count = {COUNT}, input = {name}, new and length"),
+            |b| b.iter(|| dir_new_and_length(&mut params)),
+        );
+    }
+}
+
+pub fn dir3(c: &mut Criterion) {
+    dir3_with(c, "dir3");
+}
+
+pub fn dir3_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("dir3", dir3_with);
+}
+
+// Normalizes vectors where some fraction are too short to normalize, and a
+// quarter are already unit length so the fast variant can skip them. The near
+// zero fractions can be set with a comma separated list in
+// `MISC_BENCHES_NEAR_ZERO`, e.g. "0,0.01,0.1".
+fn vec3_normalize_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Vec3, Vec3)>();
+    const UNIT_FRACTION: f64 = 0.25;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    for near_zero_fraction in fractions_from_env("MISC_BENCHES_NEAR_ZERO", &[0.0, 0.1, 0.5]) {
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let mut params = VecNormalizeParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT),
+            src_array: &random_near_zero_vec3_array(
+                &mut rng,
+                COUNT,
+                near_zero_fraction,
+                UNIT_FRACTION,
+            ),
+        };
+
+        let name =
+            format!("count = {COUNT}, near zero = {near_zero_fraction}, unit = {UNIT_FRACTION}");
+
+        group.bench_function(format!("{name}, normalize"), |b| {
+            b.iter(|| vec_normalize(&mut params))
+        });
+
+        group.bench_function(format!("{name}, normalize or zero"), |b| {
+            b.iter(|| vec_normalize_or_zero(&mut params))
+        });
+
+        group.bench_function(format!("{name}, try normalize"), |b| {
+            b.iter(|| vec_try_normalize(&mut params))
+        });
+
+        group.bench_function(format!("{name}, fast"), |b| {
+            b.iter(|| vec_normalize_fast(&mut params))
+        });
+    }
+}
+
+pub fn vec3_normalize(c: &mut Criterion) {
+    vec3_normalize_with(c, "vec3_normalize");
+}
+
+pub fn vec3_normalize_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("vec3_normalize", vec3_normalize_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    direction,
+    pin_thread,
+    dir3,
+    vec3_normalize,
+    dir3_perf,
+    vec3_normalize_perf,
+);
+
+criterion_main!(direction);
@@ -1,65 +1,43 @@
 use bevy_math::prelude::*;
 use core::time::Duration;
-use criterion::{criterion_group, criterion_main, Criterion, Throughput};
-use misc_benches::util::*;
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::Measurement, BenchmarkGroup,
+    Criterion, Throughput,
+};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{easing::*, scalar::Scalar},
+    sysreport::FrequencyCapture,
+    util::*,
+};
 use rand::{rngs::StdRng, SeedableRng};
 
 ////////////////////////////////////////////////////////////////////////////////
 
-#[inline(never)]
-fn internal_smoothstep_noinline(t: f32) -> f32 {
-    (3.0 - (2.0 * t)) * t * t
-}
-
-////////////////////////////////////////////////////////////////////////////////
-
-struct SmoothstepParams<'a> {
-    dst_array: &'a mut [f32],
-    src_array: &'a [f32],
-}
-
-#[inline(never)]
-fn smoothstep_explicit(params: &mut SmoothstepParams) {
-    for i in 0..params.dst_array.len() {
-        let t = params.src_array[i];
-
-        params.dst_array[i] = (3.0 - (2.0 * t)) * t * t;
-    }
-}
-
-#[inline(never)]
-fn smoothstep_unit(params: &mut SmoothstepParams) {
-    let f = SmoothStep;
-
-    for i in 0..params.dst_array.len() {
-        let t = params.src_array[i];
-
-        params.dst_array[i] = f.sample_unchecked(t);
-    }
-}
-
-#[inline(never)]
-fn smoothstep_noinline(params: &mut SmoothstepParams) {
-    for i in 0..params.dst_array.len() {
-        let t = params.src_array[i];
-
-        params.dst_array[i] = internal_smoothstep_noinline(t);
-    }
-}
-
-#[inline(never)]
-fn smoothstep_enum(params: &mut SmoothstepParams) {
-    let f = EaseFunction::SmoothStep;
-
-    for i in 0..params.dst_array.len() {
-        let t = params.src_array[i];
+fn smoothstep_generic_variant<S: Scalar, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    src_array: &[f32],
+) {
+    let mut params = SmoothstepParams {
+        dst_array: &mut CacheAlignedVec::from_elem(S::from_f32(0.0), src_array.len()),
+        src_array: &src_array
+            .iter()
+            .copied()
+            .map(S::from_f32)
+            .collect::<CacheAlignedVec<_>>(),
+    };
 
-        params.dst_array[i] = f.sample_unchecked(t);
-    }
+    group.bench_function(format!("generic, precision = {}", S::NAME), |b| {
+        b.iter(|| {
+            smoothstep_generic(&mut params);
+        })
+    });
 }
 
-pub fn smoothstep(c: &mut Criterion) {
-    let mut group = c.benchmark_group("smoothstep");
+fn smoothstep_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
 
     const COUNT: usize = 32 * 1024;
 
@@ -70,7 +48,7 @@ pub fn smoothstep(c: &mut Criterion) {
     let mut rng = StdRng::seed_from_u64(1234);
 
     let mut params = SmoothstepParams {
-        dst_array: &mut vec![0.0f32; COUNT],
+        dst_array: &mut CacheAlignedVec::from_elem(0.0f32, COUNT),
         src_array: &random_array(&mut rng, COUNT),
     };
 
@@ -97,58 +75,85 @@ pub fn smoothstep(c: &mut Criterion) {
             smoothstep_enum(&mut params);
         })
     });
-}
 
-////////////////////////////////////////////////////////////////////////////////
+    smoothstep_generic_variant::<f32, _>(&mut group, params.src_array);
+    smoothstep_generic_variant::<f64, _>(&mut group, params.src_array);
 
-struct SmoothstepIndirectParams<'a> {
-    dst_array: &'a mut [f32],
-    src_array: &'a [f32],
-    index_array: &'a [usize],
-}
+    #[cfg(misc_benches_nightly)]
+    group.bench_function("simd", |b| {
+        b.iter(|| {
+            misc_benches::kernels::simd::smoothstep_simd(&mut params);
+        })
+    });
 
-#[inline(never)]
-fn smoothstep_indirect_explicit(params: &mut SmoothstepIndirectParams) {
-    for i in 0..params.dst_array.len() {
-        let t = params.src_array[params.index_array[i]];
+    #[cfg(target_arch = "aarch64")]
+    group.bench_function("neon", |b| {
+        b.iter(|| {
+            misc_benches::kernels::neon::smoothstep_neon(&mut params);
+        })
+    });
 
-        params.dst_array[i] = (3.0 - (2.0 * t)) * t * t;
+    // The "dispatch" variant checks the CPU features on every call, so the
+    // difference from the direct variant is the cost of the check.
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        use misc_benches::kernels::x86::*;
+
+        if let Some(avx2) = Avx2::detect() {
+            group.bench_function("avx2", |b| {
+                b.iter(|| {
+                    smoothstep_avx2(avx2, &mut params);
+                })
+            });
+        }
+
+        if let Some(avx512) = Avx512::detect() {
+            group.bench_function("avx512", |b| {
+                b.iter(|| {
+                    smoothstep_avx512(avx512, &mut params);
+                })
+            });
+        }
+
+        group.bench_function("dispatch", |b| {
+            b.iter(|| {
+                smoothstep_dispatch(&mut params);
+            })
+        });
     }
-}
 
-#[inline(never)]
-fn smoothstep_indirect_unit(params: &mut SmoothstepIndirectParams) {
-    let f = SmoothStep;
+    for size in [256, 1024] {
+        let lut = smoothstep_lut(size);
 
-    for i in 0..params.dst_array.len() {
-        let t = params.src_array[params.index_array[i]];
+        group.bench_function(format!("lut = {size}, nearest"), |b| {
+            b.iter(|| {
+                smoothstep_lut_nearest(&mut params, &lut);
+            })
+        });
 
-        params.dst_array[i] = f.sample_unchecked(t);
+        group.bench_function(format!("lut = {size}, linear"), |b| {
+            b.iter(|| {
+                smoothstep_lut_linear(&mut params, &lut);
+            })
+        });
     }
 }
 
-#[inline(never)]
-fn smoothstep_indirect_noinline(params: &mut SmoothstepIndirectParams) {
-    for i in 0..params.dst_array.len() {
-        let t = params.src_array[params.index_array[i]];
-
-        params.dst_array[i] = internal_smoothstep_noinline(t);
-    }
+pub fn smoothstep(c: &mut Criterion) {
+    smoothstep_with(c, "smoothstep");
 }
 
-#[inline(never)]
-fn smoothstep_indirect_enum(params: &mut SmoothstepIndirectParams) {
-    let f = EaseFunction::SmoothStep;
-
-    for i in 0..params.dst_array.len() {
-        let t = params.src_array[params.index_array[i]];
-
-        params.dst_array[i] = f.sample_unchecked(t);
-    }
+pub fn smoothstep_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("smoothstep", smoothstep_with);
 }
 
-pub fn smoothstep_indirect(c: &mut Criterion) {
-    let mut group = c.benchmark_group("smoothstep");
+////////////////////////////////////////////////////////////////////////////////
+
+fn smoothstep_indirect_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
 
     const COUNT: usize = 4 * 1024;
 
@@ -161,10 +166,10 @@ pub fn smoothstep_indirect(c: &mut Criterion) {
     let index_array = random_array::<usize>(&mut rng, COUNT)
         .iter()
         .map(|i| i.rem_euclid(COUNT))
-        .collect::<Vec<_>>();
+        .collect::<CacheAlignedVec<_>>();
 
     let mut params = SmoothstepIndirectParams {
-        dst_array: &mut vec![0.0f32; COUNT],
+        dst_array: &mut CacheAlignedVec::from_elem(0.0f32, COUNT),
         src_array: &random_array(&mut rng, COUNT),
         index_array: &index_array,
     };
@@ -192,10 +197,228 @@ pub fn smoothstep_indirect(c: &mut Criterion) {
             smoothstep_indirect_enum(&mut params);
         })
     });
+
+    for size in [256, 1024] {
+        let lut = smoothstep_lut(size);
+
+        group.bench_function(format!("lut = {size}, nearest"), |b| {
+            b.iter(|| {
+                smoothstep_indirect_lut_nearest(&mut params, &lut);
+            })
+        });
+
+        group.bench_function(format!("lut = {size}, linear"), |b| {
+            b.iter(|| {
+                smoothstep_indirect_lut_linear(&mut params, &lut);
+            })
+        });
+    }
+}
+
+pub fn smoothstep_indirect(c: &mut Criterion) {
+    smoothstep_indirect_with(c, "smoothstep");
+}
+
+pub fn smoothstep_indirect_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("smoothstep", smoothstep_indirect_with);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Same as the indirect group, but the source is too big for the caches, so most
+// reads miss. The prefetch variants request the element `distance` indices
+// ahead, which is only worth it if the hardware prefetcher can't already guess
+// the addresses.
+
+fn smoothstep_prefetch_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const SRC_COUNT: usize = 32 * 1024 * 1024;
+    const COUNT: usize = 1024 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let index_array = random_array::<usize>(&mut rng, COUNT)
+        .iter()
+        .map(|i| i.rem_euclid(SRC_COUNT))
+        .collect::<CacheAlignedVec<_>>();
+
+    let mut params = SmoothstepIndirectParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0f32, COUNT),
+        src_array: &random_array(&mut rng, SRC_COUNT),
+        index_array: &index_array,
+    };
+
+    group.bench_function("explicit", |b| {
+        b.iter(|| {
+            smoothstep_indirect_explicit(&mut params);
+        })
+    });
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        use misc_benches::kernels::x86::*;
+
+        for distance in [1, 4, 16, 64, 256] {
+            group.bench_function(format!("prefetch = {distance}"), |b| {
+                b.iter(|| {
+                    smoothstep_indirect_prefetch(&mut params, distance);
+                })
+            });
+        }
+    }
+}
+
+pub fn smoothstep_prefetch(c: &mut Criterion) {
+    smoothstep_prefetch_with(c, "smoothstep_prefetch");
+}
+
+pub fn smoothstep_prefetch_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("smoothstep_prefetch", smoothstep_prefetch_with);
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
-criterion_group!(easing, smoothstep, smoothstep_indirect);
+pub fn curve(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("curve");
+    let mut group = c.benchmark_group("curve");
+
+    const COUNT: usize = 32 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let start = Vec3::new(-1.0, 2.0, 3.0);
+    let end = Vec3::new(4.0, -5.0, 6.0);
+
+    let mut params = CurveParams {
+        dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT),
+        src_array: &random_array(&mut rng, COUNT),
+        start,
+        end,
+    };
+
+    // Box the curve so the compiler can't see through the trait object.
+    let dyn_curve: Box<dyn Curve<Vec3>> =
+        Box::new(EasingCurve::new(start, end, EaseFunction::SmoothStep));
+
+    group.bench_function("direct", |b| {
+        b.iter(|| {
+            curve_direct(&mut params);
+        })
+    });
+
+    group.bench_function("easing curve", |b| {
+        b.iter(|| {
+            curve_easing(&mut params);
+        })
+    });
+
+    group.bench_function("easing curve, clamped", |b| {
+        b.iter(|| {
+            curve_easing_clamped(&mut params);
+        })
+    });
+
+    group.bench_function("dyn curve", |b| {
+        b.iter(|| {
+            curve_dyn(&mut params, black_box(&*dyn_curve));
+        })
+    });
+
+    group.bench_function("dyn curve, clamped", |b| {
+        b.iter(|| {
+            curve_dyn_clamped(&mut params, black_box(&*dyn_curve));
+        })
+    });
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn ease_mixed(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("ease_mixed");
+    let mut group = c.benchmark_group("ease_mixed");
+
+    const COUNT: usize = 32 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let src_array = random_array::<f32>(&mut rng, COUNT);
+
+    // Random order defeats the branch predictor, while sorted order is closer
+    // to a tween system that groups entities by easing function.
+
+    let random_function_array = random_array::<usize>(&mut rng, COUNT)
+        .iter()
+        .map(|i| i.rem_euclid(EASE_FUNCTIONS.len()))
+        .collect::<CacheAlignedVec<_>>();
+
+    let mut sorted_function_array = random_function_array.clone();
+    sorted_function_array.sort();
+
+    let boxes = ease_function_boxes();
+
+    for (order, function_array) in [
+        ("random", &random_function_array),
+        ("sorted", &sorted_function_array),
+    ] {
+        let mut params = EaseMixedParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0.0f32, COUNT),
+            src_array: &src_array,
+            function_array,
+        };
+
+        group.bench_function(format!("enum, {order}"), |b| {
+            b.iter(|| {
+                ease_mixed_enum(&mut params);
+            })
+        });
+
+        group.bench_function(format!("fn pointer, {order}"), |b| {
+            b.iter(|| {
+                ease_mixed_fn_pointer(&mut params);
+            })
+        });
+
+        group.bench_function(format!("dyn, {order}"), |b| {
+            b.iter(|| {
+                ease_mixed_dyn(&mut params, &boxes);
+            })
+        });
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    easing,
+    pin_thread,
+    smoothstep,
+    smoothstep_indirect,
+    smoothstep_prefetch,
+    curve,
+    ease_mixed,
+    smoothstep_perf,
+    smoothstep_indirect_perf,
+    smoothstep_prefetch_perf,
+);
 
 criterion_main!(easing);
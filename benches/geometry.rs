@@ -0,0 +1,279 @@
+use bevy_math::bounding::Aabb3d;
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, BenchmarkGroup, Criterion,
+    Throughput,
+};
+use glam::Vec3A;
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::geometry::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Tests pairs of boxes for overlap. About half the pairs overlap, in a random
+// order.
+fn aabb_overlap_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l1 = l1_sized_count::<(Aabb3d, Aabb3d)>();
+    let l2 = l2_sized_count::<(Aabb3d, Aabb3d)>();
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src = [
+            random_aabb_array(&mut rng, count),
+            random_aabb_array(&mut rng, count),
+        ];
+
+        let mut params = AabbOverlapParams {
+            dst_array: &mut CacheAlignedVec::from_elem(false, count),
+            src_array: [&src[0], &src[1]],
+        };
+
+        group.bench_function(format!("count = {count}, branchy"), |b| {
+            b.iter(|| aabb_overlap_branchy(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, branchless"), |b| {
+            b.iter(|| aabb_overlap_branchless(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, bevy"), |b| {
+            b.iter(|| aabb_overlap_bevy(&mut params))
+        });
+
+        // The counts are multiples of four, so the batches cover every pair.
+
+        let src_x4 = src.each_ref().map(|s| aabb3dx4_array_from(s));
+
+        let mut params = AabbOverlapParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0, count / Aabb3dx4::LANES),
+            src_array: [&src_x4[0], &src_x4[1]],
+        };
+
+        group.bench_function(format!("count = {count}, x4"), |b| {
+            b.iter(|| aabb_overlap_x4(&mut params))
+        });
+    }
+}
+
+pub fn aabb_overlap(c: &mut Criterion) {
+    aabb_overlap_with(c, "aabb_overlap");
+}
+
+pub fn aabb_overlap_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("aabb_overlap", aabb_overlap_with);
+}
+
+// Tests a ray against a box for each element, with a varying fraction of hits.
+fn ray_aabb_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(SlabRay, Aabb3d)>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    for hit_rate in [0.0, 0.5, 1.0] {
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let aabb_array = random_aabb_array(&mut rng, COUNT);
+        let ray_array = random_slab_ray_array(&mut rng, &aabb_array, hit_rate);
+
+        let input = format!("count = {COUNT}, hit rate = {}%", hit_rate * 100.0);
+
+        let mut params = RayAabbParams {
+            dst_array: &mut CacheAlignedVec::from_elem(false, COUNT),
+            ray_array: &ray_array,
+            aabb_array: &aabb_array,
+        };
+
+        group.bench_function(format!("{input}, division"), |b| {
+            b.iter(|| ray_aabb_division(&mut params))
+        });
+
+        group.bench_function(format!("{input}, inverse"), |b| {
+            b.iter(|| ray_aabb_inverse(&mut params))
+        });
+
+        group.bench_function(format!("{input}, nan safe"), |b| {
+            b.iter(|| ray_aabb_nan_safe(&mut params))
+        });
+
+        let ray_cast_array = ray_array
+            .iter()
+            .map(ray_cast_3d_from_slab_ray)
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut params = RayAabbParams {
+            dst_array: &mut CacheAlignedVec::from_elem(false, COUNT),
+            ray_array: &ray_cast_array,
+            aabb_array: &aabb_array,
+        };
+
+        group.bench_function(format!("{input}, bevy"), |b| {
+            b.iter(|| ray_aabb_bevy(&mut params))
+        });
+    }
+}
+
+pub fn ray_aabb(c: &mut Criterion) {
+    ray_aabb_with(c, "ray_aabb");
+}
+
+pub fn ray_aabb_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("ray_aabb", ray_aabb_with);
+}
+
+// Tests a ray against a triangle for each element. The soups are much larger
+// than the box arrays, so the kernels are closer to picking against a whole
+// mesh.
+fn ray_triangle_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l2 = l2_sized_count::<(Ray3A, Triangle3A, f32)>();
+    let ram = ram_sized_count::<(Ray3A, Triangle3A, f32)>();
+
+    for count in [l2, ram] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        for hit_rate in [0.0, 0.5, 1.0] {
+            let mut rng = StdRng::seed_from_u64(1234);
+
+            let triangle_array = random_triangle_array(&mut rng, count);
+            let ray_array = random_triangle_ray_array(&mut rng, &triangle_array, hit_rate);
+
+            let input = format!("count = {count}, hit rate = {}%", hit_rate * 100.0);
+
+            let mut params = RayTriangleParams {
+                dst_array: &mut CacheAlignedVec::from_elem(0.0, count),
+                ray_array: &ray_array,
+                triangle_array: &triangle_array,
+            };
+
+            group.bench_function(format!("{input}, moller trumbore"), |b| {
+                b.iter(|| ray_triangle_moller_trumbore(&mut params))
+            });
+
+            group.bench_function(format!("{input}, watertight"), |b| {
+                b.iter(|| ray_triangle_watertight(&mut params))
+            });
+        }
+    }
+}
+
+pub fn ray_triangle(c: &mut Criterion) {
+    ray_triangle_with(c, "ray_triangle");
+}
+
+pub fn ray_triangle_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("ray_triangle", ray_triangle_with);
+}
+
+fn ray_primitive_variants<P: RayPrimitive, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    name: &str,
+    count: usize,
+    primitive_array: &[P],
+) {
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let scene_center = Vec3A::splat(PRIMITIVE_SCENE_SIZE * 0.5);
+    let ray = random_ray_array_toward(&mut rng, 1, scene_center, 20.0, 5.0)[0];
+
+    let mut params = RayManyPrimitivesParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0, count),
+        ray,
+        primitive_array,
+    };
+
+    group.bench_function(format!("count = {count}, {name}, one ray"), |b| {
+        b.iter(|| ray_many_primitives(&mut params))
+    });
+
+    let primitive = primitive_array[0];
+    let ray_array = random_ray_array_toward(&mut rng, count, primitive.center(), 5.0, 2.0);
+
+    let mut params = ManyRaysPrimitiveParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0, count),
+        ray_array: &ray_array,
+        primitive,
+    };
+
+    group.bench_function(format!("count = {count}, {name}, one primitive"), |b| {
+        b.iter(|| many_rays_primitive(&mut params))
+    });
+}
+
+// Tests rays against spheres, planes and capsules. "One ray" is a single ray
+// against an array of primitives, and "one primitive" is an array of rays
+// against a single primitive.
+fn ray_primitive_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l1 = l1_sized_count::<(Ray3A, f32)>();
+    let l2 = l2_sized_count::<(Ray3A, f32)>();
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        ray_primitive_variants(
+            &mut group,
+            "sphere",
+            count,
+            &random_sphere_array(&mut rng, count),
+        );
+
+        ray_primitive_variants(
+            &mut group,
+            "plane",
+            count,
+            &random_plane_array(&mut rng, count),
+        );
+
+        ray_primitive_variants(
+            &mut group,
+            "capsule",
+            count,
+            &random_capsule_array(&mut rng, count),
+        );
+    }
+}
+
+pub fn ray_primitive(c: &mut Criterion) {
+    ray_primitive_with(c, "ray_primitive");
+}
+
+pub fn ray_primitive_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("ray_primitive", ray_primitive_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    geometry,
+    pin_thread,
+    aabb_overlap,
+    ray_aabb,
+    ray_triangle,
+    ray_primitive,
+    aabb_overlap_perf,
+    ray_aabb_perf,
+    ray_triangle_perf,
+    ray_primitive_perf,
+);
+
+criterion_main!(geometry);
@@ -0,0 +1,141 @@
+use bevy_transform::components::{GlobalTransform, Transform};
+use core::time::Duration;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use misc_benches::{kernels::hierarchy::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::{num::NonZero, thread, time::Instant};
+
+// Return the fastest of a few runs, for a quick estimate outside of Criterion.
+fn fastest_run(mut f: impl FnMut()) -> Duration {
+    (0..20)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+// Propagates global transforms through a forest, first on the current thread
+// and then with one rayon task per subtree on pools of 1..=N threads.
+pub fn propagate(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("propagate");
+    let mut group = c.benchmark_group("propagate");
+
+    const ROOT_COUNT: usize = 64;
+    const SUBTREE_SIZE: usize = 1024;
+    const COUNT: usize = ROOT_COUNT * SUBTREE_SIZE;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let hierarchy = random_hierarchy(&mut rng, ROOT_COUNT, SUBTREE_SIZE);
+    let dst_array = &mut CacheAlignedVec::from_elem(Transform::IDENTITY, COUNT);
+
+    let max_thread_count = thread::available_parallelism()
+        .map(NonZero::<usize>::get)
+        .unwrap_or(1);
+
+    let pools = (1..=max_thread_count)
+        .map(|thread_count| {
+            ThreadPoolBuilder::new()
+                .num_threads(thread_count)
+                .build()
+                .unwrap()
+        })
+        .collect::<Vec<ThreadPool>>();
+
+    // Criterion reports each variant separately, so print the scaling
+    // efficiency relative to the serial baseline here.
+
+    let serial = fastest_run(|| propagate_serial(black_box(dst_array), &hierarchy));
+
+    for pool in &pools {
+        let thread_count = pool.current_num_threads();
+        let parallel =
+            fastest_run(|| pool.install(|| propagate_parallel(black_box(dst_array), &hierarchy)));
+
+        let speedup = serial.as_secs_f64() / parallel.as_secs_f64();
+
+        println!(
+            "propagate: threads = {thread_count}, speedup = {speedup:.2}, efficiency = {:.0}%",
+            100.0 * speedup / thread_count as f64
+        );
+    }
+
+    group.bench_function(format!("count = {COUNT}, serial"), |b| {
+        b.iter(|| propagate_serial(dst_array, &hierarchy))
+    });
+
+    for pool in &pools {
+        group.bench_function(
+            format!(
+                "count = {COUNT}, parallel, threads = {}",
+                pool.current_num_threads()
+            ),
+            |b| b.iter(|| pool.install(|| propagate_parallel(dst_array, &hierarchy))),
+        );
+    }
+}
+
+// Computes a child's new local transform when moving it to a new parent. The
+// transform variant inverts the parent as a `Transform`, which is only exact
+// for uniform scale. The affine variant is `GlobalTransform::reparented_to`.
+pub fn reparent(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("reparent");
+    let mut group = c.benchmark_group("reparent");
+
+    let l1 = l1_sized_count::<(Transform, GlobalTransform, GlobalTransform)>();
+    let l2 = l2_sized_count::<(Transform, GlobalTransform, GlobalTransform)>();
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let child_array = (0..count)
+            .map(|_| random_uniform_transform(&mut rng))
+            .collect::<CacheAlignedVec<_>>();
+        let parent_array = (0..count)
+            .map(|_| random_uniform_transform(&mut rng))
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut params = ReparentParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Transform::IDENTITY, count),
+            child_array: &child_array,
+            parent_array: &parent_array,
+        };
+
+        group.bench_function(format!("count = {count}, transform"), |b| {
+            b.iter(|| reparent_transform(&mut params))
+        });
+
+        let global_child_array = child_array
+            .iter()
+            .copied()
+            .map(GlobalTransform::from)
+            .collect::<CacheAlignedVec<_>>();
+        let global_parent_array = parent_array
+            .iter()
+            .copied()
+            .map(GlobalTransform::from)
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut params = ReparentParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Transform::IDENTITY, count),
+            child_array: &global_child_array,
+            parent_array: &global_parent_array,
+        };
+
+        group.bench_function(format!("count = {count}, affine"), |b| {
+            b.iter(|| reparent_affine(&mut params))
+        });
+    }
+}
+
+criterion_group!(hierarchy, propagate, reparent);
+
+criterion_main!(hierarchy);
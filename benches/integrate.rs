@@ -0,0 +1,89 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::integrate::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+const DT: f32 = 1.0 / 60.0;
+
+// Steps every particle under gravity. The Verlet variants with constraints
+// link the particles into ropes of 16 and relax the links after integrating.
+// Throughput is particles per second.
+fn integrate_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    for count in [10_000, 100_000, 1_000_000] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let position_array = random_position_array(&mut rng, count);
+        let velocity_array = random_velocity_array(&mut rng, count);
+
+        let previous_array = position_array
+            .iter()
+            .zip(velocity_array.iter())
+            .map(|(&p, &v)| verlet_previous(p, v, GRAVITY, DT))
+            .collect::<CacheAlignedVec<_>>();
+
+        let constraints = rope_constraints(&position_array, 16);
+
+        for (name, f) in [
+            (
+                "euler, explicit",
+                integrate_euler_explicit as fn(&mut EulerParams),
+            ),
+            ("euler, semi-implicit", integrate_euler_semi_implicit),
+        ] {
+            let mut params = EulerParams {
+                position_array: &mut position_array.clone(),
+                velocity_array: &mut velocity_array.clone(),
+                acceleration: GRAVITY,
+                dt: DT,
+            };
+
+            group.bench_function(format!("count = {count}, {name}"), |b| {
+                b.iter(|| f(&mut params))
+            });
+        }
+
+        for iterations in [0, 1, 4] {
+            let mut params = VerletParams {
+                position_array: &mut position_array.clone(),
+                previous_array: &mut previous_array.clone(),
+                acceleration: GRAVITY,
+                dt: DT,
+            };
+
+            let name = match iterations {
+                0 => "verlet".to_string(),
+                _ => format!("verlet, constraint iterations = {iterations}"),
+            };
+
+            group.bench_function(format!("count = {count}, {name}"), |b| {
+                b.iter(|| {
+                    integrate_verlet(&mut params);
+                    relax_constraints(params.position_array, &constraints, iterations);
+                })
+            });
+        }
+    }
+}
+
+pub fn integrate(c: &mut Criterion) {
+    integrate_with(c, "integrate");
+}
+
+pub fn integrate_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("integrate", integrate_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(integrate_benches, pin_thread, integrate, integrate_perf);
+
+criterion_main!(integrate_benches);
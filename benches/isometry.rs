@@ -0,0 +1,95 @@
+use bevy_math::Isometry3d;
+use bevy_transform::components::Transform;
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::Vec3;
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::isometry::*, sysreport::FrequencyCapture, util::*};
+use rand::prelude::*;
+
+// Composes and applies scale-free transforms, as either `Transform` or
+// `Isometry3d`.
+fn isometry_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Transform, Transform, Transform)>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let transforms = [0, 1].map(|_| {
+        random_transform_array(&mut rng, COUNT)
+            .iter()
+            .map(|t| t.with_translation(rng.gen()))
+            .collect::<CacheAlignedVec<_>>()
+    });
+
+    let isometries = transforms.each_ref().map(|t| {
+        t.iter()
+            .map(isometry_from_transform)
+            .collect::<CacheAlignedVec<_>>()
+    });
+
+    let transform_dst = &mut CacheAlignedVec::from_elem(Transform::IDENTITY, COUNT);
+    let isometry_dst = &mut CacheAlignedVec::from_elem(Isometry3d::IDENTITY, COUNT);
+
+    group.bench_function(format!("count = {COUNT}, compose, transform"), |b| {
+        b.iter(|| {
+            compose_transform(&mut IsometryComposeParams {
+                dst_array: transform_dst,
+                src_array: [&transforms[0], &transforms[1]],
+            })
+        })
+    });
+
+    group.bench_function(format!("count = {COUNT}, compose, isometry"), |b| {
+        b.iter(|| {
+            compose_isometry(&mut IsometryComposeParams {
+                dst_array: isometry_dst,
+                src_array: [&isometries[0], &isometries[1]],
+            })
+        })
+    });
+
+    let src_array = random_array::<Vec3>(&mut rng, COUNT);
+    let point_dst = &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT);
+
+    group.bench_function(format!("count = {COUNT}, point, transform"), |b| {
+        b.iter(|| {
+            point_transform(&mut IsometryPointParams {
+                dst_array: point_dst,
+                src_array: &src_array,
+                transform_array: &transforms[0],
+            })
+        })
+    });
+
+    group.bench_function(format!("count = {COUNT}, point, isometry"), |b| {
+        b.iter(|| {
+            point_isometry(&mut IsometryPointParams {
+                dst_array: point_dst,
+                src_array: &src_array,
+                transform_array: &isometries[0],
+            })
+        })
+    });
+}
+
+pub fn isometry(c: &mut Criterion) {
+    isometry_with(c, "isometry");
+}
+
+pub fn isometry_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("isometry", isometry_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(isometry_benches, pin_thread, isometry, isometry_perf);
+
+criterion_main!(isometry_benches);
@@ -1,32 +1,21 @@
-use std::{f32::consts::TAU, iter::repeat_with};
-
-use criterion::{criterion_group, criterion_main, Criterion, Throughput};
-use glam::{Quat, Vec4};
-use misc_benches::util::*;
+use criterion::{
+    criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, Criterion, Throughput,
+};
+use glam::{Quat, Vec3};
+use misc_benches::{
+    kernels::{lerp::*, scalar::Scalar, smooth::*},
+    sysreport::FrequencyCapture,
+    util::*,
+};
 use rand::prelude::*;
 
-fn random_quat<R: Rng + ?Sized>(rng: &mut R) -> Quat {
-    let r0 = rng.gen_range(0.0f32..TAU);
-    let r1 = rng.gen_range(0.0f32..TAU);
-    let r2 = rng.gen_range(0.0f32..1.0f32);
-
-    let (s0, c0) = r0.sin_cos();
-    let (s1, c1) = r1.sin_cos();
-
-    let t0 = (1.0 - r2).sqrt();
-    let t1 = r2.sqrt();
-
-    Quat::from_xyzw(t0 * s0, t0 * c0, t1 * s1, t1 * c1)
-}
-
-fn random_quat_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Vec<Quat> {
-    repeat_with(|| random_quat(rng)).take(count).collect()
-}
-
 /// Return two arrays of quats, where each quat has a 50/50 chance of being
 /// a duplicate of the other array's quat. This is intended to stress the
 /// `if dot > DOT_THRESHOLD` branch in slerp.
-fn random_duplicate_quat_arrays<R: Rng + ?Sized>(rng: &mut R, count: usize) -> [Vec<Quat>; 2] {
+fn random_duplicate_quat_arrays<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> [CacheAlignedVec<Quat>; 2] {
     let mut l = random_quat_array(rng, count);
     let r = random_quat_array(rng, count);
 
@@ -41,61 +30,44 @@ fn random_duplicate_quat_arrays<R: Rng + ?Sized>(rng: &mut R, count: usize) -> [
 
 // Return an array of quats where each component is positive. This is intended
 // to avoid stressing the `if dot < 0.0` branch in slerp.
-fn random_positive_quat_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Vec<Quat> {
+fn random_positive_quat_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Quat> {
     random_quat_array(rng, count)
         .iter()
         .map(|q| Quat::from_xyzw(q.x.abs(), q.y.abs(), q.z.abs(), q.w.abs()))
         .collect()
 }
 
-fn quat_lerp(l: Quat, r: Quat, a: f32) -> Quat {
-    Quat::from_vec4(Vec4::from(l).lerp(Vec4::from(r), a))
-}
-
-fn quat_nlerp(l: Quat, r: Quat, a: f32) -> Quat {
-    l.lerp(r, a)
-}
-
-fn quat_slerp(l: Quat, r: Quat, a: f32) -> Quat {
-    l.slerp(r, a)
-}
-
-struct QuatParams<'a> {
-    dst: &'a mut [Quat],
-    src_quat: &'a [&'a [Quat]; 2],
-    src_alpha: f32,
-}
-
-fn quat_func<F>(params: &mut QuatParams, f: F)
-where
-    F: Fn(Quat, Quat, f32) -> Quat,
-{
-    for ((dst, l), r) in params
-        .dst
-        .iter_mut()
-        .zip(params.src_quat[0].iter())
-        .zip(params.src_quat[1].iter())
-    {
-        *dst = f(*l, *r, params.src_alpha);
-    }
-}
-
-#[inline(never)]
-fn quat_loop_lerp(params: &mut QuatParams) {
-    quat_func(params, quat_lerp);
-}
-
-#[inline(never)]
-fn quat_loop_nlerp(params: &mut QuatParams) {
-    quat_func(params, quat_nlerp);
-}
+fn quat_generic_variants<S: Scalar>(
+    group: &mut BenchmarkGroup<WallTime>,
+    count: usize,
+    src_quat: &[&[Quat]; 2],
+) {
+    let src_quat = src_quat.map(|s| {
+        s.iter()
+            .copied()
+            .map(S::quat_from_f32)
+            .collect::<CacheAlignedVec<_>>()
+    });
 
-#[inline(never)]
-fn quat_loop_slerp(params: &mut QuatParams) {
-    quat_func(params, quat_slerp);
+    let mut params = QuatGenericParams {
+        dst: &mut CacheAlignedVec::from_elem(S::quat_from_f32(Quat::IDENTITY), count),
+        src_quat: &[&src_quat[0], &src_quat[1]],
+        src_alpha: S::from_f32(0.5),
+    };
+
+    group.bench_function(
+        format!("count = {count}, nlerp, precision = {}", S::NAME),
+        |b| b.iter(|| quat_loop_nlerp_generic(&mut params)),
+    );
+
+    group.bench_function(
+        format!("count = {count}, slerp, precision = {}", S::NAME),
+        |b| b.iter(|| quat_loop_slerp_generic(&mut params)),
+    );
 }
 
 pub fn quat(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("quat");
     let mut group = c.benchmark_group("quat");
 
     let l1 = l1_sized_count::<(Quat, Quat, Quat)>();
@@ -107,7 +79,7 @@ pub fn quat(c: &mut Criterion) {
         let mut rng = StdRng::seed_from_u64(1234);
 
         let mut params = QuatParams {
-            dst: &mut vec![Quat::IDENTITY; count],
+            dst: &mut CacheAlignedVec::from_elem(Quat::IDENTITY, count),
             src_quat: &[
                 &random_quat_array(&mut rng, count),
                 &random_quat_array(&mut rng, count),
@@ -118,7 +90,7 @@ pub fn quat(c: &mut Criterion) {
         let src_quat_duplicates = random_duplicate_quat_arrays(&mut rng, count);
 
         let mut params_duplicates = QuatParams {
-            dst: &mut vec![Quat::IDENTITY; count],
+            dst: &mut CacheAlignedVec::from_elem(Quat::IDENTITY, count),
             src_quat: &[
                 // TODO: Is there a better way to make these Vecs into slices?
                 src_quat_duplicates[0].as_slice(),
@@ -128,7 +100,7 @@ pub fn quat(c: &mut Criterion) {
         };
 
         let mut params_positive = QuatParams {
-            dst: &mut vec![Quat::IDENTITY; count],
+            dst: &mut CacheAlignedVec::from_elem(Quat::IDENTITY, count),
             src_quat: &[
                 &random_positive_quat_array(&mut rng, count),
                 &random_positive_quat_array(&mut rng, count),
@@ -148,6 +120,20 @@ pub fn quat(c: &mut Criterion) {
             })
         });
 
+        #[cfg(misc_benches_nightly)]
+        group.bench_function(format!("count = {count}, nlerp, simd"), |b| {
+            b.iter(|| {
+                misc_benches::kernels::simd::quat_loop_nlerp_simd(&mut params);
+            })
+        });
+
+        #[cfg(target_arch = "aarch64")]
+        group.bench_function(format!("count = {count}, nlerp, neon"), |b| {
+            b.iter(|| {
+                misc_benches::kernels::neon::quat_loop_nlerp_neon(&mut params);
+            })
+        });
+
         group.bench_function(format!("count = {count}, slerp"), |b| {
             b.iter(|| {
                 quat_loop_slerp(&mut params);
@@ -165,9 +151,188 @@ pub fn quat(c: &mut Criterion) {
                 quat_loop_slerp(&mut params_positive);
             })
         });
+
+        // The f64 variants read and write twice as many bytes, so they may
+        // not fit in the same level of the cache.
+
+        quat_generic_variants::<f32>(&mut group, count, params.src_quat);
+        quat_generic_variants::<f64>(&mut group, count, params.src_quat);
+
+        #[cfg(feature = "nalgebra")]
+        {
+            use misc_benches::kernels::nalgebra_backend::*;
+            use nalgebra::UnitQuaternion;
+
+            let src_quat = params.src_quat.map(|s| {
+                s.iter()
+                    .copied()
+                    .map(unit_quat_from_glam)
+                    .collect::<CacheAlignedVec<_>>()
+            });
+
+            let mut params = UnitQuatParams {
+                dst: &mut CacheAlignedVec::from_elem(UnitQuaternion::identity(), count),
+                src_quat: &[&src_quat[0], &src_quat[1]],
+                src_alpha: 0.5,
+            };
+
+            group.bench_function(format!("count = {count}, nlerp, backend = nalgebra"), |b| {
+                b.iter(|| unit_quat_loop_nlerp(&mut params))
+            });
+
+            group.bench_function(format!("count = {count}, slerp, backend = nalgebra"), |b| {
+                b.iter(|| unit_quat_loop_slerp(&mut params))
+            });
+        }
+
+        // The wide kernels process eight quats at a time, so round down.
+
+        #[cfg(feature = "ultraviolet")]
+        {
+            use misc_benches::kernels::ultraviolet_backend::*;
+            use ultraviolet::Rotor3x8;
+
+            let src_quat = params.src_quat.map(rotor3x8_array_from_glam);
+            let wide_count = src_quat[0].len() * LANES;
+
+            group.throughput(Throughput::Elements(wide_count as u64));
+
+            let mut params = Rotor3x8Params {
+                dst: &mut CacheAlignedVec::from_elem(Rotor3x8::identity(), wide_count / LANES),
+                src_quat: &[&src_quat[0], &src_quat[1]],
+                src_alpha: 0.5,
+            };
+
+            group.bench_function(
+                format!("count = {wide_count}, nlerp, backend = ultraviolet"),
+                |b| b.iter(|| rotor3x8_loop_nlerp(&mut params)),
+            );
+
+            group.bench_function(
+                format!("count = {wide_count}, slerp, backend = ultraviolet"),
+                |b| b.iter(|| rotor3x8_loop_slerp(&mut params)),
+            );
+        }
     }
 }
 
-criterion_group!(lerp, quat);
+// Blends several weighted rotations per element. The layers are random
+// rotations near a shared base, like animation layers on the same pose.
+pub fn quat_blend(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("quat_blend");
+    let mut group = c.benchmark_group("quat_blend");
+
+    let count = l1_sized_count::<Quat>();
+
+    group.throughput(Throughput::Elements(count as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let base = random_quat_array(&mut rng, count);
+
+    for layer_count in [2, 4, 8] {
+        let layers = (0..layer_count)
+            .map(|_| {
+                base.iter()
+                    .map(|&q| q * Quat::from_scaled_axis(rng.gen::<Vec3>() * 0.6 - 0.3))
+                    .collect::<CacheAlignedVec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let src_quat = layers.iter().map(|l| l.as_slice()).collect::<Vec<_>>();
+        let src_weight = (0..layer_count).map(|_| rng.gen()).collect::<Vec<f32>>();
+
+        let mut params = QuatBlendParams {
+            dst: &mut CacheAlignedVec::from_elem(Quat::IDENTITY, count),
+            src_quat: &src_quat,
+            src_weight: &src_weight,
+        };
+
+        group.bench_function(
+            format!("count = {count}, layers = {layer_count}, slerp chain"),
+            |b| {
+                b.iter(|| {
+                    quat_blend_loop_slerp_chain(&mut params);
+                })
+            },
+        );
+
+        group.bench_function(
+            format!("count = {count}, layers = {layer_count}, nlerp"),
+            |b| {
+                b.iter(|| {
+                    quat_blend_loop_nlerp(&mut params);
+                })
+            },
+        );
+
+        group.bench_function(
+            format!("count = {count}, layers = {layer_count}, log"),
+            |b| {
+                b.iter(|| {
+                    quat_blend_loop_log(&mut params);
+                })
+            },
+        );
+    }
+}
+
+fn smooth_variants<T: Smoothable>(
+    group: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    count: usize,
+    params: &mut SmoothParams<T>,
+) {
+    group.bench_function(format!("count = {count}, {name}, lerp"), |b| {
+        b.iter(|| smooth_lerp(params, 0.1))
+    });
+
+    group.bench_function(format!("count = {count}, {name}, decay"), |b| {
+        b.iter(|| smooth_decay(params, 10.0))
+    });
+
+    group.bench_function(format!("count = {count}, {name}, damp"), |b| {
+        b.iter(|| smooth_damp(params, 0.2))
+    });
+}
+
+// Compares per-frame smoothing towards a target. Every variant runs one frame
+// per iteration over the whole array.
+pub fn smooth(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("smooth");
+    let mut group = c.benchmark_group("smooth");
+
+    let count = l1_sized_count::<(Vec3, Vec3, Vec3)>();
+
+    group.throughput(Throughput::Elements(count as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    smooth_variants(
+        &mut group,
+        "f32",
+        count,
+        &mut SmoothParams {
+            current_array: &mut random_array::<f32>(&mut rng, count),
+            velocity_array: &mut CacheAlignedVec::from_elem(0.0, count),
+            target_array: &random_array::<f32>(&mut rng, count),
+            delta_time: 1.0 / 60.0,
+        },
+    );
+
+    smooth_variants(
+        &mut group,
+        "vec3",
+        count,
+        &mut SmoothParams {
+            current_array: &mut random_array::<Vec3>(&mut rng, count),
+            velocity_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, count),
+            target_array: &random_array::<Vec3>(&mut rng, count),
+            delta_time: 1.0 / 60.0,
+        },
+    );
+}
+
+criterion_group!(lerp, quat, quat_blend, smooth);
 
 criterion_main!(lerp);
@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::{Vec3, Vec4};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::mesh::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Generates tangents for grid meshes with random heights. Throughput is
+// vertices per second.
+fn tangent_generation_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    for resolution in [16, 64, 256, 1024] {
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let mesh = random_grid_mesh(&mut rng, resolution);
+        let count = mesh.vertex_count();
+
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut params = TangentParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec4::ZERO, count),
+            bitangent_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, count),
+            mesh: &mesh,
+        };
+
+        group.bench_function(format!("vertices = {count}, lengyel"), |b| {
+            b.iter(|| tangents_lengyel(&mut params))
+        });
+
+        group.bench_function(format!("vertices = {count}, mikktspace style"), |b| {
+            b.iter(|| tangents_mikktspace_style(&mut params))
+        });
+    }
+}
+
+pub fn tangent_generation(c: &mut Criterion) {
+    tangent_generation_with(c, "tangent_generation");
+}
+
+pub fn tangent_generation_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("tangent_generation", tangent_generation_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    mesh,
+    pin_thread,
+    tangent_generation,
+    tangent_generation_perf
+);
+
+criterion_main!(mesh);
@@ -0,0 +1,144 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::{UVec2, UVec3};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{morton::*, quantize::ConvertParams},
+    sysreport::FrequencyCapture,
+    util::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Encodes random points to Morton codes and decodes them back.
+fn morton_code_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l1 = l1_sized_count::<(UVec3, u32)>();
+    let l2 = l2_sized_count::<(UVec3, u32)>();
+
+    let lut = MortonLut::new();
+
+    #[cfg(target_arch = "x86_64")]
+    let bmi2 = misc_benches::kernels::x86::Bmi2::detect();
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let points_2d = random_morton_2d_array(&mut rng, count);
+        let points_3d = random_morton_3d_array(&mut rng, count);
+
+        let mut codes = CacheAlignedVec::from_elem(0, count);
+
+        // 2D.
+
+        let mut params = ConvertParams {
+            dst_array: &mut codes,
+            src_array: &points_2d,
+        };
+
+        group.bench_function(format!("count = {count}, 2d, encode, magic"), |b| {
+            b.iter(|| morton_2d_encode_magic(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, 2d, encode, lut"), |b| {
+            b.iter(|| morton_2d_encode_lut(&mut params, &lut))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        if let Some(bmi2) = bmi2 {
+            group.bench_function(format!("count = {count}, 2d, encode, bmi2"), |b| {
+                b.iter(|| misc_benches::kernels::x86::morton_2d_encode_bmi2(bmi2, &mut params))
+            });
+        }
+
+        // Encode again in case the encode benchmarks were filtered out.
+
+        morton_2d_encode_magic(&mut params);
+
+        let mut params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(UVec2::ZERO, count),
+            src_array: &codes,
+        };
+
+        group.bench_function(format!("count = {count}, 2d, decode, magic"), |b| {
+            b.iter(|| morton_2d_decode_magic(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, 2d, decode, lut"), |b| {
+            b.iter(|| morton_2d_decode_lut(&mut params, &lut))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        if let Some(bmi2) = bmi2 {
+            group.bench_function(format!("count = {count}, 2d, decode, bmi2"), |b| {
+                b.iter(|| misc_benches::kernels::x86::morton_2d_decode_bmi2(bmi2, &mut params))
+            });
+        }
+
+        // 3D.
+
+        let mut params = ConvertParams {
+            dst_array: &mut codes,
+            src_array: &points_3d,
+        };
+
+        group.bench_function(format!("count = {count}, 3d, encode, magic"), |b| {
+            b.iter(|| morton_3d_encode_magic(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, 3d, encode, lut"), |b| {
+            b.iter(|| morton_3d_encode_lut(&mut params, &lut))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        if let Some(bmi2) = bmi2 {
+            group.bench_function(format!("count = {count}, 3d, encode, bmi2"), |b| {
+                b.iter(|| misc_benches::kernels::x86::morton_3d_encode_bmi2(bmi2, &mut params))
+            });
+        }
+
+        // Encode again in case the encode benchmarks were filtered out.
+
+        morton_3d_encode_magic(&mut params);
+
+        let mut params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(UVec3::ZERO, count),
+            src_array: &codes,
+        };
+
+        group.bench_function(format!("count = {count}, 3d, decode, magic"), |b| {
+            b.iter(|| morton_3d_decode_magic(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, 3d, decode, lut"), |b| {
+            b.iter(|| morton_3d_decode_lut(&mut params, &lut))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        if let Some(bmi2) = bmi2 {
+            group.bench_function(format!("count = {count}, 3d, decode, bmi2"), |b| {
+                b.iter(|| misc_benches::kernels::x86::morton_3d_decode_bmi2(bmi2, &mut params))
+            });
+        }
+    }
+}
+
+pub fn morton_code(c: &mut Criterion) {
+    morton_code_with(c, "morton_code");
+}
+
+pub fn morton_code_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("morton_code", morton_code_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(morton, pin_thread, morton_code, morton_code_perf);
+
+criterion_main!(morton);
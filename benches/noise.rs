@@ -0,0 +1,182 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::noise::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+const COUNT: usize = 256 * 1024;
+
+// Evaluates noise at random coordinates, so the permutation table lookups are
+// scattered. Throughput is samples per second.
+fn noise_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let table = NoiseTable::new(&mut rng);
+
+    #[cfg(feature = "noise")]
+    let (perlin, simplex) = (::noise::Perlin::new(1234), ::noise::Simplex::new(1234));
+
+    let dst_array = &mut CacheAlignedVec::from_elem(0.0f32, COUNT);
+
+    let src_array = random_noise_coords_2d(&mut rng, COUNT);
+
+    let mut params = Noise2dParams {
+        dst_array,
+        src_array: &src_array,
+    };
+
+    group.bench_function("dimensions = 2, noise = perlin, impl = scalar", |b| {
+        b.iter(|| perlin_2d(&mut params, &table))
+    });
+
+    #[cfg(feature = "noise")]
+    group.bench_function("dimensions = 2, noise = perlin, impl = noise crate", |b| {
+        b.iter(|| perlin_2d_noise_crate(&mut params, &perlin))
+    });
+
+    group.bench_function("dimensions = 2, noise = simplex, impl = scalar", |b| {
+        b.iter(|| simplex_2d(&mut params, &table))
+    });
+
+    #[cfg(feature = "noise")]
+    group.bench_function("dimensions = 2, noise = simplex, impl = noise crate", |b| {
+        b.iter(|| simplex_2d_noise_crate(&mut params, &simplex))
+    });
+
+    let src_array = random_noise_coords_3d(&mut rng, COUNT);
+
+    let mut params = Noise3dParams {
+        dst_array: params.dst_array,
+        src_array: &src_array,
+    };
+
+    group.bench_function("dimensions = 3, noise = perlin, impl = scalar", |b| {
+        b.iter(|| perlin_3d(&mut params, &table))
+    });
+
+    #[cfg(feature = "noise")]
+    group.bench_function("dimensions = 3, noise = perlin, impl = noise crate", |b| {
+        b.iter(|| perlin_3d_noise_crate(&mut params, &perlin))
+    });
+
+    group.bench_function("dimensions = 3, noise = simplex, impl = scalar", |b| {
+        b.iter(|| simplex_3d(&mut params, &table))
+    });
+
+    #[cfg(feature = "noise")]
+    group.bench_function("dimensions = 3, noise = simplex, impl = noise crate", |b| {
+        b.iter(|| simplex_3d_noise_crate(&mut params, &simplex))
+    });
+}
+
+pub fn noise(c: &mut Criterion) {
+    noise_with(c, "noise");
+}
+
+pub fn noise_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("noise", noise_with);
+}
+
+// Sums octaves of 2D Perlin noise. The loop updates the frequency and
+// amplitude per octave and normalizes at the end, while the precomputed
+// version reads them from a table.
+fn fbm_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let table = NoiseTable::new(&mut rng);
+
+    let src_array = random_noise_coords_2d(&mut rng, COUNT);
+
+    let mut params = Noise2dParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0f32, COUNT),
+        src_array: &src_array,
+    };
+
+    for octaves in [1, 2, 4, 8] {
+        let settings = FbmSettings {
+            octaves,
+            ..Default::default()
+        };
+
+        let precomputed = FbmOctaves::new(&settings);
+
+        group.bench_function(format!("octaves = {octaves}, impl = loop"), |b| {
+            b.iter(|| fbm_2d(&mut params, &table, &settings))
+        });
+
+        group.bench_function(format!("octaves = {octaves}, impl = precomputed"), |b| {
+            b.iter(|| fbm_2d_precomputed(&mut params, &table, &precomputed))
+        });
+    }
+}
+
+pub fn fbm(c: &mut Criterion) {
+    fbm_with(c, "fbm");
+}
+
+pub fn fbm_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("fbm", fbm_with);
+}
+
+// Fills a grid with white noise from hashing each cell's coordinates.
+// Throughput is cells per second.
+fn hash_noise_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let width = 1024;
+
+    group.throughput(Throughput::Elements((width * width) as u64));
+
+    let mut params = HashNoiseParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0f32, width * width),
+        width,
+        seed: 1234,
+    };
+
+    group.bench_function("hash = pcg", |b| b.iter(|| hash_noise_pcg(&mut params)));
+
+    group.bench_function("hash = wang", |b| b.iter(|| hash_noise_wang(&mut params)));
+
+    group.bench_function("hash = xxhash32", |b| {
+        b.iter(|| hash_noise_xxhash32(&mut params))
+    });
+}
+
+pub fn hash_noise(c: &mut Criterion) {
+    hash_noise_with(c, "hash_noise");
+}
+
+pub fn hash_noise_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("hash_noise", hash_noise_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    noise_benches,
+    pin_thread,
+    noise,
+    fbm,
+    hash_noise,
+    noise_perf,
+    fbm_perf,
+    hash_noise_perf
+);
+
+criterion_main!(noise_benches);
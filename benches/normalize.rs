@@ -1,52 +1,55 @@
 use bevy_math::Dir3;
 use bevy_transform::components::Transform;
-use criterion::{criterion_group, criterion_main, Criterion, Throughput};
-use glam::Quat;
-use misc_benches::util::*;
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, BatchSize, BenchmarkGroup,
+    Criterion, Throughput,
+};
+use glam::{Quat, Vec3, Vec3A};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{normalize::*, scalar::Scalar},
+    sysreport::FrequencyCapture,
+    util::*,
+};
 use rand::prelude::*;
 
-fn mul_normalize_false(l: &Transform, r: &Transform) -> Transform {
-    Transform {
-        translation: l.transform_point(r.translation),
-        rotation: l.rotation * r.rotation,
-        scale: l.scale * r.scale,
-    }
-}
-
-fn mul_normalize_true(l: &Transform, r: &Transform) -> Transform {
-    Transform {
-        translation: l.transform_point(r.translation),
-        rotation: (l.rotation * r.rotation).normalize(),
-        scale: l.scale * r.scale,
-    }
-}
-
-struct TransformNormalizeParams<'a> {
-    dst: &'a mut [Transform],
-    src: &'a [&'a [Transform]; 2],
-}
-
-fn transform_normalize_inner<F>(params: &mut TransformNormalizeParams, f: F)
-where
-    F: Fn(&Transform, &Transform) -> Transform,
-{
-    for i in 0..params.dst.len() {
-        params.dst[i] = f(&params.src[0][i], &params.src[1][i]);
-    }
-}
+fn transform_generic_variants<S: Scalar, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    count: usize,
+    src: &[&[Transform]; 2],
+) {
+    let src = src.map(|s| {
+        s.iter()
+            .map(TransformGeneric::<S>::from_transform)
+            .collect::<CacheAlignedVec<_>>()
+    });
 
-#[inline(never)]
-fn transform_normalize_false(params: &mut TransformNormalizeParams) {
-    transform_normalize_inner(params, mul_normalize_false);
-}
+    let mut params = TransformGenericParams {
+        dst: &mut CacheAlignedVec::from_elem(
+            TransformGeneric::from_transform(&Transform::IDENTITY),
+            count,
+        ),
+        src: &[&src[0], &src[1]],
+    };
 
-#[inline(never)]
-fn transform_normalize_true(params: &mut TransformNormalizeParams) {
-    transform_normalize_inner(params, mul_normalize_true);
+    group.bench_function(
+        format!(
+            "count = {count}, normalize = false, precision = {}",
+            S::NAME
+        ),
+        |b| b.iter(|| transform_generic_normalize_false(&mut params)),
+    );
+
+    group.bench_function(
+        format!("count = {count}, normalize = true, precision = {}", S::NAME),
+        |b| b.iter(|| transform_generic_normalize_true(&mut params)),
+    );
 }
 
-pub fn transform_normalize(c: &mut Criterion) {
-    let mut group = c.benchmark_group("transform_normalize");
+fn transform_normalize_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
 
     const COUNT: usize = l1_sized_count::<(Transform, Transform, Transform)>();
 
@@ -55,7 +58,7 @@ pub fn transform_normalize(c: &mut Criterion) {
     let mut rng = StdRng::seed_from_u64(1234);
 
     let mut params = TransformNormalizeParams {
-        dst: &mut vec![Transform::IDENTITY; COUNT],
+        dst: &mut CacheAlignedVec::from_elem(Transform::IDENTITY, COUNT),
         src: &[
             &random_transform_array(&mut rng, COUNT),
             &random_transform_array(&mut rng, COUNT),
@@ -73,90 +76,102 @@ pub fn transform_normalize(c: &mut Criterion) {
             transform_normalize_true(&mut params);
         })
     });
-}
 
-trait FastRenormalize {
-    fn fast_renormalize(self) -> Self;
-}
+    // Cold variants evict the source arrays from the cache before every
+    // iteration.
 
-impl FastRenormalize for Quat {
-    fn fast_renormalize(self) -> Self {
-        let length_squared = self.length_squared();
-        self * (0.5 * (3.0 - length_squared))
-    }
-}
+    let src = params.src;
 
-fn rotate_axis_normalize_false(dst: &mut Transform, src: Transform, axis: Dir3, angle: f32) {
-    *dst = src;
-    dst.rotate_axis(axis, angle);
-}
+    group.bench_function(format!("count = {COUNT}, normalize = false, cold"), |b| {
+        b.iter_batched(
+            || src.iter().for_each(|s| flush_cache(s)),
+            |_| transform_normalize_false(&mut params),
+            BatchSize::PerIteration,
+        )
+    });
 
-fn rotate_axis_normalize_true(dst: &mut Transform, src: Transform, axis: Dir3, angle: f32) {
-    *dst = src;
-    dst.rotate_axis(axis, angle);
-    dst.rotation = dst.rotation.normalize();
-}
+    group.bench_function(format!("count = {COUNT}, normalize = true, cold"), |b| {
+        b.iter_batched(
+            || src.iter().for_each(|s| flush_cache(s)),
+            |_| transform_normalize_true(&mut params),
+            BatchSize::PerIteration,
+        )
+    });
 
-fn rotate_axis_normalize_reactive(dst: &mut Transform, src: Transform, axis: Dir3, angle: f32) {
-    *dst = src;
-    dst.rotate_axis(axis, angle);
+    // The f64 variants read and write twice as many bytes, so they may not
+    // fit in the same level of the cache.
 
-    let l = dst.rotation.length_squared();
+    transform_generic_variants::<f32, _>(&mut group, COUNT, src);
+    transform_generic_variants::<f64, _>(&mut group, COUNT, src);
 
-    if (1.0 - l).abs() > 0.0001 {
-        dst.rotation = dst.rotation / l.sqrt();
-    }
-}
+    #[cfg(feature = "nalgebra")]
+    {
+        use misc_benches::kernels::nalgebra_backend::*;
+        use nalgebra::Isometry3;
 
-fn rotate_axis_normalize_fast(dst: &mut Transform, src: Transform, axis: Dir3, angle: f32) {
-    *dst = src;
-    dst.rotate_axis(axis, angle);
-    dst.rotation = dst.rotation.fast_renormalize();
-}
+        let isometry_src = src.map(|s| {
+            s.iter()
+                .map(isometry_from_transform)
+                .collect::<CacheAlignedVec<_>>()
+        });
 
-struct RotateAxisParams<'a> {
-    dst_array: &'a mut [Transform],
-    src_array: &'a [Transform],
-    axis_array: &'a [Dir3],
-    angle_array: &'a [f32],
-}
+        let mut params = IsometryNormalizeParams {
+            dst: &mut CacheAlignedVec::from_elem(Isometry3::identity(), COUNT),
+            src: &[&isometry_src[0], &isometry_src[1]],
+        };
+
+        group.bench_function(
+            format!("count = {COUNT}, normalize = false, backend = nalgebra"),
+            |b| b.iter(|| isometry_normalize_false(&mut params)),
+        );
 
-fn rotate_axis_normalize_inner<F>(params: &mut RotateAxisParams, f: F)
-where
-    F: Fn(&mut Transform, Transform, Dir3, f32),
-{
-    for i in 0..params.dst_array.len() {
-        f(
-            &mut params.dst_array[i],
-            params.src_array[i],
-            params.axis_array[i],
-            params.angle_array[i],
+        group.bench_function(
+            format!("count = {COUNT}, normalize = true, backend = nalgebra"),
+            |b| b.iter(|| isometry_normalize_true(&mut params)),
         );
     }
-}
 
-#[inline(never)]
-fn rotate_axis_normalize_false_outer(params: &mut RotateAxisParams) {
-    rotate_axis_normalize_inner(params, rotate_axis_normalize_false);
-}
+    // The wide kernels process eight transforms at a time, so round down.
+
+    #[cfg(feature = "ultraviolet")]
+    {
+        use misc_benches::kernels::ultraviolet_backend::*;
+        use ultraviolet::Isometry3x8;
+
+        let wide_src = src.map(isometry3x8_array_from_transforms);
+        let wide_count = wide_src[0].len() * LANES;
+
+        group.throughput(Throughput::Elements(wide_count as u64));
+
+        let mut params = Isometry3x8NormalizeParams {
+            dst: &mut CacheAlignedVec::from_elem(Isometry3x8::identity(), wide_count / LANES),
+            src: &[&wide_src[0], &wide_src[1]],
+        };
+
+        group.bench_function(
+            format!("count = {wide_count}, normalize = false, backend = ultraviolet"),
+            |b| b.iter(|| isometry3x8_normalize_false(&mut params)),
+        );
 
-#[inline(never)]
-fn rotate_axis_normalize_true_outer(params: &mut RotateAxisParams) {
-    rotate_axis_normalize_inner(params, rotate_axis_normalize_true);
+        group.bench_function(
+            format!("count = {wide_count}, normalize = true, backend = ultraviolet"),
+            |b| b.iter(|| isometry3x8_normalize_true(&mut params)),
+        );
+    }
 }
 
-#[inline(never)]
-fn rotate_axis_normalize_reactive_outer(params: &mut RotateAxisParams) {
-    rotate_axis_normalize_inner(params, rotate_axis_normalize_reactive);
+pub fn transform_normalize(c: &mut Criterion) {
+    transform_normalize_with(c, "transform_normalize");
 }
 
-#[inline(never)]
-fn rotate_axis_normalize_fast_outer(params: &mut RotateAxisParams) {
-    rotate_axis_normalize_inner(params, rotate_axis_normalize_fast);
+pub fn transform_normalize_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("transform_normalize", transform_normalize_with);
 }
 
-pub fn rotate_axis_normalize(c: &mut Criterion) {
-    let mut group = c.benchmark_group("rotate_axis_normalize");
+fn rotate_axis_normalize_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
 
     const COUNT: usize = l1_sized_count::<(Transform, Transform, Dir3, f32)>();
 
@@ -165,7 +180,7 @@ pub fn rotate_axis_normalize(c: &mut Criterion) {
     let mut rng = StdRng::seed_from_u64(1234);
 
     let mut params = RotateAxisParams {
-        dst_array: &mut vec![Transform::IDENTITY; COUNT],
+        dst_array: &mut CacheAlignedVec::from_elem(Transform::IDENTITY, COUNT),
         src_array: &random_transform_array(&mut rng, COUNT),
         axis_array: &random_array(&mut rng, COUNT),
         angle_array: &random_array(&mut rng, COUNT),
@@ -196,64 +211,18 @@ pub fn rotate_axis_normalize(c: &mut Criterion) {
     });
 }
 
-fn single_normalize_false(dst: &mut Transform, src: Transform) {
-    dst.rotation = src.rotation;
-}
-
-fn single_normalize_true(dst: &mut Transform, src: Transform) {
-    dst.rotation = src.rotation.normalize();
-}
-
-fn single_normalize_reactive(dst: &mut Transform, src: Transform) {
-    let l = src.rotation.length_squared();
-
-    if (1.0 - l).abs() > 0.0001 {
-        dst.rotation = src.rotation / l.sqrt();
-    } else {
-        dst.rotation = src.rotation;
-    }
-}
-
-fn single_normalize_fast(dst: &mut Transform, src: Transform) {
-    dst.rotation = src.rotation.fast_renormalize();
-}
-
-struct SingleNormalizeParams<'a> {
-    dst_array: &'a mut [Transform],
-    src_array: &'a [Transform],
-}
-
-fn single_normalize_inner<F>(params: &mut SingleNormalizeParams, f: F)
-where
-    F: Fn(&mut Transform, Transform),
-{
-    for i in 0..params.dst_array.len() {
-        f(&mut params.dst_array[i], params.src_array[i]);
-    }
-}
-
-#[inline(never)]
-fn single_normalize_false_outer(params: &mut SingleNormalizeParams) {
-    single_normalize_inner(params, single_normalize_false);
-}
-
-#[inline(never)]
-fn single_normalize_true_outer(params: &mut SingleNormalizeParams) {
-    single_normalize_inner(params, single_normalize_true);
-}
-
-#[inline(never)]
-fn single_normalize_reactive_outer(params: &mut SingleNormalizeParams) {
-    single_normalize_inner(params, single_normalize_reactive);
+pub fn rotate_axis_normalize(c: &mut Criterion) {
+    rotate_axis_normalize_with(c, "rotate_axis_normalize");
 }
 
-#[inline(never)]
-fn single_normalize_fast_outer(params: &mut SingleNormalizeParams) {
-    single_normalize_inner(params, single_normalize_fast);
+pub fn rotate_axis_normalize_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("rotate_axis_normalize", rotate_axis_normalize_with);
 }
 
-pub fn single_normalize(c: &mut Criterion) {
-    let mut group = c.benchmark_group("single_normalize");
+fn single_normalize_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
 
     const COUNT: usize = l1_sized_count::<(Transform, Transform)>();
 
@@ -262,7 +231,7 @@ pub fn single_normalize(c: &mut Criterion) {
     let mut rng = StdRng::seed_from_u64(1234);
 
     let mut params = SingleNormalizeParams {
-        dst_array: &mut vec![Transform::IDENTITY; COUNT],
+        dst_array: &mut CacheAlignedVec::from_elem(Transform::IDENTITY, COUNT),
         src_array: &random_transform_array(&mut rng, COUNT),
     };
 
@@ -291,11 +260,220 @@ pub fn single_normalize(c: &mut Criterion) {
     });
 }
 
+pub fn single_normalize(c: &mut Criterion) {
+    single_normalize_with(c, "single_normalize");
+}
+
+pub fn single_normalize_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("single_normalize", single_normalize_with);
+}
+
+fn compose_normalize_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = 1024 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let rotations = random_array::<Quat>(&mut rng, COUNT);
+
+    let strategies = [
+        ("false", compose_normalize_false as fn(&[Quat]) -> Quat),
+        ("true", compose_normalize_true),
+        ("reactive", compose_normalize_reactive),
+        ("fast", compose_normalize_fast),
+    ];
+
+    // Report how far each strategy drifts from unit length as the chain gets
+    // longer. The timings only tell half the story.
+
+    for (name, f) in strategies {
+        let deviations = (2..=COUNT.ilog10())
+            .map(|exponent| {
+                let n = 10usize.pow(exponent);
+                format!("{n} = {:.2e}", (1.0 - f(&rotations[..n]).length()).abs())
+            })
+            .collect::<Vec<_>>();
+
+        println!(
+            "{group_name}: normalize = {name}, deviation after {}",
+            deviations.join(", ")
+        );
+    }
+
+    for (name, f) in strategies {
+        group.bench_function(format!("count = {COUNT}, normalize = {name}"), |b| {
+            b.iter(|| f(&rotations))
+        });
+    }
+
+    #[cfg(feature = "nalgebra")]
+    {
+        use misc_benches::kernels::nalgebra_backend::*;
+        use nalgebra::UnitQuaternion;
+
+        let rotations = rotations
+            .iter()
+            .copied()
+            .map(unit_quat_from_glam)
+            .collect::<CacheAlignedVec<_>>();
+
+        let strategies = [
+            (
+                "false",
+                unit_quat_compose_normalize_false
+                    as fn(&[UnitQuaternion<f32>]) -> UnitQuaternion<f32>,
+            ),
+            ("true", unit_quat_compose_normalize_true),
+        ];
+
+        for (name, f) in strategies {
+            group.bench_function(
+                format!("count = {COUNT}, normalize = {name}, backend = nalgebra"),
+                |b| b.iter(|| f(&rotations)),
+            );
+        }
+    }
+}
+
+pub fn compose_normalize(c: &mut Criterion) {
+    compose_normalize_with(c, "compose_normalize");
+}
+
+pub fn compose_normalize_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("compose_normalize", compose_normalize_with);
+}
+
+// The same workload as `transform_normalize`, comparing the array of
+// `Transform` layout against split arrays of translation, rotation and scale,
+// split arrays with translation and scale padded to `Vec3A`, and a single
+// array with each transform interleaved as three `Vec4`s.
+fn transform_layout_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l1 = l1_sized_count::<(Transform, Transform, Transform)>();
+    let l2 = l2_sized_count::<(Transform, Transform, Transform)>();
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src = [
+            random_transform_array(&mut rng, count),
+            random_transform_array(&mut rng, count),
+        ];
+
+        let mut params = TransformNormalizeParams {
+            dst: &mut CacheAlignedVec::from_elem(Transform::IDENTITY, count),
+            src: &[&src[0], &src[1]],
+        };
+
+        let identity = CacheAlignedVec::from_elem(Transform::IDENTITY, count);
+
+        let split_src = src
+            .each_ref()
+            .map(|s| TransformSoa::<Vec3>::from_transforms(s));
+        let mut split_dst = TransformSoa::<Vec3>::from_transforms(&identity);
+
+        let padded_src = src
+            .each_ref()
+            .map(|s| TransformSoa::<Vec3A>::from_transforms(s));
+        let mut padded_dst = TransformSoa::<Vec3A>::from_transforms(&identity);
+
+        let interleaved_src = src.each_ref().map(|s| {
+            s.iter()
+                .map(interleave_transform)
+                .collect::<CacheAlignedVec<_>>()
+        });
+        let mut interleaved_dst = identity
+            .iter()
+            .map(interleave_transform)
+            .collect::<CacheAlignedVec<_>>();
+
+        group.bench_function(format!("count = {count}, aos, normalize = false"), |b| {
+            b.iter(|| transform_normalize_false(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, aos, normalize = true"), |b| {
+            b.iter(|| transform_normalize_true(&mut params))
+        });
+
+        let mut split_params = TransformSoaParams {
+            dst: split_dst.as_slices_mut(),
+            src: split_src.each_ref().map(TransformSoa::as_slices),
+        };
+
+        group.bench_function(format!("count = {count}, split, normalize = false"), |b| {
+            b.iter(|| transform_split_normalize_false(&mut split_params))
+        });
+
+        group.bench_function(format!("count = {count}, split, normalize = true"), |b| {
+            b.iter(|| transform_split_normalize_true(&mut split_params))
+        });
+
+        let mut padded_params = TransformSoaParams {
+            dst: padded_dst.as_slices_mut(),
+            src: padded_src.each_ref().map(TransformSoa::as_slices),
+        };
+
+        group.bench_function(format!("count = {count}, padded, normalize = false"), |b| {
+            b.iter(|| transform_padded_normalize_false(&mut padded_params))
+        });
+
+        group.bench_function(format!("count = {count}, padded, normalize = true"), |b| {
+            b.iter(|| transform_padded_normalize_true(&mut padded_params))
+        });
+
+        let mut interleaved_params = TransformInterleavedParams {
+            dst: &mut interleaved_dst,
+            src: [&interleaved_src[0], &interleaved_src[1]],
+        };
+
+        group.bench_function(
+            format!("count = {count}, interleaved, normalize = false"),
+            |b| b.iter(|| transform_interleaved_normalize_false(&mut interleaved_params)),
+        );
+
+        group.bench_function(
+            format!("count = {count}, interleaved, normalize = true"),
+            |b| b.iter(|| transform_interleaved_normalize_true(&mut interleaved_params)),
+        );
+    }
+}
+
+pub fn transform_layout(c: &mut Criterion) {
+    transform_layout_with(c, "transform_layout");
+}
+
+pub fn transform_layout_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("transform_layout", transform_layout_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
 criterion_group!(
     normalize,
+    pin_thread,
     transform_normalize,
     rotate_axis_normalize,
     single_normalize,
+    compose_normalize,
+    transform_layout,
+    transform_normalize_perf,
+    rotate_axis_normalize_perf,
+    single_normalize_perf,
+    compose_normalize_perf,
+    transform_layout_perf,
 );
 
 criterion_main!(normalize);
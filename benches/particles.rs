@@ -0,0 +1,122 @@
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, BatchSize, Criterion, Throughput,
+};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::particles::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Integrates each particle's position and velocity, ages it and fades it out.
+// Every iteration advances the same particles another step, and dead particles
+// are updated too. Throughput is particles per second.
+fn particle_update_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let settings = ParticleSettings::default();
+
+    for count in [100_000, 1_000_000] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let mut aos = random_particle_array(&mut rng, count);
+        let soa = ParticleSoa::from_particles(&aos);
+
+        // Each variant starts from the same particles.
+
+        let mut params = ParticleParams {
+            particles: aos.as_mut_slice(),
+            settings: &settings,
+        };
+
+        group.bench_function(format!("count = {count}, aos"), |b| {
+            b.iter(|| particles_update_aos(&mut params))
+        });
+
+        let soa_variants = [
+            (
+                "soa",
+                particles_update_soa as fn(&mut ParticleParams<ParticleSoa>),
+            ),
+            ("soa, x4", particles_update_soa_x4),
+        ];
+
+        for (name, f) in soa_variants {
+            let mut params = ParticleParams {
+                particles: &mut soa.clone(),
+                settings: &settings,
+            };
+
+            group.bench_function(format!("count = {count}, {name}"), |b| {
+                b.iter(|| f(&mut params))
+            });
+        }
+    }
+}
+
+pub fn particle_update(c: &mut Criterion) {
+    particle_update_with(c, "particle_update");
+}
+
+pub fn particle_update_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("particle_update", particle_update_with);
+}
+
+// Removes dead particles, where a fifth are dead and scattered at random. The
+// particles are copied before each iteration, and the copy isn't timed.
+// Throughput is particles per second, dead or alive.
+fn particle_compact_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    for count in [100_000, 1_000_000] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let aos = random_particle_array(&mut rng, count);
+        let soa = ParticleSoa::from_particles(&aos);
+
+        group.bench_function(format!("count = {count}, aos"), |b| {
+            b.iter_batched(
+                || aos.clone(),
+                |mut aos| particles_compact_aos(&mut aos),
+                BatchSize::LargeInput,
+            )
+        });
+
+        group.bench_function(format!("count = {count}, soa"), |b| {
+            b.iter_batched(
+                || soa.clone(),
+                |mut soa| particles_compact_soa(&mut soa),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+pub fn particle_compact(c: &mut Criterion) {
+    particle_compact_with(c, "particle_compact");
+}
+
+pub fn particle_compact_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("particle_compact", particle_compact_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    particles,
+    pin_thread,
+    particle_update,
+    particle_compact,
+    particle_update_perf,
+    particle_compact_perf
+);
+
+criterion_main!(particles);
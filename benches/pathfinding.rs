@@ -0,0 +1,161 @@
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, Criterion, SamplingMode, Throughput,
+};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::pathfinding::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+const COUNT: usize = 64;
+
+// Finds paths between random cells on 256x256 grids with more or fewer
+// obstacles. Throughput is paths per second. The expansions per path are
+// printed first, since they differ a lot between variants, and multiplying
+// gives expansions per second. On ties, the buckets pop the latest cell first,
+// which tends to follow one path towards the goal, while the heap goes by cell
+// index. So the bucket version can expand fewer cells for the same costs.
+fn astar_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    for coverage in [0.1, 0.3] {
+        let grid = random_grid(&mut rng, 256, 256, coverage);
+        let queries = random_path_queries(&mut rng, &grid, COUNT);
+
+        let mut params = PathParams {
+            dst_array: &mut vec![None; COUNT],
+            queries: &queries,
+            grid: &grid,
+        };
+
+        let mut heap_scratch = PathScratch::<BinaryHeapOpenList>::new(&grid);
+        let mut bucket_scratch = PathScratch::<BucketOpenList>::new(&grid);
+
+        for (name, expansions) in [
+            ("a* binary heap", astar(&mut params, &mut heap_scratch)),
+            ("a* bucket", astar(&mut params, &mut bucket_scratch)),
+            ("jps", jump_point_search(&mut params, &mut heap_scratch)),
+        ] {
+            println!(
+                "coverage = {coverage}, impl = {name}: {} expansions per path",
+                expansions / COUNT
+            );
+        }
+
+        group.bench_function(
+            format!("coverage = {coverage}, impl = a* binary heap"),
+            |b| b.iter(|| astar(&mut params, &mut heap_scratch)),
+        );
+
+        group.bench_function(format!("coverage = {coverage}, impl = a* bucket"), |b| {
+            b.iter(|| astar(&mut params, &mut bucket_scratch))
+        });
+
+        group.bench_function(format!("coverage = {coverage}, impl = jps"), |b| {
+            b.iter(|| jump_point_search(&mut params, &mut heap_scratch))
+        });
+    }
+}
+
+pub fn astar_grid(c: &mut Criterion) {
+    astar_with(c, "astar_grid");
+}
+
+pub fn astar_grid_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("astar_grid", astar_with);
+}
+
+// Finds the triangle under random points on navmeshes of different sizes,
+// with a fifth of the mesh missing. The indices are built outside the loop.
+// Throughput is points per second.
+fn navmesh_point_location_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const POINT_COUNT: usize = 4 * 1024;
+
+    // Brute force is still slow on the middle mesh.
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(POINT_COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    for size in [16, 64, 256] {
+        let mesh = random_navmesh(&mut rng, size, size, 0.2);
+        let point_array = random_navmesh_points(&mut rng, &mesh, POINT_COUNT);
+        let dst_array = &mut vec![None; POINT_COUNT];
+
+        let triangles = mesh.triangles.len();
+
+        // Too slow to be worth measuring on the biggest mesh.
+        if size <= 64 {
+            group.bench_function(
+                format!("triangles = {triangles}, impl = brute force"),
+                |b| {
+                    let mut params = NavMeshLocateParams {
+                        dst_array,
+                        point_array: &point_array,
+                        mesh: &mesh,
+                        locator: &BruteForceLocator::build(&mesh),
+                    };
+
+                    b.iter(|| navmesh_locate(&mut params))
+                },
+            );
+        }
+
+        group.bench_function(format!("triangles = {triangles}, impl = bvh"), |b| {
+            let mut params = NavMeshLocateParams {
+                dst_array,
+                point_array: &point_array,
+                mesh: &mesh,
+                locator: &BvhLocator::build(&mesh),
+            };
+
+            b.iter(|| navmesh_locate(&mut params))
+        });
+
+        group.bench_function(format!("triangles = {triangles}, impl = grid"), |b| {
+            let mut params = NavMeshLocateParams {
+                dst_array,
+                point_array: &point_array,
+                mesh: &mesh,
+                locator: &GridLocator::build(&mesh),
+            };
+
+            b.iter(|| navmesh_locate(&mut params))
+        });
+    }
+}
+
+pub fn navmesh_point_location(c: &mut Criterion) {
+    navmesh_point_location_with(c, "navmesh_point_location");
+}
+
+pub fn navmesh_point_location_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("navmesh_point_location", navmesh_point_location_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    pathfinding,
+    pin_thread,
+    astar_grid,
+    navmesh_point_location,
+    astar_grid_perf,
+    navmesh_point_location_perf
+);
+
+criterion_main!(pathfinding);
@@ -0,0 +1,112 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::Vec3;
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::point::*, sysreport::FrequencyCapture, util::*};
+use rand::prelude::*;
+
+// Transforms points by a single transform, either directly or through an
+// `Affine3A` or `Mat4` built from it. The SIMD variant is only available with
+// `--cfg misc_benches_nightly`.
+fn transform_point_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Vec3, Vec3)>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let mut params = TransformPointParams {
+        dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT),
+        src_array: &random_array(&mut rng, COUNT),
+        transform: random_transform_array(&mut rng, 1)[0],
+    };
+
+    group.bench_function(format!("count = {COUNT}, scalar"), |b| {
+        b.iter(|| transform_point_scalar(&mut params))
+    });
+
+    group.bench_function(format!("count = {COUNT}, affine3a"), |b| {
+        b.iter(|| transform_point_affine3a(&mut params))
+    });
+
+    group.bench_function(format!("count = {COUNT}, mat4"), |b| {
+        b.iter(|| transform_point_mat4(&mut params))
+    });
+
+    #[cfg(misc_benches_nightly)]
+    group.bench_function(format!("count = {COUNT}, simd"), |b| {
+        b.iter(|| misc_benches::kernels::simd::transform_point_simd(&mut params))
+    });
+}
+
+pub fn transform_point(c: &mut Criterion) {
+    transform_point_with(c, "transform_point");
+}
+
+pub fn transform_point_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("transform_point", transform_point_with);
+}
+
+// Rotates points by either a quat or a matrix converted from the quat. The
+// conversion is amortized over more points as the ratio increases, so the
+// matrix should win past some crossover.
+fn rotate_point_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = 16 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let src_array = random_array::<Vec3>(&mut rng, COUNT);
+    let rotation_array = random_quat_array(&mut rng, COUNT);
+
+    for points_per_rotation in [1, 2, 4, 8, 16, 64, 256] {
+        let mut params = RotatePointParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT),
+            src_array: &src_array,
+            rotation_array: &rotation_array[..(COUNT / points_per_rotation)],
+            points_per_rotation,
+        };
+
+        group.bench_function(
+            format!("count = {COUNT}, points per rotation = {points_per_rotation}, quat"),
+            |b| b.iter(|| rotate_point_quat(&mut params)),
+        );
+
+        group.bench_function(
+            format!("count = {COUNT}, points per rotation = {points_per_rotation}, mat3"),
+            |b| b.iter(|| rotate_point_mat3(&mut params)),
+        );
+    }
+}
+
+pub fn rotate_point(c: &mut Criterion) {
+    rotate_point_with(c, "rotate_point");
+}
+
+pub fn rotate_point_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("rotate_point", rotate_point_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    point,
+    pin_thread,
+    transform_point,
+    rotate_point,
+    transform_point_perf,
+    rotate_point_perf,
+);
+
+criterion_main!(point);
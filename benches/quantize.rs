@@ -0,0 +1,259 @@
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, BenchmarkGroup, Criterion,
+    Throughput,
+};
+use glam::{Quat, Vec3};
+use half::f16;
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::quantize::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Benchmarks decoding and encoding separately, and reports the round trip
+// error with the decode.
+fn round_trip_variant<T: Copy, E: Copy + Default, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    group_name: &str,
+    name: &str,
+    src_array: &[T],
+    encode: fn(&mut ConvertParams<T, E>),
+    decode: fn(&mut ConvertParams<E, T>),
+    error: fn(&[T], &[T]) -> f32,
+) {
+    let count = src_array.len();
+
+    let mut encode_params = ConvertParams {
+        dst_array: &mut CacheAlignedVec::from_elem(E::default(), count),
+        src_array,
+    };
+
+    encode(&mut encode_params);
+
+    let mut decode_params = ConvertParams {
+        dst_array: &mut CacheAlignedVec::from_elem(src_array[0], count),
+        src_array: encode_params.dst_array,
+    };
+
+    decode(&mut decode_params);
+
+    let max_error = error(decode_params.dst_array, src_array);
+
+    bench_function_with_metric(
+        group,
+        group_name,
+        &format!("count = {count}, {name}, decode"),
+        format_args!("max error = {max_error:e} radians"),
+        || decode(&mut decode_params),
+    );
+
+    group.bench_function(format!("count = {count}, {name}, encode"), |b| {
+        b.iter(|| encode(&mut encode_params))
+    });
+}
+
+// Encodes and decodes quats in the compressed formats used for networking and
+// animation storage.
+fn quat_compress_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Quat, HalfQuat)>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let src_array = random_quat_array(&mut rng, COUNT);
+
+    round_trip_variant(
+        &mut group,
+        group_name,
+        "smallest three",
+        &src_array,
+        quat_encode_smallest_three,
+        quat_decode_smallest_three,
+        quat_max_error,
+    );
+
+    round_trip_variant(
+        &mut group,
+        group_name,
+        "half",
+        &src_array,
+        quat_encode_half,
+        quat_decode_half,
+        quat_max_error,
+    );
+
+    round_trip_variant(
+        &mut group,
+        group_name,
+        "half, normalize",
+        &src_array,
+        quat_encode_half,
+        quat_decode_half_normalize,
+        quat_max_error,
+    );
+}
+
+pub fn quat_compress(c: &mut Criterion) {
+    quat_compress_with(c, "quat_compress");
+}
+
+pub fn quat_compress_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("quat_compress", quat_compress_with);
+}
+
+// Encodes and decodes unit normals, as a mesh pipeline does when quantizing
+// vertex data. The copy is a baseline for the bandwidth of the raw normals.
+fn normal_encode_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l1 = l1_sized_count::<(Vec3, Vec3)>();
+    let l2 = l2_sized_count::<(Vec3, Vec3)>();
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src_array = random_array::<Vec3>(&mut rng, count)
+            .iter()
+            .map(|v| ((*v * 2.0) - 1.0).normalize())
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut copy_params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, count),
+            src_array: &src_array,
+        };
+
+        group.bench_function(format!("count = {count}, copy"), |b| {
+            b.iter(|| normal_copy(&mut copy_params))
+        });
+
+        round_trip_variant(
+            &mut group,
+            group_name,
+            "oct16",
+            &src_array,
+            normal_encode_oct16,
+            normal_decode_oct16,
+            normal_max_error,
+        );
+
+        round_trip_variant(
+            &mut group,
+            group_name,
+            "oct32",
+            &src_array,
+            normal_encode_oct32,
+            normal_decode_oct32,
+            normal_max_error,
+        );
+    }
+}
+
+pub fn normal_encode(c: &mut Criterion) {
+    normal_encode_with(c, "normal_encode");
+}
+
+pub fn normal_encode_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("normal_encode", normal_encode_with);
+}
+
+// Converts vertex buffer sized arrays between `f32` and `f16`.
+fn half_float_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l2 = l2_sized_count::<(f32, f16)>();
+    let ram = ram_sized_count::<(f32, f16)>();
+
+    for count in [l2, ram] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let f32_array = random_array::<f32>(&mut rng, count)
+            .iter()
+            .map(|x| (x - 0.5) * 1000.0)
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut encode_params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(f16::ZERO, count),
+            src_array: &f32_array,
+        };
+
+        group.bench_function(format!("count = {count}, encode, software"), |b| {
+            b.iter(|| f16_from_f32_software(&mut encode_params))
+        });
+
+        group.bench_function(format!("count = {count}, encode, half"), |b| {
+            b.iter(|| f16_from_f32(&mut encode_params))
+        });
+
+        group.bench_function(format!("count = {count}, encode, half slice"), |b| {
+            b.iter(|| f16_from_f32_slice(&mut encode_params))
+        });
+
+        #[cfg(all(feature = "f16c", target_arch = "x86_64"))]
+        if let Some(f16c) = misc_benches::kernels::x86::F16c::detect() {
+            group.bench_function(format!("count = {count}, encode, f16c"), |b| {
+                b.iter(|| misc_benches::kernels::x86::f16_from_f32_f16c(f16c, &mut encode_params))
+            });
+        }
+
+        let mut decode_params = ConvertParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0.0f32, count),
+            src_array: encode_params.dst_array,
+        };
+
+        group.bench_function(format!("count = {count}, decode, software"), |b| {
+            b.iter(|| f32_from_f16_software(&mut decode_params))
+        });
+
+        group.bench_function(format!("count = {count}, decode, half"), |b| {
+            b.iter(|| f32_from_f16(&mut decode_params))
+        });
+
+        group.bench_function(format!("count = {count}, decode, half slice"), |b| {
+            b.iter(|| f32_from_f16_slice(&mut decode_params))
+        });
+
+        #[cfg(all(feature = "f16c", target_arch = "x86_64"))]
+        if let Some(f16c) = misc_benches::kernels::x86::F16c::detect() {
+            group.bench_function(format!("count = {count}, decode, f16c"), |b| {
+                b.iter(|| misc_benches::kernels::x86::f32_from_f16_f16c(f16c, &mut decode_params))
+            });
+        }
+    }
+}
+
+pub fn half_float(c: &mut Criterion) {
+    half_float_with(c, "half_float");
+}
+
+pub fn half_float_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("half_float", half_float_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    quantize,
+    pin_thread,
+    quat_compress,
+    normal_encode,
+    half_float,
+    quat_compress_perf,
+    normal_encode_perf,
+    half_float_perf,
+);
+
+criterion_main!(quantize);
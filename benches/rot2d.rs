@@ -0,0 +1,105 @@
+use bevy_math::Rot2;
+use core::f32::consts::TAU;
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::{Mat2, Quat, Vec2};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::rot2d::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Rotates 2D points by the same random angles stored as each rotation type.
+fn rot2d_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = l1_sized_count::<(Vec2, Vec2, Quat)>();
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let dst_array = &mut CacheAlignedVec::from_elem(Vec2::ZERO, COUNT);
+    let src_array = &random_array::<Vec2>(&mut rng, COUNT);
+
+    let angle_array = random_array::<f32>(&mut rng, COUNT)
+        .iter()
+        .map(|a| a * TAU)
+        .collect::<CacheAlignedVec<_>>();
+
+    let rot2_array = angle_array
+        .iter()
+        .map(|&a| Rot2::radians(a))
+        .collect::<CacheAlignedVec<_>>();
+
+    group.bench_function(format!("count = {COUNT}, rot2"), |b| {
+        b.iter(|| {
+            rot2d_rot2(&mut Rot2dParams {
+                dst_array,
+                src_array,
+                rotation_array: &rot2_array,
+            })
+        })
+    });
+
+    let quat_array = angle_array
+        .iter()
+        .map(|&a| Quat::from_rotation_z(a))
+        .collect::<CacheAlignedVec<_>>();
+
+    group.bench_function(format!("count = {COUNT}, quat"), |b| {
+        b.iter(|| {
+            rot2d_quat(&mut Rot2dParams {
+                dst_array,
+                src_array,
+                rotation_array: &quat_array,
+            })
+        })
+    });
+
+    let complex_array = angle_array
+        .iter()
+        .map(|&a| Vec2::from_angle(a))
+        .collect::<CacheAlignedVec<_>>();
+
+    group.bench_function(format!("count = {COUNT}, complex"), |b| {
+        b.iter(|| {
+            rot2d_complex(&mut Rot2dParams {
+                dst_array,
+                src_array,
+                rotation_array: &complex_array,
+            })
+        })
+    });
+
+    let mat2_array = angle_array
+        .iter()
+        .map(|&a| Mat2::from_angle(a))
+        .collect::<CacheAlignedVec<_>>();
+
+    group.bench_function(format!("count = {COUNT}, mat2"), |b| {
+        b.iter(|| {
+            rot2d_mat2(&mut Rot2dParams {
+                dst_array,
+                src_array,
+                rotation_array: &mat2_array,
+            })
+        })
+    });
+}
+
+pub fn rot2d(c: &mut Criterion) {
+    rot2d_with(c, "rot2d");
+}
+
+pub fn rot2d_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("rot2d", rot2d_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(rot2d_benches, pin_thread, rot2d, rot2d_perf);
+
+criterion_main!(rot2d_benches);
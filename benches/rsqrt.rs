@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::rsqrt::*, sysreport::FrequencyCapture, util::*};
+use rand::prelude::*;
+
+// Reciprocal square roots over arrays of positive values, in L1 and L2 sized
+// arrays. The SSE variant is only available on x86_64.
+fn rsqrt_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let l1 = l1_sized_count::<(f32, f32)>();
+    let l2 = l2_sized_count::<(f32, f32)>();
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let mut params = RsqrtParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0.0f32, count),
+            src_array: &random_array::<f32>(&mut rng, count)
+                .iter()
+                .map(|x| x + 0.01)
+                .collect::<CacheAlignedVec<_>>(),
+        };
+
+        group.bench_function(format!("count = {count}, sqrt recip"), |b| {
+            b.iter(|| rsqrt_sqrt_recip(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, div sqrt"), |b| {
+            b.iter(|| rsqrt_div_sqrt(&mut params))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        group.bench_function(format!("count = {count}, sse newton"), |b| {
+            b.iter(|| misc_benches::kernels::x86::rsqrt_sse_newton(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, bit hack"), |b| {
+            b.iter(|| rsqrt_bit_hack(&mut params))
+        });
+    }
+}
+
+pub fn rsqrt(c: &mut Criterion) {
+    rsqrt_with(c, "rsqrt");
+}
+
+pub fn rsqrt_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("rsqrt", rsqrt_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(rsqrt_benches, pin_thread, rsqrt, rsqrt_perf);
+
+criterion_main!(rsqrt_benches);
@@ -0,0 +1,125 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+use glam::{Quat, Vec2, Vec3};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{random::*, sampling::*},
+    sysreport::FrequencyCapture,
+    util::*,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::{Normal, UnitSphere};
+
+// Fills a 100 by 100 square with Poisson disk samples, where halving the
+// radius roughly quadruples the number of points. Throughput is points per
+// second.
+fn poisson_disk_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let size = Vec2::splat(100.0);
+
+    for radius in [4.0, 2.0, 1.0, 0.5] {
+        let run = || poisson_disk_bridson(&mut StdRng::seed_from_u64(1234), size, radius, 30);
+
+        let count = run().len();
+
+        println!("{group_name}: radius = {radius}, count = {count}");
+
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_function(format!("radius = {radius}, bridson"), |b| b.iter(run));
+    }
+}
+
+pub fn poisson_disk(c: &mut Criterion) {
+    poisson_disk_with(c, "poisson_disk");
+}
+
+pub fn poisson_disk_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("poisson_disk", poisson_disk_with);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The cost of one sample from each distribution, all from the same generator.
+// The sphere and quat samplers compare the trig methods to rejection methods
+// that need more random numbers but no sin or cos.
+fn distributions_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const COUNT: usize = 4 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let mut u32_array = CacheAlignedVec::from_elem(0u32, COUNT);
+    let mut f32_array = CacheAlignedVec::from_elem(0.0f32, COUNT);
+    let mut vec3_array = CacheAlignedVec::from_elem(Vec3::ZERO, COUNT);
+    let mut quat_array = CacheAlignedVec::from_elem(Quat::IDENTITY, COUNT);
+
+    group.bench_function("gen_range, u32", |b| {
+        b.iter(|| sample_array(&mut rng, &mut u32_array, |rng| rng.gen_range(0..1000)))
+    });
+
+    group.bench_function("gen_range, f32", |b| {
+        b.iter(|| sample_array(&mut rng, &mut f32_array, |rng| rng.gen_range(-1.0..1.0)))
+    });
+
+    let normal = Normal::new(0.0f32, 1.0).unwrap();
+
+    group.bench_function("normal, f32", |b| {
+        b.iter(|| sample_array(&mut rng, &mut f32_array, |rng| rng.sample(normal)))
+    });
+
+    group.bench_function("unit sphere, trig", |b| {
+        b.iter(|| sample_array(&mut rng, &mut vec3_array, random_unit_vector_trig))
+    });
+
+    group.bench_function("unit sphere, rejection", |b| {
+        b.iter(|| sample_array(&mut rng, &mut vec3_array, random_unit_vector_rejection))
+    });
+
+    group.bench_function("unit sphere, rand_distr", |b| {
+        b.iter(|| {
+            sample_array(&mut rng, &mut vec3_array, |rng| {
+                Vec3::from_array(rng.sample(UnitSphere))
+            })
+        })
+    });
+
+    group.bench_function("quat, trig", |b| {
+        b.iter(|| sample_array(&mut rng, &mut quat_array, random_quat))
+    });
+
+    group.bench_function("quat, marsaglia", |b| {
+        b.iter(|| sample_array(&mut rng, &mut quat_array, random_quat_marsaglia))
+    });
+}
+
+pub fn distributions(c: &mut Criterion) {
+    distributions_with(c, "distributions");
+}
+
+pub fn distributions_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("distributions", distributions_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    sampling,
+    pin_thread,
+    poisson_disk,
+    distributions,
+    poisson_disk_perf,
+    distributions_perf
+);
+
+criterion_main!(sampling);
@@ -0,0 +1,173 @@
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, BenchmarkGroup, Criterion,
+    Throughput,
+};
+use glam::Vec3;
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{spatial::*, vector::RangeQueryParams},
+    sysreport::FrequencyCapture,
+    util::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const QUERY_COUNT: usize = 1024;
+
+fn spatial_grid_variants<G: SpatialGrid, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    name: &str,
+    point_array: &[Vec3],
+    query_array: &[Vec3],
+    radius: f32,
+) {
+    let count = point_array.len();
+
+    group.throughput(Throughput::Elements(count as u64));
+
+    group.bench_function(format!("count = {count}, insert, {name}"), |b| {
+        b.iter(|| G::build(point_array, radius))
+    });
+
+    let grid = G::build(point_array, radius);
+
+    let mut params = RangeQueryParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0, QUERY_COUNT),
+        query_array,
+        point_array,
+        radius,
+    };
+
+    group.throughput(Throughput::Elements(QUERY_COUNT as u64));
+
+    group.bench_function(format!("count = {count}, query, {name}"), |b| {
+        b.iter(|| grid_query(&mut params, &grid))
+    });
+}
+
+// Inserts random points into a grid, then counts the points within a radius of
+// random query points. The cell size is the query radius. Insert throughput is
+// points per second, and query throughput is queries per second.
+fn spatial_grid_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const RADIUS: f32 = 4.0;
+
+    for count in [1_000, 10_000, 100_000] {
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let point_array = random_spatial_point_array(&mut rng, count);
+        let query_array = random_spatial_point_array(&mut rng, QUERY_COUNT);
+
+        spatial_grid_variants::<HashMapGrid, _>(
+            &mut group,
+            "hash map",
+            &point_array,
+            &query_array,
+            RADIUS,
+        );
+
+        spatial_grid_variants::<DenseGrid, _>(
+            &mut group,
+            "dense",
+            &point_array,
+            &query_array,
+            RADIUS,
+        );
+
+        spatial_grid_variants::<SortedGrid, _>(
+            &mut group,
+            "sorted",
+            &point_array,
+            &query_array,
+            RADIUS,
+        );
+    }
+}
+
+pub fn spatial_grid(c: &mut Criterion) {
+    spatial_grid_with(c, "spatial_grid");
+}
+
+pub fn spatial_grid_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("spatial_grid", spatial_grid_with);
+}
+
+// Compares a loose octree against the hash map grid at several densities, given
+// as the average number of points per grid cell. The octree's deepest nodes
+// are at least as large as the grid cells.
+fn spatial_density_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    const RADIUS: f32 = 4.0;
+
+    let cell_count = (SPATIAL_WORLD_SIZE / RADIUS).powi(3);
+
+    for density in [0.25, 1.0, 4.0] {
+        let count = (density * cell_count) as usize;
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let point_array = random_spatial_point_array(&mut rng, count);
+        let query_array = random_spatial_point_array(&mut rng, QUERY_COUNT);
+
+        let mut dst_array = CacheAlignedVec::from_elem(0, QUERY_COUNT);
+
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_function(format!("density = {density}, insert, hash map"), |b| {
+            b.iter(|| HashMapGrid::build(&point_array, RADIUS))
+        });
+
+        group.bench_function(format!("density = {density}, insert, octree"), |b| {
+            b.iter(|| LooseOctree::build(&point_array, RADIUS))
+        });
+
+        let grid = HashMapGrid::build(&point_array, RADIUS);
+        let octree = LooseOctree::build(&point_array, RADIUS);
+
+        let mut params = RangeQueryParams {
+            dst_array: &mut dst_array,
+            query_array: &query_array,
+            point_array: &point_array,
+            radius: RADIUS,
+        };
+
+        group.throughput(Throughput::Elements(QUERY_COUNT as u64));
+
+        group.bench_function(format!("density = {density}, query, hash map"), |b| {
+            b.iter(|| grid_query(&mut params, &grid))
+        });
+
+        group.bench_function(format!("density = {density}, query, octree"), |b| {
+            b.iter(|| octree_query(&mut params, &octree))
+        });
+    }
+}
+
+pub fn spatial_density(c: &mut Criterion) {
+    spatial_density_with(c, "spatial_density");
+}
+
+pub fn spatial_density_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("spatial_density", spatial_density_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    spatial,
+    pin_thread,
+    spatial_grid,
+    spatial_density,
+    spatial_grid_perf,
+    spatial_density_perf
+);
+
+criterion_main!(spatial);
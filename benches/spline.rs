@@ -0,0 +1,204 @@
+use bevy_math::{
+    cubic_splines::{CubicBezier, CubicGenerator},
+    Vec3,
+};
+use core::time::Duration;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use misc_benches::{kernels::spline::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const BEZIER_POINTS: [Vec3; 4] = [
+    Vec3::new(0.0, 0.0, 0.0),
+    Vec3::new(1.0, 2.0, 0.0),
+    Vec3::new(3.0, -1.0, 1.0),
+    Vec3::new(4.0, 0.0, -1.0),
+];
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn bezier(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("bezier");
+    let mut group = c.benchmark_group("bezier");
+
+    const COUNT: usize = 32 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let src_array = random_array::<f32>(&mut rng, COUNT);
+
+    let cubic = HornerCubic::from_bezier(&BEZIER_POINTS);
+    let curve = CubicBezier::new([BEZIER_POINTS]).to_curve().unwrap();
+
+    let mut params = SplineParams {
+        dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT),
+        src_array: &src_array,
+    };
+
+    group.bench_function("position, de casteljau", |b| {
+        b.iter(|| {
+            spline_de_casteljau(&mut params, &BEZIER_POINTS);
+        })
+    });
+
+    group.bench_function("position, horner", |b| {
+        b.iter(|| {
+            spline_horner(&mut params, &cubic);
+        })
+    });
+
+    group.bench_function("position, bevy", |b| {
+        b.iter(|| {
+            spline_bevy(&mut params, &curve);
+        })
+    });
+
+    let mut velocity_params = SplineVelocityParams {
+        dst_position_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT),
+        dst_velocity_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT),
+        src_array: &src_array,
+    };
+
+    group.bench_function("position + velocity, de casteljau", |b| {
+        b.iter(|| {
+            spline_velocity_de_casteljau(&mut velocity_params, &BEZIER_POINTS);
+        })
+    });
+
+    group.bench_function("position + velocity, horner", |b| {
+        b.iter(|| {
+            spline_velocity_horner(&mut velocity_params, &cubic);
+        })
+    });
+
+    group.bench_function("position + velocity, bevy", |b| {
+        b.iter(|| {
+            spline_velocity_bevy(&mut velocity_params, &curve);
+        })
+    });
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Measures building a cardinal curve from a chain of control points, and then
+// sampling the whole curve densely. A tension of 0.5 is a Catmull-Rom curve.
+// Camera paths and UI animations tend to rebuild curves as often as they
+// sample them, so both costs matter.
+pub fn cardinal(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("cardinal");
+    let mut group = c.benchmark_group("cardinal");
+
+    const SAMPLE_COUNT: usize = 32 * 1024;
+
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    for point_count in [16, 256, 4096] {
+        let points = random_array::<Vec3>(&mut rng, point_count);
+
+        let src_array = uniform_samples(SAMPLE_COUNT, point_count - 1)
+            .into_iter()
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut params = SplineParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, SAMPLE_COUNT),
+            src_array: &src_array,
+        };
+
+        for tension in [0.0, 0.5, 1.0] {
+            group.throughput(Throughput::Elements(point_count as u64));
+
+            group.bench_function(
+                format!("construct, tension = {tension:.1}, points = {point_count}"),
+                |b| b.iter(|| cardinal_curve(tension, &points)),
+            );
+
+            let curve = cardinal_curve(tension, &points);
+
+            group.throughput(Throughput::Elements(SAMPLE_COUNT as u64));
+
+            let name = format!(
+                "sample, tension = {tension:.1}, points = {point_count}, samples = {SAMPLE_COUNT}"
+            );
+
+            group.bench_function(name, |b| {
+                b.iter(|| {
+                    spline_bevy(&mut params, &curve);
+                })
+            });
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Compares ways of moving along a curve at constant speed, where each sample
+// is a distance along the curve rather than a `t`.
+pub fn arc_length(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("arc_length");
+    let mut group = c.benchmark_group("arc_length");
+
+    const POINT_COUNT: usize = 256;
+    const COUNT: usize = 32 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let points = random_array::<Vec3>(&mut rng, POINT_COUNT);
+    let curve = cardinal_curve(0.5, &points);
+
+    // The chord length underestimates the arc length, so take the length from
+    // Newton's quadrature. Otherwise the end of the curve is never queried.
+    let length = ArcLengthNewton::new(&curve, 1).length();
+
+    let src_array = (0..COUNT)
+        .map(|_| rng.gen_range(0.0..length))
+        .collect::<CacheAlignedVec<f32>>();
+
+    let mut params = ArcLengthParams {
+        dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, COUNT),
+        src_array: &src_array,
+    };
+
+    for iterations in [1, 2, 4] {
+        let newton = ArcLengthNewton::new(&curve, iterations);
+
+        group.bench_function(format!("newton, iterations = {iterations}"), |b| {
+            b.iter(|| {
+                arc_length_newton(&mut params, &curve, &newton);
+            })
+        });
+    }
+
+    for size in [POINT_COUNT * 4, POINT_COUNT * 64] {
+        let lut = ArcLengthLut::new(&curve, size);
+
+        group.bench_function(format!("lut = {size}"), |b| {
+            b.iter(|| {
+                arc_length_lut(&mut params, &curve, &lut);
+            })
+        });
+    }
+
+    let chord = ArcLengthChord::new(&curve);
+
+    group.bench_function("chord", |b| {
+        b.iter(|| {
+            arc_length_chord(&mut params, &curve, &chord);
+        })
+    });
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+criterion_group!(spline, bezier, cardinal, arc_length);
+
+criterion_main!(spline);
@@ -0,0 +1,129 @@
+use criterion::{criterion_group, criterion_main, measurement::Measurement, Criterion, Throughput};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{
+    kernels::{color::SrgbLut, texture::*},
+    sysreport::FrequencyCapture,
+    util::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Downsamples a square RGBA8 image with random pixels to half size, as when
+// generating one level of a mip chain. Throughput is source pixels per second.
+fn mipmap_downsample_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    let lut = SrgbLut::new();
+
+    #[cfg(target_arch = "x86_64")]
+    let avx2 = misc_benches::kernels::x86::Avx2::detect();
+
+    for size in [1024, 4096] {
+        let count = size * size;
+
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src_array = random_array::<[u8; 4]>(&mut rng, count);
+
+        let mut params = DownsampleParams {
+            dst_array: &mut CacheAlignedVec::from_elem([0; 4], count / 4),
+            src_array: &src_array,
+            src_width: size,
+        };
+
+        group.bench_function(format!("size = {size}, box"), |b| {
+            b.iter(|| downsample_box(&mut params))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        if let Some(avx2) = avx2 {
+            group.bench_function(format!("size = {size}, box, avx2"), |b| {
+                b.iter(|| misc_benches::kernels::x86::downsample_box_avx2(avx2, &mut params))
+            });
+        }
+
+        group.bench_function(format!("size = {size}, srgb, exact"), |b| {
+            b.iter(|| downsample_srgb_exact(&mut params))
+        });
+
+        group.bench_function(format!("size = {size}, srgb, lut"), |b| {
+            b.iter(|| downsample_srgb_lut(&mut params, &lut))
+        });
+
+        #[cfg(target_arch = "x86_64")]
+        if let Some(avx2) = avx2 {
+            group.bench_function(format!("size = {size}, srgb, lut, avx2"), |b| {
+                b.iter(|| {
+                    misc_benches::kernels::x86::downsample_srgb_lut_avx2(avx2, &mut params, &lut)
+                })
+            });
+        }
+    }
+}
+
+pub fn mipmap_downsample(c: &mut Criterion) {
+    mipmap_downsample_with(c, "mipmap_downsample");
+}
+
+pub fn mipmap_downsample_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("mipmap_downsample", mipmap_downsample_with);
+}
+
+// Compresses a square RGBA8 image of smooth gradients with noise, as when
+// compressing generated textures at runtime. Throughput is pixels per second.
+fn block_compress_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    for size in [512, 2048] {
+        let count = size * size;
+
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src_array = random_texture_image(&mut rng, size, size);
+
+        let mut params = BlockCompressParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0, count / 16),
+            src_array: &src_array,
+            src_width: size,
+        };
+
+        group.bench_function(format!("size = {size}, bc1, range fit"), |b| {
+            b.iter(|| bc1_encode_range_fit(&mut params))
+        });
+
+        group.bench_function(format!("size = {size}, bc4, range fit"), |b| {
+            b.iter(|| bc4_encode_range_fit(&mut params))
+        });
+    }
+}
+
+pub fn block_compress(c: &mut Criterion) {
+    block_compress_with(c, "block_compress");
+}
+
+pub fn block_compress_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("block_compress", block_compress_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    texture,
+    pin_thread,
+    mipmap_downsample,
+    block_compress,
+    mipmap_downsample_perf,
+    block_compress_perf
+);
+
+criterion_main!(texture);
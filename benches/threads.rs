@@ -0,0 +1,484 @@
+use bevy_transform::components::Transform;
+use criterion::{
+    criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, Criterion,
+    SamplingMode, Throughput,
+};
+use misc_benches::{
+    kernels::{easing::*, isometry::*, normalize::*, threads::*},
+    sysreport::FrequencyCapture,
+    util::{random_array, random_transform_array, CacheAlignedVec, CACHE_LINE_SIZE},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::{
+    num::NonZero,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// These benches don't pin the thread, since spawned threads inherit the
+// affinity and would all end up on one core.
+
+fn max_thread_count() -> usize {
+    thread::available_parallelism()
+        .map(NonZero::<usize>::get)
+        .unwrap_or(1)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Each thread increments its own counter. The counters are either packed
+// together, so up to a cache line's worth of threads fight over one line, or
+// padded onto separate lines. Throughput is per thread, like the rand bench, so
+// perfect scaling is a flat line.
+pub fn false_sharing(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("false_sharing");
+    let mut group = c.benchmark_group("false_sharing");
+
+    const ITERATIONS: u64 = 10_000_000;
+
+    group.throughput(Throughput::Elements(ITERATIONS));
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    let max_thread_count = max_thread_count();
+
+    println!(
+        "false_sharing: {} counters per cache line when packed",
+        CACHE_LINE_SIZE / size_of::<AtomicU64>()
+    );
+
+    let packed = (0..max_thread_count)
+        .map(|_| AtomicU64::new(0))
+        .collect::<CacheAlignedVec<_>>();
+
+    let padded = (0..max_thread_count)
+        .map(|_| CachePadded(AtomicU64::new(0)))
+        .collect::<CacheAlignedVec<_>>();
+
+    for thread_count in 1..=max_thread_count {
+        group.bench_function(format!("packed, threads = {thread_count}"), |b| {
+            b.iter(|| run_on_threads(thread_count, |i| count(&packed[i], ITERATIONS)))
+        });
+
+        group.bench_function(format!("padded, threads = {thread_count}"), |b| {
+            b.iter(|| run_on_threads(thread_count, |i| count(&padded[i].0, ITERATIONS)))
+        });
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Every thread increments one total, either with an atomic add on a shared
+// counter, by counting on a padded counter of its own and summing them at the
+// end, or through a mutex. Throughput is per thread, as above.
+pub fn atomic_contention(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("atomic_contention");
+    let mut group = c.benchmark_group("atomic_contention");
+
+    const ITERATIONS: u64 = 1_000_000;
+
+    group.throughput(Throughput::Elements(ITERATIONS));
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    let max_thread_count = max_thread_count();
+
+    let shared = AtomicU64::new(0);
+    let mutex = Mutex::new(0);
+
+    let per_thread = (0..max_thread_count)
+        .map(|_| CachePadded(AtomicU64::new(0)))
+        .collect::<CacheAlignedVec<_>>();
+
+    for thread_count in 1..=max_thread_count {
+        group.bench_function(format!("shared atomic, threads = {thread_count}"), |b| {
+            b.iter(|| run_on_threads(thread_count, |_| count_shared(&shared, ITERATIONS)))
+        });
+
+        group.bench_function(format!("per thread, threads = {thread_count}"), |b| {
+            b.iter(|| {
+                run_on_threads(thread_count, |i| count(&per_thread[i].0, ITERATIONS));
+
+                per_thread[..thread_count]
+                    .iter()
+                    .map(|c| c.0.load(Ordering::Relaxed))
+                    .sum::<u64>()
+            })
+        });
+
+        group.bench_function(format!("mutex, threads = {thread_count}"), |b| {
+            b.iter(|| run_on_threads(thread_count, |_| count_mutex(&mutex, ITERATIONS)))
+        });
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+const LOCK_ITERATIONS: u64 = 1_000_000;
+
+fn lock_variant<L: CounterLock>(group: &mut BenchmarkGroup<WallTime>, write_percent: u64) {
+    let lock = L::new(0);
+
+    for thread_count in 1..=max_thread_count() {
+        group.bench_function(
+            format!(
+                "{}, writes = {write_percent}%, threads = {thread_count}",
+                L::NAME
+            ),
+            |b| {
+                b.iter(|| {
+                    run_on_threads(thread_count, |_| {
+                        lock_mix(&lock, LOCK_ITERATIONS, write_percent);
+                    })
+                })
+            },
+        );
+    }
+}
+
+// Threads take a lock around a tiny critical section, either reading or
+// incrementing a counter. Throughput is per thread, as above.
+pub fn lock(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("lock");
+    let mut group = c.benchmark_group("lock");
+
+    group.throughput(Throughput::Elements(LOCK_ITERATIONS));
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    for write_percent in [0, 10, 50, 100] {
+        lock_variant::<Mutex<u64>>(&mut group, write_percent);
+        lock_variant::<RwLock<u64>>(&mut group, write_percent);
+
+        #[cfg(feature = "parking_lot")]
+        {
+            lock_variant::<parking_lot::Mutex<u64>>(&mut group, write_percent);
+            lock_variant::<parking_lot::RwLock<u64>>(&mut group, write_percent);
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Return the mean time of calls to `f` over roughly `duration`, after warming
+// up for the same duration.
+fn mean_time(duration: Duration, mut f: impl FnMut()) -> Duration {
+    let mut measure = || {
+        let start = Instant::now();
+        let mut runs = 0;
+
+        while start.elapsed() < duration {
+            f();
+            runs += 1;
+        }
+
+        start.elapsed() / runs
+    };
+
+    measure();
+    measure()
+}
+
+// Benchmark `run` serially and then in parallel for each chunk length on each
+// pool. `run(None)` calls the serial kernel, and `run(Some(chunk_len))` the
+// parallel one on the current pool.
+//
+// Before benchmarking, print the parallel efficiency of each combination - the
+// serial time divided by the thread count times the parallel time. 100% means
+// perfect scaling.
+fn par_chunks_variant(
+    group: &mut BenchmarkGroup<WallTime>,
+    kernel_name: &str,
+    pools: &[ThreadPool],
+    chunk_lens: &[usize],
+    mut run: impl FnMut(Option<usize>) + Send,
+) {
+    const DURATION: Duration = Duration::from_millis(200);
+
+    let serial_time = mean_time(DURATION, || run(None));
+
+    for pool in pools {
+        for &chunk_len in chunk_lens {
+            let parallel_time = pool.install(|| mean_time(DURATION, || run(Some(chunk_len))));
+            let thread_count = pool.current_num_threads();
+
+            let label =
+                format!("kernel = {kernel_name}, chunk = {chunk_len}, threads = {thread_count}");
+            let efficiency = 100.0 * serial_time.as_secs_f64()
+                / (thread_count as f64 * parallel_time.as_secs_f64());
+
+            println!("par_chunks: {label}: {efficiency:.0}% efficiency");
+        }
+    }
+
+    group.bench_function(format!("kernel = {kernel_name}, serial"), |b| {
+        b.iter(|| run(None))
+    });
+
+    for pool in pools {
+        for &chunk_len in chunk_lens {
+            group.bench_function(
+                format!(
+                    "kernel = {kernel_name}, chunk = {chunk_len}, threads = {}",
+                    pool.current_num_threads()
+                ),
+                |b| b.iter(|| pool.install(|| run(Some(chunk_len)))),
+            );
+        }
+    }
+}
+
+// The smoothstep and transform_normalize kernels split into chunks with rayon's
+// `par_chunks`, on pools of 1..=N threads. Small chunks balance the load but pay
+// more per task.
+pub fn par_chunks(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("par_chunks");
+    let mut group = c.benchmark_group("par_chunks");
+
+    const COUNT: usize = 256 * 1024;
+    const CHUNK_LENS: [usize; 4] = [64, 1024, 16 * 1024, 256 * 1024];
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let pools = (1..=max_thread_count())
+        .map(|thread_count| {
+            ThreadPoolBuilder::new()
+                .num_threads(thread_count)
+                .build()
+                .unwrap()
+        })
+        .collect::<Vec<ThreadPool>>();
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let mut params = SmoothstepParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0f32, COUNT),
+        src_array: &random_array(&mut rng, COUNT),
+    };
+
+    par_chunks_variant(
+        &mut group,
+        "smoothstep",
+        &pools,
+        &CHUNK_LENS,
+        |chunk_len| match chunk_len {
+            None => smoothstep_explicit(&mut params),
+            Some(chunk_len) => smoothstep_par_chunks(&mut params, chunk_len),
+        },
+    );
+
+    let mut params = TransformNormalizeParams {
+        dst: &mut CacheAlignedVec::from_elem(Transform::IDENTITY, COUNT),
+        src: &[
+            &random_transform_array(&mut rng, COUNT),
+            &random_transform_array(&mut rng, COUNT),
+        ],
+    };
+
+    par_chunks_variant(
+        &mut group,
+        "transform_normalize",
+        &pools,
+        &CHUNK_LENS,
+        |chunk_len| match chunk_len {
+            None => transform_normalize_true(&mut params),
+            Some(chunk_len) => transform_normalize_true_par_chunks(&mut params, chunk_len),
+        },
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A fixed number of transform compositions, split into tasks of each size and
+// spawned into a rayon scope on a pool with every thread. Tiny tasks are
+// dominated by the cost of spawning and stealing them, while huge tasks leave
+// threads idle.
+pub fn task_size(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("task_size");
+    let mut group = c.benchmark_group("task_size");
+
+    const COUNT: usize = 256 * 1024;
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(max_thread_count())
+        .build()
+        .unwrap();
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let mut params = IsometryComposeParams {
+        dst_array: &mut CacheAlignedVec::from_elem(Transform::IDENTITY, COUNT),
+        src_array: [
+            &random_transform_array(&mut rng, COUNT),
+            &random_transform_array(&mut rng, COUNT),
+        ],
+    };
+
+    group.bench_function("serial", |b| b.iter(|| compose_transform(&mut params)));
+
+    for task_len in (6..=16).step_by(2).map(|i| 1 << i) {
+        group.bench_function(
+            format!(
+                "task = {task_len}, threads = {}",
+                pool.current_num_threads()
+            ),
+            |b| b.iter(|| pool.install(|| compose_transform_tasks(&mut params, task_len))),
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A game-like loop of 1000 frames, where each frame hands a small job to every
+// thread and waits for them all to finish. The job is the same in each variant,
+// so the differences are the cost of forking and joining - spawning scoped
+// threads, spawning into a rayon scope, or waking a pool that waits at a
+// barrier. Throughput is frames.
+pub fn frame_sync(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("frame_sync");
+    let mut group = c.benchmark_group("frame_sync");
+
+    const FRAMES: u64 = 1000;
+    const JOB_ITERATIONS: u64 = 1000;
+
+    group.throughput(Throughput::Elements(FRAMES));
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    let max_thread_count = max_thread_count();
+
+    let counters = Arc::new(
+        (0..max_thread_count)
+            .map(|_| CachePadded(AtomicU64::new(0)))
+            .collect::<CacheAlignedVec<_>>(),
+    );
+
+    let job = {
+        let counters = counters.clone();
+
+        move |i: usize| count(&counters[i].0, JOB_ITERATIONS)
+    };
+
+    for thread_count in 1..=max_thread_count {
+        group.bench_function(format!("thread scope, threads = {thread_count}"), |b| {
+            b.iter(|| {
+                for _ in 0..FRAMES {
+                    run_on_threads(thread_count, &job);
+                }
+            })
+        });
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(thread_count)
+            .build()
+            .unwrap();
+
+        group.bench_function(format!("rayon scope, threads = {thread_count}"), |b| {
+            b.iter(|| {
+                pool.install(|| {
+                    for _ in 0..FRAMES {
+                        rayon::scope(|s| {
+                            for i in 0..thread_count {
+                                let job = &job;
+
+                                s.spawn(move |_| job(i));
+                            }
+                        });
+                    }
+                })
+            })
+        });
+
+        let pool = BarrierPool::new(thread_count, job.clone());
+
+        group.bench_function(format!("barrier pool, threads = {thread_count}"), |b| {
+            b.iter(|| {
+                for _ in 0..FRAMES {
+                    pool.run();
+                }
+            })
+        });
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Spawning and awaiting tasks, and sending a value back and forth over a pair
+// of channels, with OS threads and - if the "tokio" feature is enabled - tokio
+// tasks on a multi-threaded and a single-threaded runtime. Throughput is tasks
+// or round trips.
+pub fn task_overhead(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("task_overhead");
+    let mut group = c.benchmark_group("task_overhead");
+
+    const ROUND_TRIPS: u64 = 10_000;
+
+    #[cfg(feature = "tokio")]
+    let runtimes = [
+        (
+            "tokio multi thread",
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(max_thread_count())
+                .build()
+                .unwrap(),
+        ),
+        (
+            "tokio current thread",
+            tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap(),
+        ),
+    ];
+
+    for count in [1, 16, 256] {
+        group.throughput(Throughput::Elements(count));
+
+        group.bench_function(format!("spawn, count = {count}, threads"), |b| {
+            b.iter(|| spawn_join_threads(count))
+        });
+
+        #[cfg(feature = "tokio")]
+        for (name, runtime) in &runtimes {
+            group.bench_function(format!("spawn, count = {count}, {name}"), |b| {
+                b.iter(|| runtime.block_on(spawn_await_tasks(count)))
+            });
+        }
+    }
+
+    group.throughput(Throughput::Elements(ROUND_TRIPS));
+
+    group.bench_function("channel round trip, threads", |b| {
+        b.iter(|| channel_round_trips_thread(ROUND_TRIPS))
+    });
+
+    #[cfg(feature = "tokio")]
+    for (name, runtime) in &runtimes {
+        group.bench_function(format!("channel round trip, {name}"), |b| {
+            b.iter(|| runtime.block_on(channel_round_trips_task(ROUND_TRIPS)))
+        });
+    }
+}
+
+criterion_group!(
+    threads,
+    false_sharing,
+    atomic_contention,
+    lock,
+    par_chunks,
+    task_size,
+    frame_sync,
+    task_overhead
+);
+
+criterion_main!(threads);
@@ -0,0 +1,221 @@
+use core::time::Duration;
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, BenchmarkGroup, Criterion,
+    Throughput,
+};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::transcendental::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+const COUNT: usize = 32 * 1024;
+
+// Return random values in the range `-scale..scale`.
+fn random_signed_array(rng: &mut StdRng, scale: f32) -> CacheAlignedVec<f32> {
+    random_array::<f32>(rng, COUNT)
+        .iter()
+        .map(|t| ((t * 2.0) - 1.0) * scale)
+        .collect()
+}
+
+type UnaryVariant = (&'static str, fn(&mut UnaryParams));
+type BinaryVariant = (&'static str, fn(&mut BinaryParams));
+
+fn unary_variants<M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    function: &str,
+    src_array: &[f32],
+    variants: &[UnaryVariant],
+) {
+    let mut params = UnaryParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0f32, COUNT),
+        src_array,
+    };
+
+    for (name, f) in variants {
+        group.bench_function(format!("function = {function}, impl = {name}"), |b| {
+            b.iter(|| {
+                f(&mut params);
+            })
+        });
+    }
+}
+
+fn binary_variants<M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    function: &str,
+    src_array: [&[f32]; 2],
+    variants: &[BinaryVariant],
+) {
+    let mut params = BinaryParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0f32, COUNT),
+        src_array,
+    };
+
+    for (name, f) in variants {
+        group.bench_function(format!("function = {function}, impl = {name}"), |b| {
+            b.iter(|| {
+                f(&mut params);
+            })
+        });
+    }
+}
+
+fn transcendental_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    // The trig inputs cover many periods so that range reduction is included.
+    // The exp inputs stop short of where f32 overflows.
+
+    let trig_src = random_signed_array(&mut rng, 100.0);
+    let exp_src = random_signed_array(&mut rng, 80.0);
+    let atan2_src = [
+        random_signed_array(&mut rng, 1.0),
+        random_signed_array(&mut rng, 1.0),
+    ];
+
+    unary_variants(
+        &mut group,
+        "sin",
+        &trig_src,
+        &[
+            ("std", sin_std),
+            ("libm", sin_libm),
+            #[cfg(feature = "micromath")]
+            ("micromath", sin_micromath),
+        ],
+    );
+
+    unary_variants(
+        &mut group,
+        "cos",
+        &trig_src,
+        &[
+            ("std", cos_std),
+            ("libm", cos_libm),
+            #[cfg(feature = "micromath")]
+            ("micromath", cos_micromath),
+        ],
+    );
+
+    unary_variants(
+        &mut group,
+        "exp",
+        &exp_src,
+        &[
+            ("std", exp_std),
+            ("libm", exp_libm),
+            #[cfg(feature = "micromath")]
+            ("micromath", exp_micromath),
+        ],
+    );
+
+    binary_variants(
+        &mut group,
+        "atan2",
+        [&atan2_src[0], &atan2_src[1]],
+        &[
+            ("std", atan2_std),
+            ("libm", atan2_libm),
+            #[cfg(feature = "micromath")]
+            ("micromath", atan2_micromath),
+        ],
+    );
+}
+
+pub fn transcendental(c: &mut Criterion) {
+    transcendental_with(c, "transcendental");
+}
+
+pub fn transcendental_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("transcendental", transcendental_with);
+}
+
+fn sin_cos_variant<M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    group_name: &str,
+    name: &str,
+    params: &mut SinCosParams,
+    f: impl Fn(&mut SinCosParams),
+) {
+    f(params);
+
+    let max_error = sin_cos_max_error(params.dst_array, params.src_array);
+
+    bench_function_with_metric(
+        group,
+        group_name,
+        name,
+        format_args!("max error = {max_error:e}"),
+        || f(params),
+    );
+}
+
+fn sin_cos_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.throughput(Throughput::Elements(COUNT as u64));
+    group.warm_up_time(Duration::from_millis(100));
+    group.measurement_time(Duration::from_millis(1000));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    let mut params = SinCosParams {
+        dst_array: &mut CacheAlignedVec::from_elem((0.0f32, 0.0f32), COUNT),
+        src_array: &random_signed_array(&mut rng, 100.0),
+    };
+
+    sin_cos_variant(&mut group, group_name, "std", &mut params, sin_cos_std);
+    sin_cos_variant(
+        &mut group,
+        group_name,
+        "minimax",
+        &mut params,
+        sin_cos_minimax,
+    );
+
+    for size in [256, 1024, 4096] {
+        let lut = SinCosLut::new(size);
+
+        sin_cos_variant(
+            &mut group,
+            group_name,
+            &format!("lut = {size}, linear"),
+            &mut params,
+            |params| sin_cos_lut_linear(params, &lut),
+        );
+    }
+}
+
+pub fn sin_cos(c: &mut Criterion) {
+    sin_cos_with(c, "sin_cos");
+}
+
+pub fn sin_cos_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("sin_cos", sin_cos_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    transcendental_benches,
+    pin_thread,
+    transcendental,
+    sin_cos,
+    transcendental_perf,
+    sin_cos_perf,
+);
+
+criterion_main!(transcendental_benches);
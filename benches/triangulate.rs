@@ -0,0 +1,54 @@
+use criterion::{
+    criterion_group, criterion_main, measurement::Measurement, Criterion, SamplingMode, Throughput,
+};
+#[cfg(target_os = "linux")]
+use misc_benches::measure::for_each_perf_counter;
+use misc_benches::{kernels::triangulate::*, sysreport::FrequencyCapture, util::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Triangulates convex polygons, concave polygons, and concave polygons with
+// four holes. Throughput is polygon vertices per second.
+fn ear_clipping_with<M: Measurement>(c: &mut Criterion<M>, group_name: &str) {
+    let _frequency = FrequencyCapture::start(group_name);
+    let mut group = c.benchmark_group(group_name);
+
+    group.sampling_mode(SamplingMode::Flat);
+    group.sample_size(10);
+
+    for count in [32, 128, 512, 2048] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        for (shape, polygon) in [
+            ("convex", random_convex_polygon(&mut rng, count)),
+            ("concave", random_concave_polygon(&mut rng, count)),
+            ("holes", random_polygon_with_holes(&mut rng, count)),
+        ] {
+            group.bench_function(format!("vertices = {count}, {shape}, naive"), |b| {
+                b.iter(|| triangulate_ear_clip_naive(&polygon))
+            });
+
+            group.bench_function(format!("vertices = {count}, {shape}, reflex"), |b| {
+                b.iter(|| triangulate_ear_clip_reflex(&polygon))
+            });
+        }
+    }
+}
+
+pub fn ear_clipping(c: &mut Criterion) {
+    ear_clipping_with(c, "ear_clipping");
+}
+
+pub fn ear_clipping_perf(_: &mut Criterion) {
+    #[cfg(target_os = "linux")]
+    for_each_perf_counter("ear_clipping", ear_clipping_with);
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(triangulate, pin_thread, ear_clipping, ear_clipping_perf);
+
+criterion_main!(triangulate);
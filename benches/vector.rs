@@ -0,0 +1,262 @@
+use criterion::{
+    criterion_group, criterion_main,
+    measurement::{Measurement, WallTime},
+    BenchmarkGroup, Criterion, Throughput,
+};
+use glam::{Quat, Vec3, Vec3A};
+use misc_benches::{kernels::vector::*, sysreport::FrequencyCapture, util::*};
+use rand::prelude::*;
+
+fn vec3_layout_variants<V: Vector3 + Default + From<Vec3>>(
+    group: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    src: &[CacheAlignedVec<Vec3>; 2],
+) {
+    let count = src[0].len();
+
+    let src = src.each_ref().map(|s| {
+        s.iter()
+            .copied()
+            .map(V::from)
+            .collect::<CacheAlignedVec<_>>()
+    });
+
+    let mut dot_params = VectorParams {
+        dst_array: &mut CacheAlignedVec::from_elem(0.0, count),
+        src_array: [&src[0], &src[1]],
+    };
+
+    group.bench_function(format!("count = {count}, {name}, dot"), |b| {
+        b.iter(|| vector_dot(&mut dot_params))
+    });
+
+    let mut params = VectorParams {
+        dst_array: &mut CacheAlignedVec::from_elem(V::default(), count),
+        src_array: [&src[0], &src[1]],
+    };
+
+    group.bench_function(format!("count = {count}, {name}, cross"), |b| {
+        b.iter(|| vector_cross(&mut params))
+    });
+
+    group.bench_function(format!("count = {count}, {name}, normalize"), |b| {
+        b.iter(|| vector_normalize(&mut params))
+    });
+}
+
+// Runs the same loops over `Vec3`, `Vec3A` and `[f32; 3]`. The counts are
+// sized for `Vec3A`, so every layout processes the same number of elements
+// but the smaller layouts have a smaller working set.
+pub fn vec3_layout(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("vec3_layout");
+    let mut group = c.benchmark_group("vec3_layout");
+
+    let l1 = l1_sized_count::<(Vec3A, Vec3A, Vec3A)>();
+    let l2 = l2_sized_count::<(Vec3A, Vec3A, Vec3A)>();
+    let ram = ram_sized_count::<(Vec3A, Vec3A, Vec3A)>();
+
+    for count in [l1, l2, ram] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let src = [
+            random_array::<Vec3>(&mut rng, count),
+            random_array::<Vec3>(&mut rng, count),
+        ];
+
+        vec3_layout_variants::<Vec3>(&mut group, "vec3", &src);
+        vec3_layout_variants::<Vec3A>(&mut group, "vec3a", &src);
+        vec3_layout_variants::<[f32; 3]>(&mut group, "array", &src);
+    }
+}
+
+// Reflect, refract and project vectors against unit normals, with the vectors
+// stored as AoS `Vec3` or SoA component arrays.
+pub fn vec3_ops(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("vec3_ops");
+    let mut group = c.benchmark_group("vec3_ops");
+
+    let l1 = l1_sized_count::<(Vec3, Vec3, Vec3)>();
+    let l2 = l2_sized_count::<(Vec3, Vec3, Vec3)>();
+
+    const ETA: f32 = 1.0 / 1.33;
+
+    for count in [l1, l2] {
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let v = random_array::<Vec3>(&mut rng, count);
+        let n = random_array::<Vec3>(&mut rng, count)
+            .iter()
+            .map(|n| (*n - 0.5).normalize())
+            .collect::<CacheAlignedVec<_>>();
+
+        let mut params = VectorParams {
+            dst_array: &mut CacheAlignedVec::from_elem(Vec3::ZERO, count),
+            src_array: [&v, &n],
+        };
+
+        group.bench_function(format!("count = {count}, aos, reflect"), |b| {
+            b.iter(|| vector_reflect(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, aos, refract"), |b| {
+            b.iter(|| vector_refract(&mut params, ETA))
+        });
+
+        group.bench_function(format!("count = {count}, aos, project onto"), |b| {
+            b.iter(|| vector_project_onto(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, aos, reject from"), |b| {
+            b.iter(|| vector_reject_from(&mut params))
+        });
+
+        let v_soa = Vec3Soa::from_vec3s(&v);
+        let n_soa = Vec3Soa::from_vec3s(&n);
+
+        let mut params = VectorSoaParams {
+            dst: &mut Vec3Soa::from_vec3s(&v),
+            src: [&v_soa, &n_soa],
+        };
+
+        group.bench_function(format!("count = {count}, soa, reflect"), |b| {
+            b.iter(|| vector_soa_reflect(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, soa, refract"), |b| {
+            b.iter(|| vector_soa_refract(&mut params, ETA))
+        });
+
+        group.bench_function(format!("count = {count}, soa, project onto"), |b| {
+            b.iter(|| vector_soa_project_onto(&mut params))
+        });
+
+        group.bench_function(format!("count = {count}, soa, reject from"), |b| {
+            b.iter(|| vector_soa_reject_from(&mut params))
+        });
+    }
+}
+
+fn angle_variant<D: Copy, M: Measurement>(
+    group: &mut BenchmarkGroup<M>,
+    name: &str,
+    params: &mut VectorParams<Vec3, D>,
+    f: impl Fn(&mut VectorParams<Vec3, D>),
+    error: impl Fn(&[D], [&[Vec3]; 2]) -> f32,
+) {
+    f(params);
+
+    let max_error = error(params.dst_array, params.src_array);
+
+    bench_function_with_metric(
+        group,
+        "angle",
+        name,
+        format_args!("max error = {max_error:e}"),
+        || f(params),
+    );
+}
+
+// Compares the angle and rotation arc between pairs of unit vectors, including
+// pairs that are nearly parallel or nearly opposite.
+pub fn angle(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("angle");
+    let mut group = c.benchmark_group("angle");
+
+    let count = l1_sized_count::<(Vec3, Vec3, Quat)>();
+
+    group.throughput(Throughput::Elements(count as u64));
+
+    let mut rng = StdRng::seed_from_u64(1234);
+
+    for kind in PairKind::ALL {
+        let src = random_pair_arrays(&mut rng, count, kind);
+        let src_array = [src[0].as_slice(), src[1].as_slice()];
+        let input = kind.name();
+
+        let mut params = VectorParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0.0, count),
+            src_array,
+        };
+
+        angle_variant(
+            &mut group,
+            &format!("count = {count}, input = {input}, angle between"),
+            &mut params,
+            vector_angle_between,
+            angle_max_error,
+        );
+
+        angle_variant(
+            &mut group,
+            &format!("count = {count}, input = {input}, atan2"),
+            &mut params,
+            vector_angle_atan2,
+            angle_max_error,
+        );
+
+        angle_variant(
+            &mut group,
+            &format!("count = {count}, input = {input}, rotation arc"),
+            &mut VectorParams {
+                dst_array: &mut CacheAlignedVec::from_elem(Quat::IDENTITY, count),
+                src_array,
+            },
+            vector_rotation_arc,
+            rotation_arc_max_error,
+        );
+    }
+}
+
+// Counts the points within a radius of each query point, comparing either the
+// distance or the squared distance. The points are uniform in the unit cube,
+// so the radius selects roughly a tenth of them.
+pub fn range_query(c: &mut Criterion) {
+    let _frequency = FrequencyCapture::start("range_query");
+    let mut group = c.benchmark_group("range_query");
+
+    const QUERY_COUNT: usize = 64;
+
+    let l1 = l1_sized_count::<Vec3>();
+    let l2 = l2_sized_count::<Vec3>();
+
+    for point_count in [64, 1024, l1, l2] {
+        // Each element is one query/point pair.
+        group.throughput(Throughput::Elements((QUERY_COUNT * point_count) as u64));
+
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        let mut params = RangeQueryParams {
+            dst_array: &mut CacheAlignedVec::from_elem(0, QUERY_COUNT),
+            query_array: &random_array(&mut rng, QUERY_COUNT),
+            point_array: &random_array(&mut rng, point_count),
+            radius: 0.3,
+        };
+
+        group.bench_function(format!("points = {point_count}, distance"), |b| {
+            b.iter(|| range_query_distance(&mut params))
+        });
+
+        group.bench_function(format!("points = {point_count}, distance squared"), |b| {
+            b.iter(|| range_query_distance_squared(&mut params))
+        });
+    }
+}
+
+pub fn pin_thread(_: &mut Criterion) {
+    pin_from_env();
+}
+
+criterion_group!(
+    vector,
+    pin_thread,
+    vec3_layout,
+    vec3_ops,
+    angle,
+    range_query
+);
+
+criterion_main!(vector);
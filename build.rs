@@ -0,0 +1,31 @@
+use std::{env, process::Command};
+
+// Capture compiler and target details for `sysreport`, since they're not
+// available at runtime.
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or("rustc".to_string());
+
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or("not available".to_string());
+
+    println!("cargo:rustc-env=MISC_BENCHES_RUSTC_VERSION={rustc_version}");
+    println!(
+        "cargo:rustc-env=MISC_BENCHES_TARGET={}",
+        env::var("TARGET").unwrap()
+    );
+    println!(
+        "cargo:rustc-env=MISC_BENCHES_PROFILE={}",
+        env::var("PROFILE").unwrap()
+    );
+    println!("cargo:rerun-if-env-changed=RUSTC");
+
+    // `std::simd` versions of some kernels are enabled with
+    // `RUSTFLAGS="--cfg misc_benches_nightly"` on a nightly compiler. This is a
+    // cfg rather than a feature so that `--all-features` builds on stable.
+    println!("cargo:rustc-check-cfg=cfg(misc_benches_nightly)");
+}
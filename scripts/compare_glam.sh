@@ -0,0 +1,15 @@
+#!/bin/sh
+# Run the glam-heavy suites with glam's SIMD backend and save the results as a
+# Criterion baseline, then run them again with the scalar backend and compare
+# against that baseline.
+#
+# Usage: scripts/compare_glam.sh [extra criterion args, e.g. a filter]
+set -e
+
+cd "$(dirname "$0")/.."
+
+BENCHES="--bench normalize --bench point --bench decompose --bench direction --bench isometry
+    --bench lerp --bench easing"
+
+cargo bench $BENCHES --features glam-simd -- --save-baseline glam-simd "$@"
+cargo bench $BENCHES --features glam-scalar -- --baseline glam-simd "$@"
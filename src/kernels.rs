@@ -0,0 +1,94 @@
+pub mod animation;
+pub mod audio;
+pub mod bandwidth;
+pub mod boids;
+pub mod bounding;
+pub mod bvh;
+pub mod camera;
+pub mod color;
+pub mod convex;
+pub mod culling;
+pub mod decompose;
+pub mod direction;
+pub mod easing;
+pub mod geometry;
+pub mod hierarchy;
+pub mod integrate;
+pub mod isometry;
+pub mod lerp;
+pub mod memory;
+pub mod mesh;
+pub mod morton;
+#[cfg(feature = "nalgebra")]
+pub mod nalgebra_backend;
+#[cfg(target_arch = "aarch64")]
+pub mod neon;
+pub mod noise;
+pub mod normalize;
+pub mod particles;
+pub mod pathfinding;
+pub mod point;
+pub mod quantize;
+pub mod random;
+pub mod rot2d;
+pub mod rsqrt;
+pub mod sampling;
+pub mod scalar;
+#[cfg(misc_benches_nightly)]
+pub mod simd;
+pub mod smooth;
+pub mod spatial;
+pub mod spline;
+pub mod texture;
+pub mod threads;
+pub mod transcendental;
+pub mod triangulate;
+#[cfg(feature = "ultraviolet")]
+pub mod ultraviolet_backend;
+pub mod vector;
+#[cfg(target_arch = "x86_64")]
+pub mod x86;
+
+#[cfg(test)]
+mod test_util {
+    use glam::Quat;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // The default array length and tolerance for kernel tests. Modules that
+    // need a remainder or tighter bounds define their own.
+    pub const COUNT: usize = 1000;
+    pub const TOLERANCE: f32 = 1e-4;
+
+    // A fixed seed, so failures are reproducible.
+    pub fn test_rng() -> StdRng {
+        StdRng::seed_from_u64(1234)
+    }
+
+    // Run `f` on `dst` and return it, so that each variant of a kernel can
+    // write to its own output.
+    pub fn run_into<T>(mut dst: Vec<T>, f: impl FnOnce(&mut [T])) -> Vec<T> {
+        f(&mut dst);
+
+        dst
+    }
+
+    // Quats q and -q represent the same rotation, so compare both.
+    pub fn quat_near(l: Quat, r: Quat, tolerance: f32) -> bool {
+        l.abs_diff_eq(r, tolerance) || l.abs_diff_eq(-r, tolerance)
+    }
+
+    // Return the distance between two floats in units in the last place.
+    pub fn ulps(l: f32, r: f32) -> u32 {
+        let to_ordered = |f: f32| {
+            let bits = f.to_bits() as i32;
+
+            if bits < 0 {
+                i32::MIN - bits
+            } else {
+                bits
+            }
+        };
+
+        to_ordered(l).abs_diff(to_ordered(r))
+    }
+}
@@ -0,0 +1,821 @@
+use crate::util::random_quat;
+use glam::{Affine3A, Mat3A, Mat4, Quat, Vec3, Vec3A};
+use rand::Rng;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A scalar animation curve with linear interpolation between keyframes. The
+// times are sorted and there are always at least two keyframes.
+pub struct Track {
+    pub times: Vec<f32>,
+    pub values: Vec<f32>,
+}
+
+impl Track {
+    pub fn duration(&self) -> f32 {
+        *self.times.last().unwrap()
+    }
+
+    // Interpolate between keyframe `i` and the next keyframe. Times outside the
+    // track are clamped to the first or last keyframe.
+    #[inline]
+    pub fn sample(&self, i: usize, t: f32) -> f32 {
+        let (t0, t1) = (self.times[i], self.times[i + 1]);
+        let (v0, v1) = (self.values[i], self.values[i + 1]);
+
+        let fraction = ((t - t0) / (t1 - t0)).clamp(0.0, 1.0);
+
+        v0 + ((v1 - v0) * fraction)
+    }
+}
+
+// Return a track with randomly spaced keyframes starting at time zero.
+pub fn random_track(rng: &mut impl Rng, count: usize) -> Track {
+    let times = std::iter::successors(Some(0.0f32), |&t| Some(t + rng.gen_range(0.01..0.1)))
+        .take(count)
+        .collect();
+
+    let values = (0..count).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+    Track { times, values }
+}
+
+// The keyframe search functions return the index of the keyframe at or before
+// `t`, clamped so that there's always a following keyframe to interpolate to.
+
+#[inline]
+pub fn find_keyframe_linear(times: &[f32], t: f32) -> usize {
+    let last = times.len() - 2;
+    let mut i = 0;
+
+    while (i < last) && (times[i + 1] <= t) {
+        i += 1;
+    }
+
+    i
+}
+
+#[inline]
+pub fn find_keyframe_binary(times: &[f32], t: f32) -> usize {
+    times[1..(times.len() - 1)].partition_point(|&k| k <= t)
+}
+
+// Remembers the last keyframe found, and steps forward from there. This is
+// cheap if time advances by less than a keyframe per lookup. If time goes
+// backwards then it falls back to binary search.
+#[derive(Default)]
+pub struct KeyframeCursor {
+    pub index: usize,
+}
+
+impl KeyframeCursor {
+    #[inline]
+    pub fn find(&mut self, times: &[f32], t: f32) -> usize {
+        if t < times[self.index] {
+            self.index = find_keyframe_binary(times, t);
+        } else {
+            let last = times.len() - 2;
+
+            while (self.index < last) && (times[self.index + 1] <= t) {
+                self.index += 1;
+            }
+        }
+
+        self.index
+    }
+}
+
+pub struct KeyframeParams<'a> {
+    pub dst_array: &'a mut [f32],
+    // Sample times.
+    pub src_array: &'a [f32],
+    pub track: &'a Track,
+}
+
+#[inline(never)]
+pub fn keyframe_linear(params: &mut KeyframeParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+        let key = find_keyframe_linear(&params.track.times, t);
+
+        params.dst_array[i] = params.track.sample(key, t);
+    }
+}
+
+#[inline(never)]
+pub fn keyframe_binary(params: &mut KeyframeParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+        let key = find_keyframe_binary(&params.track.times, t);
+
+        params.dst_array[i] = params.track.sample(key, t);
+    }
+}
+
+#[inline(never)]
+pub fn keyframe_cursor(params: &mut KeyframeParams) {
+    let mut cursor = KeyframeCursor::default();
+
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+        let key = cursor.find(&params.track.times, t);
+
+        params.dst_array[i] = params.track.sample(key, t);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A vertex influenced by up to four joints. The weights sum to one.
+#[derive(Clone, Copy, Debug)]
+pub struct SkinVertex {
+    pub position: Vec3,
+    pub joints: [u16; 4],
+    pub weights: [f32; 4],
+}
+
+pub fn random_skin_vertex_array(
+    rng: &mut impl Rng,
+    count: usize,
+    joint_count: usize,
+) -> Vec<SkinVertex> {
+    (0..count)
+        .map(|_| {
+            let weights: [f32; 4] = rng.gen();
+            let total = weights.iter().sum::<f32>();
+
+            SkinVertex {
+                position: rng.gen::<Vec3>() * 2.0 - 1.0,
+                joints: std::array::from_fn(|_| rng.gen_range(0..joint_count) as u16),
+                weights: weights.map(|w| w / total),
+            }
+        })
+        .collect()
+}
+
+// Return rigid joint transforms as rotations and translations, which can be
+// converted to any of the palette types.
+pub fn random_joint_array(rng: &mut impl Rng, count: usize) -> Vec<(Quat, Vec3)> {
+    (0..count)
+        .map(|_| (random_quat(rng), rng.gen::<Vec3>() * 2.0 - 1.0))
+        .collect()
+}
+
+// A rigid transform stored as a dual quaternion. Blending dual quaternions
+// avoids the volume loss of blending matrices, but can't represent scale.
+#[derive(Clone, Copy, Debug)]
+pub struct DualQuat {
+    pub real: Quat,
+    pub dual: Quat,
+}
+
+impl DualQuat {
+    pub const ZERO: Self = DualQuat {
+        real: Quat::from_xyzw(0.0, 0.0, 0.0, 0.0),
+        dual: Quat::from_xyzw(0.0, 0.0, 0.0, 0.0),
+    };
+
+    pub fn from_rotation_translation(rotation: Quat, translation: Vec3) -> Self {
+        DualQuat {
+            real: rotation,
+            dual: Quat::from_xyzw(translation.x, translation.y, translation.z, 0.0)
+                * rotation
+                * 0.5,
+        }
+    }
+
+    // Assumes the real part is normalized.
+    #[inline]
+    pub fn transform_point3(&self, point: Vec3) -> Vec3 {
+        let (r, d) = (self.real, self.dual);
+
+        let r_xyz = Vec3::new(r.x, r.y, r.z);
+        let d_xyz = Vec3::new(d.x, d.y, d.z);
+
+        let translation = 2.0 * ((r.w * d_xyz) - (d.w * r_xyz) + r_xyz.cross(d_xyz));
+
+        (r * point) + translation
+    }
+}
+
+pub struct SkinParams<'a, P> {
+    pub dst_array: &'a mut [Vec3],
+    pub src_array: &'a [SkinVertex],
+    pub palette: &'a [P],
+}
+
+#[inline(never)]
+pub fn skin_mat4(params: &mut SkinParams<Mat4>) {
+    for i in 0..params.dst_array.len() {
+        let vertex = &params.src_array[i];
+
+        let mut blended = Mat4::ZERO;
+
+        for j in 0..4 {
+            blended += params.palette[vertex.joints[j] as usize] * vertex.weights[j];
+        }
+
+        params.dst_array[i] = blended.transform_point3(vertex.position);
+    }
+}
+
+#[inline(never)]
+pub fn skin_affine(params: &mut SkinParams<Affine3A>) {
+    for i in 0..params.dst_array.len() {
+        let vertex = &params.src_array[i];
+
+        let mut matrix3 = Mat3A::ZERO;
+        let mut translation = Vec3A::ZERO;
+
+        for j in 0..4 {
+            let joint = &params.palette[vertex.joints[j] as usize];
+
+            matrix3 += joint.matrix3 * vertex.weights[j];
+            translation += joint.translation * vertex.weights[j];
+        }
+
+        let blended = Affine3A {
+            matrix3,
+            translation,
+        };
+
+        params.dst_array[i] = blended.transform_point3(vertex.position);
+    }
+}
+
+#[inline(never)]
+pub fn skin_dual_quat(params: &mut SkinParams<DualQuat>) {
+    for i in 0..params.dst_array.len() {
+        let vertex = &params.src_array[i];
+
+        let first = params.palette[vertex.joints[0] as usize].real;
+
+        let mut blended = DualQuat::ZERO;
+
+        for j in 0..4 {
+            let joint = &params.palette[vertex.joints[j] as usize];
+
+            // Keep all the joints in the same hemisphere as the first, so that
+            // the blend takes the shortest path.
+            let weight = if joint.real.dot(first) < 0.0 {
+                -vertex.weights[j]
+            } else {
+                vertex.weights[j]
+            };
+
+            blended.real = blended.real + (joint.real * weight);
+            blended.dual = blended.dual + (joint.dual * weight);
+        }
+
+        let inverse_length = blended.real.length_recip();
+
+        blended.real = blended.real * inverse_length;
+        blended.dual = blended.dual * inverse_length;
+
+        params.dst_array[i] = blended.transform_point3(vertex.position);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MorphVertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+}
+
+pub fn random_morph_vertex_array(rng: &mut impl Rng, count: usize) -> Vec<MorphVertex> {
+    (0..count)
+        .map(|_| MorphVertex {
+            position: rng.gen::<Vec3>() * 2.0 - 1.0,
+            normal: rng.gen::<Vec3>() * 2.0 - 1.0,
+        })
+        .collect()
+}
+
+// Convert vertices to a flat array of components, with all the position x
+// components first, then all the position y components, and so on.
+pub fn morph_vertex_array_to_soa(vertices: &[MorphVertex]) -> Vec<f32> {
+    let components: [fn(&MorphVertex) -> f32; 6] = [
+        |v| v.position.x,
+        |v| v.position.y,
+        |v| v.position.z,
+        |v| v.normal.x,
+        |v| v.normal.y,
+        |v| v.normal.z,
+    ];
+
+    components
+        .iter()
+        .flat_map(|component| vertices.iter().map(component))
+        .collect()
+}
+
+// Adds weighted target deltas to a base mesh. The SoA kernel uses the same
+// params with `T = f32`, where each array is in the layout returned by
+// `morph_vertex_array_to_soa`.
+pub struct MorphParams<'a, T> {
+    pub dst_array: &'a mut [T],
+    pub base_array: &'a [T],
+    pub target_arrays: &'a [&'a [T]],
+    pub weights: &'a [f32],
+}
+
+// Visit each vertex once and accumulate all the targets for it.
+#[inline(never)]
+pub fn morph_per_vertex(params: &mut MorphParams<MorphVertex>) {
+    for i in 0..params.dst_array.len() {
+        let mut vertex = params.base_array[i];
+
+        for (target_array, &weight) in params.target_arrays.iter().zip(params.weights) {
+            vertex.position += target_array[i].position * weight;
+            vertex.normal += target_array[i].normal * weight;
+        }
+
+        params.dst_array[i] = vertex;
+    }
+}
+
+// Copy the base, then make one pass over the destination for each target.
+#[inline(never)]
+pub fn morph_per_target(params: &mut MorphParams<MorphVertex>) {
+    params.dst_array.copy_from_slice(params.base_array);
+
+    for (target_array, &weight) in params.target_arrays.iter().zip(params.weights) {
+        for i in 0..params.dst_array.len() {
+            params.dst_array[i].position += target_array[i].position * weight;
+            params.dst_array[i].normal += target_array[i].normal * weight;
+        }
+    }
+}
+
+// Same as `morph_per_target`, but on SoA components.
+#[inline(never)]
+pub fn morph_soa(params: &mut MorphParams<f32>) {
+    params.dst_array.copy_from_slice(params.base_array);
+
+    for (target_array, &weight) in params.target_arrays.iter().zip(params.weights) {
+        for i in 0..params.dst_array.len() {
+            params.dst_array[i] += target_array[i] * weight;
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The joint positions of a chain of two bones, like an arm or a leg.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TwoBoneChain {
+    pub root: Vec3A,
+    pub mid: Vec3A,
+    pub end: Vec3A,
+}
+
+// Where to put the end of a chain. The chain bends towards the pole, like a
+// knee towards its kneecap.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IkTarget {
+    pub position: Vec3A,
+    pub pole: Vec3A,
+}
+
+fn random_bone(rng: &mut impl Rng) -> Vec3A {
+    Vec3A::from(random_quat(rng) * Vec3::X) * rng.gen_range(0.3..0.5)
+}
+
+pub fn random_two_bone_chain_array(rng: &mut impl Rng, count: usize) -> Vec<TwoBoneChain> {
+    (0..count)
+        .map(|_| {
+            let root = (rng.gen::<Vec3A>() - 0.5) * 10.0;
+            let mid = root + random_bone(rng);
+            let end = mid + random_bone(rng);
+
+            TwoBoneChain { root, mid, end }
+        })
+        .collect()
+}
+
+// Targets are in a random direction from the root, at up to 1.1 times the
+// chain's length, so some are out of reach.
+pub fn random_ik_target(rng: &mut impl Rng, root: Vec3A, length: f32) -> IkTarget {
+    let direction = Vec3A::from(random_quat(rng) * Vec3::X);
+
+    IkTarget {
+        position: root + (direction * (length * rng.gen_range(0.1..1.1))),
+        pole: root + ((rng.gen::<Vec3A>() - 0.5) * length * 2.0),
+    }
+}
+
+pub struct TwoBoneParams<'a> {
+    // World space rotations to apply to the upper and lower bones.
+    pub dst_array: &'a mut [[Quat; 2]],
+    pub chain_array: &'a [TwoBoneChain],
+    pub target_array: &'a [IkTarget],
+}
+
+// Place the mid joint with the law of cosines, in the plane of the target and
+// the pole, then return the shortest rotation that moves each bone into place.
+// Targets out of reach straighten the chain towards them.
+pub fn two_bone_ik_single(chain: &TwoBoneChain, target: &IkTarget) -> [Quat; 2] {
+    let upper = chain.mid - chain.root;
+    let lower = chain.end - chain.mid;
+
+    let (a, b) = (upper.length(), lower.length());
+
+    let to_target = target.position - chain.root;
+    let direction = to_target.normalize_or(upper / a);
+    let d = to_target.length().clamp((a - b).abs(), a + b);
+
+    // If the pole is in line with the target then bend towards the mid joint
+    // instead, and failing that, anywhere.
+    let bend = (target.pole - chain.root)
+        .reject_from_normalized(direction)
+        .try_normalize()
+        .or_else(|| upper.reject_from_normalized(direction).try_normalize())
+        .unwrap_or_else(|| direction.any_orthonormal_vector());
+
+    let cos_root = (((a * a) + (d * d) - (b * b)) / (2.0 * a * d)).clamp(-1.0, 1.0);
+    let sin_root = (1.0 - (cos_root * cos_root)).sqrt();
+
+    let new_mid = chain.root + (direction * (a * cos_root)) + (bend * (a * sin_root));
+    let new_end = chain.root + (direction * d);
+
+    [
+        Quat::from_rotation_arc((upper / a).into(), ((new_mid - chain.root) / a).into()),
+        Quat::from_rotation_arc((lower / b).into(), ((new_end - new_mid) / b).into()),
+    ]
+}
+
+#[inline(never)]
+pub fn two_bone_ik(params: &mut TwoBoneParams) {
+    for ((dst, chain), target) in params
+        .dst_array
+        .iter_mut()
+        .zip(params.chain_array)
+        .zip(params.target_array)
+    {
+        *dst = two_bone_ik_single(chain, target);
+    }
+}
+
+// Return chains of `joint_count` joints as a flat array, each a random walk
+// from its root.
+pub fn random_ik_chain_array(rng: &mut impl Rng, count: usize, joint_count: usize) -> Vec<Vec3A> {
+    let mut joints = Vec::with_capacity(count * joint_count);
+
+    for _ in 0..count {
+        let mut joint = (rng.gen::<Vec3A>() - 0.5) * 10.0;
+
+        joints.push(joint);
+
+        for _ in 1..joint_count {
+            joint += random_bone(rng);
+            joints.push(joint);
+        }
+    }
+
+    joints
+}
+
+pub fn chain_length(joints: &[Vec3A]) -> f32 {
+    joints.windows(2).map(|w| w[0].distance(w[1])).sum()
+}
+
+// The solved joints are written to `dst_array`, in the same layout as
+// `src_array`.
+pub struct FabrikParams<'a> {
+    pub dst_array: &'a mut [Vec3A],
+    pub src_array: &'a [Vec3A],
+    pub target_array: &'a [Vec3A],
+    pub joint_count: usize,
+    pub max_iterations: usize,
+    pub tolerance: f32,
+}
+
+// The most joints in a FABRIK chain, so the bone lengths fit on the stack.
+pub const FABRIK_MAX_JOINTS: usize = 16;
+
+// Aristidou and Lasenby, "FABRIK: A fast, iterative solver for the Inverse
+// Kinematics problem" (2011). Each iteration drags the chain from the end to
+// the target, then back to the root, keeping the bone lengths. Stops when the
+// end is within `tolerance` of the target.
+pub fn fabrik_single(joints: &mut [Vec3A], target: Vec3A, max_iterations: usize, tolerance: f32) {
+    let n = joints.len();
+
+    assert!(n <= FABRIK_MAX_JOINTS);
+
+    let mut lengths = [0.0; FABRIK_MAX_JOINTS];
+
+    for i in 0..(n - 1) {
+        lengths[i] = joints[i].distance(joints[i + 1]);
+    }
+
+    let root = joints[0];
+
+    // Place `to` on the line from `from` towards its current position.
+    let follow =
+        |from: Vec3A, to: Vec3A, length: f32| from + ((to - from).normalize_or_zero() * length);
+
+    // Out of reach, so straighten towards the target.
+    if root.distance(target) >= lengths.iter().sum::<f32>() {
+        for i in 0..(n - 1) {
+            joints[i + 1] = follow(joints[i], target, lengths[i]);
+        }
+
+        return;
+    }
+
+    for _ in 0..max_iterations {
+        if joints[n - 1].distance(target) <= tolerance {
+            break;
+        }
+
+        joints[n - 1] = target;
+
+        for i in (0..(n - 1)).rev() {
+            joints[i] = follow(joints[i + 1], joints[i], lengths[i]);
+        }
+
+        joints[0] = root;
+
+        for i in 0..(n - 1) {
+            joints[i + 1] = follow(joints[i], joints[i + 1], lengths[i]);
+        }
+    }
+}
+
+#[inline(never)]
+pub fn fabrik(params: &mut FabrikParams) {
+    params.dst_array.copy_from_slice(params.src_array);
+
+    for (joints, &target) in params
+        .dst_array
+        .chunks_exact_mut(params.joint_count)
+        .zip(params.target_array)
+    {
+        fabrik_single(joints, target, params.max_iterations, params.tolerance);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{run_into, test_rng, COUNT};
+
+    #[test]
+    fn keyframe() {
+        let mut rng = test_rng();
+
+        for key_count in [2, 3, 16, 256] {
+            let track = random_track(&mut rng, key_count);
+
+            // Include times outside the track to check the clamping.
+            let random_times = (0..COUNT)
+                .map(|_| rng.gen_range(-0.1..(track.duration() + 0.1)))
+                .collect::<Vec<_>>();
+
+            let mut sorted_times = random_times.clone();
+            sorted_times.sort_by(f32::total_cmp);
+
+            for src_array in [&random_times, &sorted_times] {
+                let variants = [
+                    keyframe_linear as fn(&mut KeyframeParams),
+                    keyframe_binary,
+                    keyframe_cursor,
+                ];
+
+                let [expected, binary, cursor] = variants.map(|f| {
+                    run_into(vec![0.0; COUNT], |dst_array| {
+                        f(&mut KeyframeParams {
+                            dst_array,
+                            src_array,
+                            track: &track,
+                        })
+                    })
+                });
+
+                assert_eq!(binary, expected);
+                assert_eq!(cursor, expected);
+            }
+        }
+    }
+
+    fn run_skin<P>(
+        f: fn(&mut SkinParams<P>),
+        src_array: &[SkinVertex],
+        joints: &[(Quat, Vec3)],
+        convert: fn(Quat, Vec3) -> P,
+    ) -> Vec<Vec3> {
+        let palette = joints
+            .iter()
+            .map(|&(r, t)| convert(r, t))
+            .collect::<Vec<_>>();
+
+        run_into(vec![Vec3::ZERO; src_array.len()], |dst_array| {
+            f(&mut SkinParams {
+                dst_array,
+                src_array,
+                palette: &palette,
+            })
+        })
+    }
+
+    #[test]
+    fn skin() {
+        let mut rng = test_rng();
+
+        let joints = random_joint_array(&mut rng, 16);
+        let blended = random_skin_vertex_array(&mut rng, COUNT, joints.len());
+
+        // With a single joint per vertex there's no blending, so dual
+        // quaternions must agree with matrices.
+        let single = blended
+            .iter()
+            .map(|&v| SkinVertex {
+                joints: [v.joints[0]; 4],
+                weights: [1.0, 0.0, 0.0, 0.0],
+                ..v
+            })
+            .collect::<Vec<_>>();
+
+        for src_array in [&blended, &single] {
+            let mat4 = run_skin(
+                skin_mat4,
+                src_array,
+                &joints,
+                Mat4::from_rotation_translation,
+            );
+            let affine = run_skin(
+                skin_affine,
+                src_array,
+                &joints,
+                Affine3A::from_rotation_translation,
+            );
+
+            for i in 0..COUNT {
+                assert!(affine[i].abs_diff_eq(mat4[i], 1e-4));
+            }
+        }
+
+        let mat4 = run_skin(skin_mat4, &single, &joints, Mat4::from_rotation_translation);
+        let dual_quat = run_skin(
+            skin_dual_quat,
+            &single,
+            &joints,
+            DualQuat::from_rotation_translation,
+        );
+
+        for i in 0..COUNT {
+            assert!(dual_quat[i].abs_diff_eq(mat4[i], 1e-4));
+        }
+    }
+
+    #[test]
+    fn morph() {
+        let mut rng = test_rng();
+
+        const TARGET_COUNT: usize = 8;
+
+        let base_array = random_morph_vertex_array(&mut rng, COUNT);
+        let targets = (0..TARGET_COUNT)
+            .map(|_| random_morph_vertex_array(&mut rng, COUNT))
+            .collect::<Vec<_>>();
+        let target_arrays = targets.iter().map(|t| &t[..]).collect::<Vec<_>>();
+        let weights = (0..TARGET_COUNT).map(|_| rng.gen()).collect::<Vec<f32>>();
+
+        let [per_vertex, per_target] = [
+            morph_per_vertex as fn(&mut MorphParams<MorphVertex>),
+            morph_per_target,
+        ]
+        .map(|f| {
+            run_into(vec![MorphVertex::default(); COUNT], |dst_array| {
+                f(&mut MorphParams {
+                    dst_array,
+                    base_array: &base_array,
+                    target_arrays: &target_arrays,
+                    weights: &weights,
+                })
+            })
+        });
+
+        assert_eq!(per_target, per_vertex);
+
+        let soa_targets = targets
+            .iter()
+            .map(|t| morph_vertex_array_to_soa(t))
+            .collect::<Vec<_>>();
+        let soa_target_arrays = soa_targets.iter().map(|t| &t[..]).collect::<Vec<_>>();
+
+        let mut soa = vec![0.0; COUNT * 6];
+
+        morph_soa(&mut MorphParams {
+            dst_array: &mut soa,
+            base_array: &morph_vertex_array_to_soa(&base_array),
+            target_arrays: &soa_target_arrays,
+            weights: &weights,
+        });
+
+        assert_eq!(soa, morph_vertex_array_to_soa(&per_vertex));
+    }
+
+    #[test]
+    fn ik() {
+        let mut rng = test_rng();
+
+        // Two bones reach any target in range and keep their lengths. Targets
+        // out of range get the chain pointing straight at them.
+
+        let chain_array = random_two_bone_chain_array(&mut rng, COUNT);
+        let target_array = chain_array
+            .iter()
+            .map(|c| {
+                random_ik_target(
+                    &mut rng,
+                    c.root,
+                    c.root.distance(c.mid) + c.mid.distance(c.end),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut dst_array = vec![[Quat::IDENTITY; 2]; COUNT];
+
+        two_bone_ik(&mut TwoBoneParams {
+            dst_array: &mut dst_array,
+            chain_array: &chain_array,
+            target_array: &target_array,
+        });
+
+        for ((chain, target), [upper, lower]) in
+            chain_array.iter().zip(&target_array).zip(&dst_array)
+        {
+            let (a, b) = (chain.mid - chain.root, chain.end - chain.mid);
+
+            let mid = chain.root + (*upper * a);
+            let end = mid + (*lower * b);
+
+            let reach = a.length() + b.length();
+            let distance = chain.root.distance(target.position);
+
+            if (distance < reach) && (distance > (a.length() - b.length()).abs()) {
+                assert!(end.distance(target.position) < 1e-4);
+            } else {
+                let direction = (target.position - chain.root).normalize();
+
+                assert!((end - chain.root).normalize().dot(direction) > 0.9999);
+            }
+
+            assert!((mid.distance(chain.root) - a.length()).abs() < 1e-5);
+            assert!((end.distance(mid) - b.length()).abs() < 1e-5);
+        }
+
+        // FABRIK gets within tolerance of targets in range, keeping the root
+        // and the bone lengths.
+
+        let joint_count = 5;
+
+        let src_array = random_ik_chain_array(&mut rng, COUNT, joint_count);
+        let target_array = src_array
+            .chunks_exact(joint_count)
+            .map(|joints| random_ik_target(&mut rng, joints[0], chain_length(joints)).position)
+            .collect::<Vec<_>>();
+
+        let mut dst_array = vec![Vec3A::ZERO; src_array.len()];
+
+        let tolerance = 1e-3;
+
+        fabrik(&mut FabrikParams {
+            dst_array: &mut dst_array,
+            src_array: &src_array,
+            target_array: &target_array,
+            joint_count,
+            max_iterations: 100,
+            tolerance,
+        });
+
+        let (mut in_range, mut reached) = (0, 0);
+
+        for ((src, dst), target) in src_array
+            .chunks_exact(joint_count)
+            .zip(dst_array.chunks_exact(joint_count))
+            .zip(&target_array)
+        {
+            assert_eq!(src[0], dst[0]);
+
+            for (s, d) in src.windows(2).zip(dst.windows(2)) {
+                assert!((s[0].distance(s[1]) - d[0].distance(d[1])).abs() < 1e-4);
+            }
+
+            if src[0].distance(*target) < chain_length(src) {
+                in_range += 1;
+                reached += (dst[joint_count - 1].distance(*target) <= tolerance) as usize;
+            }
+        }
+
+        // Targets near the root can still be out of reach, if one bone is
+        // longer than the rest put together.
+        assert!(in_range > (COUNT / 2));
+        assert!(reached >= (in_range * 99 / 100));
+    }
+}
@@ -0,0 +1,425 @@
+use crate::util::CacheAlignedVec;
+use glam::Vec4;
+use rand::Rng;
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Streams are stereo, with the left and right samples interleaved. One left
+// and right pair is a frame.
+
+pub const CHANNELS: usize = 2;
+
+// A few sine waves plus some noise, so the samples aren't all the same size.
+pub fn random_audio_stream<R: Rng + ?Sized>(rng: &mut R, frames: usize) -> CacheAlignedVec<f32> {
+    let frequencies = [(); 3].map(|_| rng.gen_range(0.001..0.1));
+
+    (0..(frames * CHANNELS))
+        .map(|i| {
+            let t = (i / CHANNELS) as f32;
+
+            let tone = frequencies.iter().map(|f| (t * f).sin()).sum::<f32>() / 4.0;
+
+            tone + rng.gen_range(-0.25..0.25)
+        })
+        .collect()
+}
+
+// Gains that pan each voice somewhere between left and right, at a random
+// volume.
+pub fn random_voice_gains<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Vec<[f32; CHANNELS]> {
+    (0..count)
+        .map(|_| {
+            let pan = rng.gen::<f32>() * std::f32::consts::FRAC_PI_2;
+            let volume = rng.gen_range(0.1..1.0);
+
+            [pan.cos() * volume, pan.sin() * volume]
+        })
+        .collect()
+}
+
+// The voices are summed into `dst_array`, which is overwritten. Each voice has
+// a gain per channel. All versions add the voices in the same order, so they
+// give identical results.
+pub struct MixParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub src_arrays: &'a [&'a [f32]],
+    pub gains: &'a [[f32; CHANNELS]],
+}
+
+// The frames left over after the unrolled or vector loop.
+pub fn mix_remainder(dst: &mut [f32], src: &[f32], l: f32, r: f32) {
+    for (d, s) in dst
+        .chunks_exact_mut(CHANNELS)
+        .zip(src.chunks_exact(CHANNELS))
+    {
+        d[0] += s[0] * l;
+        d[1] += s[1] * r;
+    }
+}
+
+#[inline(never)]
+pub fn mix_scalar(params: &mut MixParams) {
+    params.dst_array.fill(0.0);
+
+    for (src, gain) in params.src_arrays.iter().zip(params.gains) {
+        for (dst, src) in params
+            .dst_array
+            .chunks_exact_mut(CHANNELS)
+            .zip(src.chunks_exact(CHANNELS))
+        {
+            dst[0] += src[0] * gain[0];
+            dst[1] += src[1] * gain[1];
+        }
+    }
+}
+
+// Four frames per iteration, written out by hand. Fixed size chunks let the
+// compiler drop the bounds checks.
+#[inline(never)]
+pub fn mix_unrolled(params: &mut MixParams) {
+    const SAMPLES: usize = 4 * CHANNELS;
+
+    params.dst_array.fill(0.0);
+
+    for (src, &[l, r]) in params.src_arrays.iter().zip(params.gains) {
+        let mut dst_chunks = params.dst_array.chunks_exact_mut(SAMPLES);
+        let mut src_chunks = src.chunks_exact(SAMPLES);
+
+        for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+            d[0] += s[0] * l;
+            d[1] += s[1] * r;
+            d[2] += s[2] * l;
+            d[3] += s[3] * r;
+            d[4] += s[4] * l;
+            d[5] += s[5] * r;
+            d[6] += s[6] * l;
+            d[7] += s[7] * r;
+        }
+
+        mix_remainder(dst_chunks.into_remainder(), src_chunks.remainder(), l, r);
+    }
+}
+
+// Two frames per vector, with the gains repeated to match.
+#[inline(never)]
+pub fn mix_x4(params: &mut MixParams) {
+    const LANES: usize = 4;
+
+    params.dst_array.fill(0.0);
+
+    for (src, &[l, r]) in params.src_arrays.iter().zip(params.gains) {
+        let gain = Vec4::new(l, r, l, r);
+
+        let mut dst_chunks = params.dst_array.chunks_exact_mut(LANES);
+        let mut src_chunks = src.chunks_exact(LANES);
+
+        for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+            (Vec4::from_slice(d) + (Vec4::from_slice(s) * gain)).write_to_slice(d);
+        }
+
+        mix_remainder(dst_chunks.into_remainder(), src_chunks.remainder(), l, r);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub const SAMPLE_RATE: f32 = 48000.0;
+
+// Normalized so that a0 is one. The difference equation is:
+//
+//     y[n] = b0 x[n] + b1 x[n-1] + b2 x[n-2] - a1 y[n-1] - a2 y[n-2]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BiquadCoefficients {
+    pub b0: f32,
+    pub b1: f32,
+    pub b2: f32,
+    pub a1: f32,
+    pub a2: f32,
+}
+
+// From Bristow-Johnson, "Cookbook formulae for audio EQ biquad filter
+// coefficients".
+impl BiquadCoefficients {
+    fn normalized(b: [f32; 3], a: [f32; 3]) -> Self {
+        BiquadCoefficients {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+        }
+    }
+
+    pub fn low_pass(frequency: f32, q: f32) -> Self {
+        let w0 = std::f32::consts::TAU * frequency / SAMPLE_RATE;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+
+        Self::normalized(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    // A shelf slope of one, the steepest without overshoot.
+    pub fn high_shelf(frequency: f32, gain_db: f32) -> Self {
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let w0 = std::f32::consts::TAU * frequency / SAMPLE_RATE;
+        let (sin, cos) = w0.sin_cos();
+        let alpha_sqrt_a = (sin / std::f32::consts::SQRT_2) * 2.0 * a.sqrt();
+
+        Self::normalized(
+            [
+                a * ((a + 1.0) + ((a - 1.0) * cos) + alpha_sqrt_a),
+                -2.0 * a * ((a - 1.0) + ((a + 1.0) * cos)),
+                a * ((a + 1.0) + ((a - 1.0) * cos) - alpha_sqrt_a),
+            ],
+            [
+                (a + 1.0) - ((a - 1.0) * cos) + alpha_sqrt_a,
+                2.0 * ((a - 1.0) - ((a + 1.0) * cos)),
+                (a + 1.0) - ((a - 1.0) * cos) - alpha_sqrt_a,
+            ],
+        )
+    }
+}
+
+// Alternating low-pass and high-shelf filters, like a chain of EQ bands.
+pub fn random_biquad_chain<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Vec<BiquadCoefficients> {
+    (0..count)
+        .map(|i| {
+            if (i & 1) == 0 {
+                BiquadCoefficients::low_pass(
+                    rng.gen_range(2000.0..16000.0),
+                    rng.gen_range(0.5..2.0),
+                )
+            } else {
+                BiquadCoefficients::high_shelf(
+                    rng.gen_range(1000.0..8000.0),
+                    rng.gen_range(-12.0..12.0),
+                )
+            }
+        })
+        .collect()
+}
+
+// The memory of one biquad in a chain, which depends on how the difference
+// equation is arranged.
+pub trait BiquadState: Copy + Default {
+    fn process(&mut self, c: &BiquadCoefficients, x: f32) -> f32;
+}
+
+// Keeps the last two inputs and outputs. Four values of state, but the
+// feedback only goes through the outputs, so it's robust to coefficient
+// changes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DirectForm1 {
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl BiquadState for DirectForm1 {
+    #[inline(always)]
+    fn process(&mut self, c: &BiquadCoefficients, x: f32) -> f32 {
+        let y =
+            (c.b0 * x) + (c.b1 * self.x1) + (c.b2 * self.x2) - (c.a1 * self.y1) - (c.a2 * self.y2);
+
+        *self = DirectForm1 {
+            x1: x,
+            x2: self.x1,
+            y1: y,
+            y2: self.y1,
+        };
+
+        y
+    }
+}
+
+// Two values of state, and better numerical behavior in floating point than
+// direct form II.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TransposedDirectForm2 {
+    s1: f32,
+    s2: f32,
+}
+
+impl BiquadState for TransposedDirectForm2 {
+    #[inline(always)]
+    fn process(&mut self, c: &BiquadCoefficients, x: f32) -> f32 {
+        let y = (c.b0 * x) + self.s1;
+
+        self.s1 = (c.b1 * x) - (c.a1 * y) + self.s2;
+        self.s2 = (c.b2 * x) - (c.a2 * y);
+
+        y
+    }
+}
+
+// Filters `src_array` through every biquad in the chain, in order, and writes
+// the result to `dst_array`. There's one state per biquad, which carries over
+// between calls like a stream of buffers.
+pub struct BiquadParams<'a, S> {
+    pub dst_array: &'a mut [f32],
+    pub src_array: &'a [f32],
+    pub chain: &'a [BiquadCoefficients],
+    pub states: &'a mut [S],
+}
+
+// Each sample goes through the whole chain before the next, so every filter's
+// coefficients and state are live at once.
+#[inline(never)]
+pub fn biquad_per_sample<S: BiquadState>(params: &mut BiquadParams<S>) {
+    for (dst, &src) in params.dst_array.iter_mut().zip(params.src_array) {
+        let mut x = src;
+
+        for (c, state) in params.chain.iter().zip(params.states.iter_mut()) {
+            x = state.process(c, x);
+        }
+
+        *dst = x;
+    }
+}
+
+// Each block of samples goes through one filter at a time, so only one
+// filter's coefficients and state are live. Same results as
+// `biquad_per_sample`.
+#[inline(never)]
+pub fn biquad_blocks<S: BiquadState>(params: &mut BiquadParams<S>, block_len: usize) {
+    for (dst, src) in params
+        .dst_array
+        .chunks_mut(block_len)
+        .zip(params.src_array.chunks(block_len))
+    {
+        dst.copy_from_slice(src);
+
+        for (c, state) in params.chain.iter().zip(params.states.iter_mut()) {
+            let mut s = *state;
+
+            for x in dst.iter_mut() {
+                *x = s.process(c, *x);
+            }
+
+            *state = s;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{run_into, test_rng};
+
+    // Not a multiple of the unroll or vector width, so the remainder is tested.
+    const FRAMES: usize = 1003;
+
+    #[test]
+    fn mix() {
+        let mut rng = test_rng();
+
+        let streams = (0..5)
+            .map(|_| random_audio_stream(&mut rng, FRAMES))
+            .collect::<Vec<_>>();
+
+        let src_arrays = streams.iter().map(|s| s.as_slice()).collect::<Vec<_>>();
+        let gains = random_voice_gains(&mut rng, src_arrays.len());
+
+        let run = |f: fn(&mut MixParams)| {
+            run_into(vec![1.0; FRAMES * CHANNELS], |dst_array| {
+                f(&mut MixParams {
+                    dst_array,
+                    src_arrays: &src_arrays,
+                    gains: &gains,
+                })
+            })
+        };
+
+        let expected = run(mix_scalar);
+
+        assert_eq!(run(mix_unrolled), expected);
+        assert_eq!(run(mix_x4), expected);
+
+        for (frame, dst) in expected.chunks_exact(CHANNELS).enumerate() {
+            for (channel, &d) in dst.iter().enumerate() {
+                let sum = src_arrays
+                    .iter()
+                    .zip(&gains)
+                    .map(|(s, g)| s[(frame * CHANNELS) + channel] * g[channel])
+                    .sum::<f32>();
+
+                assert!((d - sum).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn biquad() {
+        let mut rng = test_rng();
+
+        let src_array = random_audio_stream(&mut rng, FRAMES);
+        let chain = random_biquad_chain(&mut rng, 5);
+
+        fn run<S: BiquadState>(
+            src_array: &[f32],
+            chain: &[BiquadCoefficients],
+            f: impl Fn(&mut BiquadParams<S>),
+        ) -> Vec<f32> {
+            run_into(vec![0.0; src_array.len()], |dst_array| {
+                f(&mut BiquadParams {
+                    dst_array,
+                    src_array,
+                    chain,
+                    states: &mut vec![S::default(); chain.len()],
+                })
+            })
+        }
+
+        let df1 = run::<DirectForm1>(&src_array, &chain, biquad_per_sample);
+        let tdf2 = run::<TransposedDirectForm2>(&src_array, &chain, biquad_per_sample);
+
+        for block_len in [1, 16, 64, src_array.len()] {
+            assert_eq!(
+                run(&src_array, &chain, |p: &mut BiquadParams<DirectForm1>| {
+                    biquad_blocks(p, block_len)
+                }),
+                df1
+            );
+            assert_eq!(
+                run(
+                    &src_array,
+                    &chain,
+                    |p: &mut BiquadParams<TransposedDirectForm2>| biquad_blocks(p, block_len)
+                ),
+                tdf2
+            );
+        }
+
+        // The forms are the same filter, arranged differently.
+        for (l, r) in df1.iter().zip(&tdf2) {
+            assert!((l - r).abs() < 1e-4);
+        }
+
+        // Check the gain at DC and at the Nyquist frequency, once the filter
+        // has settled.
+
+        let gain = |c: BiquadCoefficients, nyquist: bool| {
+            let src_array = (0..1000)
+                .map(|i| if nyquist && ((i & 1) == 1) { -1.0 } else { 1.0 })
+                .collect::<Vec<f32>>();
+
+            let dst_array = run::<TransposedDirectForm2>(&src_array, &[c], biquad_per_sample);
+
+            dst_array[dst_array.len() - 1].abs()
+        };
+
+        let low_pass = BiquadCoefficients::low_pass(1000.0, std::f32::consts::FRAC_1_SQRT_2);
+
+        assert!((gain(low_pass, false) - 1.0).abs() < 1e-3);
+        assert!(gain(low_pass, true) < 1e-3);
+
+        let high_shelf = BiquadCoefficients::high_shelf(1000.0, 12.0);
+
+        assert!((gain(high_shelf, false) - 1.0).abs() < 1e-3);
+        assert!((gain(high_shelf, true) - 10.0f32.powf(12.0 / 20.0)).abs() < 1e-2);
+    }
+}
@@ -0,0 +1,128 @@
+use rayon::prelude::*;
+use std::ops::{Add, Mul};
+
+// McCalpin's STREAM kernels, "Memory Bandwidth and Machine Balance in Current
+// High Performance Computers" (1995). Each one streams through whole arrays
+// with barely any arithmetic, so on large arrays they measure the memory
+// bandwidth that other kernels can hope for.
+
+pub trait StreamElement: Copy + Send + Sync + Add<Output = Self> + Mul<Output = Self> {}
+
+impl<T: Copy + Send + Sync + Add<Output = T> + Mul<Output = T>> StreamElement for T {}
+
+// Both sources must be as long as the destination, though copy and scale only
+// read the first.
+pub struct StreamParams<'a, T> {
+    pub dst_array: &'a mut [T],
+    pub src_arrays: [&'a [T]; 2],
+    pub scalar: T,
+}
+
+// The number of arrays each kernel reads or writes, for counting bytes.
+pub const STREAM_COPY_ARRAYS: usize = 2;
+pub const STREAM_SCALE_ARRAYS: usize = 2;
+pub const STREAM_ADD_ARRAYS: usize = 3;
+pub const STREAM_TRIAD_ARRAYS: usize = 3;
+
+#[inline(never)]
+pub fn stream_copy<T: StreamElement>(params: &mut StreamParams<T>) {
+    params.dst_array.copy_from_slice(params.src_arrays[0]);
+}
+
+#[inline(never)]
+pub fn stream_scale<T: StreamElement>(params: &mut StreamParams<T>) {
+    let q = params.scalar;
+
+    for (dst, &c) in params.dst_array.iter_mut().zip(params.src_arrays[0]) {
+        *dst = q * c;
+    }
+}
+
+#[inline(never)]
+pub fn stream_add<T: StreamElement>(params: &mut StreamParams<T>) {
+    let [a, b] = params.src_arrays;
+
+    for ((dst, &a), &b) in params.dst_array.iter_mut().zip(a).zip(b) {
+        *dst = a + b;
+    }
+}
+
+#[inline(never)]
+pub fn stream_triad<T: StreamElement>(params: &mut StreamParams<T>) {
+    let [b, c] = params.src_arrays;
+    let q = params.scalar;
+
+    for ((dst, &b), &c) in params.dst_array.iter_mut().zip(b).zip(c) {
+        *dst = b + (q * c);
+    }
+}
+
+// Split the arrays into one contiguous chunk per thread of the current rayon
+// pool, and run `f` on each chunk.
+#[inline(never)]
+pub fn stream_parallel<T: StreamElement>(
+    params: &mut StreamParams<T>,
+    f: fn(&mut StreamParams<T>),
+) {
+    let len = params.dst_array.len();
+    let chunk_len = len.div_ceil(rayon::current_num_threads()).max(1);
+
+    let [a, b] = params.src_arrays;
+    let scalar = params.scalar;
+
+    params
+        .dst_array
+        .par_chunks_mut(chunk_len)
+        .enumerate()
+        .for_each(|(i, dst_array)| {
+            let range = (i * chunk_len)..((i * chunk_len) + dst_array.len());
+
+            f(&mut StreamParams {
+                dst_array,
+                src_arrays: [&a[range.clone()], &b[range]],
+                scalar,
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNT: usize = 1003;
+
+    #[test]
+    fn stream() {
+        let a = (0..COUNT).map(|i| i as f64).collect::<Vec<_>>();
+        let b = (0..COUNT).map(|i| (i * 2) as f64).collect::<Vec<_>>();
+
+        let run = |f: fn(&mut StreamParams<f64>), parallel: bool| {
+            let mut dst_array = vec![0.0; COUNT];
+
+            let mut params = StreamParams {
+                dst_array: &mut dst_array,
+                src_arrays: [&a, &b],
+                scalar: 3.0,
+            };
+
+            if parallel {
+                stream_parallel(&mut params, f);
+            } else {
+                f(&mut params);
+            }
+
+            dst_array
+        };
+
+        for parallel in [false, true] {
+            for i in [0, 1, COUNT / 2, COUNT - 1] {
+                let (a, b) = (a[i], b[i]);
+
+                assert_eq!(run(stream_copy, parallel)[i], a);
+                assert_eq!(run(stream_scale, parallel)[i], 3.0 * a);
+                assert_eq!(run(stream_add, parallel)[i], a + b);
+                assert_eq!(run(stream_triad, parallel)[i], a + (3.0 * b));
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{kernels::spatial::SpatialGrid, util::CacheAlignedVec};
+use glam::{IVec3, Vec3};
+use rand::Rng;
+use rayon::prelude::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Boid {
+    pub position: Vec3,
+    pub velocity: Vec3,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BoidSettings {
+    // Boids only see others within this distance.
+    pub radius: f32,
+    pub separation_weight: f32,
+    pub alignment_weight: f32,
+    pub cohesion_weight: f32,
+    pub max_speed: f32,
+    pub dt: f32,
+    // Boids wrap around a cube of this size, starting at the origin.
+    pub world_size: f32,
+}
+
+impl BoidSettings {
+    // Scale the world with the number of boids so each one has around twenty
+    // neighbors.
+    pub fn with_count(count: usize) -> Self {
+        Self {
+            radius: 5.0,
+            separation_weight: 10.0,
+            alignment_weight: 1.0,
+            cohesion_weight: 0.5,
+            max_speed: 10.0,
+            dt: 1.0 / 60.0,
+            world_size: 30.0 * ((count as f32) / 1000.0).cbrt(),
+        }
+    }
+}
+
+pub fn random_boid_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+    settings: &BoidSettings,
+) -> CacheAlignedVec<Boid> {
+    (0..count)
+        .map(|_| Boid {
+            position: rng.gen::<Vec3>() * settings.world_size,
+            velocity: (rng.gen::<Vec3>() - 0.5) * settings.max_speed,
+        })
+        .collect()
+}
+
+// Double buffered, so each boid sees the others as they were at the start of
+// the step.
+pub struct BoidParams<'a> {
+    pub dst_array: &'a mut [Boid],
+    pub src_array: &'a [Boid],
+    pub settings: &'a BoidSettings,
+}
+
+// The sums of each rule over a boid's neighbors.
+#[derive(Default)]
+struct Neighbors {
+    separation: Vec3,
+    velocity_sum: Vec3,
+    position_sum: Vec3,
+    count: u32,
+}
+
+impl Neighbors {
+    fn add(&mut self, boid: &Boid, other: &Boid, radius_squared: f32) {
+        let offset = boid.position - other.position;
+        let distance_squared = offset.length_squared();
+
+        // Also skips the boid itself.
+        if (distance_squared > 0.0) && (distance_squared < radius_squared) {
+            self.separation += offset / distance_squared;
+            self.velocity_sum += other.velocity;
+            self.position_sum += other.position;
+            self.count += 1;
+        }
+    }
+
+    fn steer(&self, boid: &Boid, settings: &BoidSettings) -> Boid {
+        let mut acceleration = self.separation * settings.separation_weight;
+
+        if self.count > 0 {
+            let n = self.count as f32;
+
+            acceleration += ((self.velocity_sum / n) - boid.velocity) * settings.alignment_weight;
+            acceleration += ((self.position_sum / n) - boid.position) * settings.cohesion_weight;
+        }
+
+        let velocity =
+            (boid.velocity + (acceleration * settings.dt)).clamp_length_max(settings.max_speed);
+
+        let position =
+            (boid.position + (velocity * settings.dt)).rem_euclid(Vec3::splat(settings.world_size));
+
+        Boid { position, velocity }
+    }
+}
+
+// Neighbors across the wrapped edges of the world aren't seen, in all versions.
+fn boid_update_grid_single<G: SpatialGrid>(
+    grid: &G,
+    src_array: &[Boid],
+    boid: &Boid,
+    settings: &BoidSettings,
+) -> Boid {
+    let radius_squared = settings.radius * settings.radius;
+    let center = (boid.position / grid.cell_size()).floor().as_ivec3();
+
+    let mut neighbors = Neighbors::default();
+
+    for z in -1..=1 {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                grid.for_each_in_cell(center + IVec3::new(x, y, z), |i| {
+                    neighbors.add(boid, &src_array[i as usize], radius_squared);
+                });
+            }
+        }
+    }
+
+    neighbors.steer(boid, settings)
+}
+
+fn build_boid_grid<G: SpatialGrid>(params: &BoidParams) -> G {
+    let positions = params
+        .src_array
+        .iter()
+        .map(|b| b.position)
+        .collect::<Vec<_>>();
+
+    G::build(&positions, params.settings.radius)
+}
+
+// Every boid checks every other boid.
+#[inline(never)]
+pub fn boids_update_naive(params: &mut BoidParams) {
+    let settings = params.settings;
+    let radius_squared = settings.radius * settings.radius;
+
+    for (dst, boid) in params.dst_array.iter_mut().zip(params.src_array) {
+        let mut neighbors = Neighbors::default();
+
+        for other in params.src_array {
+            neighbors.add(boid, other, radius_squared);
+        }
+
+        *dst = neighbors.steer(boid, settings);
+    }
+}
+
+// Rebuild a grid with cells the size of the radius, then only check the
+// boids in nearby cells. The build is included.
+#[inline(never)]
+pub fn boids_update_grid<G: SpatialGrid>(params: &mut BoidParams) {
+    let grid = build_boid_grid::<G>(params);
+
+    for (dst, boid) in params.dst_array.iter_mut().zip(params.src_array) {
+        *dst = boid_update_grid_single(&grid, params.src_array, boid, params.settings);
+    }
+}
+
+// Same as `boids_update_grid`, but the boids are updated in parallel on
+// whatever rayon thread pool is current. The grid is still built serially.
+#[inline(never)]
+pub fn boids_update_grid_parallel<G: SpatialGrid + Sync>(params: &mut BoidParams) {
+    let grid = build_boid_grid::<G>(params);
+    let (src_array, settings) = (params.src_array, params.settings);
+
+    params
+        .dst_array
+        .par_iter_mut()
+        .zip(src_array)
+        .for_each(|(dst, boid)| {
+            *dst = boid_update_grid_single(&grid, src_array, boid, settings);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::{
+        spatial::{DenseGrid, HashMapGrid},
+        test_util::{run_into, test_rng, COUNT},
+    };
+
+    #[test]
+    fn boids() {
+        let mut rng = test_rng();
+
+        let settings = BoidSettings::with_count(COUNT);
+
+        let src_array = random_boid_array(&mut rng, COUNT, &settings);
+
+        let run = |f: fn(&mut BoidParams)| {
+            run_into(vec![Boid::default(); COUNT], |dst_array| {
+                f(&mut BoidParams {
+                    dst_array,
+                    src_array: &src_array,
+                    settings: &settings,
+                })
+            })
+        };
+
+        let naive = run(boids_update_naive);
+
+        for f in [
+            boids_update_grid::<HashMapGrid>,
+            boids_update_grid::<DenseGrid>,
+            boids_update_grid_parallel::<DenseGrid>,
+        ] {
+            // The neighbors are summed in a different order.
+            for (l, r) in naive.iter().zip(run(f)) {
+                assert!(l.position.abs_diff_eq(r.position, 1e-4));
+                assert!(l.velocity.abs_diff_eq(r.velocity, 1e-3));
+            }
+        }
+
+        for b in &naive {
+            assert!(b.velocity.length() <= (settings.max_speed * (1.0 + 1e-6)));
+            assert!(b.position.cmpge(Vec3::ZERO).all());
+            assert!(b.position.cmplt(Vec3::splat(settings.world_size)).all());
+        }
+
+        // The boids should steer, so most velocities change.
+
+        let changed = naive
+            .iter()
+            .zip(src_array.iter())
+            .filter(|(l, r)| l.velocity.distance(r.velocity) > 1e-3)
+            .count();
+
+        assert!(changed > (COUNT / 2));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::util::CacheAlignedVec;
+use bevy_math::{bounding::BoundingSphere, Isometry3d};
+use glam::{Mat3, Vec3A};
+use rand::Rng;
+
+// Return points in a flattened box, since Ritter's first guess is worse when
+// the cloud isn't round. The points are in random order, which Welzl's
+// algorithm needs for its expected linear time.
+pub fn random_point_cloud<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Vec3A> {
+    (0..count)
+        .map(|_| (rng.gen::<Vec3A>() - 0.5) * Vec3A::new(40.0, 20.0, 10.0))
+        .collect()
+}
+
+// Allow for rounding so points on the boundary don't force a new sphere.
+fn sphere_contains(sphere: &BoundingSphere, p: Vec3A) -> bool {
+    let radius = sphere.radius() * (1.0 + 1e-5);
+
+    sphere.center.distance_squared(p) <= (radius * radius)
+}
+
+fn sphere_from_2(a: Vec3A, b: Vec3A) -> BoundingSphere {
+    BoundingSphere::new((a + b) * 0.5, a.distance(b) * 0.5)
+}
+
+// The smallest sphere with three points on its boundary is centered on their
+// circumcircle.
+fn sphere_from_3(a: Vec3A, b: Vec3A, c: Vec3A) -> BoundingSphere {
+    let ab = b - a;
+    let ac = c - a;
+    let n = ab.cross(ac);
+
+    let denominator = 2.0 * n.length_squared();
+
+    // Collinear points, so two of them are the diameter.
+    if denominator <= f32::EPSILON {
+        return [
+            sphere_from_2(a, b),
+            sphere_from_2(a, c),
+            sphere_from_2(b, c),
+        ]
+        .into_iter()
+        .max_by(|l, r| l.radius().total_cmp(&r.radius()))
+        .unwrap();
+    }
+
+    let offset =
+        ((n.cross(ab) * ac.length_squared()) + (ac.cross(n) * ab.length_squared())) / denominator;
+
+    BoundingSphere::new(a + offset, offset.length())
+}
+
+fn sphere_from_4(a: Vec3A, b: Vec3A, c: Vec3A, d: Vec3A) -> BoundingSphere {
+    let ab = b - a;
+    let ac = c - a;
+    let ad = d - a;
+
+    // Solve for the offset from `a` that's equidistant from all four points.
+    let m = Mat3::from_cols(ab.into(), ac.into(), ad.into()).transpose();
+
+    // Coplanar points have no circumsphere, so fall back to three.
+    if m.determinant().abs() <= f32::EPSILON {
+        return sphere_from_3(a, b, c);
+    }
+
+    let rhs = Vec3A::new(
+        ab.length_squared(),
+        ac.length_squared(),
+        ad.length_squared(),
+    ) * 0.5;
+    let offset = m.inverse().mul_vec3a(rhs);
+
+    BoundingSphere::new(a + offset, offset.length())
+}
+
+// Ritter, "An Efficient Bounding Sphere" (1990). Start with the sphere between
+// two far apart points, then grow it to cover any point outside. Three passes
+// over the points, and the result is usually a few percent larger than the
+// smallest sphere.
+#[inline(never)]
+pub fn bounding_sphere_ritter(point_array: &[Vec3A]) -> BoundingSphere {
+    let farthest_from = |from: Vec3A| {
+        point_array
+            .iter()
+            .copied()
+            .max_by(|l, r| {
+                from.distance_squared(*l)
+                    .total_cmp(&from.distance_squared(*r))
+            })
+            .unwrap()
+    };
+
+    let y = farthest_from(point_array[0]);
+    let z = farthest_from(y);
+
+    let mut center = (y + z) * 0.5;
+    let mut radius = y.distance(z) * 0.5;
+
+    for &p in point_array {
+        let distance = center.distance(p);
+
+        if distance > radius {
+            let new_radius = (radius + distance) * 0.5;
+
+            center += (p - center) * ((new_radius - radius) / distance);
+            radius = new_radius;
+        }
+    }
+
+    BoundingSphere::new(center, radius)
+}
+
+// Welzl, "Smallest enclosing disks (balls and ellipsoids)" (1991). This is the
+// iterative form, where each level of the loop fixes one more point on the
+// boundary. Exact, and linear time on average if the points are in random
+// order. Sorted input can make it much slower.
+#[inline(never)]
+pub fn bounding_sphere_welzl(point_array: &[Vec3A]) -> BoundingSphere {
+    let mut sphere = BoundingSphere::new(point_array[0], 0.0);
+
+    for i in 1..point_array.len() {
+        let pi = point_array[i];
+
+        if sphere_contains(&sphere, pi) {
+            continue;
+        }
+
+        sphere = BoundingSphere::new(pi, 0.0);
+
+        for j in 0..i {
+            let pj = point_array[j];
+
+            if sphere_contains(&sphere, pj) {
+                continue;
+            }
+
+            sphere = sphere_from_2(pi, pj);
+
+            for k in 0..j {
+                let pk = point_array[k];
+
+                if sphere_contains(&sphere, pk) {
+                    continue;
+                }
+
+                sphere = sphere_from_3(pi, pj, pk);
+
+                for &pl in &point_array[..k] {
+                    if !sphere_contains(&sphere, pl) {
+                        sphere = sphere_from_4(pi, pj, pk, pl);
+                    }
+                }
+            }
+        }
+    }
+
+    sphere
+}
+
+// Centered on the average of the points, so it's two passes and the radius can
+// be much larger than the smallest sphere.
+#[inline(never)]
+pub fn bounding_sphere_bevy(point_array: &[Vec3A]) -> BoundingSphere {
+    BoundingSphere::from_point_cloud(Isometry3d::IDENTITY, point_array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{test_rng, COUNT};
+
+    #[test]
+    fn bounding_sphere() {
+        // The smallest sphere around a cube passes through its corners.
+
+        let corners = (0..8)
+            .map(|i| Vec3A::new((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32))
+            .collect::<Vec<_>>();
+
+        let welzl = bounding_sphere_welzl(&corners);
+
+        assert!(welzl.center.abs_diff_eq(Vec3A::splat(0.5), 1e-5));
+        assert!((welzl.radius() - (3.0f32.sqrt() * 0.5)).abs() < 1e-5);
+
+        let mut rng = test_rng();
+
+        let point_array = random_point_cloud(&mut rng, COUNT);
+
+        let welzl = bounding_sphere_welzl(&point_array);
+        let ritter = bounding_sphere_ritter(&point_array);
+        let bevy = bounding_sphere_bevy(&point_array);
+
+        for sphere in [&welzl, &ritter, &bevy] {
+            assert!(point_array.iter().all(|&p| sphere_contains(sphere, p)));
+        }
+
+        assert!(welzl.radius() <= ritter.radius());
+        assert!(welzl.radius() <= bevy.radius());
+
+        // The smallest sphere always touches at least two points.
+
+        let touching = point_array
+            .iter()
+            .filter(|&&p| (welzl.center.distance(p) - welzl.radius()).abs() < 1e-3)
+            .count();
+
+        assert!(touching >= 2);
+    }
+}
@@ -0,0 +1,437 @@
+use crate::{
+    kernels::{
+        culling::{
+            aabb_frustum_containment, aabb_in_frustum, random_culling_point, Containment, Frustum,
+        },
+        geometry::{random_unit_vec3a, slab_ray_distance, SlabRay},
+    },
+    util::CacheAlignedVec,
+};
+use bevy_math::bounding::{Aabb3d, BoundingVolume};
+use glam::{Mat4, Vec3};
+use rand::Rng;
+use std::ops::Range;
+
+pub const BVH_LEAF_SIZE: usize = 8;
+
+// Return the bounds of the items. If there's more than a leaf's worth then also
+// reorder the items around the median of the longest axis, and return the
+// index of the first item in the second half.
+fn median_split<T>(items: &mut [T], aabb: impl Fn(&T) -> Aabb3d) -> (Aabb3d, Option<usize>) {
+    let bounds = items[1..]
+        .iter()
+        .fold(aabb(&items[0]), |b, i| b.merge(&aabb(i)));
+
+    if items.len() <= BVH_LEAF_SIZE {
+        return (bounds, None);
+    }
+
+    let extent = bounds.max - bounds.min;
+
+    let axis = if (extent.x >= extent.y) && (extent.x >= extent.z) {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+
+    let mid = items.len() / 2;
+
+    items.select_nth_unstable_by(mid, |l, r| {
+        let (l, r) = (aabb(l), aabb(r));
+
+        (l.min[axis] + l.max[axis]).total_cmp(&(r.min[axis] + r.max[axis]))
+    });
+
+    (bounds, Some(mid))
+}
+
+// The two layouts are traversed by the same code, so only the memory layout
+// differs.
+pub trait Bvh {
+    type Node;
+
+    fn root(&self) -> &Self::Node;
+
+    fn node_aabb(node: &Self::Node) -> &Aabb3d;
+
+    // The range of boxes covered by the node.
+    fn node_range(node: &Self::Node) -> Range<usize>;
+
+    // The children of an internal node, or `None` if the node is a leaf.
+    fn node_children<'a>(&'a self, node: &'a Self::Node) -> Option<[&'a Self::Node; 2]>;
+}
+
+// Each node covers a contiguous range of the boxes. Internal nodes have two
+// children at `child` and `child + 1`, and leaves have a `child` of zero.
+#[derive(Clone, Copy, Debug)]
+pub struct FlatBvhNode {
+    pub aabb: Aabb3d,
+    pub child: u32,
+    pub first: u32,
+    pub count: u32,
+}
+
+// All the nodes in one array, with siblings next to each other.
+pub struct FlatBvh {
+    pub nodes: Vec<FlatBvhNode>,
+}
+
+impl FlatBvh {
+    // The boxes are reordered so that each node's boxes are contiguous.
+    pub fn build(aabbs: &mut [Aabb3d]) -> Self {
+        Self::build_by(aabbs, |a| *a)
+    }
+
+    // Same as `build`, but for anything with bounds. The items are reordered
+    // instead, so the node ranges can index other arrays that go with them.
+    pub fn build_by<T>(items: &mut [T], aabb: impl Fn(&T) -> Aabb3d + Copy) -> Self {
+        let mut nodes = vec![FlatBvhNode {
+            aabb: aabb(&items[0]),
+            child: 0,
+            first: 0,
+            count: 0,
+        }];
+
+        flat_build_node(&mut nodes, 0, items, 0, aabb);
+
+        FlatBvh { nodes }
+    }
+}
+
+// Fill in the node at `slot`, then append its children and recurse.
+fn flat_build_node<T>(
+    nodes: &mut Vec<FlatBvhNode>,
+    slot: usize,
+    items: &mut [T],
+    first: usize,
+    aabb: impl Fn(&T) -> Aabb3d + Copy,
+) {
+    let (bounds, mid) = median_split(items, aabb);
+
+    nodes[slot] = FlatBvhNode {
+        aabb: bounds,
+        child: 0,
+        first: first as u32,
+        count: items.len() as u32,
+    };
+
+    let Some(mid) = mid else {
+        return;
+    };
+
+    let child = nodes.len();
+
+    nodes.extend([nodes[slot]; 2]);
+    nodes[slot].child = child as u32;
+
+    let (l, r) = items.split_at_mut(mid);
+
+    flat_build_node(nodes, child, l, first, aabb);
+    flat_build_node(nodes, child + 1, r, first + mid, aabb);
+}
+
+impl Bvh for FlatBvh {
+    type Node = FlatBvhNode;
+
+    fn root(&self) -> &FlatBvhNode {
+        &self.nodes[0]
+    }
+
+    fn node_aabb(node: &FlatBvhNode) -> &Aabb3d {
+        &node.aabb
+    }
+
+    fn node_range(node: &FlatBvhNode) -> Range<usize> {
+        (node.first as usize)..((node.first + node.count) as usize)
+    }
+
+    fn node_children<'a>(&'a self, node: &'a FlatBvhNode) -> Option<[&'a FlatBvhNode; 2]> {
+        let child = node.child as usize;
+
+        (child != 0).then(|| [&self.nodes[child], &self.nodes[child + 1]])
+    }
+}
+
+// Every node is a separate allocation, so nodes end up wherever the allocator
+// puts them.
+pub struct PointerBvhNode {
+    pub aabb: Aabb3d,
+    pub first: u32,
+    pub count: u32,
+    pub children: Option<[Box<PointerBvhNode>; 2]>,
+}
+
+pub struct PointerBvh {
+    pub root: Box<PointerBvhNode>,
+}
+
+impl PointerBvh {
+    // Same as `FlatBvh::build`, so the boxes end up in the same order.
+    pub fn build(aabbs: &mut [Aabb3d]) -> Self {
+        PointerBvh {
+            root: pointer_build_node(aabbs, 0),
+        }
+    }
+}
+
+fn pointer_build_node(aabbs: &mut [Aabb3d], first: usize) -> Box<PointerBvhNode> {
+    let (aabb, mid) = median_split(aabbs, |a| *a);
+    let count = aabbs.len();
+
+    let children = mid.map(|mid| {
+        let (l, r) = aabbs.split_at_mut(mid);
+
+        [
+            pointer_build_node(l, first),
+            pointer_build_node(r, first + mid),
+        ]
+    });
+
+    Box::new(PointerBvhNode {
+        aabb,
+        first: first as u32,
+        count: count as u32,
+        children,
+    })
+}
+
+impl Bvh for PointerBvh {
+    type Node = PointerBvhNode;
+
+    fn root(&self) -> &PointerBvhNode {
+        &self.root
+    }
+
+    fn node_aabb(node: &PointerBvhNode) -> &Aabb3d {
+        &node.aabb
+    }
+
+    fn node_range(node: &PointerBvhNode) -> Range<usize> {
+        (node.first as usize)..((node.first + node.count) as usize)
+    }
+
+    fn node_children<'a>(&'a self, node: &'a PointerBvhNode) -> Option<[&'a PointerBvhNode; 2]> {
+        node.children.as_ref().map(|[l, r]| [&**l, &**r])
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Rays that start anywhere in the culling scene and point in any direction.
+pub fn random_bvh_ray_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<SlabRay> {
+    (0..count)
+        .map(|_| SlabRay::new(random_culling_point(rng), random_unit_vec3a(rng)))
+        .collect()
+}
+
+// Frustums like a spotlight or a small camera, so each one sees a small part
+// of the culling scene.
+pub fn random_query_frustum_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<Frustum> {
+    let projection = Mat4::perspective_rh(60.0f32.to_radians(), 16.0 / 9.0, 0.1, 50.0);
+
+    (0..count)
+        .map(|_| {
+            let eye = Vec3::from(random_culling_point(rng));
+            let direction = Vec3::from(random_unit_vec3a(rng));
+
+            // Avoid a degenerate view matrix.
+            let up = if direction.y.abs() > 0.99 {
+                Vec3::X
+            } else {
+                Vec3::Y
+            };
+
+            Frustum::from_view_projection(projection * Mat4::look_to_rh(eye, direction, up))
+        })
+        .collect()
+}
+
+// Each result is the distance to the nearest box, or infinity if the ray
+// missed every box.
+pub struct BvhRayParams<'a, B> {
+    pub dst_array: &'a mut [f32],
+    pub ray_array: &'a [SlabRay],
+    pub bvh: &'a B,
+    pub aabb_array: &'a [Aabb3d],
+}
+
+pub fn bvh_ray_single<B: Bvh>(bvh: &B, aabb_array: &[Aabb3d], ray: &SlabRay) -> f32 {
+    let mut nearest = f32::INFINITY;
+
+    let mut stack = [bvh.root(); 64];
+    let mut stack_len = 1;
+
+    while stack_len > 0 {
+        stack_len -= 1;
+
+        let node = stack[stack_len];
+
+        // Skip nodes that are missed or further than the nearest hit so far.
+        if slab_ray_distance(ray, B::node_aabb(node)) >= nearest {
+            continue;
+        }
+
+        if let Some([l, r]) = bvh.node_children(node) {
+            stack[stack_len] = l;
+            stack[stack_len + 1] = r;
+            stack_len += 2;
+        } else {
+            for aabb in &aabb_array[B::node_range(node)] {
+                nearest = nearest.min(slab_ray_distance(ray, aabb));
+            }
+        }
+    }
+
+    nearest
+}
+
+#[inline(never)]
+pub fn bvh_ray<B: Bvh>(params: &mut BvhRayParams<B>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = bvh_ray_single(params.bvh, params.aabb_array, &params.ray_array[i]);
+    }
+}
+
+// Each result is the number of boxes visible to the frustum.
+pub struct BvhFrustumParams<'a, B> {
+    pub dst_array: &'a mut [u32],
+    pub frustum_array: &'a [Frustum],
+    pub bvh: &'a B,
+    pub aabb_array: &'a [Aabb3d],
+}
+
+pub fn bvh_frustum_single<B: Bvh>(bvh: &B, aabb_array: &[Aabb3d], frustum: &Frustum) -> u32 {
+    let mut count = 0;
+
+    let mut stack = [bvh.root(); 64];
+    let mut stack_len = 1;
+
+    while stack_len > 0 {
+        stack_len -= 1;
+
+        let node = stack[stack_len];
+        let range = B::node_range(node);
+
+        match aabb_frustum_containment(frustum, B::node_aabb(node)) {
+            Containment::Outside => (),
+            Containment::Inside => count += range.len() as u32,
+            Containment::Intersecting => {
+                if let Some([l, r]) = bvh.node_children(node) {
+                    stack[stack_len] = l;
+                    stack[stack_len + 1] = r;
+                    stack_len += 2;
+                } else {
+                    for aabb in &aabb_array[range] {
+                        count += aabb_in_frustum(frustum, aabb) as u32;
+                    }
+                }
+            }
+        }
+    }
+
+    count
+}
+
+#[inline(never)]
+pub fn bvh_frustum<B: Bvh>(params: &mut BvhFrustumParams<B>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] =
+            bvh_frustum_single(params.bvh, params.aabb_array, &params.frustum_array[i]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::{
+        culling::random_culling_aabb_array,
+        test_util::{test_rng, COUNT},
+    };
+
+    #[test]
+    fn build() {
+        let mut rng = test_rng();
+
+        let mut aabbs = random_culling_aabb_array(&mut rng, COUNT);
+        let mut pointer_aabbs = aabbs.clone();
+
+        let flat = FlatBvh::build(&mut aabbs);
+        let pointer = PointerBvh::build(&mut pointer_aabbs);
+
+        // Both layouts must give the same tree. Every node must contain its
+        // boxes, and the leaves must cover every box exactly once.
+
+        let mut covered = vec![0; COUNT];
+        let mut stack = vec![(flat.root(), pointer.root())];
+
+        while let Some((f, p)) = stack.pop() {
+            let range = FlatBvh::node_range(f);
+
+            assert_eq!(range, PointerBvh::node_range(p));
+            assert_eq!(f.aabb, p.aabb);
+
+            for aabb in &aabbs[range.clone()] {
+                assert!(f.aabb.contains(aabb));
+            }
+
+            match (flat.node_children(f), pointer.node_children(p)) {
+                (Some(f), Some(p)) => stack.extend([(f[0], p[0]), (f[1], p[1])]),
+                (None, None) => covered[range].iter_mut().for_each(|c| *c += 1),
+                _ => panic!("layouts differ"),
+            }
+        }
+
+        assert!(covered.iter().all(|&c| c == 1));
+        assert_eq!(aabbs.as_slice(), pointer_aabbs.as_slice());
+    }
+
+    #[test]
+    fn queries() {
+        let mut rng = test_rng();
+
+        let mut aabbs = random_culling_aabb_array(&mut rng, COUNT);
+        let flat = FlatBvh::build(&mut aabbs);
+        let pointer = PointerBvh::build(&mut aabbs.clone());
+
+        let rays = random_bvh_ray_array(&mut rng, 100);
+
+        let mut hit_count = 0;
+
+        for ray in rays.iter() {
+            let expected = aabbs
+                .iter()
+                .map(|aabb| slab_ray_distance(ray, aabb))
+                .fold(f32::INFINITY, f32::min);
+
+            hit_count += expected.is_finite() as usize;
+
+            assert_eq!(expected, bvh_ray_single(&flat, &aabbs, ray));
+            assert_eq!(expected, bvh_ray_single(&pointer, &aabbs, ray));
+        }
+
+        assert!(hit_count > 0);
+
+        let frustums = random_query_frustum_array(&mut rng, 100);
+
+        let mut visible_count = 0;
+
+        for frustum in frustums.iter() {
+            let expected = aabbs.iter().filter(|a| aabb_in_frustum(frustum, a)).count() as u32;
+
+            visible_count += expected;
+
+            assert_eq!(expected, bvh_frustum_single(&flat, &aabbs, frustum));
+            assert_eq!(expected, bvh_frustum_single(&pointer, &aabbs, frustum));
+        }
+
+        assert!(visible_count > 0);
+    }
+}
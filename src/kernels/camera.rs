@@ -0,0 +1,362 @@
+use bevy_transform::components::Transform;
+use glam::{Mat3, Mat4, Quat, Vec3};
+use rand::Rng;
+
+// The kinds of eye/target pairs for the look kernels. In the near parallel
+// case the view direction is within about 1e-4 radians of the up vector, which
+// is close to the fallback thresholds but doesn't reach them. In the parallel
+// case it's exactly along the up vector, so `Transform::looking_at` and
+// `look_basis_single` fall back. In the backward case it's exactly opposite
+// the default forward direction, so `Quat::from_rotation_arc` falls back. The
+// mixed case picks one of the other kinds at random for each pair, so the
+// branches can't be predicted.
+#[derive(Clone, Copy, Debug)]
+pub enum LookKind {
+    Random,
+    NearParallel,
+    Parallel,
+    Backward,
+    Mixed,
+}
+
+impl LookKind {
+    pub const ALL: [LookKind; 5] = [
+        LookKind::Random,
+        LookKind::NearParallel,
+        LookKind::Parallel,
+        LookKind::Backward,
+        LookKind::Mixed,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LookKind::Random => "random",
+            LookKind::NearParallel => "near parallel",
+            LookKind::Parallel => "parallel",
+            LookKind::Backward => "backward",
+            LookKind::Mixed => "mixed",
+        }
+    }
+}
+
+// Return a target for the eye, with a view direction of the given kind.
+pub fn random_look_target<R: Rng + ?Sized>(rng: &mut R, eye: Vec3, kind: LookKind) -> Vec3 {
+    // The degenerate kinds offset the eye along one axis, so the other
+    // components of the view direction are exactly zero.
+    let distance = rng.gen_range(1.0..10.0);
+    let up = if rng.gen() { Vec3::Y } else { Vec3::NEG_Y };
+
+    match kind {
+        LookKind::Random => (rng.gen::<Vec3>() * 20.0) - 10.0,
+        LookKind::NearParallel => {
+            // Scale the jitter by the distance, as a fixed amount would be
+            // rounded away for a distant eye.
+            let jitter = ((rng.gen::<Vec3>() * 2.0) - 1.0) * (distance * 1e-4);
+
+            eye + (up * distance) + jitter
+        }
+        LookKind::Parallel => eye + (up * distance),
+        LookKind::Backward => eye + (Vec3::Z * distance),
+        LookKind::Mixed => {
+            let kind = LookKind::ALL[rng.gen_range(0..(LookKind::ALL.len() - 1))];
+
+            random_look_target(rng, eye, kind)
+        }
+    }
+}
+
+// Return eye and target arrays of the given kind.
+pub fn random_look_arrays<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+    kind: LookKind,
+) -> (Vec<Vec3>, Vec<Vec3>) {
+    (0..count)
+        .map(|_| {
+            let eye = (rng.gen::<Vec3>() * 20.0) - 10.0;
+
+            (eye, random_look_target(rng, eye, kind))
+        })
+        .unzip()
+}
+
+pub struct LookParams<'a> {
+    pub dst_array: &'a mut [Quat],
+    pub eye_array: &'a [Vec3],
+    pub target_array: &'a [Vec3],
+}
+
+pub fn look_inner<F>(params: &mut LookParams, f: F)
+where
+    F: Fn(Vec3, Vec3) -> Quat,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(params.eye_array[i], params.target_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn look_transform(params: &mut LookParams) {
+    look_inner(params, |eye, target| {
+        Transform::from_translation(eye)
+            .looking_at(target, Vec3::Y)
+            .rotation
+    });
+}
+
+// The shortest arc from the default forward direction. This ignores the up
+// vector, so the roll differs from the other kernels.
+#[inline(never)]
+pub fn look_rotation_arc(params: &mut LookParams) {
+    look_inner(params, |eye, target| {
+        Quat::from_rotation_arc(Vec3::NEG_Z, (target - eye).normalize())
+    });
+}
+
+// Same as `Transform::looking_at`, but without the conversions to `Dir3` and
+// with a single branch for the parallel case.
+pub fn look_basis_single(eye: Vec3, target: Vec3) -> Quat {
+    let back = (eye - target).normalize();
+    let right = Vec3::Y.cross(back);
+    let length_squared = right.length_squared();
+
+    let right = if length_squared > 1e-12 {
+        right * length_squared.sqrt().recip()
+    } else {
+        Vec3::Y.any_orthonormal_vector()
+    };
+
+    Quat::from_mat3(&Mat3::from_cols(right, back.cross(right), back))
+}
+
+#[inline(never)]
+pub fn look_basis(params: &mut LookParams) {
+    look_inner(params, look_basis_single);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectionInput {
+    pub fov_y: f32,
+    pub aspect_ratio: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+// Return inputs like the cascades of a shadow map, where each cascade covers a
+// different depth range.
+pub fn random_projection_input<R: Rng + ?Sized>(rng: &mut R) -> ProjectionInput {
+    let near = rng.gen_range(0.1..10.0);
+
+    ProjectionInput {
+        fov_y: rng.gen_range(0.5..1.5),
+        aspect_ratio: rng.gen_range(1.0..2.5),
+        near,
+        far: near * rng.gen_range(2.0..100.0),
+    }
+}
+
+pub struct ProjectionParams<'a> {
+    pub dst_array: &'a mut [Mat4],
+    pub src_array: &'a [ProjectionInput],
+}
+
+pub fn projection_inner<F>(params: &mut ProjectionParams, f: F)
+where
+    F: Fn(&ProjectionInput) -> Mat4,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(&params.src_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn projection_perspective(params: &mut ProjectionParams) {
+    projection_inner(params, |p| {
+        Mat4::perspective_rh(p.fov_y, p.aspect_ratio, p.near, p.far)
+    });
+}
+
+// The box is sized to the far plane so that the inputs don't need a tan.
+#[inline(never)]
+pub fn projection_orthographic(params: &mut ProjectionParams) {
+    projection_inner(params, |p| {
+        let half_width = p.far * p.aspect_ratio;
+
+        Mat4::orthographic_rh(-half_width, half_width, -p.far, p.far, p.near, p.far)
+    });
+}
+
+#[inline(never)]
+pub fn projection_infinite_reverse(params: &mut ProjectionParams) {
+    projection_inner(params, |p| {
+        Mat4::perspective_infinite_reverse_rh(p.fov_y, p.aspect_ratio, p.near)
+    });
+}
+
+// The corners of a perspective frustum in view space - the near plane corners
+// followed by the far plane corners.
+pub type FrustumCorners = [Vec3; 8];
+
+pub struct FrustumCornersParams<'a, S> {
+    pub dst_array: &'a mut [FrustumCorners],
+    pub src_array: &'a [S],
+}
+
+// Transform the corners of the NDC cube by the inverse projection. This works
+// for any finite projection.
+#[inline(never)]
+pub fn frustum_corners_inverse(params: &mut FrustumCornersParams<Mat4>) {
+    const NDC_CORNERS: [Vec3; 8] = [
+        Vec3::new(-1.0, -1.0, 0.0),
+        Vec3::new(1.0, -1.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.0),
+        Vec3::new(-1.0, 1.0, 0.0),
+        Vec3::new(-1.0, -1.0, 1.0),
+        Vec3::new(1.0, -1.0, 1.0),
+        Vec3::new(1.0, 1.0, 1.0),
+        Vec3::new(-1.0, 1.0, 1.0),
+    ];
+
+    for i in 0..params.dst_array.len() {
+        let inverse = params.src_array[i].inverse();
+
+        params.dst_array[i] = NDC_CORNERS.map(|c| inverse.project_point3(c));
+    }
+}
+
+// Compute the corners directly from the perspective parameters.
+#[inline(never)]
+pub fn frustum_corners_analytic(params: &mut FrustumCornersParams<ProjectionInput>) {
+    for i in 0..params.dst_array.len() {
+        let p = params.src_array[i];
+        let tan_half_fov = (p.fov_y * 0.5).tan();
+
+        let corners = |z: f32| {
+            let y = z * tan_half_fov;
+            let x = y * p.aspect_ratio;
+
+            [
+                Vec3::new(-x, -y, -z),
+                Vec3::new(x, -y, -z),
+                Vec3::new(x, y, -z),
+                Vec3::new(-x, y, -z),
+            ]
+        };
+
+        let [n0, n1, n2, n3] = corners(p.near);
+        let [f0, f1, f2, f3] = corners(p.far);
+
+        params.dst_array[i] = [n0, n1, n2, n3, f0, f1, f2, f3];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{quat_near, run_into, test_rng, COUNT, TOLERANCE};
+
+    fn run(f: fn(&mut LookParams), eye_array: &[Vec3], target_array: &[Vec3]) -> Vec<Quat> {
+        run_into(vec![Quat::IDENTITY; COUNT], |dst_array| {
+            f(&mut LookParams {
+                dst_array,
+                eye_array,
+                target_array,
+            })
+        })
+    }
+
+    #[test]
+    fn look() {
+        let mut rng = test_rng();
+
+        for kind in LookKind::ALL {
+            let (eye_array, target_array) = random_look_arrays(&mut rng, COUNT, kind);
+
+            let transform = run(look_transform, &eye_array, &target_array);
+            let arc = run(look_rotation_arc, &eye_array, &target_array);
+            let basis = run(look_basis, &eye_array, &target_array);
+
+            for i in 0..COUNT {
+                let forward = (target_array[i] - eye_array[i]).normalize();
+
+                for rotation in [transform[i], arc[i], basis[i]] {
+                    assert!((rotation * Vec3::NEG_Z).abs_diff_eq(forward, TOLERANCE));
+                }
+
+                // The parallel fallbacks can pick different right vectors.
+                if let LookKind::Random = kind {
+                    assert!(quat_near(basis[i], transform[i], TOLERANCE));
+                }
+
+                // Check that the degenerate kinds hit the fallbacks exactly.
+                let direction = target_array[i] - eye_array[i];
+
+                match kind {
+                    LookKind::Parallel => assert_eq!(Vec3::Y.cross(direction), Vec3::ZERO),
+                    LookKind::Backward => {
+                        assert_eq!(Vec3::Z.cross(direction), Vec3::ZERO);
+                        assert!(direction.z > 0.0);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn projection() {
+        let mut rng = test_rng();
+
+        let src_array = (0..COUNT)
+            .map(|_| random_projection_input(&mut rng))
+            .collect::<Vec<_>>();
+
+        let [perspective, orthographic, infinite_reverse] = [
+            projection_perspective as fn(&mut ProjectionParams),
+            projection_orthographic,
+            projection_infinite_reverse,
+        ]
+        .map(|f| {
+            run_into(vec![Mat4::IDENTITY; COUNT], |dst_array| {
+                f(&mut ProjectionParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        });
+
+        let mut inverse = vec![[Vec3::ZERO; 8]; COUNT];
+        let mut analytic = vec![[Vec3::ZERO; 8]; COUNT];
+
+        frustum_corners_inverse(&mut FrustumCornersParams {
+            dst_array: &mut inverse,
+            src_array: &perspective,
+        });
+
+        frustum_corners_analytic(&mut FrustumCornersParams {
+            dst_array: &mut analytic,
+            src_array: &src_array,
+        });
+
+        for (i, p) in src_array.iter().enumerate() {
+            let near = Vec3::new(0.0, 0.0, -p.near);
+            let far = Vec3::new(0.0, 0.0, -p.far);
+
+            // Depth is zero at the near plane and one at the far plane, except
+            // for reverse z.
+            assert!(perspective[i].project_point3(near).z.abs() < TOLERANCE);
+            assert!((perspective[i].project_point3(far).z - 1.0).abs() < TOLERANCE);
+            assert!(orthographic[i].project_point3(near).z.abs() < TOLERANCE);
+            assert!((orthographic[i].project_point3(far).z - 1.0).abs() < TOLERANCE);
+            assert!((infinite_reverse[i].project_point3(near).z - 1.0).abs() < TOLERANCE);
+
+            // The far plane can be a long way away, so use a relative
+            // tolerance.
+            for (inverse, analytic) in inverse[i].iter().zip(analytic[i].iter()) {
+                assert!(inverse.abs_diff_eq(*analytic, p.far * 1e-3));
+            }
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use crate::{
+    kernels::quantize::{convert_inner, ConvertParams},
+    util::CacheAlignedVec,
+};
+use glam::{Mat3, Vec3, Vec4};
+use rand::Rng;
+
+// Conversions between sRGB and linear colors. The pixels are RGBA, and alpha
+// is always linear so it's passed through unchanged.
+
+fn map_rgb(c: Vec4, f: impl Fn(f32) -> f32) -> Vec4 {
+    Vec4::new(f(c.x), f(c.y), f(c.z), c.w)
+}
+
+fn srgb_to_linear_f64(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb_f64(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        (1.055 * c.powf(1.0 / 2.4)) - 0.055
+    }
+}
+
+pub fn srgb_to_linear_single(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c * (1.0 / 12.92)
+    } else {
+        ((c + 0.055) * (1.0 / 1.055)).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb_single(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        (1.055 * c.powf(1.0 / 2.4)) - 0.055
+    }
+}
+
+// The standard formula evaluated in `f64`. This is the reference for the
+// error of the other variants.
+#[inline(never)]
+pub fn srgb_to_linear_exact(params: &mut ConvertParams<Vec4, Vec4>) {
+    convert_inner(params, |c| {
+        map_rgb(c, |c| srgb_to_linear_f64(c as f64) as f32)
+    });
+}
+
+#[inline(never)]
+pub fn srgb_to_linear_piecewise(params: &mut ConvertParams<Vec4, Vec4>) {
+    convert_inner(params, |c| map_rgb(c, srgb_to_linear_single));
+}
+
+// A single power curve without the linear segment, as used by some engines
+// when the exact curve isn't important.
+#[inline(never)]
+pub fn srgb_to_linear_gamma22(params: &mut ConvertParams<Vec4, Vec4>) {
+    convert_inner(params, |c| map_rgb(c, |c| c.powf(2.2)));
+}
+
+#[inline(never)]
+pub fn linear_to_srgb_exact(params: &mut ConvertParams<Vec4, Vec4>) {
+    convert_inner(params, |c| {
+        map_rgb(c, |c| linear_to_srgb_f64(c as f64) as f32)
+    });
+}
+
+#[inline(never)]
+pub fn linear_to_srgb_piecewise(params: &mut ConvertParams<Vec4, Vec4>) {
+    convert_inner(params, |c| map_rgb(c, linear_to_srgb_single));
+}
+
+#[inline(never)]
+pub fn linear_to_srgb_gamma22(params: &mut ConvertParams<Vec4, Vec4>) {
+    convert_inner(params, |c| map_rgb(c, |c| c.powf(1.0 / 2.2)));
+}
+
+// Lookup tables for 8-bit sRGB. Converting to linear is exact since there are
+// only 256 inputs. Converting from linear rounds to the nearest of
+// `FROM_LINEAR_SIZE` entries. `from_linear` has three bytes of padding so a
+// 32-bit gather can read any entry.
+pub struct SrgbLut {
+    pub to_linear: [f32; 256],
+    pub from_linear: Vec<u8>,
+}
+
+impl SrgbLut {
+    pub const FROM_LINEAR_SIZE: usize = 4096;
+
+    pub fn new() -> Self {
+        let max = (Self::FROM_LINEAR_SIZE - 1) as f64;
+
+        SrgbLut {
+            to_linear: std::array::from_fn(|i| srgb_to_linear_f64(i as f64 / 255.0) as f32),
+            from_linear: (0..Self::FROM_LINEAR_SIZE)
+                .map(|i| (linear_to_srgb_f64(i as f64 / max) * 255.0).round() as u8)
+                .chain([0; 3])
+                .collect(),
+        }
+    }
+}
+
+impl Default for SrgbLut {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline(never)]
+pub fn srgb_to_linear_lut(params: &mut ConvertParams<[u8; 4], Vec4>, lut: &SrgbLut) {
+    convert_inner(params, |[r, g, b, a]| {
+        Vec4::new(
+            lut.to_linear[r as usize],
+            lut.to_linear[g as usize],
+            lut.to_linear[b as usize],
+            (a as f32) * (1.0 / 255.0),
+        )
+    });
+}
+
+#[inline(never)]
+pub fn linear_to_srgb_lut(params: &mut ConvertParams<Vec4, [u8; 4]>, lut: &SrgbLut) {
+    let max = (SrgbLut::FROM_LINEAR_SIZE - 1) as f32;
+
+    convert_inner(params, |c| {
+        let i = (c.clamp(Vec4::ZERO, Vec4::ONE) * max).round().as_uvec4();
+
+        [
+            lut.from_linear[i.x as usize],
+            lut.from_linear[i.y as usize],
+            lut.from_linear[i.z as usize],
+            (c.w.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ]
+    });
+}
+
+pub fn rgba8_to_vec4(c: [u8; 4]) -> Vec4 {
+    Vec4::from_array(c.map(|c| c as f32)) * (1.0 / 255.0)
+}
+
+// Return the maximum difference of any channel.
+pub fn color_max_error(dst_array: &[Vec4], expected_array: &[Vec4]) -> f32 {
+    dst_array
+        .iter()
+        .zip(expected_array.iter())
+        .map(|(&l, &r)| (l - r).abs().max_element())
+        .fold(0.0, f32::max)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Convert between any two `bevy_color` types. Most conversions go through
+// `LinearRgba`, so converting between two non-linear spaces costs two
+// conversions.
+#[inline(never)]
+pub fn color_convert<S: Copy, D: From<S>>(params: &mut ConvertParams<S, D>) {
+    convert_inner(params, D::from);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Pack RGBA colors into `u32` with red in the lowest byte, so the memory
+// layout matches `[u8; 4]` on little endian.
+
+// Truncate towards zero. Float to int `as` casts saturate in Rust, so this
+// also clamps.
+pub fn pack_rgba8_truncate_single(c: Vec4) -> u32 {
+    let c = c * 255.0;
+
+    u32::from_le_bytes([c.x as u8, c.y as u8, c.z as u8, c.w as u8])
+}
+
+// Round to nearest by adding a half before truncating. This relies on the
+// saturating cast for inputs outside [0, 1].
+pub fn pack_rgba8_round_single(c: Vec4) -> u32 {
+    let c = (c * 255.0) + 0.5;
+
+    u32::from_le_bytes([c.x as u8, c.y as u8, c.z as u8, c.w as u8])
+}
+
+// Clamp and round explicitly, so the compiler can use vector instructions
+// instead of relying on the saturating cast.
+pub fn pack_rgba8_clamp_single(c: Vec4) -> u32 {
+    let c = (c.clamp(Vec4::ZERO, Vec4::ONE) * 255.0).round().as_uvec4();
+
+    c.x | (c.y << 8) | (c.z << 16) | (c.w << 24)
+}
+
+pub fn unpack_rgba8_single(c: u32) -> Vec4 {
+    rgba8_to_vec4(c.to_le_bytes())
+}
+
+#[inline(never)]
+pub fn pack_rgba8_truncate(params: &mut ConvertParams<Vec4, u32>) {
+    convert_inner(params, pack_rgba8_truncate_single);
+}
+
+#[inline(never)]
+pub fn pack_rgba8_round(params: &mut ConvertParams<Vec4, u32>) {
+    convert_inner(params, pack_rgba8_round_single);
+}
+
+#[inline(never)]
+pub fn pack_rgba8_clamp(params: &mut ConvertParams<Vec4, u32>) {
+    convert_inner(params, pack_rgba8_clamp_single);
+}
+
+#[inline(never)]
+pub fn unpack_rgba8(params: &mut ConvertParams<u32, Vec4>) {
+    convert_inner(params, unpack_rgba8_single);
+}
+
+pub struct Rgba8Lut([f32; 256]);
+
+impl Rgba8Lut {
+    pub fn new() -> Self {
+        Rgba8Lut(std::array::from_fn(|i| (i as f32) * (1.0 / 255.0)))
+    }
+}
+
+impl Default for Rgba8Lut {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline(never)]
+pub fn unpack_rgba8_lut(params: &mut ConvertParams<u32, Vec4>, lut: &Rgba8Lut) {
+    convert_inner(params, |c| {
+        Vec4::from_array(c.to_le_bytes().map(|c| lut.0[c as usize]))
+    });
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Return linear HDR pixels with exposures spread over eight stops, like a
+// rendered scene before tonemapping.
+pub fn random_hdr_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Vec4> {
+    (0..count)
+        .map(|_| (rng.gen::<Vec3>() * rng.gen_range(-4.0f32..4.0).exp2()).extend(1.0))
+        .collect()
+}
+
+pub fn tonemap_reinhard_single(c: Vec3) -> Vec3 {
+    c / (1.0 + c)
+}
+
+// Stephen Hill's fit of the ACES reference rendering and output transforms.
+pub fn tonemap_aces_fitted_single(c: Vec3) -> Vec3 {
+    const INPUT: Mat3 = Mat3::from_cols_array(&[
+        0.59719, 0.07600, 0.02840, 0.35458, 0.90834, 0.13383, 0.04823, 0.01566, 0.83777,
+    ]);
+
+    const OUTPUT: Mat3 = Mat3::from_cols_array(&[
+        1.60475, -0.10208, -0.00327, -0.53108, 1.10813, -0.07276, -0.07367, -0.00605, 1.07602,
+    ]);
+
+    let c = INPUT * c;
+    let c = (c * (c + 0.0245786) - 0.000090537) / (c * (0.983729 * c + 0.432951) + 0.238081);
+
+    (OUTPUT * c).clamp(Vec3::ZERO, Vec3::ONE)
+}
+
+// A minimal AgX - the inset matrix, a log2 encoding, and a polynomial fit of
+// the default contrast curve, then the outset matrix.
+pub fn tonemap_agx_single(c: Vec3) -> Vec3 {
+    const INSET: Mat3 = Mat3::from_cols_array(&[
+        0.84247906,
+        0.042328242,
+        0.042375655,
+        0.0784336,
+        0.87846864,
+        0.0784336,
+        0.079223745,
+        0.07916613,
+        0.879143,
+    ]);
+
+    const OUTSET: Mat3 = Mat3::from_cols_array(&[
+        1.196879,
+        -0.052896852,
+        -0.052971636,
+        -0.09802088,
+        1.1519031,
+        -0.09804345,
+        -0.09902974,
+        -0.098961177,
+        1.1510737,
+    ]);
+
+    const MIN_EV: f32 = -12.47393;
+    const MAX_EV: f32 = 4.026069;
+
+    let c = (INSET * c).max(Vec3::splat(1e-10));
+    let c = Vec3::from_array(c.to_array().map(f32::log2));
+    let x = (c.clamp(Vec3::splat(MIN_EV), Vec3::splat(MAX_EV)) - MIN_EV) / (MAX_EV - MIN_EV);
+
+    let x2 = x * x;
+    let x4 = x2 * x2;
+
+    let c = (15.5 * x4 * x2) - (40.14 * x4 * x) + (31.96 * x4) - (6.868 * x2 * x)
+        + (0.4298 * x2)
+        + (0.1191 * x)
+        - 0.00232;
+
+    OUTSET * c
+}
+
+pub fn tonemap_inner<F>(params: &mut ConvertParams<Vec4, Vec4>, f: F)
+where
+    F: Fn(Vec3) -> Vec3,
+{
+    convert_inner(params, |c| f(c.truncate()).extend(c.w));
+}
+
+#[inline(never)]
+pub fn tonemap_reinhard(params: &mut ConvertParams<Vec4, Vec4>) {
+    tonemap_inner(params, tonemap_reinhard_single);
+}
+
+#[inline(never)]
+pub fn tonemap_aces_fitted(params: &mut ConvertParams<Vec4, Vec4>) {
+    tonemap_inner(params, tonemap_aces_fitted_single);
+}
+
+#[inline(never)]
+pub fn tonemap_agx(params: &mut ConvertParams<Vec4, Vec4>) {
+    tonemap_inner(params, tonemap_agx_single);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng, COUNT},
+        util::*,
+    };
+    use bevy_color::{ColorToComponents, Hsla, LinearRgba, Oklaba, Oklcha, Srgba};
+
+    fn run<S, D: Copy>(src_array: &[S], init: D, f: impl Fn(&mut ConvertParams<S, D>)) -> Vec<D> {
+        run_into(vec![init; src_array.len()], |dst_array| {
+            f(&mut ConvertParams {
+                dst_array,
+                src_array,
+            })
+        })
+    }
+
+    #[test]
+    fn srgb() {
+        let mut rng = test_rng();
+
+        let lut = SrgbLut::new();
+
+        let rgba8 = random_array::<[u8; 4]>(&mut rng, COUNT);
+        let srgb = rgba8.iter().copied().map(rgba8_to_vec4).collect::<Vec<_>>();
+
+        let expected = run(&srgb, Vec4::ZERO, srgb_to_linear_exact);
+
+        let piecewise = run(&srgb, Vec4::ZERO, srgb_to_linear_piecewise);
+        let gamma22 = run(&srgb, Vec4::ZERO, srgb_to_linear_gamma22);
+        let lut_linear = run(&rgba8, Vec4::ZERO, |p| srgb_to_linear_lut(p, &lut));
+
+        assert!(color_max_error(&piecewise, &expected) < 1e-6);
+        assert!(color_max_error(&gamma22, &expected) < 0.01);
+        assert!(color_max_error(&lut_linear, &expected) < 1e-6);
+
+        let linear = expected;
+
+        let expected = run(&linear, Vec4::ZERO, linear_to_srgb_exact);
+
+        let piecewise = run(&linear, Vec4::ZERO, linear_to_srgb_piecewise);
+        let gamma22 = run(&linear, Vec4::ZERO, linear_to_srgb_gamma22);
+        let lut_srgb = run(&linear, [0; 4], |p| linear_to_srgb_lut(p, &lut));
+
+        assert!(color_max_error(&piecewise, &expected) < 1e-6);
+        assert!(color_max_error(&gamma22, &expected) < 0.05);
+        assert!(color_max_error(&expected, &srgb) < 1e-5);
+
+        // Round trips through the LUTs are exact.
+        assert_eq!(lut_srgb, rgba8.as_slice());
+    }
+
+    #[test]
+    fn convert() {
+        let mut rng = test_rng();
+
+        let srgba = random_array::<[f32; 4]>(&mut rng, COUNT)
+            .iter()
+            .map(|&[r, g, b, a]| Srgba::new(r, g, b, a))
+            .collect::<Vec<_>>();
+
+        let linear = run(&srgba, LinearRgba::NONE, color_convert);
+        let oklaba = run(&linear, Oklaba::default(), color_convert);
+        let oklcha = run(&oklaba, Oklcha::default(), color_convert);
+        let hsla = run(&srgba, Hsla::default(), color_convert);
+
+        // Round trip every conversion back to sRGB.
+
+        let linear = run(&linear, Srgba::NONE, color_convert);
+        let oklaba = run(&oklaba, Srgba::NONE, color_convert);
+        let oklcha = run(&oklcha, Srgba::NONE, color_convert);
+        let hsla = run(&hsla, Srgba::NONE, color_convert);
+
+        let expected = srgba.iter().map(|c| c.to_vec4()).collect::<Vec<_>>();
+
+        for actual in [linear, oklaba, oklcha, hsla] {
+            let actual = actual.iter().map(|c| c.to_vec4()).collect::<Vec<_>>();
+
+            assert!(color_max_error(&actual, &expected) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn rgba8() {
+        let mut rng = test_rng();
+
+        // Include values outside [0, 1] to test the clamping.
+        let src_array = random_array::<Vec4>(&mut rng, COUNT)
+            .iter()
+            .map(|c| (*c * 1.2) - 0.1)
+            .collect::<Vec<_>>();
+
+        let truncate = run(&src_array, 0, pack_rgba8_truncate);
+        let round = run(&src_array, 0, pack_rgba8_round);
+        let clamp = run(&src_array, 0, pack_rgba8_clamp);
+
+        assert_eq!(round, clamp);
+
+        let clamped = src_array
+            .iter()
+            .map(|c| c.clamp(Vec4::ZERO, Vec4::ONE))
+            .collect::<Vec<_>>();
+
+        let truncate = run(&truncate, Vec4::ZERO, unpack_rgba8);
+        let round = run(&round, Vec4::ZERO, unpack_rgba8);
+
+        assert!(color_max_error(&truncate, &clamped) < 1.0 / 255.0);
+        assert!(color_max_error(&round, &clamped) <= 0.5 / 255.0);
+
+        let lut = Rgba8Lut::new();
+
+        assert_eq!(
+            round,
+            run(&clamp, Vec4::ZERO, |p| unpack_rgba8_lut(p, &lut))
+        );
+    }
+
+    #[test]
+    fn tonemap() {
+        let mut rng = test_rng();
+
+        let src_array = random_hdr_array(&mut rng, COUNT);
+
+        let ramp = (0..COUNT)
+            .map(|i| Vec4::new(1.0, 1.0, 1.0, 0.0) * (i as f32 * 0.02))
+            .collect::<Vec<_>>();
+
+        for f in [
+            tonemap_reinhard as fn(&mut ConvertParams<Vec4, Vec4>),
+            tonemap_aces_fitted,
+            tonemap_agx,
+        ] {
+            // The AgX polynomial overshoots slightly at both ends.
+            for c in run(&src_array, Vec4::ZERO, f) {
+                assert!(c.cmpge(Vec4::splat(-0.01)).all() && c.cmple(Vec4::splat(1.01)).all());
+                assert_eq!(c.w, 1.0);
+            }
+
+            let ramp = run(&ramp, Vec4::ZERO, f);
+
+            for pair in ramp.windows(2) {
+                assert!(pair[1].x >= pair[0].x - 1e-6);
+            }
+        }
+    }
+}
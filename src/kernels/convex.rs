@@ -0,0 +1,655 @@
+use crate::util::CacheAlignedVec;
+use glam::{DVec3, Quat, Vec3A};
+use rand::Rng;
+use std::collections::HashMap;
+
+// A convex polytope in world space. SAT needs the face normals and edge
+// directions, which only include one of each parallel pair.
+#[derive(Clone, Debug)]
+pub struct ConvexPolytope {
+    pub vertices: Vec<Vec3A>,
+    pub face_normals: Vec<Vec3A>,
+    pub edge_directions: Vec<Vec3A>,
+}
+
+impl ConvexPolytope {
+    // A prism extruded from a regular polygon with an even number of sides, so
+    // four sides is a box. The prism has twice as many vertices as sides.
+    pub fn prism(
+        sides: usize,
+        radius: f32,
+        half_height: f32,
+        center: Vec3A,
+        rotation: Quat,
+    ) -> Self {
+        assert!((sides >= 4) && (sides & 1) == 0);
+
+        let rotate = |v: Vec3A| rotation * v;
+
+        let corner = |i: usize| {
+            let angle = (i as f32) * std::f32::consts::TAU / (sides as f32);
+
+            Vec3A::new(angle.cos() * radius, angle.sin() * radius, 0.0)
+        };
+
+        let vertices = (0..sides)
+            .flat_map(|i| [-half_height, half_height].map(|z| corner(i) + Vec3A::new(0.0, 0.0, z)))
+            .map(|v| center + rotate(v))
+            .collect();
+
+        // Opposite sides are parallel, so only the first half are needed.
+        let side_edges = (0..(sides / 2)).map(|i| (corner(i + 1) - corner(i)).normalize());
+
+        let face_normals = side_edges
+            .clone()
+            .map(|e| Vec3A::new(e.y, -e.x, 0.0))
+            .chain([Vec3A::Z])
+            .map(rotate)
+            .collect();
+
+        let edge_directions = side_edges.chain([Vec3A::Z]).map(rotate).collect();
+
+        ConvexPolytope {
+            vertices,
+            face_normals,
+            edge_directions,
+        }
+    }
+
+    // Return the vertex furthest in the given direction.
+    pub fn support(&self, direction: Vec3A) -> Vec3A {
+        let mut best = self.vertices[0];
+        let mut best_dot = best.dot(direction);
+
+        for &v in &self.vertices[1..] {
+            let dot = v.dot(direction);
+
+            if dot > best_dot {
+                best = v;
+                best_dot = dot;
+            }
+        }
+
+        best
+    }
+
+    pub fn project(&self, axis: Vec3A) -> (f32, f32) {
+        self.vertices
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                let d = v.dot(axis);
+
+                (min.min(d), max.max(d))
+            })
+    }
+}
+
+// Return prisms with centers in a small cube, so roughly half of the pairs from
+// two arrays overlap. Like `random_aabb_array`.
+pub fn random_prism_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+    sides: usize,
+) -> CacheAlignedVec<ConvexPolytope> {
+    (0..count)
+        .map(|_| {
+            let center = rng.gen::<Vec3A>() * 4.0;
+            let radius = rng.gen_range(0.5..1.5);
+            let half_height = rng.gen_range(0.5..1.5);
+
+            ConvexPolytope::prism(sides, radius, half_height, center, rng.gen::<Quat>())
+        })
+        .collect()
+}
+
+pub struct ConvexOverlapParams<'a> {
+    pub dst_array: &'a mut [bool],
+    pub src_array: [&'a [ConvexPolytope]; 2],
+}
+
+pub fn convex_overlap_inner<F>(params: &mut ConvexOverlapParams, f: F)
+where
+    F: Fn(&ConvexPolytope, &ConvexPolytope) -> bool,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(&params.src_array[0][i], &params.src_array[1][i]);
+    }
+}
+
+// Test the face normals of both polytopes and the cross product of every pair
+// of edges, projecting all the vertices onto each axis. The number of axes
+// grows with the product of the edge counts.
+pub fn sat_overlap_single(a: &ConvexPolytope, b: &ConvexPolytope) -> bool {
+    let separated = |axis: Vec3A| {
+        let (a_min, a_max) = a.project(axis);
+        let (b_min, b_max) = b.project(axis);
+
+        (a_max < b_min) || (b_max < a_min)
+    };
+
+    if a.face_normals
+        .iter()
+        .chain(&b.face_normals)
+        .any(|&n| separated(n))
+    {
+        return false;
+    }
+
+    for &ea in &a.edge_directions {
+        for &eb in &b.edge_directions {
+            let axis = ea.cross(eb);
+
+            // Parallel edges give no new axis.
+            if axis.length_squared() > 1e-6 && separated(axis) {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+fn minkowski_support(a: &ConvexPolytope, b: &ConvexPolytope, direction: Vec3A) -> Vec3A {
+    a.support(direction) - b.support(-direction)
+}
+
+// Reduce a simplex to the feature nearest the origin and return the next search
+// direction, or `None` if the simplex contains the origin. The newest point is
+// last.
+fn gjk_simplex(simplex: &mut [Vec3A; 4], len: &mut usize) -> Option<Vec3A> {
+    let line = |simplex: &mut [Vec3A; 4], len: &mut usize, b: Vec3A, a: Vec3A| {
+        let ab = b - a;
+        let ao = -a;
+
+        if ab.dot(ao) > 0.0 {
+            *simplex = [b, a, Vec3A::ZERO, Vec3A::ZERO];
+            *len = 2;
+
+            ab.cross(ao).cross(ab)
+        } else {
+            *simplex = [a, Vec3A::ZERO, Vec3A::ZERO, Vec3A::ZERO];
+            *len = 1;
+
+            ao
+        }
+    };
+
+    let triangle = |simplex: &mut [Vec3A; 4], len: &mut usize, c: Vec3A, b: Vec3A, a: Vec3A| {
+        let ab = b - a;
+        let ac = c - a;
+        let ao = -a;
+        let abc = ab.cross(ac);
+
+        if abc.cross(ac).dot(ao) > 0.0 {
+            if ac.dot(ao) > 0.0 {
+                *simplex = [c, a, Vec3A::ZERO, Vec3A::ZERO];
+                *len = 2;
+
+                ac.cross(ao).cross(ac)
+            } else {
+                line(simplex, len, b, a)
+            }
+        } else if ab.cross(abc).dot(ao) > 0.0 {
+            line(simplex, len, b, a)
+        } else if abc.dot(ao) > 0.0 {
+            *simplex = [c, b, a, Vec3A::ZERO];
+            *len = 3;
+
+            abc
+        } else {
+            // Swap the winding so the normal faces the origin.
+            *simplex = [b, c, a, Vec3A::ZERO];
+            *len = 3;
+
+            -abc
+        }
+    };
+
+    let direction = match *len {
+        2 => line(simplex, len, simplex[0], simplex[1]),
+        3 => triangle(simplex, len, simplex[0], simplex[1], simplex[2]),
+        _ => {
+            let [d, c, b, a] = *simplex;
+            let ab = b - a;
+            let ac = c - a;
+            let ad = d - a;
+            let ao = -a;
+
+            if ab.cross(ac).dot(ao) > 0.0 {
+                triangle(simplex, len, c, b, a)
+            } else if ac.cross(ad).dot(ao) > 0.0 {
+                triangle(simplex, len, d, c, a)
+            } else if ad.cross(ab).dot(ao) > 0.0 {
+                triangle(simplex, len, b, d, a)
+            } else {
+                return None;
+            }
+        }
+    };
+
+    // The origin is on the simplex, so it's touching.
+    if direction.length_squared() == 0.0 {
+        return None;
+    }
+
+    Some(direction)
+}
+
+// Gilbert, Johnson and Keerthi, "A fast procedure for computing the distance
+// between complex objects in three-dimensional space" (1988). Boolean version
+// that stops as soon as the simplex contains the origin or a support point
+// fails to pass it. The cost is linear in the vertex count.
+pub fn gjk_overlap_single(a: &ConvexPolytope, b: &ConvexPolytope) -> bool {
+    const MAX_ITERATIONS: usize = 64;
+
+    let first = minkowski_support(a, b, Vec3A::X);
+
+    let mut simplex = [first, Vec3A::ZERO, Vec3A::ZERO, Vec3A::ZERO];
+    let mut len = 1;
+    let mut direction = -first;
+
+    if direction.length_squared() == 0.0 {
+        return true;
+    }
+
+    for _ in 0..MAX_ITERATIONS {
+        let p = minkowski_support(a, b, direction);
+
+        if p.dot(direction) < 0.0 {
+            return false;
+        }
+
+        simplex[len] = p;
+        len += 1;
+
+        match gjk_simplex(&mut simplex, &mut len) {
+            Some(d) => direction = d,
+            None => return true,
+        }
+    }
+
+    // Only reached if the shapes are touching and rounding stops convergence.
+    true
+}
+
+#[inline(never)]
+pub fn convex_overlap_sat(params: &mut ConvexOverlapParams) {
+    convex_overlap_inner(params, sat_overlap_single);
+}
+
+#[inline(never)]
+pub fn convex_overlap_gjk(params: &mut ConvexOverlapParams) {
+    convex_overlap_inner(params, gjk_overlap_single);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Return points uniformly distributed inside a unit ball. The number of hull
+// vertices grows with roughly the square root of the point count.
+pub fn random_hull_point_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<Vec3A> {
+    (0..count)
+        .map(|_| loop {
+            let p = (rng.gen::<Vec3A>() * 2.0) - 1.0;
+
+            if p.length_squared() <= 1.0 {
+                break p;
+            }
+        })
+        .collect()
+}
+
+// The plane is in `f64`, since faces get small and thin as the point count
+// grows, and rounding can make neighbouring faces disagree about which side a
+// point is on. That breaks the horizon.
+struct HullFace {
+    vertices: [u32; 3],
+    normal: DVec3,
+    distance: f64,
+    // Points above this face that haven't been added to the hull yet.
+    outside: Vec<u32>,
+    alive: bool,
+    visible: bool,
+}
+
+impl HullFace {
+    fn new(point_array: &[Vec3A], vertices: [u32; 3]) -> Self {
+        let [a, b, c] = vertices.map(|v| point_array[v as usize].as_dvec3());
+        let normal = (b - a).cross(c - a).normalize();
+
+        HullFace {
+            vertices,
+            normal,
+            distance: normal.dot(a),
+            outside: Vec::new(),
+            alive: true,
+            visible: false,
+        }
+    }
+
+    fn height(&self, p: Vec3A) -> f32 {
+        (self.normal.dot(p.as_dvec3()) - self.distance) as f32
+    }
+
+    fn edges(&self) -> [(u32, u32); 3] {
+        let [a, b, c] = self.vertices;
+
+        [(a, b), (b, c), (c, a)]
+    }
+}
+
+// Assign each point to the first face it's above, and drop points that aren't
+// above any face since they're inside the hull.
+fn assign_outside(
+    faces: &mut [HullFace],
+    new_faces: &[usize],
+    point_array: &[Vec3A],
+    points: impl Iterator<Item = u32>,
+    epsilon: f32,
+) {
+    for i in points {
+        let p = point_array[i as usize];
+
+        if let Some(&f) = new_faces.iter().find(|&&f| faces[f].height(p) > epsilon) {
+            faces[f].outside.push(i);
+        }
+    }
+}
+
+// Barber, Dobkin and Huhdanpaa, "The Quickhull Algorithm for Convex Hulls"
+// (1996). Start from a tetrahedron, then repeatedly take the farthest point
+// above a face, remove every face it can see, and connect it to the horizon.
+// Returns counter-clockwise triangles when viewed from outside. Coplanar
+// faces aren't merged, so flat areas are triangulated arbitrarily.
+#[inline(never)]
+pub fn quickhull(point_array: &[Vec3A]) -> Vec<[u32; 3]> {
+    assert!(point_array.len() >= 4);
+
+    // The initial tetrahedron uses the two most distant of the extreme points
+    // on each axis, then the farthest point from their line, then the
+    // farthest point from that plane.
+
+    let mut extremes = [0u32; 6];
+
+    for (i, &p) in point_array.iter().enumerate() {
+        for axis in 0..3 {
+            if p[axis] < point_array[extremes[axis * 2] as usize][axis] {
+                extremes[axis * 2] = i as u32;
+            }
+
+            if p[axis] > point_array[extremes[(axis * 2) + 1] as usize][axis] {
+                extremes[(axis * 2) + 1] = i as u32;
+            }
+        }
+    }
+
+    let scale = extremes
+        .iter()
+        .map(|&e| point_array[e as usize].abs().max_element())
+        .fold(0.0, f32::max);
+
+    let epsilon = scale * 1e-5;
+
+    let point = |i: u32| point_array[i as usize];
+
+    let farthest = |f: &dyn Fn(Vec3A) -> f32| {
+        (0..point_array.len() as u32)
+            .max_by(|&l, &r| f(point(l)).total_cmp(&f(point(r))))
+            .unwrap()
+    };
+
+    let (a, b) = (0..3)
+        .map(|axis| (extremes[axis * 2], extremes[(axis * 2) + 1]))
+        .max_by(|l, r| {
+            let distance = |(a, b): (u32, u32)| point(a).distance_squared(point(b));
+
+            distance(*l).total_cmp(&distance(*r))
+        })
+        .unwrap();
+
+    let c = farthest(&|p| (p - point(a)).cross(point(b) - point(a)).length_squared());
+
+    let plane = HullFace::new(point_array, [a, b, c]);
+
+    let d = farthest(&|p| plane.height(p).abs());
+
+    assert!(
+        plane.height(point(d)).abs() > epsilon,
+        "points are coplanar"
+    );
+
+    // Wind the first face so it points away from the fourth point.
+    let (b, c) = if plane.height(point(d)) > 0.0 {
+        (c, b)
+    } else {
+        (b, c)
+    };
+
+    let mut faces = [[a, b, c], [a, d, b], [b, d, c], [c, d, a]]
+        .map(|v| HullFace::new(point_array, v))
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    assign_outside(
+        &mut faces,
+        &[0, 1, 2, 3],
+        point_array,
+        (0..point_array.len() as u32).filter(|i| ![a, b, c, d].contains(i)),
+        epsilon,
+    );
+
+    // Map each directed edge to the face that contains it, so the neighbour
+    // across an edge is the face containing the reversed edge.
+    let mut edge_faces = HashMap::<(u32, u32), usize>::new();
+
+    for (f, face) in faces.iter().enumerate() {
+        for edge in face.edges() {
+            edge_faces.insert(edge, f);
+        }
+    }
+
+    let mut stack = (0..faces.len()).collect::<Vec<_>>();
+    let mut visible = Vec::new();
+    let mut horizon = Vec::new();
+    let mut orphans = Vec::new();
+    let mut new_faces = Vec::new();
+
+    while let Some(f) = stack.pop() {
+        if !faces[f].alive || faces[f].outside.is_empty() {
+            continue;
+        }
+
+        let eye = *faces[f]
+            .outside
+            .iter()
+            .max_by(|&&l, &&r| {
+                faces[f]
+                    .height(point(l))
+                    .total_cmp(&faces[f].height(point(r)))
+            })
+            .unwrap();
+
+        let eye_point = point(eye);
+
+        // Flood fill the faces that can see the eye. This doesn't use the
+        // epsilon, since faces that are nearly coplanar with the eye can split
+        // the visible faces into separate regions, and leaving one behind
+        // makes the hull concave.
+
+        visible.clear();
+        visible.push(f);
+        faces[f].visible = true;
+
+        let mut i = 0;
+
+        while i < visible.len() {
+            for (u, v) in faces[visible[i]].edges() {
+                let n = edge_faces[&(v, u)];
+
+                if !faces[n].visible && faces[n].height(eye_point) > 0.0 {
+                    faces[n].visible = true;
+                    visible.push(n);
+                }
+            }
+
+            i += 1;
+        }
+
+        // The horizon is the edges between visible and hidden faces.
+
+        horizon.clear();
+        orphans.clear();
+
+        for &v in &visible {
+            for (a, b) in faces[v].edges() {
+                if !faces[edge_faces[&(b, a)]].visible {
+                    horizon.push((a, b));
+                }
+            }
+
+            faces[v].alive = false;
+            orphans.append(&mut faces[v].outside);
+        }
+
+        for &v in &visible {
+            for edge in faces[v].edges() {
+                edge_faces.remove(&edge);
+            }
+        }
+
+        new_faces.clear();
+
+        for &(a, b) in &horizon {
+            let face = HullFace::new(point_array, [a, b, eye]);
+
+            for edge in face.edges() {
+                edge_faces.insert(edge, faces.len());
+            }
+
+            new_faces.push(faces.len());
+            faces.push(face);
+        }
+
+        assign_outside(
+            &mut faces,
+            &new_faces,
+            point_array,
+            orphans.iter().copied().filter(|&o| o != eye),
+            epsilon,
+        );
+
+        stack.extend(
+            new_faces
+                .iter()
+                .copied()
+                .filter(|&n| !faces[n].outside.is_empty()),
+        );
+    }
+
+    faces
+        .iter()
+        .filter(|f| f.alive)
+        .map(|f| f.vertices)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{run_into, test_rng, COUNT};
+
+    #[test]
+    fn convex_overlap() {
+        let unit_box = |center| ConvexPolytope::prism(4, 1.0, 0.5, center, Quat::IDENTITY);
+
+        let origin = unit_box(Vec3A::ZERO);
+
+        for (center, expected) in [
+            (Vec3A::new(0.5, 0.0, 0.0), true),
+            (Vec3A::new(0.0, 0.0, 0.9), true),
+            (Vec3A::new(3.0, 0.0, 0.0), false),
+            (Vec3A::new(0.0, 0.0, 1.1), false),
+        ] {
+            let other = unit_box(center);
+
+            assert_eq!(sat_overlap_single(&origin, &other), expected);
+            assert_eq!(gjk_overlap_single(&origin, &other), expected);
+        }
+
+        let mut rng = test_rng();
+
+        for sides in [4, 8, 16, 32] {
+            let src = [
+                random_prism_array(&mut rng, COUNT, sides),
+                random_prism_array(&mut rng, COUNT, sides),
+            ];
+
+            let run = |f: fn(&mut ConvexOverlapParams)| {
+                run_into(vec![false; COUNT], |dst_array| {
+                    f(&mut ConvexOverlapParams {
+                        dst_array,
+                        src_array: [&src[0], &src[1]],
+                    })
+                })
+            };
+
+            let expected = run(convex_overlap_sat);
+
+            let overlap_count = expected.iter().filter(|&&o| o).count();
+
+            assert!(overlap_count > COUNT / 4 && overlap_count < COUNT * 3 / 4);
+
+            assert_eq!(expected, run(convex_overlap_gjk));
+        }
+    }
+
+    #[test]
+    fn convex_hull() {
+        let mut rng = test_rng();
+
+        // Cube corners plus points inside the cube, so the hull is the corners.
+
+        let corners = (0..8)
+            .map(|i| Vec3A::new((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32));
+
+        let cube = corners
+            .chain((0..COUNT).map(|_| (rng.gen::<Vec3A>() * 0.8) + 0.1))
+            .collect::<Vec<_>>();
+
+        let hull = quickhull(&cube);
+
+        assert_eq!(hull.len(), 12);
+        assert!(hull.iter().flatten().all(|&v| v < 8));
+
+        let point_array = random_hull_point_array(&mut rng, COUNT);
+
+        let hull = quickhull(&point_array);
+
+        // Every point is on or below every face, every edge is shared by two
+        // faces with opposite winding, and the Euler characteristic is two.
+
+        for &vertices in &hull {
+            let face = HullFace::new(&point_array, vertices);
+
+            assert!(point_array.iter().all(|&p| face.height(p) <= 1e-5));
+        }
+
+        let edges = hull
+            .iter()
+            .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+            .collect::<std::collections::HashSet<_>>();
+
+        assert_eq!(edges.len(), hull.len() * 3);
+        assert!(edges.iter().all(|&(a, b)| edges.contains(&(b, a))));
+
+        let vertex_count = hull
+            .iter()
+            .flatten()
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+
+        assert_eq!(vertex_count + hull.len(), (edges.len() / 2) + 2);
+    }
+}
@@ -0,0 +1,406 @@
+use crate::{
+    kernels::{bvh::FlatBvh, vector::Vec3Soa},
+    util::CacheAlignedVec,
+};
+use bevy_math::bounding::{Aabb3d, BoundingSphere};
+use glam::{Mat4, Vec3, Vec3A, Vec4};
+use rand::Rng;
+
+// Six planes facing inwards, with the normal in xyz and the distance in w.
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    // Gribb and Hartmann's method, for a projection with a depth range of 0 to
+    // 1. The planes are normalized so that sphere tests can compare against
+    // the radius.
+    pub fn from_view_projection(m: Mat4) -> Self {
+        let [r0, r1, r2, r3] = [0, 1, 2, 3].map(|i| m.row(i));
+
+        Frustum {
+            planes: [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2]
+                .map(|p| p / p.truncate().length()),
+        }
+    }
+}
+
+// Volumes are scattered through a cube of this size, centered on the origin.
+pub const CULLING_SCENE_SIZE: f32 = 400.0;
+
+// A camera at the center of the scene that sees to the edge of the scene.
+// Roughly a tenth of the volumes are visible.
+pub fn culling_frustum() -> Frustum {
+    let projection = Mat4::perspective_rh(
+        60.0f32.to_radians(),
+        16.0 / 9.0,
+        0.1,
+        CULLING_SCENE_SIZE * 0.5,
+    );
+
+    let view = Mat4::look_to_rh(Vec3::ZERO, Vec3::new(1.0, -0.2, -1.0), Vec3::Y);
+
+    Frustum::from_view_projection(projection * view)
+}
+
+pub fn random_culling_point<R: Rng + ?Sized>(rng: &mut R) -> Vec3A {
+    (rng.gen::<Vec3A>() - 0.5) * CULLING_SCENE_SIZE
+}
+
+pub fn random_culling_sphere_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<BoundingSphere> {
+    (0..count)
+        .map(|_| BoundingSphere::new(random_culling_point(rng), rng.gen_range(0.5..2.0)))
+        .collect()
+}
+
+pub fn random_culling_aabb_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<Aabb3d> {
+    (0..count)
+        .map(|_| {
+            let half_size = Vec3A::from(rng.gen::<Vec3>() * 1.5) + 0.5;
+
+            Aabb3d::new(random_culling_point(rng), half_size)
+        })
+        .collect()
+}
+
+// The plane distance with a fixed order of operations, so the scalar and SIMD
+// kernels give identical results.
+fn plane_distance(plane: Vec4, p: Vec3A) -> f32 {
+    (((plane.x * p.x) + (plane.y * p.y)) + (plane.z * p.z)) + plane.w
+}
+
+// The corner of the box that's furthest along the plane normal.
+fn aabb_positive_corner(plane: Vec4, aabb: &Aabb3d) -> Vec3A {
+    Vec3A::select(
+        Vec3A::from(plane.truncate()).cmpgt(Vec3A::ZERO),
+        aabb.max,
+        aabb.min,
+    )
+}
+
+// The corner of the box that's furthest against the plane normal.
+fn aabb_negative_corner(plane: Vec4, aabb: &Aabb3d) -> Vec3A {
+    Vec3A::select(
+        Vec3A::from(plane.truncate()).cmpgt(Vec3A::ZERO),
+        aabb.min,
+        aabb.max,
+    )
+}
+
+// True if the sphere is not entirely outside any plane. This is conservative -
+// spheres near the frustum's corners can pass without being visible.
+pub fn sphere_in_frustum(frustum: &Frustum, sphere: &BoundingSphere) -> bool {
+    frustum
+        .planes
+        .iter()
+        .all(|&p| plane_distance(p, sphere.center) >= -sphere.radius())
+}
+
+// Same as `sphere_in_frustum`, testing the corner of the box furthest along each
+// plane's normal.
+pub fn aabb_in_frustum(frustum: &Frustum, aabb: &Aabb3d) -> bool {
+    frustum
+        .planes
+        .iter()
+        .all(|&p| plane_distance(p, aabb_positive_corner(p, aabb)) >= 0.0)
+}
+
+pub struct CullParams<'a, V: ?Sized> {
+    pub dst_array: &'a mut [bool],
+    pub volumes: &'a V,
+    pub frustum: &'a Frustum,
+}
+
+// Test each plane in turn, stopping at the first plane the volume is outside.
+#[inline(never)]
+pub fn cull_spheres_scalar(params: &mut CullParams<[BoundingSphere]>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = sphere_in_frustum(params.frustum, &params.volumes[i]);
+    }
+}
+
+#[inline(never)]
+pub fn cull_aabbs_scalar(params: &mut CullParams<[Aabb3d]>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = aabb_in_frustum(params.frustum, &params.volumes[i]);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct SphereSoa {
+    pub center: Vec3Soa,
+    pub radius: CacheAlignedVec<f32>,
+}
+
+impl SphereSoa {
+    pub fn from_spheres(spheres: &[BoundingSphere]) -> Self {
+        SphereSoa {
+            center: Vec3Soa::from_vec3s(
+                &spheres
+                    .iter()
+                    .map(|s| Vec3::from(s.center))
+                    .collect::<Vec<_>>(),
+            ),
+            radius: spheres.iter().map(|s| s.radius()).collect(),
+        }
+    }
+
+    pub fn get(&self, i: usize) -> BoundingSphere {
+        BoundingSphere::new(self.center.get(i), self.radius[i])
+    }
+}
+
+pub struct AabbSoa {
+    pub min: Vec3Soa,
+    pub max: Vec3Soa,
+}
+
+impl AabbSoa {
+    pub fn from_aabbs(aabbs: &[Aabb3d]) -> Self {
+        let axis = |f: fn(&Aabb3d) -> Vec3A| {
+            Vec3Soa::from_vec3s(&aabbs.iter().map(|a| Vec3::from(f(a))).collect::<Vec<_>>())
+        };
+
+        AabbSoa {
+            min: axis(|a| a.min),
+            max: axis(|a| a.max),
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Aabb3d {
+        Aabb3d {
+            min: self.min.get(i).into(),
+            max: self.max.get(i).into(),
+        }
+    }
+}
+
+// Test four spheres at a time against each plane. There's no early out for
+// individual spheres, but the batch stops once all four are outside.
+#[inline(never)]
+pub fn cull_spheres_x4(params: &mut CullParams<SphereSoa>) {
+    const LANES: usize = 4;
+
+    let spheres = params.volumes;
+    let len = params.dst_array.len();
+    let chunk_len = len - (len % LANES);
+
+    let planes = params.frustum.planes.map(|p| p.to_array().map(Vec4::splat));
+
+    for i in (0..chunk_len).step_by(LANES) {
+        let load = |a: &[f32]| Vec4::from_slice(&a[i..]);
+
+        let x = load(&spheres.center.x);
+        let y = load(&spheres.center.y);
+        let z = load(&spheres.center.z);
+        let neg_radius = -load(&spheres.radius);
+
+        let mut mask = 0b1111;
+
+        for [px, py, pz, pw] in planes {
+            let distance = (((px * x) + (py * y)) + (pz * z)) + pw;
+
+            mask &= distance.cmpge(neg_radius).bitmask();
+
+            if mask == 0 {
+                break;
+            }
+        }
+
+        for lane in 0..LANES {
+            params.dst_array[i + lane] = (mask & (1 << lane)) != 0;
+        }
+    }
+
+    for i in chunk_len..len {
+        params.dst_array[i] = sphere_in_frustum(params.frustum, &spheres.get(i));
+    }
+}
+
+// Same as `cull_spheres_x4`. The sign of each plane's normal is the same for
+// every lane, so the corner is selected per plane rather than per lane.
+#[inline(never)]
+pub fn cull_aabbs_x4(params: &mut CullParams<AabbSoa>) {
+    const LANES: usize = 4;
+
+    let aabbs = params.volumes;
+    let len = params.dst_array.len();
+    let chunk_len = len - (len % LANES);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        let load = |a: &[f32]| Vec4::from_slice(&a[i..]);
+
+        let min = [load(&aabbs.min.x), load(&aabbs.min.y), load(&aabbs.min.z)];
+        let max = [load(&aabbs.max.x), load(&aabbs.max.y), load(&aabbs.max.z)];
+
+        let mut mask = 0b1111;
+
+        for p in params.frustum.planes {
+            let corner = |axis: usize| {
+                if p[axis] > 0.0 {
+                    max[axis]
+                } else {
+                    min[axis]
+                }
+            };
+
+            let distance = (((Vec4::splat(p.x) * corner(0)) + (Vec4::splat(p.y) * corner(1)))
+                + (Vec4::splat(p.z) * corner(2)))
+                + Vec4::splat(p.w);
+
+            mask &= distance.cmpge(Vec4::ZERO).bitmask();
+
+            if mask == 0 {
+                break;
+            }
+        }
+
+        for lane in 0..LANES {
+            params.dst_array[i + lane] = (mask & (1 << lane)) != 0;
+        }
+    }
+
+    for i in chunk_len..len {
+        params.dst_array[i] = aabb_in_frustum(params.frustum, &aabbs.get(i));
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Containment {
+    Outside,
+    Intersecting,
+    Inside,
+}
+
+pub fn aabb_frustum_containment(frustum: &Frustum, aabb: &Aabb3d) -> Containment {
+    let mut containment = Containment::Inside;
+
+    for &p in &frustum.planes {
+        if plane_distance(p, aabb_positive_corner(p, aabb)) < 0.0 {
+            return Containment::Outside;
+        }
+
+        if plane_distance(p, aabb_negative_corner(p, aabb)) < 0.0 {
+            containment = Containment::Intersecting;
+        }
+    }
+
+    containment
+}
+
+// Walk the tree, skipping nodes that are entirely outside or inside the
+// frustum. Only the leaves that intersect the frustum test their volumes.
+// The volumes must be in the order given by `FlatBvh::build`.
+#[inline(never)]
+pub fn cull_aabbs_bvh(params: &mut CullParams<[Aabb3d]>, bvh: &FlatBvh) {
+    let mut stack = [0u32; 64];
+    let mut stack_len = 1;
+
+    while stack_len > 0 {
+        stack_len -= 1;
+
+        let node = &bvh.nodes[stack[stack_len] as usize];
+        let range = (node.first as usize)..((node.first + node.count) as usize);
+
+        match aabb_frustum_containment(params.frustum, &node.aabb) {
+            Containment::Outside => params.dst_array[range].fill(false),
+            Containment::Inside => params.dst_array[range].fill(true),
+            Containment::Intersecting if node.child == 0 => {
+                for i in range {
+                    params.dst_array[i] = aabb_in_frustum(params.frustum, &params.volumes[i]);
+                }
+            }
+            Containment::Intersecting => {
+                stack[stack_len] = node.child;
+                stack[stack_len + 1] = node.child + 1;
+                stack_len += 2;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{run_into, test_rng};
+
+    // Not a multiple of any vector width, so the remainder is tested.
+    const COUNT: usize = 1003;
+
+    #[test]
+    fn spheres() {
+        let mut rng = test_rng();
+
+        let frustum = culling_frustum();
+        let spheres = random_culling_sphere_array(&mut rng, COUNT);
+
+        let mut expected = vec![false; COUNT];
+
+        cull_spheres_scalar(&mut CullParams {
+            dst_array: &mut expected,
+            volumes: &spheres,
+            frustum: &frustum,
+        });
+
+        let visible_count = expected.iter().filter(|&&v| v).count();
+
+        assert!(visible_count > COUNT / 20 && visible_count < COUNT / 5);
+
+        let mut actual = vec![false; COUNT];
+
+        cull_spheres_x4(&mut CullParams {
+            dst_array: &mut actual,
+            volumes: &SphereSoa::from_spheres(&spheres),
+            frustum: &frustum,
+        });
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn aabbs() {
+        let mut rng = test_rng();
+
+        let frustum = culling_frustum();
+        let mut aabbs = random_culling_aabb_array(&mut rng, COUNT);
+        let bvh = FlatBvh::build(&mut aabbs);
+
+        let run = |f: &dyn Fn(&mut CullParams<[Aabb3d]>)| {
+            run_into(vec![false; COUNT], |dst_array| {
+                f(&mut CullParams {
+                    dst_array,
+                    volumes: &aabbs,
+                    frustum: &frustum,
+                })
+            })
+        };
+
+        let expected = run(&cull_aabbs_scalar);
+
+        let visible_count = expected.iter().filter(|&&v| v).count();
+
+        assert!(visible_count > COUNT / 20 && visible_count < COUNT / 5);
+
+        assert_eq!(expected, run(&|params| cull_aabbs_bvh(params, &bvh)));
+
+        let mut actual = vec![false; COUNT];
+
+        cull_aabbs_x4(&mut CullParams {
+            dst_array: &mut actual,
+            volumes: &AabbSoa::from_aabbs(&aabbs),
+            frustum: &frustum,
+        });
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::util::{random_quat, CacheAlignedVec};
+use glam::{Affine3A, Mat3, Mat4, Quat, Vec3};
+use rand::Rng;
+use std::iter::repeat_with;
+
+// The kinds of matrix that editor and reparenting code has to decompose. A
+// sheared matrix can't be represented as scale, rotation and translation, so
+// decomposing it loses information - the benchmark measures the cost anyway.
+#[derive(Clone, Copy, Debug)]
+pub enum MatrixKind {
+    PositiveScale,
+    NegativeScale,
+    Shear,
+}
+
+impl MatrixKind {
+    pub const ALL: [MatrixKind; 3] = [
+        MatrixKind::PositiveScale,
+        MatrixKind::NegativeScale,
+        MatrixKind::Shear,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MatrixKind::PositiveScale => "positive scale",
+            MatrixKind::NegativeScale => "negative scale",
+            MatrixKind::Shear => "shear",
+        }
+    }
+}
+
+pub fn random_affine<R: Rng + ?Sized>(rng: &mut R, kind: MatrixKind) -> Affine3A {
+    let mut scale = Vec3::new(
+        rng.gen_range(0.5..2.0),
+        rng.gen_range(0.5..2.0),
+        rng.gen_range(0.5..2.0),
+    );
+
+    if let MatrixKind::NegativeScale = kind {
+        scale.x = -scale.x;
+    }
+
+    let rotation = random_quat(rng);
+    let translation = Vec3::new(
+        rng.gen_range(-10.0..10.0),
+        rng.gen_range(-10.0..10.0),
+        rng.gen_range(-10.0..10.0),
+    );
+
+    let affine = Affine3A::from_scale_rotation_translation(scale, rotation, translation);
+
+    match kind {
+        MatrixKind::Shear => {
+            let shear = Mat3::from_cols(
+                Vec3::X,
+                Vec3::new(rng.gen_range(0.1..0.5), 1.0, 0.0),
+                Vec3::Z,
+            );
+
+            affine * Affine3A::from_mat3(shear)
+        }
+        _ => affine,
+    }
+}
+
+pub fn random_affine_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+    kind: MatrixKind,
+) -> CacheAlignedVec<Affine3A> {
+    repeat_with(|| random_affine(rng, kind))
+        .take(count)
+        .collect()
+}
+
+pub type ScaleRotationTranslation = (Vec3, Quat, Vec3);
+
+pub struct DecomposeParams<'a, M> {
+    pub dst_array: &'a mut [ScaleRotationTranslation],
+    pub src_array: &'a [M],
+}
+
+#[inline(never)]
+pub fn decompose_mat4(params: &mut DecomposeParams<Mat4>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.src_array[i].to_scale_rotation_translation();
+    }
+}
+
+#[inline(never)]
+pub fn decompose_affine3a(params: &mut DecomposeParams<Affine3A>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.src_array[i].to_scale_rotation_translation();
+    }
+}
+
+pub struct RecomposeParams<'a, M> {
+    pub dst_array: &'a mut [M],
+    pub src_array: &'a [ScaleRotationTranslation],
+}
+
+#[inline(never)]
+pub fn recompose_mat4(params: &mut RecomposeParams<Mat4>) {
+    for i in 0..params.dst_array.len() {
+        let (scale, rotation, translation) = params.src_array[i];
+
+        params.dst_array[i] = Mat4::from_scale_rotation_translation(scale, rotation, translation);
+    }
+}
+
+#[inline(never)]
+pub fn recompose_affine3a(params: &mut RecomposeParams<Affine3A>) {
+    for i in 0..params.dst_array.len() {
+        let (scale, rotation, translation) = params.src_array[i];
+
+        params.dst_array[i] =
+            Affine3A::from_scale_rotation_translation(scale, rotation, translation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{test_rng, COUNT, TOLERANCE};
+
+    // Matrices without shear should survive a round trip. A negative scale
+    // may come back as a different combination of negative scale and
+    // rotation, so compare the matrices rather than the parts.
+    #[test]
+    fn round_trip() {
+        let mut rng = test_rng();
+
+        for kind in [MatrixKind::PositiveScale, MatrixKind::NegativeScale] {
+            let affine_array = random_affine_array(&mut rng, COUNT, kind);
+            let mat4_array = affine_array
+                .iter()
+                .copied()
+                .map(Mat4::from)
+                .collect::<Vec<_>>();
+
+            let mut parts = vec![(Vec3::ZERO, Quat::IDENTITY, Vec3::ZERO); COUNT];
+            let mut affine_result = vec![Affine3A::IDENTITY; COUNT];
+            let mut mat4_result = vec![Mat4::IDENTITY; COUNT];
+
+            decompose_affine3a(&mut DecomposeParams {
+                dst_array: &mut parts,
+                src_array: &affine_array,
+            });
+
+            recompose_affine3a(&mut RecomposeParams {
+                dst_array: &mut affine_result,
+                src_array: &parts,
+            });
+
+            decompose_mat4(&mut DecomposeParams {
+                dst_array: &mut parts,
+                src_array: &mat4_array,
+            });
+
+            recompose_mat4(&mut RecomposeParams {
+                dst_array: &mut mat4_result,
+                src_array: &parts,
+            });
+
+            for i in 0..COUNT {
+                assert!(affine_result[i].abs_diff_eq(affine_array[i], TOLERANCE));
+                assert!(mat4_result[i].abs_diff_eq(mat4_array[i], TOLERANCE));
+            }
+        }
+    }
+}
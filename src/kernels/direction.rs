@@ -0,0 +1,182 @@
+use crate::{
+    kernels::rsqrt::rsqrt_bit_hack_single,
+    util::{random_array, CacheAlignedVec},
+};
+use bevy_math::Dir3;
+use glam::Vec3;
+use rand::Rng;
+
+// Construct directions from arbitrary vectors. Zero length vectors can't be
+// normalized, so every variant falls back to `Dir3::Y`. The unchecked variant
+// uses glam's fallback instead of checking the result.
+pub struct DirParams<'a> {
+    pub dst_array: &'a mut [Dir3],
+    pub src_array: &'a [Vec3],
+}
+
+#[inline(never)]
+pub fn dir_new(params: &mut DirParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = Dir3::new(params.src_array[i]).unwrap_or(Dir3::Y);
+    }
+}
+
+#[inline(never)]
+pub fn dir_new_unchecked(params: &mut DirParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = Dir3::new_unchecked(params.src_array[i].normalize_or(Vec3::Y));
+    }
+}
+
+#[inline(never)]
+pub fn dir_new_and_length(params: &mut DirParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = Dir3::new_and_length(params.src_array[i])
+            .map(|(dir, _)| dir)
+            .unwrap_or(Dir3::Y);
+    }
+}
+
+// Return random vectors in [-0.5, 0.5], where roughly `near_zero_fraction` of
+// them are scaled down so far that their length squared is zero, and roughly
+// `unit_fraction` of them are already normalized.
+pub fn random_near_zero_vec3_array(
+    rng: &mut impl Rng,
+    count: usize,
+    near_zero_fraction: f64,
+    unit_fraction: f64,
+) -> CacheAlignedVec<Vec3> {
+    random_array::<Vec3>(rng, count)
+        .iter()
+        .map(|&v| {
+            let r = rng.gen::<f64>();
+
+            if r < near_zero_fraction {
+                (v - 0.5) * 1e-25
+            } else if r < (near_zero_fraction + unit_fraction) {
+                (v - 0.5).normalize()
+            } else {
+                v - 0.5
+            }
+        })
+        .collect()
+}
+
+// Normalize vectors that may be zero length. Apart from `vec_normalize`, which
+// produces NaNs, every variant returns zero for those vectors.
+pub struct VecNormalizeParams<'a> {
+    pub dst_array: &'a mut [Vec3],
+    pub src_array: &'a [Vec3],
+}
+
+pub fn vec_normalize_inner<F>(params: &mut VecNormalizeParams, f: F)
+where
+    F: Fn(Vec3) -> Vec3,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(params.src_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn vec_normalize(params: &mut VecNormalizeParams) {
+    vec_normalize_inner(params, Vec3::normalize);
+}
+
+#[inline(never)]
+pub fn vec_normalize_or_zero(params: &mut VecNormalizeParams) {
+    vec_normalize_inner(params, Vec3::normalize_or_zero);
+}
+
+#[inline(never)]
+pub fn vec_try_normalize(params: &mut VecNormalizeParams) {
+    vec_normalize_inner(params, |v| v.try_normalize().unwrap_or(Vec3::ZERO));
+}
+
+// Like `normalize::reactive_renormalize`, vectors that are already unit length
+// are returned unchanged. Others use the bit hack reciprocal square root, so
+// the result is only accurate to about 0.2%.
+pub fn reactive_normalize_fast(v: Vec3) -> Vec3 {
+    let l = v.length_squared();
+
+    if (1.0 - l).abs() <= 0.0001 {
+        v
+    } else if l > 0.0 {
+        v * rsqrt_bit_hack_single(l)
+    } else {
+        Vec3::ZERO
+    }
+}
+
+#[inline(never)]
+pub fn vec_normalize_fast(params: &mut VecNormalizeParams) {
+    vec_normalize_inner(params, reactive_normalize_fast);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{run_into, test_rng, COUNT, TOLERANCE};
+
+    #[test]
+    fn dir() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<Vec3>(&mut rng, COUNT)
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| if i % 4 == 0 { v * 1e-25 } else { v - 0.5 })
+            .collect::<Vec<_>>();
+
+        let [new, unchecked, new_and_length] = [
+            dir_new as fn(&mut DirParams),
+            dir_new_unchecked,
+            dir_new_and_length,
+        ]
+        .map(|f| {
+            run_into(vec![Dir3::X; COUNT], |dst_array| {
+                f(&mut DirParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        });
+
+        assert_eq!(new, new_and_length);
+
+        for i in 0..COUNT {
+            if i % 4 == 0 {
+                assert_eq!(new[i], Dir3::Y);
+            }
+
+            assert!(new[i].abs_diff_eq(*unchecked[i], TOLERANCE));
+        }
+    }
+
+    #[test]
+    fn vec_normalize_variants() {
+        let mut rng = test_rng();
+
+        let src_array = random_near_zero_vec3_array(&mut rng, COUNT, 0.25, 0.25);
+
+        let [expected, try_normalize, fast] = [
+            vec_normalize_or_zero as fn(&mut VecNormalizeParams),
+            vec_try_normalize,
+            vec_normalize_fast,
+        ]
+        .map(|f| {
+            run_into(vec![Vec3::ONE; COUNT], |dst_array| {
+                f(&mut VecNormalizeParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        });
+
+        assert_eq!(try_normalize, expected);
+
+        for i in 0..COUNT {
+            assert!(fast[i].abs_diff_eq(expected[i], 0.002));
+        }
+    }
+}
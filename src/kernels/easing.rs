@@ -0,0 +1,581 @@
+use crate::kernels::scalar::Scalar;
+use bevy_math::prelude::*;
+use rayon::prelude::*;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[inline(never)]
+pub fn internal_smoothstep_noinline(t: f32) -> f32 {
+    (3.0 - (2.0 * t)) * t * t
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Generic over the element type so `smoothstep_generic` can also run on `f64`.
+// The other kernels only take `f32`.
+pub struct SmoothstepParams<'a, S = f32> {
+    pub dst_array: &'a mut [S],
+    pub src_array: &'a [S],
+}
+
+#[inline(never)]
+pub fn smoothstep_explicit(params: &mut SmoothstepParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = (3.0 - (2.0 * t)) * t * t;
+    }
+}
+
+// Split the arrays into chunks of `chunk_len` and run `smoothstep_explicit` on
+// each chunk as a task on the current rayon pool.
+#[inline(never)]
+pub fn smoothstep_par_chunks(params: &mut SmoothstepParams, chunk_len: usize) {
+    params
+        .dst_array
+        .par_chunks_mut(chunk_len)
+        .zip(params.src_array.par_chunks(chunk_len))
+        .for_each(|(dst_array, src_array)| {
+            smoothstep_explicit(&mut SmoothstepParams {
+                dst_array,
+                src_array,
+            })
+        });
+}
+
+#[inline(never)]
+pub fn smoothstep_unit(params: &mut SmoothstepParams) {
+    let f = SmoothStep;
+
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = f.sample_unchecked(t);
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_noinline(params: &mut SmoothstepParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = internal_smoothstep_noinline(t);
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_enum(params: &mut SmoothstepParams) {
+    let f = EaseFunction::SmoothStep;
+
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = f.sample_unchecked(t);
+    }
+}
+
+// Same as `smoothstep_explicit`, at either precision.
+#[inline(never)]
+pub fn smoothstep_generic<S: Scalar>(params: &mut SmoothstepParams<S>) {
+    let two = S::from_f32(2.0);
+    let three = S::from_f32(3.0);
+
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = (three - (two * t)) * t * t;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct SmoothstepIndirectParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub src_array: &'a [f32],
+    pub index_array: &'a [usize],
+}
+
+#[inline(never)]
+pub fn smoothstep_indirect_explicit(params: &mut SmoothstepIndirectParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[params.index_array[i]];
+
+        params.dst_array[i] = (3.0 - (2.0 * t)) * t * t;
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_indirect_unit(params: &mut SmoothstepIndirectParams) {
+    let f = SmoothStep;
+
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[params.index_array[i]];
+
+        params.dst_array[i] = f.sample_unchecked(t);
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_indirect_noinline(params: &mut SmoothstepIndirectParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[params.index_array[i]];
+
+        params.dst_array[i] = internal_smoothstep_noinline(t);
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_indirect_enum(params: &mut SmoothstepIndirectParams) {
+    let f = EaseFunction::SmoothStep;
+
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[params.index_array[i]];
+
+        params.dst_array[i] = f.sample_unchecked(t);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A lookup table of a function sampled at evenly spaced points over [0, 1].
+pub struct EaseLut {
+    // One more than the size so that linear interpolation can read the entry
+    // after the last interval without a bounds check.
+    values: Vec<f32>,
+    scale: f32,
+}
+
+impl EaseLut {
+    pub fn new(size: usize, f: impl Fn(f32) -> f32) -> Self {
+        EaseLut {
+            values: (0..=size).map(|i| f(i as f32 / size as f32)).collect(),
+            scale: size as f32,
+        }
+    }
+
+    #[inline]
+    pub fn sample_nearest(&self, t: f32) -> f32 {
+        let i = ((t * self.scale) + 0.5) as usize;
+
+        self.values[i.min(self.values.len() - 1)]
+    }
+
+    #[inline]
+    pub fn sample_linear(&self, t: f32) -> f32 {
+        let x = t * self.scale;
+        let i = (x as usize).min(self.values.len() - 2);
+        let a = x - (i as f32);
+
+        self.values[i].lerp(self.values[i + 1], a)
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_lut_nearest(params: &mut SmoothstepParams, lut: &EaseLut) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = lut.sample_nearest(t);
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_lut_linear(params: &mut SmoothstepParams, lut: &EaseLut) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = lut.sample_linear(t);
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_indirect_lut_nearest(params: &mut SmoothstepIndirectParams, lut: &EaseLut) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[params.index_array[i]];
+
+        params.dst_array[i] = lut.sample_nearest(t);
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_indirect_lut_linear(params: &mut SmoothstepIndirectParams, lut: &EaseLut) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[params.index_array[i]];
+
+        params.dst_array[i] = lut.sample_linear(t);
+    }
+}
+
+pub fn smoothstep_lut(size: usize) -> EaseLut {
+    EaseLut::new(size, |t| (3.0 - (2.0 * t)) * t * t)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Generate the same list of easing functions as an array of enums, an array
+// of function pointers, and an array of boxed closures. Each function pointer
+// and closure calls a single variant, so the match can be optimized out.
+macro_rules! ease_functions {
+    ($($f:expr),* $(,)?) => {
+        pub const EASE_FUNCTIONS: &[EaseFunction] = &[$($f),*];
+
+        pub const EASE_FUNCTION_POINTERS: &[fn(f32) -> f32] = &[$(|t| $f.sample_unchecked(t)),*];
+
+        pub fn ease_function_boxes() -> Vec<Box<dyn Fn(f32) -> f32>> {
+            vec![$(Box::new(|t| $f.sample_unchecked(t))),*]
+        }
+    };
+}
+
+// Steps is left out since its parameters vary between Bevy versions.
+ease_functions!(
+    EaseFunction::Linear,
+    EaseFunction::QuadraticIn,
+    EaseFunction::QuadraticOut,
+    EaseFunction::QuadraticInOut,
+    EaseFunction::CubicIn,
+    EaseFunction::CubicOut,
+    EaseFunction::CubicInOut,
+    EaseFunction::QuarticIn,
+    EaseFunction::QuarticOut,
+    EaseFunction::QuarticInOut,
+    EaseFunction::QuinticIn,
+    EaseFunction::QuinticOut,
+    EaseFunction::QuinticInOut,
+    EaseFunction::SmoothStepIn,
+    EaseFunction::SmoothStepOut,
+    EaseFunction::SmoothStep,
+    EaseFunction::SmootherStepIn,
+    EaseFunction::SmootherStepOut,
+    EaseFunction::SmootherStep,
+    EaseFunction::SineIn,
+    EaseFunction::SineOut,
+    EaseFunction::SineInOut,
+    EaseFunction::CircularIn,
+    EaseFunction::CircularOut,
+    EaseFunction::CircularInOut,
+    EaseFunction::ExponentialIn,
+    EaseFunction::ExponentialOut,
+    EaseFunction::ExponentialInOut,
+    EaseFunction::ElasticIn,
+    EaseFunction::ElasticOut,
+    EaseFunction::ElasticInOut,
+    EaseFunction::BackIn,
+    EaseFunction::BackOut,
+    EaseFunction::BackInOut,
+    EaseFunction::BounceIn,
+    EaseFunction::BounceOut,
+    EaseFunction::BounceInOut,
+    EaseFunction::Elastic(50.0),
+);
+
+pub struct EaseMixedParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub src_array: &'a [f32],
+    /// Index into `EASE_FUNCTIONS` for each element.
+    pub function_array: &'a [usize],
+}
+
+#[inline(never)]
+pub fn ease_mixed_enum(params: &mut EaseMixedParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+        let f = EASE_FUNCTIONS[params.function_array[i]];
+
+        params.dst_array[i] = f.sample_unchecked(t);
+    }
+}
+
+#[inline(never)]
+pub fn ease_mixed_fn_pointer(params: &mut EaseMixedParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+        let f = EASE_FUNCTION_POINTERS[params.function_array[i]];
+
+        params.dst_array[i] = f(t);
+    }
+}
+
+#[inline(never)]
+pub fn ease_mixed_dyn(params: &mut EaseMixedParams, functions: &[Box<dyn Fn(f32) -> f32>]) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+        let f = &functions[params.function_array[i]];
+
+        params.dst_array[i] = f(t);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct CurveParams<'a> {
+    pub dst_array: &'a mut [Vec3],
+    pub src_array: &'a [f32],
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+#[inline(never)]
+pub fn curve_direct(params: &mut CurveParams) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = params.start.lerp(params.end, (3.0 - (2.0 * t)) * t * t);
+    }
+}
+
+#[inline(never)]
+pub fn curve_easing(params: &mut CurveParams) {
+    let curve = EasingCurve::new(params.start, params.end, EaseFunction::SmoothStep);
+
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = curve.sample_unchecked(t);
+    }
+}
+
+#[inline(never)]
+pub fn curve_easing_clamped(params: &mut CurveParams) {
+    let curve = EasingCurve::new(params.start, params.end, EaseFunction::SmoothStep);
+
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = curve.sample_clamped(t);
+    }
+}
+
+#[inline(never)]
+pub fn curve_dyn(params: &mut CurveParams, curve: &dyn Curve<Vec3>) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = curve.sample_unchecked(t);
+    }
+}
+
+#[inline(never)]
+pub fn curve_dyn_clamped(params: &mut CurveParams, curve: &dyn Curve<Vec3>) {
+    for i in 0..params.dst_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = curve.sample_clamped(t);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng, ulps, COUNT},
+        util::*,
+    };
+
+    const MAX_ULPS: u32 = 4;
+
+    fn assert_all_near(expected: &[f32], actual: &[f32]) {
+        for (&e, &a) in expected.iter().zip(actual.iter()) {
+            assert!(ulps(e, a) <= MAX_ULPS, "expected {e}, got {a}");
+        }
+    }
+
+    #[test]
+    fn smoothstep() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+
+        let variants: [fn(&mut SmoothstepParams); 5] = [
+            smoothstep_explicit,
+            smoothstep_unit,
+            smoothstep_noinline,
+            smoothstep_enum,
+            smoothstep_generic::<f32>,
+        ];
+
+        let results = variants.map(|f| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut SmoothstepParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        });
+
+        for result in &results[1..] {
+            assert_all_near(&results[0], result);
+        }
+
+        for chunk_len in [1, 64, COUNT * 2] {
+            let mut dst_array = vec![0.0; COUNT];
+
+            smoothstep_par_chunks(
+                &mut SmoothstepParams {
+                    dst_array: &mut dst_array,
+                    src_array: &src_array,
+                },
+                chunk_len,
+            );
+
+            assert_eq!(dst_array, results[0]);
+        }
+    }
+
+    #[test]
+    fn smoothstep_indirect() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+
+        let index_array = random_array::<usize>(&mut rng, COUNT)
+            .iter()
+            .map(|i| i.rem_euclid(COUNT))
+            .collect::<Vec<_>>();
+
+        let mut expected = vec![0.0; COUNT];
+
+        smoothstep_explicit(&mut SmoothstepParams {
+            dst_array: &mut expected,
+            src_array: &index_array
+                .iter()
+                .map(|&i| src_array[i])
+                .collect::<Vec<_>>(),
+        });
+
+        let variants: [fn(&mut SmoothstepIndirectParams); 4] = [
+            smoothstep_indirect_explicit,
+            smoothstep_indirect_unit,
+            smoothstep_indirect_noinline,
+            smoothstep_indirect_enum,
+        ];
+
+        for f in variants {
+            let mut dst_array = vec![0.0; COUNT];
+
+            f(&mut SmoothstepIndirectParams {
+                dst_array: &mut dst_array,
+                src_array: &src_array,
+                index_array: &index_array,
+            });
+
+            assert_all_near(&expected, &dst_array);
+        }
+    }
+
+    #[test]
+    fn smoothstep_lut() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+
+        let mut expected = vec![0.0; COUNT];
+
+        smoothstep_explicit(&mut SmoothstepParams {
+            dst_array: &mut expected,
+            src_array: &src_array,
+        });
+
+        // The nearest error is bounded by half an interval times the maximum
+        // slope of 1.5, and the linear error by the interval squared over 8
+        // times the maximum curvature of 6.
+        for size in [256, 1024] {
+            let lut = super::smoothstep_lut(size);
+            let interval = 1.0 / size as f32;
+
+            let variants = [
+                (
+                    smoothstep_lut_nearest as fn(&mut SmoothstepParams, &EaseLut),
+                    0.5 * interval * 1.5,
+                ),
+                (smoothstep_lut_linear, (interval * interval / 8.0) * 6.0),
+            ];
+
+            for (f, tolerance) in variants {
+                let mut dst_array = vec![0.0; COUNT];
+
+                f(
+                    &mut SmoothstepParams {
+                        dst_array: &mut dst_array,
+                        src_array: &src_array,
+                    },
+                    &lut,
+                );
+
+                for (&e, &a) in expected.iter().zip(dst_array.iter()) {
+                    assert!((e - a).abs() <= tolerance * 1.01, "expected {e}, got {a}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn curve() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+        let start = Vec3::new(-1.0, 2.0, 3.0);
+        let end = Vec3::new(4.0, -5.0, 6.0);
+        let curve = EasingCurve::new(start, end, EaseFunction::SmoothStep);
+
+        let variants: [&dyn Fn(&mut CurveParams); 5] = [
+            &curve_direct,
+            &curve_easing,
+            &curve_easing_clamped,
+            &|params| curve_dyn(params, &curve),
+            &|params| curve_dyn_clamped(params, &curve),
+        ];
+
+        let results = variants.map(|f| {
+            run_into(vec![Vec3::ZERO; COUNT], |dst_array| {
+                f(&mut CurveParams {
+                    dst_array,
+                    src_array: &src_array,
+                    start,
+                    end,
+                })
+            })
+        });
+
+        for result in &results[1..] {
+            for (&e, &a) in results[0].iter().zip(result.iter()) {
+                assert!(e.abs_diff_eq(a, 1e-5), "expected {e}, got {a}");
+            }
+        }
+    }
+
+    #[test]
+    fn ease_mixed() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+
+        let function_array = random_array::<usize>(&mut rng, COUNT)
+            .iter()
+            .map(|i| i.rem_euclid(EASE_FUNCTIONS.len()))
+            .collect::<Vec<_>>();
+
+        let boxes = ease_function_boxes();
+
+        let variants: [&dyn Fn(&mut EaseMixedParams); 3] =
+            [&ease_mixed_enum, &ease_mixed_fn_pointer, &|params| {
+                ease_mixed_dyn(params, &boxes)
+            }];
+
+        let results = variants.map(|f| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut EaseMixedParams {
+                    dst_array,
+                    src_array: &src_array,
+                    function_array: &function_array,
+                })
+            })
+        });
+
+        for result in &results[1..] {
+            assert_all_near(&results[0], result);
+        }
+    }
+}
@@ -0,0 +1,962 @@
+use crate::util::CacheAlignedVec;
+use bevy_math::{
+    bounding::{Aabb3d, BoundingSphere, IntersectsVolume, RayCast3d},
+    Dir3A,
+};
+use glam::{Vec3, Vec3A, Vec4};
+use rand::Rng;
+
+// Return boxes with centers in a small cube, so roughly half of the pairs from
+// two arrays overlap and the branches can't be predicted.
+pub fn random_aabb_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Aabb3d> {
+    (0..count)
+        .map(|_| {
+            let center = rng.gen::<Vec3>() * 4.0;
+            let half_size = rng.gen::<Vec3>() + 0.5;
+
+            Aabb3d::new(center, half_size)
+        })
+        .collect()
+}
+
+pub struct AabbOverlapParams<'a, A, D> {
+    pub dst_array: &'a mut [D],
+    pub src_array: [&'a [A]; 2],
+}
+
+pub fn aabb_overlap_inner<F>(params: &mut AabbOverlapParams<Aabb3d, bool>, f: F)
+where
+    F: Fn(&Aabb3d, &Aabb3d) -> bool,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(&params.src_array[0][i], &params.src_array[1][i]);
+    }
+}
+
+// Compare one axis at a time and return as soon as an axis is separated.
+#[inline(never)]
+pub fn aabb_overlap_branchy(params: &mut AabbOverlapParams<Aabb3d, bool>) {
+    aabb_overlap_inner(params, |l, r| {
+        l.min.x <= r.max.x
+            && l.max.x >= r.min.x
+            && l.min.y <= r.max.y
+            && l.max.y >= r.min.y
+            && l.min.z <= r.max.z
+            && l.max.z >= r.min.z
+    });
+}
+
+// Compare every axis and combine the masks, so there's no branch.
+#[inline(never)]
+pub fn aabb_overlap_branchless(params: &mut AabbOverlapParams<Aabb3d, bool>) {
+    aabb_overlap_inner(params, |l, r| {
+        (l.min.cmple(r.max) & l.max.cmpge(r.min)).all()
+    });
+}
+
+#[inline(never)]
+pub fn aabb_overlap_bevy(params: &mut AabbOverlapParams<Aabb3d, bool>) {
+    aabb_overlap_inner(params, |l, r| l.intersects(r));
+}
+
+// Four boxes in SoA form, so each comparison tests one axis of four boxes.
+#[derive(Clone, Copy)]
+pub struct Aabb3dx4 {
+    pub min: [Vec4; 3],
+    pub max: [Vec4; 3],
+}
+
+impl Aabb3dx4 {
+    pub const LANES: usize = 4;
+
+    pub fn from_aabbs(aabbs: &[Aabb3d; 4]) -> Self {
+        let axis = |f: fn(&Aabb3d) -> Vec3| {
+            let [a, b, c, d] = aabbs.each_ref().map(f);
+
+            [
+                Vec4::new(a.x, b.x, c.x, d.x),
+                Vec4::new(a.y, b.y, c.y, d.y),
+                Vec4::new(a.z, b.z, c.z, d.z),
+            ]
+        };
+
+        Aabb3dx4 {
+            min: axis(|a| a.min.into()),
+            max: axis(|a| a.max.into()),
+        }
+    }
+}
+
+// Convert an array of boxes to batches of four. Any remainder is dropped.
+pub fn aabb3dx4_array_from(aabbs: &[Aabb3d]) -> CacheAlignedVec<Aabb3dx4> {
+    aabbs
+        .chunks_exact(Aabb3dx4::LANES)
+        .map(|c| Aabb3dx4::from_aabbs(c.try_into().unwrap()))
+        .collect()
+}
+
+// Each result is a bitmask of the four pairs in the batch.
+#[inline(never)]
+pub fn aabb_overlap_x4(params: &mut AabbOverlapParams<Aabb3dx4, u32>) {
+    for i in 0..params.dst_array.len() {
+        let (l, r) = (&params.src_array[0][i], &params.src_array[1][i]);
+
+        let overlap = |axis: usize| l.min[axis].cmple(r.max[axis]) & l.max[axis].cmpge(r.min[axis]);
+
+        params.dst_array[i] = (overlap(0) & overlap(1) & overlap(2)).bitmask();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug)]
+pub struct SlabRay {
+    pub origin: Vec3A,
+    pub direction: Vec3A,
+    pub inverse_direction: Vec3A,
+}
+
+impl SlabRay {
+    pub fn new(origin: Vec3A, direction: Vec3A) -> Self {
+        SlabRay {
+            origin,
+            direction,
+            inverse_direction: direction.recip(),
+        }
+    }
+}
+
+// Return a ray for each box. With probability `hit_rate` the ray passes
+// through a random point inside the box, and otherwise it points away from
+// that point so it's guaranteed to miss.
+pub fn random_slab_ray_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    aabb_array: &[Aabb3d],
+    hit_rate: f64,
+) -> CacheAlignedVec<SlabRay> {
+    aabb_array
+        .iter()
+        .map(|aabb| {
+            let target = aabb.min + ((aabb.max - aabb.min) * rng.gen::<Vec3A>());
+
+            // Start outside the box, somewhere on a sphere around the target.
+            let offset = random_unit_vec3a(rng) * 10.0;
+            let origin = target + offset;
+
+            let direction = if rng.gen_bool(hit_rate) {
+                -offset.normalize()
+            } else {
+                offset.normalize()
+            };
+
+            SlabRay::new(origin, direction)
+        })
+        .collect()
+}
+
+pub fn random_unit_vec3a<R: Rng + ?Sized>(rng: &mut R) -> Vec3A {
+    ((rng.gen::<Vec3A>() * 2.0) - 1.0).normalize_or(Vec3A::X)
+}
+
+pub struct RayAabbParams<'a, R> {
+    pub dst_array: &'a mut [bool],
+    pub ray_array: &'a [R],
+    pub aabb_array: &'a [Aabb3d],
+}
+
+pub fn ray_aabb_inner<R, F>(params: &mut RayAabbParams<R>, f: F)
+where
+    F: Fn(&R, &Aabb3d) -> bool,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(&params.ray_array[i], &params.aabb_array[i]);
+    }
+}
+
+// Divide by the direction on every test. If the direction has a zero
+// component and the origin is on that slab's plane then the result is NaN,
+// and the outcome depends on the order of the min/max arguments.
+#[inline(never)]
+pub fn ray_aabb_division(params: &mut RayAabbParams<SlabRay>) {
+    ray_aabb_inner(params, |ray, aabb| {
+        let t0 = (aabb.min - ray.origin) / ray.direction;
+        let t1 = (aabb.max - ray.origin) / ray.direction;
+
+        let t_min = t0.min(t1).max_element().max(0.0);
+        let t_max = t0.max(t1).min_element();
+
+        t_min <= t_max
+    });
+}
+
+// Same as `ray_aabb_division`, but multiply by the precomputed inverse.
+#[inline(never)]
+pub fn ray_aabb_inverse(params: &mut RayAabbParams<SlabRay>) {
+    ray_aabb_inner(params, |ray, aabb| {
+        let t0 = (aabb.min - ray.origin) * ray.inverse_direction;
+        let t1 = (aabb.max - ray.origin) * ray.inverse_direction;
+
+        let t_min = t0.min(t1).max_element().max(0.0);
+        let t_max = t0.max(t1).min_element();
+
+        t_min <= t_max
+    });
+}
+
+// Tavian Barnes' formulation, which clamps each axis's interval to the current
+// interval. `f32::min` and `f32::max` return the other argument if one is NaN,
+// so the result never depends on argument order - a ray that lies exactly in
+// the plane of a face always misses. The interval collapses to a point for
+// boxes behind the ray, so the final comparison is strict.
+#[inline(never)]
+pub fn ray_aabb_nan_safe(params: &mut RayAabbParams<SlabRay>) {
+    ray_aabb_inner(params, |ray, aabb| {
+        let t0 = (aabb.min - ray.origin) * ray.inverse_direction;
+        let t1 = (aabb.max - ray.origin) * ray.inverse_direction;
+
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+
+        for axis in 0..3 {
+            t_min = t_min.max(t0[axis].min(t1[axis]).min(t_max));
+            t_max = t_max.min(t0[axis].max(t1[axis]).max(t_min));
+        }
+
+        t_min < t_max
+    });
+}
+
+// Same as `ray_aabb_nan_safe`, but returns the distance to the box, or infinity
+// if the ray misses. The distance is zero if the origin is inside the box.
+pub fn slab_ray_distance(ray: &SlabRay, aabb: &Aabb3d) -> f32 {
+    let t0 = (aabb.min - ray.origin) * ray.inverse_direction;
+    let t1 = (aabb.max - ray.origin) * ray.inverse_direction;
+
+    let mut t_min = 0.0f32;
+    let mut t_max = f32::INFINITY;
+
+    for axis in 0..3 {
+        t_min = t_min.max(t0[axis].min(t1[axis]).min(t_max));
+        t_max = t_max.min(t0[axis].max(t1[axis]).max(t_min));
+    }
+
+    if t_min < t_max {
+        t_min
+    } else {
+        f32::INFINITY
+    }
+}
+
+pub fn ray_cast_3d_from_slab_ray(ray: &SlabRay) -> RayCast3d {
+    RayCast3d::new(ray.origin, Dir3A::new_unchecked(ray.direction), f32::MAX)
+}
+
+#[inline(never)]
+pub fn ray_aabb_bevy(params: &mut RayAabbParams<RayCast3d>) {
+    ray_aabb_inner(params, |ray, aabb| ray.intersects(aabb));
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray3A {
+    pub origin: Vec3A,
+    pub direction: Vec3A,
+}
+
+pub type Triangle3A = [Vec3A; 3];
+
+// Return triangles scattered through a large cube, with edges of roughly one
+// unit. There's no relation between neighbouring triangles.
+pub fn random_triangle_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<Triangle3A> {
+    (0..count)
+        .map(|_| {
+            let center = rng.gen::<Vec3A>() * 100.0;
+
+            [(); 3].map(|_| center + random_unit_vec3a(rng))
+        })
+        .collect()
+}
+
+// Return a ray for each triangle. With probability `hit_rate` the ray passes
+// through a random point inside the triangle, and otherwise through a point in
+// the triangle's plane that's beyond one of its vertices. Misses are rejected
+// by the barycentric tests rather than by the ray pointing away.
+pub fn random_triangle_ray_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    triangle_array: &[Triangle3A],
+    hit_rate: f64,
+) -> CacheAlignedVec<Ray3A> {
+    triangle_array
+        .iter()
+        .map(|&[a, b, c]| {
+            let target = if rng.gen_bool(hit_rate) {
+                let (mut u, mut v) = (rng.gen::<f32>(), rng.gen::<f32>());
+
+                if (u + v) > 1.0 {
+                    (u, v) = (1.0 - u, 1.0 - v);
+                }
+
+                a + ((b - a) * u) + ((c - a) * v)
+            } else {
+                let centroid = (a + b + c) / 3.0;
+                let vertex = [a, b, c][rng.gen_range(0..3)];
+
+                vertex + ((vertex - centroid) * rng.gen_range(0.1..1.0))
+            };
+
+            let offset = random_unit_vec3a(rng) * 10.0;
+
+            Ray3A {
+                origin: target + offset,
+                direction: -offset.normalize(),
+            }
+        })
+        .collect()
+}
+
+// Each result is the distance along the ray to the hit, or infinity if the ray
+// missed.
+pub struct RayTriangleParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub ray_array: &'a [Ray3A],
+    pub triangle_array: &'a [Triangle3A],
+}
+
+pub fn ray_triangle_inner<F>(params: &mut RayTriangleParams, f: F)
+where
+    F: Fn(&Ray3A, &Triangle3A) -> f32,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(&params.ray_array[i], &params.triangle_array[i]);
+    }
+}
+
+// Möller and Trumbore, "Fast, Minimum Storage Ray/Triangle Intersection"
+// (1997). Double sided. Rays that hit an edge shared by two triangles can miss
+// both due to rounding.
+pub fn moller_trumbore_single(ray: &Ray3A, triangle: &Triangle3A) -> f32 {
+    let [a, b, c] = *triangle;
+
+    let e1 = b - a;
+    let e2 = c - a;
+    let p = ray.direction.cross(e2);
+    let det = e1.dot(p);
+
+    if det.abs() < 1e-8 {
+        return f32::INFINITY;
+    }
+
+    let inv_det = det.recip();
+    let s = ray.origin - a;
+    let u = s.dot(p) * inv_det;
+
+    if !(0.0..=1.0).contains(&u) {
+        return f32::INFINITY;
+    }
+
+    let q = s.cross(e1);
+    let v = ray.direction.dot(q) * inv_det;
+
+    if (v < 0.0) || ((u + v) > 1.0) {
+        return f32::INFINITY;
+    }
+
+    let t = e2.dot(q) * inv_det;
+
+    if t >= 0.0 {
+        t
+    } else {
+        f32::INFINITY
+    }
+}
+
+// Woop, Benthin and Wald, "Watertight Ray/Triangle Intersection" (2013).
+// Shears the triangle into a space where the ray runs along the z axis, so the
+// edge tests are 2D and consistent between triangles that share an edge. Edge
+// tests that land exactly on zero are recomputed in f64. A practical version
+// would precompute the shear per ray.
+pub fn watertight_single(ray: &Ray3A, triangle: &Triangle3A) -> f32 {
+    let d = ray.direction;
+    let abs = d.abs();
+
+    let kz = if abs.x > abs.y {
+        if abs.x > abs.z {
+            0
+        } else {
+            2
+        }
+    } else if abs.y > abs.z {
+        1
+    } else {
+        2
+    };
+
+    let (kx, ky) = if d[kz] < 0.0 {
+        ((kz + 2) % 3, (kz + 1) % 3)
+    } else {
+        ((kz + 1) % 3, (kz + 2) % 3)
+    };
+
+    let sz = d[kz].recip();
+    let sx = d[kx] * sz;
+    let sy = d[ky] * sz;
+
+    let [a, b, c] = triangle.map(|v| v - ray.origin);
+
+    let ax = a[kx] - (sx * a[kz]);
+    let ay = a[ky] - (sy * a[kz]);
+    let bx = b[kx] - (sx * b[kz]);
+    let by = b[ky] - (sy * b[kz]);
+    let cx = c[kx] - (sx * c[kz]);
+    let cy = c[ky] - (sy * c[kz]);
+
+    let mut u = (cx * by) - (cy * bx);
+    let mut v = (ax * cy) - (ay * cx);
+    let mut w = (bx * ay) - (by * ax);
+
+    if (u == 0.0) || (v == 0.0) || (w == 0.0) {
+        let edge = |px: f32, py: f32, qx: f32, qy: f32| {
+            ((px as f64 * qy as f64) - (py as f64 * qx as f64)) as f32
+        };
+
+        u = edge(cx, cy, bx, by);
+        v = edge(ax, ay, cx, cy);
+        w = edge(bx, by, ax, ay);
+    }
+
+    if ((u < 0.0) || (v < 0.0) || (w < 0.0)) && ((u > 0.0) || (v > 0.0) || (w > 0.0)) {
+        return f32::INFINITY;
+    }
+
+    let det = u + v + w;
+
+    if det == 0.0 {
+        return f32::INFINITY;
+    }
+
+    let t = ((u * a[kz]) + (v * b[kz]) + (w * c[kz])) * sz;
+    let t = t / det;
+
+    if t >= 0.0 {
+        t
+    } else {
+        f32::INFINITY
+    }
+}
+
+#[inline(never)]
+pub fn ray_triangle_moller_trumbore(params: &mut RayTriangleParams) {
+    ray_triangle_inner(params, moller_trumbore_single);
+}
+
+#[inline(never)]
+pub fn ray_triangle_watertight(params: &mut RayTriangleParams) {
+    ray_triangle_inner(params, watertight_single);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A primitive that can return the distance along a ray to its surface, or
+// infinity if the ray misses. Rays must have a normalized direction.
+pub trait RayPrimitive: Copy {
+    fn ray_distance(&self, ray: &Ray3A) -> f32;
+
+    fn center(&self) -> Vec3A;
+}
+
+impl RayPrimitive for BoundingSphere {
+    fn ray_distance(&self, ray: &Ray3A) -> f32 {
+        let offset = ray.origin - self.center;
+        let b = offset.dot(ray.direction);
+        let c = offset.length_squared() - (self.radius() * self.radius());
+
+        // Outside the sphere and pointing away.
+        if (c > 0.0) && (b > 0.0) {
+            return f32::INFINITY;
+        }
+
+        let discriminant = (b * b) - c;
+
+        if discriminant < 0.0 {
+            return f32::INFINITY;
+        }
+
+        (-b - discriminant.sqrt()).max(0.0)
+    }
+
+    fn center(&self) -> Vec3A {
+        self.center
+    }
+}
+
+// The set of points `p` where `normal.dot(p) == distance`. Double sided.
+#[derive(Clone, Copy, Debug)]
+pub struct Plane3A {
+    pub normal: Vec3A,
+    pub distance: f32,
+}
+
+impl RayPrimitive for Plane3A {
+    fn ray_distance(&self, ray: &Ray3A) -> f32 {
+        let denominator = self.normal.dot(ray.direction);
+
+        if denominator.abs() <= f32::EPSILON {
+            return f32::INFINITY;
+        }
+
+        let t = (self.distance - self.normal.dot(ray.origin)) / denominator;
+
+        if t >= 0.0 {
+            t
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn center(&self) -> Vec3A {
+        self.normal * self.distance
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Capsule3A {
+    pub a: Vec3A,
+    pub b: Vec3A,
+    pub radius: f32,
+}
+
+impl RayPrimitive for Capsule3A {
+    // Inigo Quilez's version - intersect the infinite cylinder, then fall back
+    // to whichever end sphere is on the near side of the hit.
+    fn ray_distance(&self, ray: &Ray3A) -> f32 {
+        let ba = self.b - self.a;
+        let oa = ray.origin - self.a;
+
+        let baba = ba.dot(ba);
+        let bard = ba.dot(ray.direction);
+        let baoa = ba.dot(oa);
+        let rdoa = ray.direction.dot(oa);
+        let oaoa = oa.dot(oa);
+
+        let a = baba - (bard * bard);
+        let b = (baba * rdoa) - (baoa * bard);
+        let c = (baba * oaoa) - (baoa * baoa) - (self.radius * self.radius * baba);
+
+        let h = (b * b) - (a * c);
+
+        if h < 0.0 {
+            return f32::INFINITY;
+        }
+
+        let t = (-b - h.sqrt()) / a;
+        let y = baoa + (t * bard);
+
+        let t = if (y > 0.0) && (y < baba) {
+            t
+        } else {
+            let oc = if y <= 0.0 { oa } else { ray.origin - self.b };
+            let b = ray.direction.dot(oc);
+            let c = oc.dot(oc) - (self.radius * self.radius);
+            let h = (b * b) - c;
+
+            if h <= 0.0 {
+                return f32::INFINITY;
+            }
+
+            -b - h.sqrt()
+        };
+
+        if t >= 0.0 {
+            t
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn center(&self) -> Vec3A {
+        (self.a + self.b) * 0.5
+    }
+}
+
+// Primitives are scattered through a cube of this size, starting at the origin.
+pub const PRIMITIVE_SCENE_SIZE: f32 = 10.0;
+
+fn random_scene_point<R: Rng + ?Sized>(rng: &mut R) -> Vec3A {
+    rng.gen::<Vec3A>() * PRIMITIVE_SCENE_SIZE
+}
+
+pub fn random_sphere_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<BoundingSphere> {
+    (0..count)
+        .map(|_| BoundingSphere::new(random_scene_point(rng), rng.gen_range(0.5..1.5)))
+        .collect()
+}
+
+pub fn random_plane_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Plane3A> {
+    (0..count)
+        .map(|_| {
+            let normal = random_unit_vec3a(rng);
+
+            Plane3A {
+                normal,
+                distance: normal.dot(random_scene_point(rng)),
+            }
+        })
+        .collect()
+}
+
+pub fn random_capsule_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<Capsule3A> {
+    (0..count)
+        .map(|_| {
+            let a = random_scene_point(rng);
+
+            Capsule3A {
+                a,
+                b: a + (random_unit_vec3a(rng) * rng.gen_range(0.5..2.0)),
+                radius: rng.gen_range(0.25..0.75),
+            }
+        })
+        .collect()
+}
+
+// Return rays that start outside a sphere of `distance` around `target`, and
+// point at random points in a cube of `spread` around the target.
+pub fn random_ray_array_toward<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+    target: Vec3A,
+    distance: f32,
+    spread: f32,
+) -> CacheAlignedVec<Ray3A> {
+    (0..count)
+        .map(|_| {
+            let origin = target + (random_unit_vec3a(rng) * distance);
+            let aim = target + (((rng.gen::<Vec3A>() * 2.0) - 1.0) * spread);
+
+            Ray3A {
+                origin,
+                direction: (aim - origin).normalize(),
+            }
+        })
+        .collect()
+}
+
+// One ray against every primitive in the array, like a picker testing against
+// every object in a scene.
+pub struct RayManyPrimitivesParams<'a, P> {
+    pub dst_array: &'a mut [f32],
+    pub ray: Ray3A,
+    pub primitive_array: &'a [P],
+}
+
+#[inline(never)]
+pub fn ray_many_primitives<P: RayPrimitive>(params: &mut RayManyPrimitivesParams<P>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.primitive_array[i].ray_distance(&params.ray);
+    }
+}
+
+// Every ray in the array against one primitive, like a lightmapper testing
+// against a single occluder.
+pub struct ManyRaysPrimitiveParams<'a, P> {
+    pub dst_array: &'a mut [f32],
+    pub ray_array: &'a [Ray3A],
+    pub primitive: P,
+}
+
+#[inline(never)]
+pub fn many_rays_primitive<P: RayPrimitive>(params: &mut ManyRaysPrimitiveParams<P>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.primitive.ray_distance(&params.ray_array[i]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{run_into, test_rng, COUNT};
+
+    #[test]
+    fn aabb_overlap() {
+        let mut rng = test_rng();
+
+        let src = [
+            random_aabb_array(&mut rng, COUNT),
+            random_aabb_array(&mut rng, COUNT),
+        ];
+
+        let run = |f: fn(&mut AabbOverlapParams<Aabb3d, bool>)| {
+            run_into(vec![false; COUNT], |dst_array| {
+                f(&mut AabbOverlapParams {
+                    dst_array,
+                    src_array: [&src[0], &src[1]],
+                })
+            })
+        };
+
+        let expected = run(aabb_overlap_bevy);
+
+        let overlap_count = expected.iter().filter(|&&o| o).count();
+
+        assert!(overlap_count > COUNT / 4 && overlap_count < COUNT * 3 / 4);
+
+        assert_eq!(expected, run(aabb_overlap_branchy));
+        assert_eq!(expected, run(aabb_overlap_branchless));
+
+        let src_x4 = src.each_ref().map(|s| aabb3dx4_array_from(s));
+
+        let mut dst_array = vec![0; COUNT / Aabb3dx4::LANES];
+
+        aabb_overlap_x4(&mut AabbOverlapParams {
+            dst_array: &mut dst_array,
+            src_array: [&src_x4[0], &src_x4[1]],
+        });
+
+        for (i, mask) in dst_array.iter().enumerate() {
+            for lane in 0..Aabb3dx4::LANES {
+                assert_eq!(
+                    (mask & (1 << lane)) != 0,
+                    expected[(i * Aabb3dx4::LANES) + lane]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ray_aabb() {
+        let mut rng = test_rng();
+
+        let aabb_array = random_aabb_array(&mut rng, COUNT);
+        let ray_array = random_slab_ray_array(&mut rng, &aabb_array, 0.5);
+
+        let run = |f: fn(&mut RayAabbParams<SlabRay>), ray_array: &[SlabRay]| {
+            run_into(vec![false; ray_array.len()], |dst_array| {
+                f(&mut RayAabbParams {
+                    dst_array,
+                    ray_array,
+                    aabb_array: &aabb_array,
+                })
+            })
+        };
+
+        let expected = run(ray_aabb_division, &ray_array);
+
+        let hit_count = expected.iter().filter(|&&h| h).count();
+
+        assert!(hit_count > COUNT / 4 && hit_count < COUNT * 3 / 4);
+
+        assert_eq!(expected, run(ray_aabb_inverse, &ray_array));
+        assert_eq!(expected, run(ray_aabb_nan_safe, &ray_array));
+
+        let ray_cast_array = ray_array
+            .iter()
+            .map(ray_cast_3d_from_slab_ray)
+            .collect::<Vec<_>>();
+
+        let mut bevy = vec![false; COUNT];
+
+        ray_aabb_bevy(&mut RayAabbParams {
+            dst_array: &mut bevy,
+            ray_array: &ray_cast_array,
+            aabb_array: &aabb_array,
+        });
+
+        assert_eq!(expected, bevy);
+
+        // Rays that slide along the min and max faces of the box, so one axis
+        // has a zero direction and an origin on the slab's plane.
+
+        let aabb = aabb_array[0];
+        let y = (aabb.min.y + aabb.max.y) * 0.5;
+
+        let grazing = [aabb.min.x, aabb.max.x]
+            .map(|x| SlabRay::new(Vec3A::new(x, y, aabb.min.z - 1.0), Vec3A::Z));
+
+        let mut dst_array = [true; 2];
+
+        ray_aabb_nan_safe(&mut RayAabbParams {
+            dst_array: &mut dst_array,
+            ray_array: &grazing,
+            aabb_array: &[aabb; 2],
+        });
+
+        assert_eq!(dst_array, [false; 2]);
+    }
+
+    #[test]
+    fn ray_triangle() {
+        let mut rng = test_rng();
+
+        let triangle_array = random_triangle_array(&mut rng, COUNT);
+        let ray_array = random_triangle_ray_array(&mut rng, &triangle_array, 0.5);
+
+        let run = |f: fn(&mut RayTriangleParams)| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut RayTriangleParams {
+                    dst_array,
+                    ray_array: &ray_array,
+                    triangle_array: &triangle_array,
+                })
+            })
+        };
+
+        let moller_trumbore = run(ray_triangle_moller_trumbore);
+        let watertight = run(ray_triangle_watertight);
+
+        let hit_count = moller_trumbore.iter().filter(|t| t.is_finite()).count();
+
+        assert!(hit_count > COUNT / 4 && hit_count < COUNT * 3 / 4);
+
+        for (m, w) in moller_trumbore.iter().zip(watertight.iter()) {
+            assert_eq!(m.is_finite(), w.is_finite());
+
+            if m.is_finite() {
+                assert!((m - w).abs() < 1e-3, "{m} != {w}");
+            }
+        }
+
+        // Rays through points on an edge shared by two coplanar triangles. The
+        // watertight test must hit at least one of them.
+
+        let [a, b, c] = [
+            Vec3A::new(0.1, 0.2, 0.3),
+            Vec3A::new(0.7, 0.5, 0.9),
+            Vec3A::new(1.3, -0.6, 0.2),
+        ];
+
+        let triangles = [[a, b, c], [b, a, (a + b) - c]];
+
+        for i in 0..100 {
+            let target = a.lerp(b, (i as f32) / 100.0);
+            let offset = random_unit_vec3a(&mut rng) * 10.0;
+
+            let ray = Ray3A {
+                origin: target + offset,
+                direction: -offset.normalize(),
+            };
+
+            assert!(triangles
+                .iter()
+                .any(|t| watertight_single(&ray, t).is_finite()));
+        }
+    }
+
+    #[test]
+    fn ray_primitives() {
+        let mut rng = test_rng();
+
+        let center = Vec3A::splat(PRIMITIVE_SCENE_SIZE * 0.5);
+        let ray_array = random_ray_array_toward(&mut rng, COUNT, center, 20.0, 5.0);
+
+        // Spheres against bevy.
+
+        let sphere = random_sphere_array(&mut rng, 1)[0];
+        let ray_array_sphere = random_ray_array_toward(&mut rng, COUNT, sphere.center, 5.0, 2.0);
+
+        let mut dst_array = vec![0.0; COUNT];
+
+        many_rays_primitive(&mut ManyRaysPrimitiveParams {
+            dst_array: &mut dst_array,
+            ray_array: &ray_array_sphere,
+            primitive: sphere,
+        });
+
+        let hit_count = dst_array.iter().filter(|t| t.is_finite()).count();
+
+        assert!(hit_count > COUNT / 10 && hit_count < COUNT * 9 / 10);
+
+        for (ray, t) in ray_array_sphere.iter().zip(dst_array.iter()) {
+            let expected =
+                RayCast3d::new(ray.origin, Dir3A::new_unchecked(ray.direction), f32::MAX)
+                    .sphere_intersection_at(&sphere)
+                    .unwrap_or(f32::INFINITY);
+
+            assert!(
+                (t == &expected) || ((t - expected).abs() < 1e-4),
+                "{t} != {expected}"
+            );
+        }
+
+        // Planes, by checking that the hit is on the plane and that misses are
+        // parallel or behind.
+
+        let plane_array = random_plane_array(&mut rng, COUNT);
+
+        let mut dst_array = vec![0.0; COUNT];
+
+        ray_many_primitives(&mut RayManyPrimitivesParams {
+            dst_array: &mut dst_array,
+            ray: ray_array[0],
+            primitive_array: &plane_array,
+        });
+
+        for (plane, t) in plane_array.iter().zip(dst_array.iter()) {
+            let ray = ray_array[0];
+
+            if t.is_finite() {
+                let point = ray.origin + (ray.direction * t);
+
+                assert!((plane.normal.dot(point) - plane.distance).abs() < (t.max(10.0) * 1e-5));
+            } else {
+                let side = plane.normal.dot(ray.origin) - plane.distance;
+
+                assert!((side * plane.normal.dot(ray.direction)) >= 0.0);
+            }
+        }
+
+        // Capsules against sphere tracing the signed distance function.
+
+        let capsule_array = random_capsule_array(&mut rng, COUNT);
+
+        let capsule_distance = |c: &Capsule3A, p: Vec3A| {
+            let pa = p - c.a;
+            let ba = c.b - c.a;
+            let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+
+            (pa - (ba * h)).length() - c.radius
+        };
+
+        let mut hit_count = 0;
+
+        for (capsule, ray) in capsule_array.iter().zip(ray_array.iter()) {
+            let ray = Ray3A {
+                direction: ((capsule.center() - ray.origin).normalize() + (ray.direction * 0.1))
+                    .normalize(),
+                ..*ray
+            };
+
+            let t = capsule.ray_distance(&ray);
+
+            let mut expected = 0.0;
+
+            while expected < 100.0 {
+                let d = capsule_distance(capsule, ray.origin + (ray.direction * expected));
+
+                if d < 1e-5 {
+                    break;
+                }
+
+                expected += d;
+            }
+
+            if expected < 100.0 {
+                hit_count += 1;
+
+                assert!((t - expected).abs() < 1e-3, "{t} != {expected}");
+            } else {
+                assert_eq!(t, f32::INFINITY);
+            }
+        }
+
+        assert!(hit_count > COUNT / 10 && hit_count < COUNT * 9 / 10);
+    }
+}
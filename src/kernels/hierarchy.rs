@@ -0,0 +1,236 @@
+use bevy_transform::components::{GlobalTransform, Transform};
+use glam::Vec3;
+use rand::Rng;
+use rayon::prelude::*;
+use std::ops::Range;
+
+use crate::util::random_quat;
+
+// A forest of transforms. Each root's subtree is stored contiguously, and
+// within a subtree every parent comes before its children, so propagation is
+// a single forward pass.
+pub struct Hierarchy {
+    pub local_array: Vec<Transform>,
+    // The parent of each node, or `None` for roots.
+    pub parent_array: Vec<Option<u32>>,
+    // The nodes covered by each root's subtree, in order.
+    pub subtrees: Vec<Range<usize>>,
+}
+
+impl Hierarchy {
+    pub fn len(&self) -> usize {
+        self.local_array.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.local_array.is_empty()
+    }
+}
+
+// Return a transform with a small uniform scale, as is typical in a hierarchy.
+pub fn random_uniform_transform(rng: &mut impl Rng) -> Transform {
+    Transform {
+        translation: rng.gen::<Vec3>() * 2.0 - 1.0,
+        rotation: random_quat(rng),
+        scale: Vec3::splat(rng.gen_range(0.9..1.1)),
+    }
+}
+
+// Return a hierarchy with `root_count` subtrees of `subtree_size` nodes each.
+// Each node's parent is one of the few nodes before it, which gives a mix of
+// deep chains and wide fans.
+pub fn random_hierarchy(rng: &mut impl Rng, root_count: usize, subtree_size: usize) -> Hierarchy {
+    let count = root_count * subtree_size;
+
+    let local_array = (0..count).map(|_| random_uniform_transform(rng)).collect();
+
+    let subtrees = (0..root_count)
+        .map(|root| (root * subtree_size)..((root + 1) * subtree_size))
+        .collect::<Vec<_>>();
+
+    let parent_array = subtrees
+        .iter()
+        .flat_map(|subtree| {
+            let start = subtree.start;
+
+            subtree.clone().map(move |i| (start, i))
+        })
+        .map(|(start, i)| {
+            (i > start).then(|| rng.gen_range(i.saturating_sub(8).max(start)..i) as u32)
+        })
+        .collect();
+
+    Hierarchy {
+        local_array,
+        parent_array,
+        subtrees,
+    }
+}
+
+// Propagate the nodes starting at `start`, where `dst_array` holds the global
+// transforms of those nodes. All parents must be within the same range.
+pub fn propagate_range(dst_array: &mut [Transform], start: usize, hierarchy: &Hierarchy) {
+    for j in 0..dst_array.len() {
+        let i = start + j;
+        let local = hierarchy.local_array[i];
+
+        dst_array[j] = match hierarchy.parent_array[i] {
+            Some(parent) => dst_array[parent as usize - start].mul_transform(local),
+            None => local,
+        };
+    }
+}
+
+#[inline(never)]
+pub fn propagate_serial(dst_array: &mut [Transform], hierarchy: &Hierarchy) {
+    propagate_range(dst_array, 0, hierarchy);
+}
+
+// Propagate each subtree as a separate rayon task, on whatever thread pool is
+// current.
+#[inline(never)]
+pub fn propagate_parallel(dst_array: &mut [Transform], hierarchy: &Hierarchy) {
+    let mut chunks = Vec::with_capacity(hierarchy.subtrees.len());
+    let mut rest = dst_array;
+
+    for subtree in &hierarchy.subtrees {
+        let (chunk, tail) = rest.split_at_mut(subtree.len());
+
+        chunks.push((chunk, subtree.start));
+        rest = tail;
+    }
+
+    chunks
+        .into_par_iter()
+        .for_each(|(chunk, start)| propagate_range(chunk, start, hierarchy));
+}
+
+// Compute a child's local transform relative to a new parent, given the global
+// transforms of both.
+pub struct ReparentParams<'a, G> {
+    pub dst_array: &'a mut [Transform],
+    pub child_array: &'a [G],
+    pub parent_array: &'a [G],
+}
+
+// The inverse of a transform with uniform scale. A transform with non-uniform
+// scale and rotation has an inverse that can't be represented as a `Transform`.
+pub fn inverse_uniform(transform: &Transform) -> Transform {
+    let rotation = transform.rotation.inverse();
+    let scale = transform.scale.recip();
+
+    Transform {
+        translation: rotation * (-transform.translation * scale),
+        rotation,
+        scale,
+    }
+}
+
+#[inline(never)]
+pub fn reparent_transform(params: &mut ReparentParams<Transform>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] =
+            inverse_uniform(&params.parent_array[i]).mul_transform(params.child_array[i]);
+    }
+}
+
+// Bevy's implementation, which inverts the parent's affine and decomposes the
+// result.
+#[inline(never)]
+pub fn reparent_affine(params: &mut ReparentParams<GlobalTransform>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.child_array[i].reparented_to(&params.parent_array[i]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{quat_near, test_rng, COUNT, TOLERANCE};
+
+    #[test]
+    fn propagate() {
+        let mut rng = test_rng();
+
+        let hierarchy = random_hierarchy(&mut rng, 16, 64);
+
+        let mut serial = vec![Transform::IDENTITY; hierarchy.len()];
+        let mut parallel = vec![Transform::IDENTITY; hierarchy.len()];
+
+        propagate_serial(&mut serial, &hierarchy);
+        propagate_parallel(&mut parallel, &hierarchy);
+
+        assert_eq!(parallel, serial);
+
+        // Spot check against a direct walk up the parents.
+        for i in (0..hierarchy.len()).step_by(97) {
+            let mut expected = hierarchy.local_array[i];
+            let mut node = i;
+
+            while let Some(parent) = hierarchy.parent_array[node] {
+                node = parent as usize;
+                expected = hierarchy.local_array[node].mul_transform(expected);
+            }
+
+            assert!(expected
+                .translation
+                .abs_diff_eq(serial[i].translation, 1e-3));
+        }
+    }
+
+    // Reparenting and then propagating through the new parent should give
+    // back the child's global transform.
+    #[test]
+    fn reparent() {
+        let mut rng = test_rng();
+
+        let child_array = (0..COUNT)
+            .map(|_| random_uniform_transform(&mut rng))
+            .collect::<Vec<_>>();
+        let parent_array = (0..COUNT)
+            .map(|_| random_uniform_transform(&mut rng))
+            .collect::<Vec<_>>();
+
+        let global_child_array = child_array
+            .iter()
+            .copied()
+            .map(GlobalTransform::from)
+            .collect::<Vec<_>>();
+        let global_parent_array = parent_array
+            .iter()
+            .copied()
+            .map(GlobalTransform::from)
+            .collect::<Vec<_>>();
+
+        let mut transform = vec![Transform::IDENTITY; COUNT];
+        let mut affine = vec![Transform::IDENTITY; COUNT];
+
+        reparent_transform(&mut ReparentParams {
+            dst_array: &mut transform,
+            child_array: &child_array,
+            parent_array: &parent_array,
+        });
+
+        reparent_affine(&mut ReparentParams {
+            dst_array: &mut affine,
+            child_array: &global_child_array,
+            parent_array: &global_parent_array,
+        });
+
+        for i in 0..COUNT {
+            for local in [transform[i], affine[i]] {
+                let global = parent_array[i].mul_transform(local);
+
+                assert!(global
+                    .translation
+                    .abs_diff_eq(child_array[i].translation, TOLERANCE));
+                assert!(global.scale.abs_diff_eq(child_array[i].scale, TOLERANCE));
+                assert!(quat_near(
+                    global.rotation,
+                    child_array[i].rotation,
+                    TOLERANCE
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::util::CacheAlignedVec;
+use glam::Vec3A;
+use rand::Rng;
+
+pub const GRAVITY: Vec3A = Vec3A::new(0.0, -9.8, 0.0);
+
+pub fn random_position_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Vec3A> {
+    (0..count)
+        .map(|_| (rng.gen::<Vec3A>() - 0.5) * 100.0)
+        .collect()
+}
+
+pub fn random_velocity_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Vec3A> {
+    (0..count)
+        .map(|_| (rng.gen::<Vec3A>() - 0.5) * 10.0)
+        .collect()
+}
+
+pub struct EulerParams<'a> {
+    pub position_array: &'a mut [Vec3A],
+    pub velocity_array: &'a mut [Vec3A],
+    pub acceleration: Vec3A,
+    pub dt: f32,
+}
+
+// Verlet stores the previous position instead of the velocity.
+pub struct VerletParams<'a> {
+    pub position_array: &'a mut [Vec3A],
+    pub previous_array: &'a mut [Vec3A],
+    pub acceleration: Vec3A,
+    pub dt: f32,
+}
+
+// Update the position with the old velocity. Gains energy, so orbits and
+// springs blow up unless the step is small.
+#[inline(never)]
+pub fn integrate_euler_explicit(params: &mut EulerParams) {
+    let (a, dt) = (params.acceleration, params.dt);
+
+    for (p, v) in params
+        .position_array
+        .iter_mut()
+        .zip(params.velocity_array.iter_mut())
+    {
+        *p += *v * dt;
+        *v += a * dt;
+    }
+}
+
+// Update the position with the new velocity. Same cost as explicit Euler, but
+// symplectic, so energy stays bounded.
+#[inline(never)]
+pub fn integrate_euler_semi_implicit(params: &mut EulerParams) {
+    let (a, dt) = (params.acceleration, params.dt);
+
+    for (p, v) in params
+        .position_array
+        .iter_mut()
+        .zip(params.velocity_array.iter_mut())
+    {
+        *v += a * dt;
+        *p += *v * dt;
+    }
+}
+
+// Return the previous position that makes Verlet match a starting velocity.
+// Exact for constant acceleration.
+pub fn verlet_previous(position: Vec3A, velocity: Vec3A, acceleration: Vec3A, dt: f32) -> Vec3A {
+    position - (velocity * dt) + (acceleration * (0.5 * dt * dt))
+}
+
+// Position Verlet, as in Jakobsen's "Advanced Character Physics" (2001). The
+// velocity is implicit in the last step, so constraints can move positions
+// directly.
+#[inline(never)]
+pub fn integrate_verlet(params: &mut VerletParams) {
+    let a_dt_dt = params.acceleration * (params.dt * params.dt);
+
+    for (p, prev) in params
+        .position_array
+        .iter_mut()
+        .zip(params.previous_array.iter_mut())
+    {
+        let next = (*p * 2.0) - *prev + a_dt_dt;
+
+        *prev = *p;
+        *p = next;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DistanceConstraint {
+    pub a: u32,
+    pub b: u32,
+    pub rest_length: f32,
+}
+
+// Link consecutive particles into ropes of `rope_length` particles, with each
+// link's rest length set to its current length.
+pub fn rope_constraints(position_array: &[Vec3A], rope_length: usize) -> Vec<DistanceConstraint> {
+    position_array
+        .chunks(rope_length)
+        .enumerate()
+        .flat_map(|(rope, positions)| {
+            let start = rope * rope_length;
+
+            positions
+                .windows(2)
+                .enumerate()
+                .map(move |(i, w)| DistanceConstraint {
+                    a: (start + i) as u32,
+                    b: (start + i + 1) as u32,
+                    rest_length: w[0].distance(w[1]),
+                })
+        })
+        .collect()
+}
+
+// Gauss-Seidel relaxation: move both ends of each constraint halfway to its
+// rest length, in order, so later constraints see earlier corrections. More
+// iterations converge further.
+#[inline(never)]
+pub fn relax_constraints(
+    position_array: &mut [Vec3A],
+    constraints: &[DistanceConstraint],
+    iterations: usize,
+) {
+    for _ in 0..iterations {
+        for c in constraints {
+            let (a, b) = (c.a as usize, c.b as usize);
+
+            let delta = position_array[b] - position_array[a];
+            let length = delta.length();
+
+            if length > 0.0 {
+                let correction = delta * (0.5 * (length - c.rest_length) / length);
+
+                position_array[a] += correction;
+                position_array[b] -= correction;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{test_rng, COUNT};
+
+    #[test]
+    fn integrate() {
+        let mut rng = test_rng();
+
+        let start = random_position_array(&mut rng, COUNT);
+        let velocity = random_velocity_array(&mut rng, COUNT);
+
+        let (dt, steps) = (1.0 / 60.0, 120);
+        let t = dt * (steps as f32);
+
+        // Under constant acceleration, Verlet is exact and the two Eulers are
+        // off by half a step's worth of velocity change, in opposite
+        // directions. Verlet's rounding builds up faster, since each step
+        // depends on the last two, but it's still well under the Euler error.
+
+        let mut explicit = (start.clone(), velocity.clone());
+        let mut semi_implicit = (start.clone(), velocity.clone());
+
+        let mut verlet = (
+            start.clone(),
+            start
+                .iter()
+                .zip(velocity.iter())
+                .map(|(&p, &v)| verlet_previous(p, v, GRAVITY, dt))
+                .collect::<CacheAlignedVec<_>>(),
+        );
+
+        for _ in 0..steps {
+            for (f, (p, v)) in [
+                (
+                    integrate_euler_explicit as fn(&mut EulerParams),
+                    &mut explicit,
+                ),
+                (integrate_euler_semi_implicit, &mut semi_implicit),
+            ] {
+                f(&mut EulerParams {
+                    position_array: p,
+                    velocity_array: v,
+                    acceleration: GRAVITY,
+                    dt,
+                });
+            }
+
+            integrate_verlet(&mut VerletParams {
+                position_array: &mut verlet.0,
+                previous_array: &mut verlet.1,
+                acceleration: GRAVITY,
+                dt,
+            });
+        }
+
+        let error = GRAVITY * (0.5 * dt * t);
+
+        assert!(error.length() > 0.1);
+
+        for i in 0..COUNT {
+            let exact = start[i] + (velocity[i] * t) + (GRAVITY * (0.5 * t * t));
+
+            assert!(verlet.0[i].abs_diff_eq(exact, 0.02));
+            assert!(explicit.0[i].abs_diff_eq(exact - error, 1e-3));
+            assert!(semi_implicit.0[i].abs_diff_eq(exact + error, 1e-3));
+        }
+
+        // Relaxing stretched ropes brings the links back towards their rest
+        // lengths, and more iterations get closer.
+
+        let constraints = rope_constraints(&start, 16);
+
+        assert_eq!(constraints.len(), COUNT - COUNT.div_ceil(16));
+
+        let mean_error = |positions: &[Vec3A]| {
+            constraints
+                .iter()
+                .map(|c| {
+                    let length = positions[c.a as usize].distance(positions[c.b as usize]);
+
+                    (length - c.rest_length).abs() / c.rest_length
+                })
+                .sum::<f32>()
+                / (constraints.len() as f32)
+        };
+
+        let stretched = start.iter().map(|p| *p * 1.1).collect::<Vec<_>>();
+
+        let mut errors = [1, 4, 16].map(|iterations| {
+            let mut positions = stretched.clone();
+
+            relax_constraints(&mut positions, &constraints, iterations);
+
+            mean_error(&positions)
+        });
+
+        errors.reverse();
+
+        assert!(errors.is_sorted());
+        assert!(errors[2] < mean_error(&stretched));
+    }
+}
@@ -0,0 +1,124 @@
+use bevy_math::Isometry3d;
+use bevy_transform::components::Transform;
+use glam::Vec3;
+
+// Compares `Isometry3d` to a `Transform` with unit scale. The isometry skips
+// the scale multiplies, and stores the translation as a `Vec3A` so both fields
+// are 16 byte aligned.
+
+pub struct IsometryComposeParams<'a, T> {
+    pub dst_array: &'a mut [T],
+    pub src_array: [&'a [T]; 2],
+}
+
+#[inline(never)]
+pub fn compose_transform(params: &mut IsometryComposeParams<Transform>) {
+    let [l, r] = params.src_array;
+
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = l[i].mul_transform(r[i]);
+    }
+}
+
+#[inline(never)]
+pub fn compose_isometry(params: &mut IsometryComposeParams<Isometry3d>) {
+    let [l, r] = params.src_array;
+
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = l[i] * r[i];
+    }
+}
+
+// Transform each point by its own transform.
+pub struct IsometryPointParams<'a, T> {
+    pub dst_array: &'a mut [Vec3],
+    pub src_array: &'a [Vec3],
+    pub transform_array: &'a [T],
+}
+
+#[inline(never)]
+pub fn point_transform(params: &mut IsometryPointParams<Transform>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.transform_array[i].transform_point(params.src_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn point_isometry(params: &mut IsometryPointParams<Isometry3d>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.transform_array[i] * params.src_array[i];
+    }
+}
+
+// Return the isometry equivalent to a transform, ignoring its scale.
+pub fn isometry_from_transform(transform: &Transform) -> Isometry3d {
+    Isometry3d::new(transform.translation, transform.rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{quat_near, test_rng, COUNT, TOLERANCE},
+        util::*,
+    };
+    use rand::rngs::StdRng;
+
+    // Random rotations and translations with unit scale.
+    fn random_transforms(rng: &mut StdRng) -> Vec<Transform> {
+        random_transform_array(rng, COUNT)
+            .iter()
+            .zip(random_array::<Vec3>(rng, COUNT).iter())
+            .map(|(t, &translation)| t.with_translation(translation))
+            .collect()
+    }
+
+    #[test]
+    fn isometry_matches_transform() {
+        let mut rng = test_rng();
+
+        let transforms = [random_transforms(&mut rng), random_transforms(&mut rng)];
+        let isometries = transforms
+            .each_ref()
+            .map(|t| t.iter().map(isometry_from_transform).collect::<Vec<_>>());
+
+        let mut composed_transforms = vec![Transform::IDENTITY; COUNT];
+        let mut composed_isometries = vec![Isometry3d::IDENTITY; COUNT];
+
+        compose_transform(&mut IsometryComposeParams {
+            dst_array: &mut composed_transforms,
+            src_array: [&transforms[0], &transforms[1]],
+        });
+
+        compose_isometry(&mut IsometryComposeParams {
+            dst_array: &mut composed_isometries,
+            src_array: [&isometries[0], &isometries[1]],
+        });
+
+        for (t, i) in composed_transforms.iter().zip(composed_isometries.iter()) {
+            assert!(t.translation.abs_diff_eq(i.translation.into(), TOLERANCE));
+            assert!(quat_near(t.rotation, i.rotation, TOLERANCE));
+        }
+
+        let src_array = random_array::<Vec3>(&mut rng, COUNT);
+
+        let mut transformed = vec![Vec3::ZERO; COUNT];
+        let mut isometry_transformed = vec![Vec3::ZERO; COUNT];
+
+        point_transform(&mut IsometryPointParams {
+            dst_array: &mut transformed,
+            src_array: &src_array,
+            transform_array: &transforms[0],
+        });
+
+        point_isometry(&mut IsometryPointParams {
+            dst_array: &mut isometry_transformed,
+            src_array: &src_array,
+            transform_array: &isometries[0],
+        });
+
+        for (t, i) in transformed.iter().zip(isometry_transformed.iter()) {
+            assert!(t.abs_diff_eq(*i, TOLERANCE));
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::kernels::scalar::Scalar;
+use glam::{Quat, Vec3, Vec4};
+
+pub fn quat_lerp(l: Quat, r: Quat, a: f32) -> Quat {
+    Quat::from_vec4(Vec4::from(l).lerp(Vec4::from(r), a))
+}
+
+pub fn quat_nlerp(l: Quat, r: Quat, a: f32) -> Quat {
+    l.lerp(r, a)
+}
+
+pub fn quat_slerp(l: Quat, r: Quat, a: f32) -> Quat {
+    l.slerp(r, a)
+}
+
+pub struct QuatParams<'a> {
+    pub dst: &'a mut [Quat],
+    pub src_quat: &'a [&'a [Quat]; 2],
+    pub src_alpha: f32,
+}
+
+pub fn quat_func<F>(params: &mut QuatParams, f: F)
+where
+    F: Fn(Quat, Quat, f32) -> Quat,
+{
+    for ((dst, l), r) in params
+        .dst
+        .iter_mut()
+        .zip(params.src_quat[0].iter())
+        .zip(params.src_quat[1].iter())
+    {
+        *dst = f(*l, *r, params.src_alpha);
+    }
+}
+
+#[inline(never)]
+pub fn quat_loop_lerp(params: &mut QuatParams) {
+    quat_func(params, quat_lerp);
+}
+
+#[inline(never)]
+pub fn quat_loop_nlerp(params: &mut QuatParams) {
+    quat_func(params, quat_nlerp);
+}
+
+#[inline(never)]
+pub fn quat_loop_slerp(params: &mut QuatParams) {
+    quat_func(params, quat_slerp);
+}
+
+// Same as `QuatParams`, but generic over precision.
+pub struct QuatGenericParams<'a, S: Scalar> {
+    pub dst: &'a mut [S::Quat],
+    pub src_quat: &'a [&'a [S::Quat]; 2],
+    pub src_alpha: S,
+}
+
+pub fn quat_generic_func<S, F>(params: &mut QuatGenericParams<S>, f: F)
+where
+    S: Scalar,
+    F: Fn(S::Quat, S::Quat, S) -> S::Quat,
+{
+    for ((dst, l), r) in params
+        .dst
+        .iter_mut()
+        .zip(params.src_quat[0].iter())
+        .zip(params.src_quat[1].iter())
+    {
+        *dst = f(*l, *r, params.src_alpha);
+    }
+}
+
+#[inline(never)]
+pub fn quat_loop_nlerp_generic<S: Scalar>(params: &mut QuatGenericParams<S>) {
+    quat_generic_func(params, S::quat_nlerp);
+}
+
+#[inline(never)]
+pub fn quat_loop_slerp_generic<S: Scalar>(params: &mut QuatGenericParams<S>) {
+    quat_generic_func(params, S::quat_slerp);
+}
+
+// Blend N weighted rotations per element, as in animation layering. Each
+// blend function takes the layers and weights plus the element index. The
+// weights don't need to sum to one, and layers with zero weight are ignored.
+// If every weight is zero the result is the first layer.
+pub struct QuatBlendParams<'a> {
+    pub dst: &'a mut [Quat],
+    pub src_quat: &'a [&'a [Quat]],
+    pub src_weight: &'a [f32],
+}
+
+// Reference blend - a chain of slerps, where each step blends in the next
+// layer by its share of the weight so far.
+pub fn quat_blend_slerp_chain(src_quat: &[&[Quat]], src_weight: &[f32], i: usize) -> Quat {
+    let mut blended = src_quat[0][i];
+    let mut total = src_weight[0];
+
+    for k in 1..src_quat.len() {
+        total += src_weight[k];
+
+        if total > 0.0 {
+            blended = blended.slerp(src_quat[k][i], src_weight[k] / total);
+        }
+    }
+
+    blended
+}
+
+// Same as the slerp chain, but with nlerp at each step.
+pub fn quat_blend_nlerp(src_quat: &[&[Quat]], src_weight: &[f32], i: usize) -> Quat {
+    let mut blended = src_quat[0][i];
+    let mut total = src_weight[0];
+
+    for k in 1..src_quat.len() {
+        total += src_weight[k];
+
+        if total > 0.0 {
+            blended = blended.lerp(src_quat[k][i], src_weight[k] / total);
+        }
+    }
+
+    blended
+}
+
+// Average the rotations in the tangent space of the first rotation.
+pub fn quat_blend_log(src_quat: &[&[Quat]], src_weight: &[f32], i: usize) -> Quat {
+    let first = src_quat[0][i];
+    let inverse_first = first.inverse();
+
+    let mut sum = Vec3::ZERO;
+    let mut total = src_weight[0];
+
+    for k in 1..src_quat.len() {
+        let mut relative = inverse_first * src_quat[k][i];
+
+        if relative.w < 0.0 {
+            relative = -relative;
+        }
+
+        sum += relative.to_scaled_axis() * src_weight[k];
+        total += src_weight[k];
+    }
+
+    if total > 0.0 {
+        first * Quat::from_scaled_axis(sum / total)
+    } else {
+        first
+    }
+}
+
+pub fn quat_blend_func<F>(params: &mut QuatBlendParams, f: F)
+where
+    F: Fn(&[&[Quat]], &[f32], usize) -> Quat,
+{
+    for i in 0..params.dst.len() {
+        params.dst[i] = f(params.src_quat, params.src_weight, i);
+    }
+}
+
+#[inline(never)]
+pub fn quat_blend_loop_slerp_chain(params: &mut QuatBlendParams) {
+    quat_blend_func(params, quat_blend_slerp_chain);
+}
+
+#[inline(never)]
+pub fn quat_blend_loop_nlerp(params: &mut QuatBlendParams) {
+    quat_blend_func(params, quat_blend_nlerp);
+}
+
+#[inline(never)]
+pub fn quat_blend_loop_log(params: &mut QuatBlendParams) {
+    quat_blend_func(params, quat_blend_log);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{quat_near, run_into, test_rng, COUNT, TOLERANCE},
+        util::*,
+    };
+    use rand::Rng;
+
+    fn run(f: fn(&mut QuatParams), src_quat: &[&[Quat]; 2], src_alpha: f32) -> Vec<Quat> {
+        run_into(vec![Quat::IDENTITY; COUNT], |dst| {
+            f(&mut QuatParams {
+                dst,
+                src_quat,
+                src_alpha,
+            })
+        })
+    }
+
+    #[test]
+    fn endpoints() {
+        let mut rng = test_rng();
+
+        let l = random_quat_array(&mut rng, COUNT);
+        let r = random_quat_array(&mut rng, COUNT);
+        let src_quat = [&l[..], &r[..]];
+
+        for f in [quat_loop_lerp, quat_loop_nlerp, quat_loop_slerp] {
+            let at_0 = run(f, &src_quat, 0.0);
+            let at_1 = run(f, &src_quat, 1.0);
+
+            for i in 0..COUNT {
+                assert!(quat_near(at_0[i], l[i], TOLERANCE));
+                assert!(quat_near(at_1[i], r[i], TOLERANCE));
+            }
+        }
+    }
+
+    // Slerp and nlerp follow the same arc at different speeds, so they only
+    // agree at the midpoint.
+    #[test]
+    fn nlerp_matches_slerp_at_midpoint() {
+        let mut rng = test_rng();
+
+        let l = random_quat_array(&mut rng, COUNT);
+        let r = random_quat_array(&mut rng, COUNT);
+        let src_quat = [&l[..], &r[..]];
+
+        let nlerp = run(quat_loop_nlerp, &src_quat, 0.5);
+        let slerp = run(quat_loop_slerp, &src_quat, 0.5);
+
+        for i in 0..COUNT {
+            assert!(nlerp[i].is_normalized());
+            assert!(quat_near(nlerp[i], slerp[i], TOLERANCE));
+        }
+    }
+
+    #[test]
+    fn lerp_matches_nlerp_after_normalize() {
+        let mut rng = test_rng();
+
+        // Keep the quats in the same hemisphere, since lerp doesn't take the
+        // shortest path.
+        let l = random_quat_array(&mut rng, COUNT);
+        let r = random_quat_array(&mut rng, COUNT)
+            .iter()
+            .zip(l.iter())
+            .map(|(&r, &l)| if l.dot(r) < 0.0 { -r } else { r })
+            .collect::<Vec<_>>();
+
+        let src_quat = [&l[..], &r[..]];
+
+        let lerp = run(quat_loop_lerp, &src_quat, 0.3);
+        let nlerp = run(quat_loop_nlerp, &src_quat, 0.3);
+
+        for i in 0..COUNT {
+            assert!(quat_near(lerp[i].normalize(), nlerp[i], TOLERANCE));
+        }
+    }
+
+    // The f64 kernels should match the f32 kernels to within f32 precision.
+    #[test]
+    fn generic_matches_f32() {
+        let mut rng = test_rng();
+
+        let l = random_quat_array(&mut rng, COUNT);
+        let r = random_quat_array(&mut rng, COUNT);
+        let src_quat = [&l[..], &r[..]];
+
+        let l64 = l
+            .iter()
+            .copied()
+            .map(f64::quat_from_f32)
+            .collect::<Vec<_>>();
+        let r64 = r
+            .iter()
+            .copied()
+            .map(f64::quat_from_f32)
+            .collect::<Vec<_>>();
+
+        for (f, f64_variant) in [
+            (
+                quat_loop_nlerp as fn(&mut QuatParams),
+                quat_loop_nlerp_generic::<f64> as fn(&mut QuatGenericParams<f64>),
+            ),
+            (quat_loop_slerp, quat_loop_slerp_generic::<f64>),
+        ] {
+            let expected = run(f, &src_quat, 0.3);
+
+            let mut actual = vec![glam::DQuat::IDENTITY; COUNT];
+
+            f64_variant(&mut QuatGenericParams {
+                dst: &mut actual,
+                src_quat: &[&l64, &r64],
+                src_alpha: 0.3,
+            });
+
+            for i in 0..COUNT {
+                assert!(quat_near(actual[i].as_quat(), expected[i], TOLERANCE));
+            }
+        }
+    }
+
+    // Animation layers tend to be variations on a similar pose, so blend
+    // rotations that are within a small angle of each other. The blends only
+    // approximate the slerp chain, so the tolerance is loose.
+    #[test]
+    fn blend_matches_slerp_chain() {
+        let mut rng = test_rng();
+
+        const LAYER_COUNT: usize = 4;
+
+        let base = random_quat_array(&mut rng, COUNT);
+        let layers = (0..LAYER_COUNT)
+            .map(|_| {
+                base.iter()
+                    .map(|&q| {
+                        let offset = Vec3::new(
+                            rng.gen_range(-0.3..0.3),
+                            rng.gen_range(-0.3..0.3),
+                            rng.gen_range(-0.3..0.3),
+                        );
+
+                        q * Quat::from_scaled_axis(offset)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let src_quat = layers.iter().map(|l| &l[..]).collect::<Vec<_>>();
+        // Include leading zero weights, which would divide by zero if not
+        // handled, and all zero weights.
+        for src_weight in [
+            [0.4, 0.3, 0.2, 0.1],
+            [0.0, 0.0, 0.6, 0.4],
+            [0.0, 0.0, 0.0, 0.0],
+        ] {
+            let [expected, nlerp, log] = [
+                quat_blend_loop_slerp_chain as fn(&mut QuatBlendParams),
+                quat_blend_loop_nlerp,
+                quat_blend_loop_log,
+            ]
+            .map(|f| {
+                run_into(vec![Quat::IDENTITY; COUNT], |dst| {
+                    f(&mut QuatBlendParams {
+                        dst,
+                        src_quat: &src_quat,
+                        src_weight: &src_weight,
+                    })
+                })
+            });
+
+            for i in 0..COUNT {
+                assert!(expected[i].is_normalized());
+                assert!(nlerp[i].is_normalized());
+                assert!(log[i].is_normalized());
+                assert!(quat_near(nlerp[i], expected[i], 0.01));
+                assert!(quat_near(log[i], expected[i], 0.01));
+            }
+        }
+    }
+}
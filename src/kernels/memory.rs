@@ -0,0 +1,170 @@
+use rand::Rng;
+use rayon::prelude::*;
+
+#[inline(never)]
+pub fn memcpy_inner(dst: &mut [u8], src: &[u8]) {
+    dst.clone_from_slice(src);
+}
+
+// Split the copy into one contiguous chunk per thread of the current rayon
+// pool, like `stream_parallel`.
+#[inline(never)]
+pub fn memcpy_parallel(dst: &mut [u8], src: &[u8]) {
+    let chunk_len = dst.len().div_ceil(rayon::current_num_threads()).max(1);
+
+    dst.par_chunks_mut(chunk_len)
+        .zip(src.par_chunks(chunk_len))
+        .for_each(|(dst, src)| memcpy_inner(dst, src));
+}
+
+#[inline(never)]
+pub fn memset_inner(dst: &mut [u8], value: u8) {
+    dst.fill(value);
+}
+
+// The smallest page size on common platforms. Larger pages just mean some
+// touches hit a page that's already mapped.
+pub const PAGE_SIZE: usize = 4096;
+
+// Allocates with `alloc_zeroed`. Large allocations get fresh pages from the
+// OS, which are already zero, so nothing is written until the pages are used.
+#[inline(never)]
+pub fn vec_zeroed(size: usize) -> Vec<u8> {
+    vec![0u8; size]
+}
+
+// Same as `vec_zeroed`, but then writes a byte to each page, so the cost of
+// the OS mapping and zeroing the pages shows up.
+#[inline(never)]
+pub fn vec_zeroed_touched(size: usize) -> Vec<u8> {
+    let mut v = vec![0u8; size];
+
+    for i in (0..size).step_by(PAGE_SIZE) {
+        v[i] = 1;
+    }
+
+    v
+}
+
+// Allocates without zeroing, then writes the zeros.
+#[inline(never)]
+pub fn vec_memset(size: usize) -> Vec<u8> {
+    let mut v = Vec::<u8>::with_capacity(size);
+
+    // SAFETY: The capacity is at least `size`, and all the bytes are written
+    // before the length covers them.
+    unsafe {
+        v.as_mut_ptr().write_bytes(0, size);
+        v.set_len(size);
+    }
+
+    v
+}
+
+// Sum every `stride`th element. Integers, since a float sum would be limited by
+// the latency of each add rather than the loads.
+#[inline(never)]
+pub fn strided_sum(src: &[u32], stride: usize) -> u32 {
+    src.iter()
+        .step_by(stride)
+        .fold(0, |sum, &v| sum.wrapping_add(v))
+}
+
+// Sum the elements at each index, in order.
+#[inline(never)]
+pub fn gather_sum(src: &[u32], index_array: &[usize]) -> u32 {
+    index_array
+        .iter()
+        .fold(0, |sum, &i| sum.wrapping_add(src[i]))
+}
+
+// Return a permutation where following `next[i]` from any element visits every
+// element once before returning to the start. Sattolo's algorithm, which is
+// Fisher-Yates but never swaps an element with itself.
+pub fn random_cycle(rng: &mut impl Rng, len: usize) -> Vec<usize> {
+    let mut order = (0..len).collect::<Vec<_>>();
+
+    for i in (1..len).rev() {
+        order.swap(i, rng.gen_range(0..i));
+    }
+
+    let mut next = vec![0; len];
+
+    for (i, &from) in order.iter().enumerate() {
+        next[from] = order[(i + 1) % len];
+    }
+
+    next
+}
+
+// Follow `steps` links from element 0 and return where it ends up. Each load
+// depends on the last, so this measures latency rather than bandwidth.
+#[inline(never)]
+pub fn pointer_chase(next: &[usize], steps: usize) -> usize {
+    let mut i = 0;
+
+    for _ in 0..steps {
+        i = next[i];
+    }
+
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::test_rng;
+
+    #[test]
+    fn memcpy() {
+        let src = (0..=255).collect::<Vec<u8>>();
+        let mut dst = vec![0u8; src.len()];
+
+        memcpy_inner(&mut dst, &src);
+
+        assert_eq!(dst, src);
+
+        let mut dst = vec![0u8; src.len()];
+
+        memcpy_parallel(&mut dst, &src);
+
+        assert_eq!(dst, src);
+    }
+
+    #[test]
+    fn memset() {
+        let mut dst = vec![0u8; 1003];
+
+        memset_inner(&mut dst, 0xab);
+
+        assert!(dst.iter().all(|&b| b == 0xab));
+
+        let size = (PAGE_SIZE * 3) + 5;
+
+        assert_eq!(vec_zeroed(size), vec_memset(size));
+        assert!(vec_memset(size).iter().all(|&b| b == 0));
+
+        let touched = vec_zeroed_touched(size);
+
+        assert_eq!(touched.len(), size);
+        assert_eq!(touched.iter().filter(|&&b| b == 1).count(), 4);
+    }
+
+    #[test]
+    fn access_pattern() {
+        let src = (0..1003).collect::<Vec<u32>>();
+
+        assert_eq!(strided_sum(&src, 1), src.iter().sum::<u32>());
+        assert_eq!(strided_sum(&src, 4), (0..1003).step_by(4).sum::<u32>());
+        assert_eq!(strided_sum(&src, 4096), 0);
+
+        assert_eq!(gather_sum(&src, &[5, 1000, 5]), 1010);
+
+        let mut rng = test_rng();
+
+        let next = random_cycle(&mut rng, 1003);
+
+        assert_eq!(pointer_chase(&next, 1003), 0);
+        assert!((1..1003).all(|steps| pointer_chase(&next, steps) != 0));
+    }
+}
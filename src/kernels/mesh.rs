@@ -0,0 +1,224 @@
+use glam::{Vec2, Vec3, Vec4};
+use rand::Rng;
+
+#[derive(Clone, Debug, Default)]
+pub struct IndexedMesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    pub indices: Vec<u32>,
+}
+
+impl IndexedMesh {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.indices
+            .chunks_exact(3)
+            .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+    }
+}
+
+// A square grid of `resolution` quads on each side, with random heights so the
+// normals vary. UVs map the whole grid to the unit square. The normals are the
+// area weighted average of the face normals.
+pub fn random_grid_mesh<R: Rng + ?Sized>(rng: &mut R, resolution: usize) -> IndexedMesh {
+    let side = resolution + 1;
+
+    let mut mesh = IndexedMesh::default();
+
+    for z in 0..side {
+        for x in 0..side {
+            let height = rng.gen_range(-0.25..0.25);
+
+            mesh.positions.push(Vec3::new(x as f32, height, z as f32));
+            mesh.uvs
+                .push(Vec2::new(x as f32, z as f32) / (resolution as f32));
+        }
+    }
+
+    for z in 0..resolution {
+        for x in 0..resolution {
+            let i = ((z * side) + x) as u32;
+            let s = side as u32;
+
+            mesh.indices
+                .extend([i, i + s, i + 1, i + 1, i + s, i + s + 1]);
+        }
+    }
+
+    let mut normals = vec![Vec3::ZERO; mesh.vertex_count()];
+
+    for triangle in mesh.triangles() {
+        let [p0, p1, p2] = triangle.map(|i| mesh.positions[i]);
+        let normal = (p1 - p0).cross(p2 - p0);
+
+        for i in triangle {
+            normals[i] += normal;
+        }
+    }
+
+    mesh.normals = normals.into_iter().map(Vec3::normalize).collect();
+
+    mesh
+}
+
+// Each result is a tangent with the bitangent sign in w, so the bitangent is
+// `normal.cross(tangent) * w`.
+pub struct TangentParams<'a> {
+    pub dst_array: &'a mut [Vec4],
+    // Scratch space for accumulating bitangents, one per vertex.
+    pub bitangent_array: &'a mut [Vec3],
+    pub mesh: &'a IndexedMesh,
+}
+
+// Return the direction of increasing u and v across a triangle, scaled by the
+// inverse of the UV area. Triangles with no UV area return zero.
+fn triangle_tangent_bitangent(mesh: &IndexedMesh, [i0, i1, i2]: [usize; 3]) -> (Vec3, Vec3) {
+    let [p0, p1, p2] = [i0, i1, i2].map(|i| mesh.positions[i]);
+    let [uv0, uv1, uv2] = [i0, i1, i2].map(|i| mesh.uvs[i]);
+
+    let (e1, e2) = (p1 - p0, p2 - p0);
+    let (d1, d2) = (uv1 - uv0, uv2 - uv0);
+
+    let determinant = d1.perp_dot(d2);
+
+    if determinant == 0.0 {
+        return (Vec3::ZERO, Vec3::ZERO);
+    }
+
+    let r = 1.0 / determinant;
+
+    (
+        ((e1 * d2.y) - (e2 * d1.y)) * r,
+        ((e2 * d1.x) - (e1 * d2.x)) * r,
+    )
+}
+
+// Make the tangent perpendicular to the normal and find the bitangent's side.
+fn finish_tangents(params: &mut TangentParams) {
+    let normals = &params.mesh.normals;
+
+    for ((dst, &b), &n) in params
+        .dst_array
+        .iter_mut()
+        .zip(params.bitangent_array.iter())
+        .zip(normals)
+    {
+        let t = (dst.truncate() - (n * n.dot(dst.truncate()))).normalize_or_zero();
+        let w = if n.cross(t).dot(b) < 0.0 { -1.0 } else { 1.0 };
+
+        *dst = t.extend(w);
+    }
+}
+
+// Lengyel, "Computing Tangent Space Basis Vectors for an Arbitrary Mesh"
+// (2001). Sum each triangle's tangent into its vertices without normalizing,
+// so triangles are weighted by their size relative to their size in UV space.
+#[inline(never)]
+pub fn tangents_lengyel(params: &mut TangentParams) {
+    params.dst_array.fill(Vec4::ZERO);
+    params.bitangent_array.fill(Vec3::ZERO);
+
+    for triangle in params.mesh.triangles() {
+        let (t, b) = triangle_tangent_bitangent(params.mesh, triangle);
+
+        for i in triangle {
+            params.dst_array[i] += t.extend(0.0);
+            params.bitangent_array[i] += b;
+        }
+    }
+
+    finish_tangents(params);
+}
+
+// Follows the weighting of Mikkelsen's mikktspace: each triangle's tangent is
+// normalized, projected onto the plane of each corner's normal, and weighted
+// by the corner's angle. Mikktspace also splits vertices where the tangents
+// disagree, which isn't done here.
+#[inline(never)]
+pub fn tangents_mikktspace_style(params: &mut TangentParams) {
+    params.dst_array.fill(Vec4::ZERO);
+    params.bitangent_array.fill(Vec3::ZERO);
+
+    let mesh = params.mesh;
+
+    for triangle in mesh.triangles() {
+        let (t, b) = triangle_tangent_bitangent(mesh, triangle);
+        let (t, b) = (t.normalize_or_zero(), b.normalize_or_zero());
+
+        let [p0, p1, p2] = triangle.map(|i| mesh.positions[i]);
+
+        for (i, [p, a, c]) in triangle
+            .into_iter()
+            .zip([[p0, p1, p2], [p1, p2, p0], [p2, p0, p1]])
+        {
+            let n = mesh.normals[i];
+            let angle = (a - p).angle_between(c - p);
+
+            let project = |v: Vec3| (v - (n * n.dot(v))).normalize_or_zero() * angle;
+
+            params.dst_array[i] += project(t).extend(0.0);
+            params.bitangent_array[i] += project(b);
+        }
+    }
+
+    finish_tangents(params);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{run_into, test_rng};
+
+    #[test]
+    fn tangents() {
+        let mut rng = test_rng();
+
+        let run = |mesh: &IndexedMesh, f: fn(&mut TangentParams)| {
+            run_into(vec![Vec4::ZERO; mesh.vertex_count()], |dst_array| {
+                f(&mut TangentParams {
+                    dst_array,
+                    bitangent_array: &mut vec![Vec3::ZERO; mesh.vertex_count()],
+                    mesh,
+                })
+            })
+        };
+
+        // On a flat grid, u increases along x and v along z.
+
+        let mut flat = random_grid_mesh(&mut rng, 4);
+
+        for p in &mut flat.positions {
+            p.y = 0.0;
+        }
+
+        flat.normals.fill(Vec3::Y);
+
+        for f in [tangents_lengyel, tangents_mikktspace_style] {
+            for t in run(&flat, f) {
+                assert!(t.abs_diff_eq(Vec4::new(1.0, 0.0, 0.0, -1.0), 1e-6));
+            }
+        }
+
+        // On a bumpy grid, the tangents are unit length, perpendicular to the
+        // normals, and the two methods roughly agree.
+
+        let mesh = random_grid_mesh(&mut rng, 32);
+
+        let lengyel = run(&mesh, tangents_lengyel);
+        let mikktspace = run(&mesh, tangents_mikktspace_style);
+
+        for ((l, m), n) in lengyel.iter().zip(&mikktspace).zip(&mesh.normals) {
+            for t in [l, m] {
+                assert!((t.truncate().length() - 1.0).abs() < 1e-5);
+                assert!(t.truncate().dot(*n).abs() < 1e-5);
+            }
+
+            assert_eq!(l.w, m.w);
+            assert!(l.truncate().dot(m.truncate()) > 0.9);
+        }
+    }
+}
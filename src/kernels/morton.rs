@@ -0,0 +1,312 @@
+use crate::{
+    kernels::quantize::{convert_inner, ConvertParams},
+    util::CacheAlignedVec,
+};
+use glam::{UVec2, UVec3};
+use rand::Rng;
+
+// 2D codes have 16 bits per axis and 3D codes have 10 bits per axis, so both
+// fit in a u32. Bits of x are in the lowest position of each group.
+pub const MORTON_2D_BITS: u32 = 16;
+pub const MORTON_3D_BITS: u32 = 10;
+
+pub fn random_morton_2d_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<UVec2> {
+    (0..count)
+        .map(|_| UVec2::from_array([(); 2].map(|_| rng.gen_range(0..(1 << MORTON_2D_BITS)))))
+        .collect()
+}
+
+pub fn random_morton_3d_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<UVec3> {
+    (0..count)
+        .map(|_| UVec3::from_array([(); 3].map(|_| rng.gen_range(0..(1 << MORTON_3D_BITS)))))
+        .collect()
+}
+
+// Spread the low 16 bits so there's a zero bit between each.
+pub fn part_1_by_1(x: u32) -> u32 {
+    let x = x & 0x0000ffff;
+    let x = (x | (x << 8)) & 0x00ff00ff;
+    let x = (x | (x << 4)) & 0x0f0f0f0f;
+    let x = (x | (x << 2)) & 0x33333333;
+
+    (x | (x << 1)) & 0x55555555
+}
+
+// The inverse of `part_1_by_1`.
+pub fn compact_1_by_1(x: u32) -> u32 {
+    let x = x & 0x55555555;
+    let x = (x | (x >> 1)) & 0x33333333;
+    let x = (x | (x >> 2)) & 0x0f0f0f0f;
+    let x = (x | (x >> 4)) & 0x00ff00ff;
+
+    (x | (x >> 8)) & 0x0000ffff
+}
+
+// Spread the low 10 bits so there's two zero bits between each.
+pub fn part_1_by_2(x: u32) -> u32 {
+    let x = x & 0x000003ff;
+    let x = (x | (x << 16)) & 0xff0000ff;
+    let x = (x | (x << 8)) & 0x0300f00f;
+    let x = (x | (x << 4)) & 0x030c30c3;
+
+    (x | (x << 2)) & 0x09249249
+}
+
+// The inverse of `part_1_by_2`.
+pub fn compact_1_by_2(x: u32) -> u32 {
+    let x = x & 0x09249249;
+    let x = (x | (x >> 2)) & 0x030c30c3;
+    let x = (x | (x >> 4)) & 0x0300f00f;
+    let x = (x | (x >> 8)) & 0xff0000ff;
+
+    (x | (x >> 16)) & 0x000003ff
+}
+
+pub fn morton_2d_encode_magic_single(p: UVec2) -> u32 {
+    part_1_by_1(p.x) | (part_1_by_1(p.y) << 1)
+}
+
+pub fn morton_2d_decode_magic_single(code: u32) -> UVec2 {
+    UVec2::new(compact_1_by_1(code), compact_1_by_1(code >> 1))
+}
+
+pub fn morton_3d_encode_magic_single(p: UVec3) -> u32 {
+    part_1_by_2(p.x) | (part_1_by_2(p.y) << 1) | (part_1_by_2(p.z) << 2)
+}
+
+pub fn morton_3d_decode_magic_single(code: u32) -> UVec3 {
+    UVec3::new(
+        compact_1_by_2(code),
+        compact_1_by_2(code >> 1),
+        compact_1_by_2(code >> 2),
+    )
+}
+
+#[inline(never)]
+pub fn morton_2d_encode_magic(params: &mut ConvertParams<UVec2, u32>) {
+    convert_inner(params, morton_2d_encode_magic_single);
+}
+
+#[inline(never)]
+pub fn morton_2d_decode_magic(params: &mut ConvertParams<u32, UVec2>) {
+    convert_inner(params, morton_2d_decode_magic_single);
+}
+
+#[inline(never)]
+pub fn morton_3d_encode_magic(params: &mut ConvertParams<UVec3, u32>) {
+    convert_inner(params, morton_3d_encode_magic_single);
+}
+
+#[inline(never)]
+pub fn morton_3d_decode_magic(params: &mut ConvertParams<u32, UVec3>) {
+    convert_inner(params, morton_3d_decode_magic_single);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Encoding spreads a byte of each axis at a time. Decoding takes a byte of the
+// code at a time for 2D, and nine bits at a time for 3D, so each entry has a
+// whole number of bits for every axis.
+pub struct MortonLut {
+    pub encode_2d: [u16; 256],
+    pub encode_3d: [u32; 256],
+    pub decode_2d: [[u8; 2]; 256],
+    pub decode_3d: [[u8; 3]; 512],
+}
+
+impl MortonLut {
+    pub fn new() -> Self {
+        MortonLut {
+            encode_2d: std::array::from_fn(|i| part_1_by_1(i as u32) as u16),
+            encode_3d: std::array::from_fn(|i| part_1_by_2(i as u32)),
+            decode_2d: std::array::from_fn(|i| {
+                morton_2d_decode_magic_single(i as u32)
+                    .to_array()
+                    .map(|c| c as u8)
+            }),
+            decode_3d: std::array::from_fn(|i| {
+                morton_3d_decode_magic_single(i as u32)
+                    .to_array()
+                    .map(|c| c as u8)
+            }),
+        }
+    }
+}
+
+impl Default for MortonLut {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline(never)]
+pub fn morton_2d_encode_lut(params: &mut ConvertParams<UVec2, u32>, lut: &MortonLut) {
+    let spread = |x: u32| {
+        (lut.encode_2d[(x & 0xff) as usize] as u32)
+            | ((lut.encode_2d[((x >> 8) & 0xff) as usize] as u32) << 16)
+    };
+
+    convert_inner(params, |p| spread(p.x) | (spread(p.y) << 1));
+}
+
+#[inline(never)]
+pub fn morton_2d_decode_lut(params: &mut ConvertParams<u32, UVec2>, lut: &MortonLut) {
+    convert_inner(params, |code| {
+        let mut p = UVec2::ZERO;
+
+        for byte in 0..4 {
+            let [x, y] = lut.decode_2d[((code >> (byte * 8)) & 0xff) as usize];
+
+            p = p | (UVec2::new(x as u32, y as u32) << (byte * 4));
+        }
+
+        p
+    });
+}
+
+#[inline(never)]
+pub fn morton_3d_encode_lut(params: &mut ConvertParams<UVec3, u32>, lut: &MortonLut) {
+    let spread = |x: u32| {
+        lut.encode_3d[(x & 0xff) as usize] | (lut.encode_3d[((x >> 8) & 0x3) as usize] << 24)
+    };
+
+    convert_inner(params, |p| {
+        spread(p.x) | (spread(p.y) << 1) | (spread(p.z) << 2)
+    });
+}
+
+#[inline(never)]
+pub fn morton_3d_decode_lut(params: &mut ConvertParams<u32, UVec3>, lut: &MortonLut) {
+    convert_inner(params, |code| {
+        let mut p = UVec3::ZERO;
+
+        for chunk in 0..4 {
+            let [x, y, z] = lut.decode_3d[((code >> (chunk * 9)) & 0x1ff) as usize];
+
+            p = p | (UVec3::new(x as u32, y as u32, z as u32) << (chunk * 3));
+        }
+
+        p
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{test_rng, COUNT};
+
+    #[test]
+    fn morton_2d() {
+        assert_eq!(morton_2d_encode_magic_single(UVec2::new(1, 0)), 0b01);
+        assert_eq!(morton_2d_encode_magic_single(UVec2::new(0, 1)), 0b10);
+        assert_eq!(
+            morton_2d_encode_magic_single(UVec2::splat(0xffff)),
+            u32::MAX
+        );
+
+        let mut rng = test_rng();
+
+        let points = random_morton_2d_array(&mut rng, COUNT);
+        let lut = MortonLut::new();
+
+        let mut magic = vec![0; COUNT];
+        let mut table = vec![0; COUNT];
+
+        morton_2d_encode_magic(&mut ConvertParams {
+            dst_array: &mut magic,
+            src_array: &points,
+        });
+
+        morton_2d_encode_lut(
+            &mut ConvertParams {
+                dst_array: &mut table,
+                src_array: &points,
+            },
+            &lut,
+        );
+
+        assert_eq!(magic, table);
+
+        let mut decoded = vec![UVec2::ZERO; COUNT];
+
+        morton_2d_decode_magic(&mut ConvertParams {
+            dst_array: &mut decoded,
+            src_array: &magic,
+        });
+
+        assert_eq!(decoded, points.as_slice());
+
+        decoded.fill(UVec2::ZERO);
+
+        morton_2d_decode_lut(
+            &mut ConvertParams {
+                dst_array: &mut decoded,
+                src_array: &magic,
+            },
+            &lut,
+        );
+
+        assert_eq!(decoded, points.as_slice());
+    }
+
+    #[test]
+    fn morton_3d() {
+        assert_eq!(morton_3d_encode_magic_single(UVec3::new(1, 0, 0)), 0b001);
+        assert_eq!(morton_3d_encode_magic_single(UVec3::new(0, 1, 0)), 0b010);
+        assert_eq!(morton_3d_encode_magic_single(UVec3::new(0, 0, 1)), 0b100);
+        assert_eq!(
+            morton_3d_encode_magic_single(UVec3::splat(0x3ff)),
+            (1 << 30) - 1
+        );
+
+        let mut rng = test_rng();
+
+        let points = random_morton_3d_array(&mut rng, COUNT);
+        let lut = MortonLut::new();
+
+        let mut magic = vec![0; COUNT];
+        let mut table = vec![0; COUNT];
+
+        morton_3d_encode_magic(&mut ConvertParams {
+            dst_array: &mut magic,
+            src_array: &points,
+        });
+
+        morton_3d_encode_lut(
+            &mut ConvertParams {
+                dst_array: &mut table,
+                src_array: &points,
+            },
+            &lut,
+        );
+
+        assert_eq!(magic, table);
+
+        let mut decoded = vec![UVec3::ZERO; COUNT];
+
+        morton_3d_decode_magic(&mut ConvertParams {
+            dst_array: &mut decoded,
+            src_array: &magic,
+        });
+
+        assert_eq!(decoded, points.as_slice());
+
+        decoded.fill(UVec3::ZERO);
+
+        morton_3d_decode_lut(
+            &mut ConvertParams {
+                dst_array: &mut decoded,
+                src_array: &magic,
+            },
+            &lut,
+        );
+
+        assert_eq!(decoded, points.as_slice());
+    }
+}
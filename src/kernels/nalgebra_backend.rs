@@ -0,0 +1,200 @@
+use bevy_transform::components::Transform;
+use glam::Quat;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+
+// nalgebra versions of the transform, composition and lerp kernels, so that
+// the two libraries can be compared on the same data. Isometries can't
+// represent scale, so these assume the transforms have unit scale.
+
+pub fn unit_quat_from_glam(q: Quat) -> UnitQuaternion<f32> {
+    UnitQuaternion::new_unchecked(Quaternion::new(q.w, q.x, q.y, q.z))
+}
+
+pub fn unit_quat_to_glam(q: UnitQuaternion<f32>) -> Quat {
+    Quat::from_xyzw(q.i, q.j, q.k, q.w)
+}
+
+pub fn isometry_from_transform(t: &Transform) -> Isometry3<f32> {
+    Isometry3::from_parts(
+        Translation3::new(t.translation.x, t.translation.y, t.translation.z),
+        unit_quat_from_glam(t.rotation),
+    )
+}
+
+pub struct IsometryNormalizeParams<'a> {
+    pub dst: &'a mut [Isometry3<f32>],
+    pub src: &'a [&'a [Isometry3<f32>]; 2],
+}
+
+#[inline(never)]
+pub fn isometry_normalize_false(params: &mut IsometryNormalizeParams) {
+    for i in 0..params.dst.len() {
+        params.dst[i] = params.src[0][i] * params.src[1][i];
+    }
+}
+
+#[inline(never)]
+pub fn isometry_normalize_true(params: &mut IsometryNormalizeParams) {
+    for i in 0..params.dst.len() {
+        let mut isometry = params.src[0][i] * params.src[1][i];
+
+        isometry.rotation.renormalize();
+
+        params.dst[i] = isometry;
+    }
+}
+
+#[inline(never)]
+pub fn unit_quat_compose_normalize_false(rotations: &[UnitQuaternion<f32>]) -> UnitQuaternion<f32> {
+    rotations
+        .iter()
+        .fold(UnitQuaternion::identity(), |acc, r| acc * r)
+}
+
+#[inline(never)]
+pub fn unit_quat_compose_normalize_true(rotations: &[UnitQuaternion<f32>]) -> UnitQuaternion<f32> {
+    rotations.iter().fold(UnitQuaternion::identity(), |acc, r| {
+        let mut q = acc * r;
+        q.renormalize();
+        q
+    })
+}
+
+pub struct UnitQuatParams<'a> {
+    pub dst: &'a mut [UnitQuaternion<f32>],
+    pub src_quat: &'a [&'a [UnitQuaternion<f32>]; 2],
+    pub src_alpha: f32,
+}
+
+// Unlike glam, nalgebra's nlerp doesn't take the shortest path, so flip the
+// second quat if needed to match `quat_nlerp`.
+#[inline(never)]
+pub fn unit_quat_loop_nlerp(params: &mut UnitQuatParams) {
+    for i in 0..params.dst.len() {
+        let l = params.src_quat[0][i];
+        let mut r = params.src_quat[1][i];
+
+        if l.coords.dot(&r.coords) < 0.0 {
+            r = UnitQuaternion::new_unchecked(-r.into_inner());
+        }
+
+        params.dst[i] = l.nlerp(&r, params.src_alpha);
+    }
+}
+
+#[inline(never)]
+pub fn unit_quat_loop_slerp(params: &mut UnitQuatParams) {
+    for i in 0..params.dst.len() {
+        params.dst[i] = params.src_quat[0][i].slerp(&params.src_quat[1][i], params.src_alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::{
+            lerp::*,
+            normalize::*,
+            test_util::{quat_near, test_rng, COUNT, TOLERANCE},
+        },
+        util::*,
+    };
+
+    #[test]
+    fn isometry_normalize() {
+        let mut rng = test_rng();
+
+        let src = [
+            random_transform_array(&mut rng, COUNT),
+            random_transform_array(&mut rng, COUNT),
+        ];
+
+        let mut expected = vec![Transform::IDENTITY; COUNT];
+
+        transform_normalize_true(&mut TransformNormalizeParams {
+            dst: &mut expected,
+            src: &[&src[0], &src[1]],
+        });
+
+        let isometry_src = src
+            .each_ref()
+            .map(|s| s.iter().map(isometry_from_transform).collect::<Vec<_>>());
+
+        let mut actual = vec![Isometry3::identity(); COUNT];
+
+        isometry_normalize_true(&mut IsometryNormalizeParams {
+            dst: &mut actual,
+            src: &[&isometry_src[0], &isometry_src[1]],
+        });
+
+        for (actual, expected) in actual.iter().zip(expected.iter()) {
+            assert!(quat_near(
+                unit_quat_to_glam(actual.rotation),
+                expected.rotation,
+                TOLERANCE
+            ));
+        }
+    }
+
+    #[test]
+    fn unit_quat_lerp() {
+        let mut rng = test_rng();
+
+        let l = random_quat_array(&mut rng, COUNT);
+        let r = random_quat_array(&mut rng, COUNT);
+
+        let l_na = l
+            .iter()
+            .copied()
+            .map(unit_quat_from_glam)
+            .collect::<Vec<_>>();
+        let r_na = r
+            .iter()
+            .copied()
+            .map(unit_quat_from_glam)
+            .collect::<Vec<_>>();
+
+        for (f, f_na) in [
+            (
+                quat_slerp as fn(Quat, Quat, f32) -> Quat,
+                unit_quat_loop_slerp as fn(&mut UnitQuatParams),
+            ),
+            (quat_nlerp, unit_quat_loop_nlerp),
+        ] {
+            let mut dst = vec![UnitQuaternion::identity(); COUNT];
+
+            f_na(&mut UnitQuatParams {
+                dst: &mut dst,
+                src_quat: &[&l_na, &r_na],
+                src_alpha: 0.3,
+            });
+
+            for i in 0..COUNT {
+                assert!(quat_near(
+                    unit_quat_to_glam(dst[i]),
+                    f(l[i], r[i], 0.3),
+                    TOLERANCE
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn unit_quat_compose_normalize() {
+        let mut rng = test_rng();
+
+        let rotations = random_quat_array(&mut rng, COUNT);
+        let rotations_na = rotations
+            .iter()
+            .copied()
+            .map(unit_quat_from_glam)
+            .collect::<Vec<_>>();
+
+        assert!(quat_near(
+            unit_quat_to_glam(unit_quat_compose_normalize_true(&rotations_na)),
+            compose_normalize_true(&rotations),
+            0.01
+        ));
+    }
+}
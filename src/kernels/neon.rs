@@ -0,0 +1,175 @@
+use crate::kernels::{easing::SmoothstepParams, lerp::QuatParams};
+use glam::Quat;
+use std::arch::aarch64::*;
+
+// Hand written NEON kernels. NEON is part of the aarch64 baseline, so unlike
+// the x86 kernels these don't need runtime detection. The intrinsics still
+// need a `#[target_feature]` context, hence the inner functions.
+
+#[target_feature(enable = "neon")]
+fn smoothstep_neon_inner(params: &mut SmoothstepParams) {
+    const LANES: usize = 4;
+
+    let len = params.dst_array.len().min(params.src_array.len());
+    let chunk_len = len - (len % LANES);
+
+    let src = params.src_array.as_ptr();
+    let dst = params.dst_array.as_mut_ptr();
+
+    let two = vdupq_n_f32(2.0);
+    let three = vdupq_n_f32(3.0);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the load is in bounds.
+        let t = unsafe { vld1q_f32(src.add(i)) };
+
+        // `vfmsq_f32(a, b, c)` is `a - (b * c)`.
+        let r = vmulq_f32(vmulq_f32(vfmsq_f32(three, two, t), t), t);
+
+        // SAFETY: `i + LANES <= len`, so the store is in bounds.
+        unsafe { vst1q_f32(dst.add(i), r) };
+    }
+
+    for i in chunk_len..len {
+        let t = params.src_array[i];
+
+        params.dst_array[i] = (3.0 - (2.0 * t)) * t * t;
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_neon(params: &mut SmoothstepParams) {
+    // SAFETY: NEON is part of the aarch64 baseline.
+    unsafe { smoothstep_neon_inner(params) }
+}
+
+#[target_feature(enable = "neon")]
+fn quat_loop_nlerp_neon_inner(params: &mut QuatParams) {
+    const LANES: usize = 4;
+
+    let len = params
+        .dst
+        .len()
+        .min(params.src_quat[0].len())
+        .min(params.src_quat[1].len());
+    let chunk_len = len - (len % LANES);
+
+    let l_ptr = params.src_quat[0].as_ptr() as *const f32;
+    let r_ptr = params.src_quat[1].as_ptr() as *const f32;
+    let dst_ptr = params.dst.as_mut_ptr() as *mut f32;
+
+    let alpha = vdupq_n_f32(params.src_alpha);
+    let negative_alpha = vdupq_n_f32(-params.src_alpha);
+    let inverse_alpha = vdupq_n_f32(1.0 - params.src_alpha);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: Quats are four f32s, and `i + LANES <= len`, so the loads
+        // are in bounds.
+        let (l, r) = unsafe { (vld4q_f32(l_ptr.add(i * 4)), vld4q_f32(r_ptr.add(i * 4))) };
+
+        let dot = vaddq_f32(
+            vaddq_f32(vmulq_f32(l.0, r.0), vmulq_f32(l.1, r.1)),
+            vaddq_f32(vmulq_f32(l.2, r.2), vmulq_f32(l.3, r.3)),
+        );
+
+        let r_alpha = vbslq_f32(vcgeq_f32(dot, vdupq_n_f32(0.0)), alpha, negative_alpha);
+
+        let x = vfmaq_f32(vmulq_f32(l.0, inverse_alpha), r.0, r_alpha);
+        let y = vfmaq_f32(vmulq_f32(l.1, inverse_alpha), r.1, r_alpha);
+        let z = vfmaq_f32(vmulq_f32(l.2, inverse_alpha), r.2, r_alpha);
+        let w = vfmaq_f32(vmulq_f32(l.3, inverse_alpha), r.3, r_alpha);
+
+        let length_squared = vaddq_f32(
+            vaddq_f32(vmulq_f32(x, x), vmulq_f32(y, y)),
+            vaddq_f32(vmulq_f32(z, z), vmulq_f32(w, w)),
+        );
+
+        let inverse_length = vdivq_f32(vdupq_n_f32(1.0), vsqrtq_f32(length_squared));
+
+        let result = float32x4x4_t(
+            vmulq_f32(x, inverse_length),
+            vmulq_f32(y, inverse_length),
+            vmulq_f32(z, inverse_length),
+            vmulq_f32(w, inverse_length),
+        );
+
+        // SAFETY: As above, the store is in bounds.
+        unsafe { vst4q_f32(dst_ptr.add(i * 4), result) };
+    }
+
+    for i in chunk_len..len {
+        params.dst[i] = params.src_quat[0][i].lerp(params.src_quat[1][i], params.src_alpha);
+    }
+}
+
+// Same as `quat_nlerp`, four quats at a time. The interleaved loads and stores
+// transpose the quats to and from SoA form.
+#[inline(never)]
+pub fn quat_loop_nlerp_neon(params: &mut QuatParams) {
+    // SAFETY: NEON is part of the aarch64 baseline.
+    unsafe { quat_loop_nlerp_neon_inner(params) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::{
+            easing::*,
+            lerp::*,
+            test_util::{quat_near, run_into, test_rng},
+        },
+        util::*,
+    };
+
+    // Not a multiple of the lane count, so the remainder is tested.
+    const COUNT: usize = 1003;
+
+    #[test]
+    fn smoothstep() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+
+        let [expected, actual] = [
+            smoothstep_explicit as fn(&mut SmoothstepParams),
+            smoothstep_neon,
+        ]
+        .map(|f| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut SmoothstepParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        });
+
+        // FMA rounds differently, so allow some error.
+        for i in 0..COUNT {
+            assert!((actual[i] - expected[i]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn nlerp() {
+        let mut rng = test_rng();
+
+        let l = random_quat_array(&mut rng, COUNT);
+        let r = random_quat_array(&mut rng, COUNT);
+
+        let [expected, actual] = [quat_loop_nlerp as fn(&mut QuatParams), quat_loop_nlerp_neon]
+            .map(|f| {
+                run_into(vec![Quat::IDENTITY; COUNT], |dst| {
+                    f(&mut QuatParams {
+                        dst,
+                        src_quat: &[&l, &r],
+                        src_alpha: 0.3,
+                    })
+                })
+            });
+
+        for i in 0..COUNT {
+            assert!(quat_near(actual[i], expected[i], 1e-4));
+        }
+    }
+}
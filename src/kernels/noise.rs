@@ -0,0 +1,625 @@
+use crate::util::CacheAlignedVec;
+use glam::{Vec2, Vec3A};
+use rand::{seq::SliceRandom, Rng};
+
+// Return coordinates in the range `-64..64`, so each sample lands in a random
+// cell and the permutation table lookups are scattered.
+pub fn random_noise_coords_2d<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Vec2> {
+    (0..count)
+        .map(|_| (rng.gen::<Vec2>() - 0.5) * 128.0)
+        .collect()
+}
+
+pub fn random_noise_coords_3d<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<Vec3A> {
+    (0..count)
+        .map(|_| (rng.gen::<Vec3A>() - 0.5) * 128.0)
+        .collect()
+}
+
+// A shuffled permutation of `0..256`, repeated so that hashing each coordinate
+// in turn never needs to wrap.
+pub struct NoiseTable {
+    perm: [u8; 512],
+}
+
+impl NoiseTable {
+    pub fn new<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let mut perm: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+        perm.shuffle(rng);
+
+        Self {
+            perm: std::array::from_fn(|i| perm[i & 255]),
+        }
+    }
+
+    fn hash_2d(&self, x: i32, y: i32) -> u8 {
+        self.perm[self.perm[(x & 255) as usize] as usize + (y & 255) as usize]
+    }
+
+    fn hash_3d(&self, x: i32, y: i32, z: i32) -> u8 {
+        self.perm[self.hash_2d(x, y) as usize + (z & 255) as usize]
+    }
+}
+
+pub struct Noise2dParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub src_array: &'a [Vec2],
+}
+
+pub struct Noise3dParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub src_array: &'a [Vec3A],
+}
+
+pub fn noise_2d_inner<F>(params: &mut Noise2dParams, f: F)
+where
+    F: Fn(Vec2) -> f32,
+{
+    for (dst, &src) in params.dst_array.iter_mut().zip(params.src_array) {
+        *dst = f(src);
+    }
+}
+
+pub fn noise_3d_inner<F>(params: &mut Noise3dParams, f: F)
+where
+    F: Fn(Vec3A) -> f32,
+{
+    for (dst, &src) in params.dst_array.iter_mut().zip(params.src_array) {
+        *dst = f(src);
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + ((b - a) * t)
+}
+
+// 6t^5 - 15t^4 + 10t^3, so the second derivative is continuous across cells.
+fn fade(t: f32) -> f32 {
+    t * t * t * ((t * ((t * 6.0) - 15.0)) + 10.0)
+}
+
+// Dot product with one of the four diagonals.
+fn gradient_2d(hash: u8, x: f32, y: f32) -> f32 {
+    let x = if (hash & 1) == 0 { x } else { -x };
+    let y = if (hash & 2) == 0 { y } else { -y };
+
+    x + y
+}
+
+// Dot product with one of the twelve directions to the edges of a cube, as in
+// Perlin's "Improving Noise" (2002). Sixteen hashes cover the twelve, with four
+// repeated.
+fn gradient_3d(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+
+    (if (h & 1) == 0 { u } else { -u }) + (if (h & 2) == 0 { v } else { -v })
+}
+
+// Gradient noise that interpolates between the four corners of the enclosing
+// square. The result is roughly -1..1, and zero at every integer coordinate.
+pub fn perlin_2d_single(table: &NoiseTable, p: Vec2) -> f32 {
+    let cell = p.floor();
+    let (x, y) = (cell.x as i32, cell.y as i32);
+    let f = p - cell;
+
+    let n00 = gradient_2d(table.hash_2d(x, y), f.x, f.y);
+    let n10 = gradient_2d(table.hash_2d(x + 1, y), f.x - 1.0, f.y);
+    let n01 = gradient_2d(table.hash_2d(x, y + 1), f.x, f.y - 1.0);
+    let n11 = gradient_2d(table.hash_2d(x + 1, y + 1), f.x - 1.0, f.y - 1.0);
+
+    let (u, v) = (fade(f.x), fade(f.y));
+
+    lerp(lerp(n00, n10, u), lerp(n01, n11, u), v)
+}
+
+pub fn perlin_3d_single(table: &NoiseTable, p: Vec3A) -> f32 {
+    let cell = p.floor();
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+    let f = p - cell;
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let hash = table.hash_3d(x + dx, y + dy, z + dz);
+
+        gradient_3d(
+            hash,
+            f.x - (dx as f32),
+            f.y - (dy as f32),
+            f.z - (dz as f32),
+        )
+    };
+
+    let (u, v, w) = (fade(f.x), fade(f.y), fade(f.z));
+
+    let z0 = lerp(
+        lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+        lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+        v,
+    );
+    let z1 = lerp(
+        lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+        lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+        v,
+    );
+
+    lerp(z0, z1, w)
+}
+
+// Gustavson, "Simplex noise demystified" (2005). Sums a radial falloff from
+// the three corners of the enclosing triangle rather than interpolating
+// Perlin's four, and the gap grows with dimensions. But the falloff is a
+// branch per corner, which can be hard to predict. Scaled to roughly -1..1.
+pub fn simplex_2d_single(table: &NoiseTable, p: Vec2) -> f32 {
+    // Skew to a grid of squares, each split into two triangles, then unskew.
+    const F2: f32 = 0.366_025_4; // (sqrt(3) - 1) / 2
+    const G2: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+
+    let s = (p.x + p.y) * F2;
+    let cell = (p + s).floor();
+    let (x, y) = (cell.x as i32, cell.y as i32);
+
+    let p0 = p - (cell - ((cell.x + cell.y) * G2));
+
+    // The lower or upper triangle of the square.
+    let (dx, dy) = if p0.x > p0.y { (1, 0) } else { (0, 1) };
+
+    let p1 = p0 - Vec2::new(dx as f32, dy as f32) + G2;
+    let p2 = p0 - 1.0 + (2.0 * G2);
+
+    let corner = |hash: u8, p: Vec2| {
+        let t = 0.5 - p.length_squared();
+
+        if t > 0.0 {
+            (t * t) * (t * t) * gradient_2d(hash, p.x, p.y)
+        } else {
+            0.0
+        }
+    };
+
+    let n0 = corner(table.hash_2d(x, y), p0);
+    let n1 = corner(table.hash_2d(x + dx, y + dy), p1);
+    let n2 = corner(table.hash_2d(x + 1, y + 1), p2);
+
+    70.0 * (n0 + n1 + n2)
+}
+
+// The paper's 3D version uses a falloff radius of 0.6, which is discontinuous
+// at the edges of each tetrahedron. This uses 0.5, and scales to match.
+pub fn simplex_3d_single(table: &NoiseTable, p: Vec3A) -> f32 {
+    const F3: f32 = 1.0 / 3.0;
+    const G3: f32 = 1.0 / 6.0;
+
+    let s = (p.x + p.y + p.z) * F3;
+    let cell = (p + s).floor();
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+
+    let p0 = p - (cell - ((cell.x + cell.y + cell.z) * G3));
+
+    // The cube is split into six tetrahedra. Find which one by ranking the
+    // coordinates, giving the offsets to its second and third corners.
+    let (o1, o2) = if p0.x >= p0.y {
+        if p0.y >= p0.z {
+            ([1, 0, 0], [1, 1, 0])
+        } else if p0.x >= p0.z {
+            ([1, 0, 0], [1, 0, 1])
+        } else {
+            ([0, 0, 1], [1, 0, 1])
+        }
+    } else if p0.y < p0.z {
+        ([0, 0, 1], [0, 1, 1])
+    } else if p0.x < p0.z {
+        ([0, 1, 0], [0, 1, 1])
+    } else {
+        ([0, 1, 0], [1, 1, 0])
+    };
+
+    let offset = |[dx, dy, dz]: [i32; 3]| Vec3A::new(dx as f32, dy as f32, dz as f32);
+
+    let p1 = p0 - offset(o1) + G3;
+    let p2 = p0 - offset(o2) + (2.0 * G3);
+    let p3 = p0 - 1.0 + (3.0 * G3);
+
+    let corner = |[dx, dy, dz]: [i32; 3], p: Vec3A| {
+        let t = 0.5 - p.length_squared();
+
+        if t > 0.0 {
+            let hash = table.hash_3d(x + dx, y + dy, z + dz);
+
+            (t * t) * (t * t) * gradient_3d(hash, p.x, p.y, p.z)
+        } else {
+            0.0
+        }
+    };
+
+    let n0 = corner([0, 0, 0], p0);
+    let n1 = corner(o1, p1);
+    let n2 = corner(o2, p2);
+    let n3 = corner([1, 1, 1], p3);
+
+    76.0 * (n0 + n1 + n2 + n3)
+}
+
+#[inline(never)]
+pub fn perlin_2d(params: &mut Noise2dParams, table: &NoiseTable) {
+    noise_2d_inner(params, |p| perlin_2d_single(table, p));
+}
+
+#[inline(never)]
+pub fn perlin_3d(params: &mut Noise3dParams, table: &NoiseTable) {
+    noise_3d_inner(params, |p| perlin_3d_single(table, p));
+}
+
+#[inline(never)]
+pub fn simplex_2d(params: &mut Noise2dParams, table: &NoiseTable) {
+    noise_2d_inner(params, |p| simplex_2d_single(table, p));
+}
+
+#[inline(never)]
+pub fn simplex_3d(params: &mut Noise3dParams, table: &NoiseTable) {
+    noise_3d_inner(params, |p| simplex_3d_single(table, p));
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Fractal Brownian motion sums octaves of noise, each at `lacunarity` times
+// the frequency and `gain` times the amplitude of the last.
+#[derive(Clone, Copy, Debug)]
+pub struct FbmSettings {
+    pub octaves: usize,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl Default for FbmSettings {
+    fn default() -> Self {
+        Self {
+            octaves: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+}
+
+pub const MAX_OCTAVES: usize = 8;
+
+// Each octave is offset so the octaves don't all cross zero at the origin.
+const OCTAVE_OFFSET: Vec2 = Vec2::new(19.1, 7.3);
+
+// The frequency and amplitude of each octave, with the amplitudes scaled so
+// they sum to one.
+#[derive(Clone, Copy, Debug)]
+pub struct FbmOctaves {
+    pub count: usize,
+    pub frequencies: [f32; MAX_OCTAVES],
+    pub amplitudes: [f32; MAX_OCTAVES],
+}
+
+impl FbmOctaves {
+    pub fn new(settings: &FbmSettings) -> Self {
+        assert!(settings.octaves <= MAX_OCTAVES);
+
+        let mut octaves = Self {
+            count: settings.octaves,
+            frequencies: [0.0; MAX_OCTAVES],
+            amplitudes: [0.0; MAX_OCTAVES],
+        };
+
+        let (mut frequency, mut amplitude) = (1.0, 1.0);
+
+        for i in 0..settings.octaves {
+            octaves.frequencies[i] = frequency;
+            octaves.amplitudes[i] = amplitude;
+
+            frequency *= settings.lacunarity;
+            amplitude *= settings.gain;
+        }
+
+        let sum = octaves.amplitudes.iter().sum::<f32>();
+
+        for a in &mut octaves.amplitudes {
+            *a /= sum;
+        }
+
+        octaves
+    }
+}
+
+// Update the frequency and amplitude as it goes, and normalize at the end.
+pub fn fbm_2d_single(table: &NoiseTable, p: Vec2, settings: &FbmSettings) -> f32 {
+    let (mut frequency, mut amplitude) = (1.0, 1.0);
+    let (mut sum, mut amplitude_sum) = (0.0, 0.0);
+
+    for i in 0..settings.octaves {
+        let offset = OCTAVE_OFFSET * (i as f32);
+
+        sum += perlin_2d_single(table, (p * frequency) + offset) * amplitude;
+        amplitude_sum += amplitude;
+
+        frequency *= settings.lacunarity;
+        amplitude *= settings.gain;
+    }
+
+    sum / amplitude_sum
+}
+
+pub fn fbm_2d_precomputed_single(table: &NoiseTable, p: Vec2, octaves: &FbmOctaves) -> f32 {
+    let mut sum = 0.0;
+
+    for i in 0..octaves.count {
+        let offset = OCTAVE_OFFSET * (i as f32);
+
+        sum +=
+            perlin_2d_single(table, (p * octaves.frequencies[i]) + offset) * octaves.amplitudes[i];
+    }
+
+    sum
+}
+
+#[inline(never)]
+pub fn fbm_2d(params: &mut Noise2dParams, table: &NoiseTable, settings: &FbmSettings) {
+    noise_2d_inner(params, |p| fbm_2d_single(table, p, settings));
+}
+
+#[inline(never)]
+pub fn fbm_2d_precomputed(params: &mut Noise2dParams, table: &NoiseTable, octaves: &FbmOctaves) {
+    noise_2d_inner(params, |p| fbm_2d_precomputed_single(table, p, octaves));
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// White noise from hashing integer coordinates, as in shaders. The result for
+// each cell of a `width` by `dst_array.len() / width` grid is in `0..1`.
+pub struct HashNoiseParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub width: usize,
+    pub seed: u32,
+}
+
+pub fn hash_noise_inner<F>(params: &mut HashNoiseParams, f: F)
+where
+    F: Fn(u32, u32, u32) -> u32,
+{
+    let seed = params.seed;
+
+    for (y, row) in params.dst_array.chunks_exact_mut(params.width).enumerate() {
+        for (x, dst) in row.iter_mut().enumerate() {
+            *dst = hash_to_float(f(x as u32, y as u32, seed));
+        }
+    }
+}
+
+// Use the top 24 bits, which is all an f32 in `0..1` can hold.
+pub fn hash_to_float(hash: u32) -> f32 {
+    ((hash >> 8) as f32) * (1.0 / ((1 << 24) as f32))
+}
+
+// Jarzynski and Olano, "Hash Functions for GPU Rendering" (2020). A step of
+// the PCG generator followed by its output permutation.
+pub fn pcg_hash(input: u32) -> u32 {
+    let state = input.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+
+    (word >> 22) ^ word
+}
+
+// Thomas Wang's integer hash, common in older shaders. Cheaper than PCG, but
+// with visible patterns in the low bits.
+pub fn wang_hash(input: u32) -> u32 {
+    let mut h = (input ^ 61) ^ (input >> 16);
+
+    h = h.wrapping_mul(9);
+    h ^= h >> 4;
+    h = h.wrapping_mul(0x27d4eb2d);
+    h ^ (h >> 15)
+}
+
+// xxHash32 of the eight bytes of `x` and `y`, matching the reference
+// implementation. It mixes both coordinates in one hash rather than nesting.
+pub fn xxhash32_2d(x: u32, y: u32, seed: u32) -> u32 {
+    const PRIME_2: u32 = 0x85ebca77;
+    const PRIME_3: u32 = 0xc2b2ae3d;
+    const PRIME_4: u32 = 0x27d4eb2f;
+    const PRIME_5: u32 = 0x165667b1;
+
+    let mut h = seed.wrapping_add(PRIME_5).wrapping_add(8);
+
+    for lane in [x, y] {
+        h = h.wrapping_add(lane.wrapping_mul(PRIME_3));
+        h = h.rotate_left(17).wrapping_mul(PRIME_4);
+    }
+
+    h ^= h >> 15;
+    h = h.wrapping_mul(PRIME_2);
+    h ^= h >> 13;
+    h = h.wrapping_mul(PRIME_3);
+    h ^ (h >> 16)
+}
+
+// Hash each coordinate in turn, feeding the last result into the next.
+#[inline(never)]
+pub fn hash_noise_pcg(params: &mut HashNoiseParams) {
+    hash_noise_inner(params, |x, y, seed| {
+        pcg_hash(x.wrapping_add(pcg_hash(y.wrapping_add(pcg_hash(seed)))))
+    });
+}
+
+#[inline(never)]
+pub fn hash_noise_wang(params: &mut HashNoiseParams) {
+    hash_noise_inner(params, |x, y, seed| {
+        wang_hash(x.wrapping_add(wang_hash(y.wrapping_add(wang_hash(seed)))))
+    });
+}
+
+#[inline(never)]
+pub fn hash_noise_xxhash32(params: &mut HashNoiseParams) {
+    hash_noise_inner(params, xxhash32_2d);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The noise crate works in f64, so these include converting each coordinate
+// and result.
+
+#[cfg(feature = "noise")]
+#[inline(never)]
+pub fn perlin_2d_noise_crate(params: &mut Noise2dParams, perlin: &::noise::Perlin) {
+    use ::noise::NoiseFn;
+
+    noise_2d_inner(params, |p| perlin.get(p.as_dvec2().to_array()) as f32);
+}
+
+#[cfg(feature = "noise")]
+#[inline(never)]
+pub fn perlin_3d_noise_crate(params: &mut Noise3dParams, perlin: &::noise::Perlin) {
+    use ::noise::NoiseFn;
+
+    noise_3d_inner(params, |p| perlin.get(p.as_dvec3().to_array()) as f32);
+}
+
+#[cfg(feature = "noise")]
+#[inline(never)]
+pub fn simplex_2d_noise_crate(params: &mut Noise2dParams, simplex: &::noise::Simplex) {
+    use ::noise::NoiseFn;
+
+    noise_2d_inner(params, |p| simplex.get(p.as_dvec2().to_array()) as f32);
+}
+
+#[cfg(feature = "noise")]
+#[inline(never)]
+pub fn simplex_3d_noise_crate(params: &mut Noise3dParams, simplex: &::noise::Simplex) {
+    use ::noise::NoiseFn;
+
+    noise_3d_inner(params, |p| simplex.get(p.as_dvec3().to_array()) as f32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{test_rng, COUNT};
+
+    #[test]
+    fn noise() {
+        let mut rng = test_rng();
+
+        let table = NoiseTable::new(&mut rng);
+
+        // Perlin noise is zero on the integer grid.
+
+        for &p in random_noise_coords_3d(&mut rng, COUNT).iter() {
+            let p = p.floor();
+
+            assert_eq!(perlin_2d_single(&table, p.truncate()), 0.0);
+            assert_eq!(perlin_3d_single(&table, p), 0.0);
+        }
+
+        // All four are roughly -1..1, vary, and are continuous.
+
+        let coords_2d = random_noise_coords_2d(&mut rng, COUNT);
+        let coords_3d = random_noise_coords_3d(&mut rng, COUNT);
+
+        let check = |f: &dyn Fn(usize, f32) -> f32| {
+            let mut range = (f32::MAX, f32::MIN);
+
+            for i in 0..COUNT {
+                let v = f(i, 0.0);
+
+                range = (range.0.min(v), range.1.max(v));
+
+                assert!((f(i, 1e-4) - v).abs() < 2e-3);
+            }
+
+            assert!((range.0 > -1.05) && (range.0 < -0.5));
+            assert!((range.1 < 1.05) && (range.1 > 0.5));
+        };
+
+        check(&|i, d| perlin_2d_single(&table, coords_2d[i] + d));
+        check(&|i, d| perlin_3d_single(&table, coords_3d[i] + d));
+        check(&|i, d| simplex_2d_single(&table, coords_2d[i] + d));
+        check(&|i, d| simplex_3d_single(&table, coords_3d[i] + d));
+    }
+
+    #[test]
+    fn fbm() {
+        let mut rng = test_rng();
+
+        let table = NoiseTable::new(&mut rng);
+
+        let coords = random_noise_coords_2d(&mut rng, COUNT);
+
+        for octaves in 1..=MAX_OCTAVES {
+            let settings = FbmSettings {
+                octaves,
+                ..Default::default()
+            };
+
+            let precomputed = FbmOctaves::new(&settings);
+
+            for &p in coords.iter() {
+                let l = fbm_2d_single(&table, p, &settings);
+                let r = fbm_2d_precomputed_single(&table, p, &precomputed);
+
+                assert!((l - r).abs() < 1e-6);
+                assert!(l.abs() <= 1.0);
+            }
+        }
+
+        // One octave is just the noise.
+
+        let settings = FbmSettings {
+            octaves: 1,
+            ..Default::default()
+        };
+
+        for &p in coords.iter() {
+            assert_eq!(
+                fbm_2d_single(&table, p, &settings),
+                perlin_2d_single(&table, p)
+            );
+        }
+    }
+
+    #[test]
+    fn hash_noise() {
+        // Reference values from the xxhash-rust crate.
+
+        assert_eq!(xxhash32_2d(0, 0, 0), 0xdeb39513);
+        assert_eq!(xxhash32_2d(12, 34, 1234), 0x077edc35);
+
+        // Each hash gives a roughly even spread of values.
+
+        let width = 256;
+
+        for f in [hash_noise_pcg, hash_noise_wang, hash_noise_xxhash32] {
+            let mut dst_array = vec![0.0; width * width];
+
+            f(&mut HashNoiseParams {
+                dst_array: &mut dst_array,
+                width,
+                seed: 1234,
+            });
+
+            let mut buckets = [0usize; 16];
+
+            for &v in &dst_array {
+                assert!((0.0..1.0).contains(&v));
+
+                buckets[(v * 16.0) as usize] += 1;
+            }
+
+            let expected = dst_array.len() / buckets.len();
+
+            assert!(buckets
+                .iter()
+                .all(|&b| b.abs_diff(expected) < (expected / 10)));
+        }
+    }
+}
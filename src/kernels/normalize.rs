@@ -0,0 +1,720 @@
+use crate::{kernels::scalar::Scalar, util::CacheAlignedVec};
+use bevy_math::Dir3;
+use bevy_transform::components::Transform;
+use glam::{Quat, Vec3, Vec3A, Vec4};
+use rayon::prelude::*;
+use std::ops::{Add, Mul};
+
+pub fn mul_normalize_false(l: &Transform, r: &Transform) -> Transform {
+    Transform {
+        translation: l.transform_point(r.translation),
+        rotation: l.rotation * r.rotation,
+        scale: l.scale * r.scale,
+    }
+}
+
+pub fn mul_normalize_true(l: &Transform, r: &Transform) -> Transform {
+    Transform {
+        translation: l.transform_point(r.translation),
+        rotation: (l.rotation * r.rotation).normalize(),
+        scale: l.scale * r.scale,
+    }
+}
+
+pub struct TransformNormalizeParams<'a> {
+    pub dst: &'a mut [Transform],
+    pub src: &'a [&'a [Transform]; 2],
+}
+
+pub fn transform_normalize_inner<F>(params: &mut TransformNormalizeParams, f: F)
+where
+    F: Fn(&Transform, &Transform) -> Transform,
+{
+    for i in 0..params.dst.len() {
+        params.dst[i] = f(&params.src[0][i], &params.src[1][i]);
+    }
+}
+
+#[inline(never)]
+pub fn transform_normalize_false(params: &mut TransformNormalizeParams) {
+    transform_normalize_inner(params, mul_normalize_false);
+}
+
+#[inline(never)]
+pub fn transform_normalize_true(params: &mut TransformNormalizeParams) {
+    transform_normalize_inner(params, mul_normalize_true);
+}
+
+// Split the arrays into chunks of `chunk_len` and run
+// `transform_normalize_true` on each chunk as a task on the current rayon pool.
+#[inline(never)]
+pub fn transform_normalize_true_par_chunks(
+    params: &mut TransformNormalizeParams,
+    chunk_len: usize,
+) {
+    let [l, r] = *params.src;
+
+    params
+        .dst
+        .par_chunks_mut(chunk_len)
+        .zip(l.par_chunks(chunk_len))
+        .zip(r.par_chunks(chunk_len))
+        .for_each(|((dst, l), r)| {
+            transform_normalize_true(&mut TransformNormalizeParams { dst, src: &[l, r] })
+        });
+}
+
+// A transform at either precision. Bevy's `Transform` is `f32` only.
+pub struct TransformGeneric<S: Scalar> {
+    pub translation: S::Vec3,
+    pub rotation: S::Quat,
+    pub scale: S::Vec3,
+}
+
+impl<S: Scalar> Clone for TransformGeneric<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: Scalar> Copy for TransformGeneric<S> {}
+
+impl<S: Scalar> TransformGeneric<S> {
+    pub fn from_transform(transform: &Transform) -> Self {
+        TransformGeneric {
+            translation: S::vec3_from_f32(transform.translation),
+            rotation: S::quat_from_f32(transform.rotation),
+            scale: S::vec3_from_f32(transform.scale),
+        }
+    }
+}
+
+pub struct TransformGenericParams<'a, S: Scalar> {
+    pub dst: &'a mut [TransformGeneric<S>],
+    pub src: &'a [&'a [TransformGeneric<S>]; 2],
+}
+
+// Same as `transform_normalize_inner` with `mul_normalize_false` or
+// `mul_normalize_true`, but generic over precision.
+pub fn transform_generic_normalize_inner<S, F>(params: &mut TransformGenericParams<S>, f: F)
+where
+    S: Scalar,
+    F: Fn(S::Quat) -> S::Quat,
+{
+    for i in 0..params.dst.len() {
+        let l = &params.src[0][i];
+        let r = &params.src[1][i];
+
+        params.dst[i] = TransformGeneric {
+            translation: S::quat_mul_vec3(l.rotation, l.scale * r.translation) + l.translation,
+            rotation: f(S::quat_mul(l.rotation, r.rotation)),
+            scale: l.scale * r.scale,
+        };
+    }
+}
+
+#[inline(never)]
+pub fn transform_generic_normalize_false<S: Scalar>(params: &mut TransformGenericParams<S>) {
+    transform_generic_normalize_inner(params, |q| q);
+}
+
+#[inline(never)]
+pub fn transform_generic_normalize_true<S: Scalar>(params: &mut TransformGenericParams<S>) {
+    transform_generic_normalize_inner(params, S::quat_normalize);
+}
+
+// Transforms stored as separate translation, rotation and scale arrays. With
+// `V = Vec3` the arrays are tightly packed. With `V = Vec3A` every element is
+// padded to 16 bytes, so each array is effectively an array of `Vec4`.
+pub struct TransformSoa<V> {
+    pub translation: CacheAlignedVec<V>,
+    pub rotation: CacheAlignedVec<Quat>,
+    pub scale: CacheAlignedVec<V>,
+}
+
+impl<V: From<Vec3>> TransformSoa<V> {
+    pub fn from_transforms(transforms: &[Transform]) -> Self {
+        TransformSoa {
+            translation: transforms.iter().map(|t| t.translation.into()).collect(),
+            rotation: transforms.iter().map(|t| t.rotation).collect(),
+            scale: transforms.iter().map(|t| t.scale.into()).collect(),
+        }
+    }
+}
+
+impl<V> TransformSoa<V> {
+    pub fn as_slices(&self) -> TransformSlices<'_, V> {
+        TransformSlices {
+            translation: &self.translation,
+            rotation: &self.rotation,
+            scale: &self.scale,
+        }
+    }
+
+    pub fn as_slices_mut(&mut self) -> TransformSlicesMut<'_, V> {
+        TransformSlicesMut {
+            translation: &mut self.translation,
+            rotation: &mut self.rotation,
+            scale: &mut self.scale,
+        }
+    }
+}
+
+pub struct TransformSlices<'a, V> {
+    pub translation: &'a [V],
+    pub rotation: &'a [Quat],
+    pub scale: &'a [V],
+}
+
+pub struct TransformSlicesMut<'a, V> {
+    pub translation: &'a mut [V],
+    pub rotation: &'a mut [Quat],
+    pub scale: &'a mut [V],
+}
+
+pub struct TransformSoaParams<'a, V> {
+    pub dst: TransformSlicesMut<'a, V>,
+    pub src: [TransformSlices<'a, V>; 2],
+}
+
+// Same as `transform_normalize_inner` with `mul_normalize_false` or
+// `mul_normalize_true`, but on SoA transforms.
+pub fn transform_soa_normalize_inner<V, F>(params: &mut TransformSoaParams<V>, f: F)
+where
+    V: Copy + Add<Output = V> + Mul<Output = V>,
+    Quat: Mul<V, Output = V>,
+    F: Fn(Quat) -> Quat,
+{
+    let [l, r] = &params.src;
+
+    for i in 0..params.dst.rotation.len() {
+        params.dst.translation[i] =
+            (l.rotation[i] * (l.scale[i] * r.translation[i])) + l.translation[i];
+        params.dst.rotation[i] = f(l.rotation[i].mul_quat(r.rotation[i]));
+        params.dst.scale[i] = l.scale[i] * r.scale[i];
+    }
+}
+
+#[inline(never)]
+pub fn transform_split_normalize_false(params: &mut TransformSoaParams<Vec3>) {
+    transform_soa_normalize_inner(params, |q| q);
+}
+
+#[inline(never)]
+pub fn transform_split_normalize_true(params: &mut TransformSoaParams<Vec3>) {
+    transform_soa_normalize_inner(params, Quat::normalize);
+}
+
+#[inline(never)]
+pub fn transform_padded_normalize_false(params: &mut TransformSoaParams<Vec3A>) {
+    transform_soa_normalize_inner(params, |q| q);
+}
+
+#[inline(never)]
+pub fn transform_padded_normalize_true(params: &mut TransformSoaParams<Vec3A>) {
+    transform_soa_normalize_inner(params, Quat::normalize);
+}
+
+// A transform interleaved as three `Vec4`s - translation, rotation and scale.
+// The w component of translation and scale is unused.
+pub type TransformInterleaved = [Vec4; 3];
+
+pub fn interleave_transform(transform: &Transform) -> TransformInterleaved {
+    [
+        transform.translation.extend(0.0),
+        Vec4::from(transform.rotation),
+        transform.scale.extend(0.0),
+    ]
+}
+
+pub fn deinterleave_transform(transform: &TransformInterleaved) -> Transform {
+    Transform {
+        translation: transform[0].truncate(),
+        rotation: Quat::from_vec4(transform[1]),
+        scale: transform[2].truncate(),
+    }
+}
+
+pub struct TransformInterleavedParams<'a> {
+    pub dst: &'a mut [TransformInterleaved],
+    pub src: [&'a [TransformInterleaved]; 2],
+}
+
+// Same as `transform_soa_normalize_inner`, but on interleaved transforms.
+pub fn transform_interleaved_normalize_inner<F>(params: &mut TransformInterleavedParams, f: F)
+where
+    F: Fn(Quat) -> Quat,
+{
+    for i in 0..params.dst.len() {
+        let [l_translation, l_rotation, l_scale] = params.src[0][i];
+        let [r_translation, r_rotation, r_scale] = params.src[1][i];
+
+        let l_rotation = Quat::from_vec4(l_rotation);
+        let l_scale = Vec3A::from_vec4(l_scale);
+
+        let translation = (l_rotation * (l_scale * Vec3A::from_vec4(r_translation)))
+            + Vec3A::from_vec4(l_translation);
+        let rotation = f(l_rotation.mul_quat(Quat::from_vec4(r_rotation)));
+        let scale = l_scale * Vec3A::from_vec4(r_scale);
+
+        params.dst[i] = [
+            translation.extend(0.0),
+            Vec4::from(rotation),
+            scale.extend(0.0),
+        ];
+    }
+}
+
+#[inline(never)]
+pub fn transform_interleaved_normalize_false(params: &mut TransformInterleavedParams) {
+    transform_interleaved_normalize_inner(params, |q| q);
+}
+
+#[inline(never)]
+pub fn transform_interleaved_normalize_true(params: &mut TransformInterleavedParams) {
+    transform_interleaved_normalize_inner(params, Quat::normalize);
+}
+
+pub trait FastRenormalize {
+    fn fast_renormalize(self) -> Self;
+}
+
+impl FastRenormalize for Quat {
+    fn fast_renormalize(self) -> Self {
+        let length_squared = self.length_squared();
+        self * (0.5 * (3.0 - length_squared))
+    }
+}
+
+pub fn rotate_axis_normalize_false(dst: &mut Transform, src: Transform, axis: Dir3, angle: f32) {
+    *dst = src;
+    dst.rotate_axis(axis, angle);
+}
+
+pub fn rotate_axis_normalize_true(dst: &mut Transform, src: Transform, axis: Dir3, angle: f32) {
+    *dst = src;
+    dst.rotate_axis(axis, angle);
+    dst.rotation = dst.rotation.normalize();
+}
+
+// Normalize the quat only if it's drifted noticeably from unit length.
+pub fn reactive_renormalize(q: Quat) -> Quat {
+    let l = q.length_squared();
+
+    if (1.0 - l).abs() > 0.0001 {
+        q / l.sqrt()
+    } else {
+        q
+    }
+}
+
+pub fn rotate_axis_normalize_reactive(dst: &mut Transform, src: Transform, axis: Dir3, angle: f32) {
+    *dst = src;
+    dst.rotate_axis(axis, angle);
+    dst.rotation = reactive_renormalize(dst.rotation);
+}
+
+pub fn rotate_axis_normalize_fast(dst: &mut Transform, src: Transform, axis: Dir3, angle: f32) {
+    *dst = src;
+    dst.rotate_axis(axis, angle);
+    dst.rotation = dst.rotation.fast_renormalize();
+}
+
+pub struct RotateAxisParams<'a> {
+    pub dst_array: &'a mut [Transform],
+    pub src_array: &'a [Transform],
+    pub axis_array: &'a [Dir3],
+    pub angle_array: &'a [f32],
+}
+
+pub fn rotate_axis_normalize_inner<F>(params: &mut RotateAxisParams, f: F)
+where
+    F: Fn(&mut Transform, Transform, Dir3, f32),
+{
+    for i in 0..params.dst_array.len() {
+        f(
+            &mut params.dst_array[i],
+            params.src_array[i],
+            params.axis_array[i],
+            params.angle_array[i],
+        );
+    }
+}
+
+#[inline(never)]
+pub fn rotate_axis_normalize_false_outer(params: &mut RotateAxisParams) {
+    rotate_axis_normalize_inner(params, rotate_axis_normalize_false);
+}
+
+#[inline(never)]
+pub fn rotate_axis_normalize_true_outer(params: &mut RotateAxisParams) {
+    rotate_axis_normalize_inner(params, rotate_axis_normalize_true);
+}
+
+#[inline(never)]
+pub fn rotate_axis_normalize_reactive_outer(params: &mut RotateAxisParams) {
+    rotate_axis_normalize_inner(params, rotate_axis_normalize_reactive);
+}
+
+#[inline(never)]
+pub fn rotate_axis_normalize_fast_outer(params: &mut RotateAxisParams) {
+    rotate_axis_normalize_inner(params, rotate_axis_normalize_fast);
+}
+
+pub fn single_normalize_false(dst: &mut Transform, src: Transform) {
+    dst.rotation = src.rotation;
+}
+
+pub fn single_normalize_true(dst: &mut Transform, src: Transform) {
+    dst.rotation = src.rotation.normalize();
+}
+
+pub fn single_normalize_reactive(dst: &mut Transform, src: Transform) {
+    dst.rotation = reactive_renormalize(src.rotation);
+}
+
+pub fn single_normalize_fast(dst: &mut Transform, src: Transform) {
+    dst.rotation = src.rotation.fast_renormalize();
+}
+
+pub struct SingleNormalizeParams<'a> {
+    pub dst_array: &'a mut [Transform],
+    pub src_array: &'a [Transform],
+}
+
+pub fn single_normalize_inner<F>(params: &mut SingleNormalizeParams, f: F)
+where
+    F: Fn(&mut Transform, Transform),
+{
+    for i in 0..params.dst_array.len() {
+        f(&mut params.dst_array[i], params.src_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn single_normalize_false_outer(params: &mut SingleNormalizeParams) {
+    single_normalize_inner(params, single_normalize_false);
+}
+
+#[inline(never)]
+pub fn single_normalize_true_outer(params: &mut SingleNormalizeParams) {
+    single_normalize_inner(params, single_normalize_true);
+}
+
+#[inline(never)]
+pub fn single_normalize_reactive_outer(params: &mut SingleNormalizeParams) {
+    single_normalize_inner(params, single_normalize_reactive);
+}
+
+#[inline(never)]
+pub fn single_normalize_fast_outer(params: &mut SingleNormalizeParams) {
+    single_normalize_inner(params, single_normalize_fast);
+}
+
+// Compose a chain of rotations, applying the given normalization after each
+// step. Returns the final rotation.
+pub fn compose_normalize_inner<F>(rotations: &[Quat], f: F) -> Quat
+where
+    F: Fn(Quat) -> Quat,
+{
+    rotations.iter().fold(Quat::IDENTITY, |acc, &r| f(acc * r))
+}
+
+#[inline(never)]
+pub fn compose_normalize_false(rotations: &[Quat]) -> Quat {
+    compose_normalize_inner(rotations, |q| q)
+}
+
+#[inline(never)]
+pub fn compose_normalize_true(rotations: &[Quat]) -> Quat {
+    compose_normalize_inner(rotations, Quat::normalize)
+}
+
+#[inline(never)]
+pub fn compose_normalize_reactive(rotations: &[Quat]) -> Quat {
+    compose_normalize_inner(rotations, reactive_renormalize)
+}
+
+#[inline(never)]
+pub fn compose_normalize_fast(rotations: &[Quat]) -> Quat {
+    compose_normalize_inner(rotations, Quat::fast_renormalize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{quat_near, run_into, test_rng, COUNT, TOLERANCE},
+        util::*,
+    };
+    use rand::{rngs::StdRng, Rng};
+
+    // Return transforms with rotations that are slightly denormalized, as they
+    // might be after accumulating floating point error.
+    fn drifted_transform_array(rng: &mut StdRng) -> Vec<Transform> {
+        random_transform_array(rng, COUNT)
+            .iter()
+            .map(|&t| Transform {
+                rotation: t.rotation * rng.gen_range(0.995..1.005),
+                ..t
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fast_renormalize() {
+        let mut rng = test_rng();
+
+        for t in drifted_transform_array(&mut rng) {
+            assert!(quat_near(
+                t.rotation.fast_renormalize(),
+                t.rotation.normalize(),
+                TOLERANCE
+            ));
+        }
+    }
+
+    #[test]
+    fn transform_normalize() {
+        let mut rng = test_rng();
+
+        let src = [
+            &random_transform_array(&mut rng, COUNT)[..],
+            &random_transform_array(&mut rng, COUNT)[..],
+        ];
+
+        let mut dst_false = vec![Transform::IDENTITY; COUNT];
+        let mut dst_true = vec![Transform::IDENTITY; COUNT];
+
+        transform_normalize_false(&mut TransformNormalizeParams {
+            dst: &mut dst_false,
+            src: &src,
+        });
+
+        transform_normalize_true(&mut TransformNormalizeParams {
+            dst: &mut dst_true,
+            src: &src,
+        });
+
+        for (f, t) in dst_false.iter().zip(dst_true.iter()) {
+            assert_eq!(f.translation, t.translation);
+            assert_eq!(f.scale, t.scale);
+            assert!(quat_near(f.rotation, t.rotation, TOLERANCE));
+            assert!(t.rotation.is_normalized());
+        }
+
+        for chunk_len in [1, 64, COUNT * 2] {
+            let mut dst = vec![Transform::IDENTITY; COUNT];
+
+            transform_normalize_true_par_chunks(
+                &mut TransformNormalizeParams {
+                    dst: &mut dst,
+                    src: &src,
+                },
+                chunk_len,
+            );
+
+            assert_eq!(dst, dst_true);
+        }
+    }
+
+    #[test]
+    fn rotate_axis_normalize() {
+        let mut rng = test_rng();
+
+        let src_array = drifted_transform_array(&mut rng);
+        let axis_array = random_array::<Dir3>(&mut rng, COUNT);
+        let angle_array = random_array::<f32>(&mut rng, COUNT);
+
+        let variants: [fn(&mut RotateAxisParams); 4] = [
+            rotate_axis_normalize_false_outer,
+            rotate_axis_normalize_true_outer,
+            rotate_axis_normalize_reactive_outer,
+            rotate_axis_normalize_fast_outer,
+        ];
+
+        let results = variants.map(|f| {
+            run_into(vec![Transform::IDENTITY; COUNT], |dst_array| {
+                f(&mut RotateAxisParams {
+                    dst_array,
+                    src_array: &src_array,
+                    axis_array: &axis_array,
+                    angle_array: &angle_array,
+                })
+            })
+        });
+
+        let [_, expected, reactive, fast] = &results;
+
+        for i in 0..COUNT {
+            assert!(quat_near(
+                reactive[i].rotation,
+                expected[i].rotation,
+                TOLERANCE
+            ));
+            assert!(quat_near(fast[i].rotation, expected[i].rotation, TOLERANCE));
+        }
+    }
+
+    #[test]
+    fn single_normalize() {
+        let mut rng = test_rng();
+
+        let src_array = drifted_transform_array(&mut rng);
+
+        let variants: [fn(&mut SingleNormalizeParams); 3] = [
+            single_normalize_true_outer,
+            single_normalize_reactive_outer,
+            single_normalize_fast_outer,
+        ];
+
+        let [expected, reactive, fast] = variants.map(|f| {
+            run_into(vec![Transform::IDENTITY; COUNT], |dst_array| {
+                f(&mut SingleNormalizeParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        });
+
+        for i in 0..COUNT {
+            assert!(quat_near(
+                reactive[i].rotation,
+                expected[i].rotation,
+                TOLERANCE
+            ));
+            assert!(quat_near(fast[i].rotation, expected[i].rotation, TOLERANCE));
+        }
+    }
+
+    #[test]
+    fn compose_normalize() {
+        let mut rng = test_rng();
+
+        let rotations = random_array::<Quat>(&mut rng, 100_000);
+
+        let expected = compose_normalize_true(&rotations);
+
+        for f in [compose_normalize_reactive, compose_normalize_fast] {
+            let actual = f(&rotations);
+
+            assert!((1.0 - actual.length()).abs() < TOLERANCE);
+            assert!(quat_near(actual, expected, 0.01));
+        }
+    }
+
+    #[test]
+    fn transform_layout() {
+        let mut rng = test_rng();
+
+        let src = [
+            random_transform_array(&mut rng, COUNT),
+            random_transform_array(&mut rng, COUNT),
+        ];
+
+        let mut expected = vec![Transform::IDENTITY; COUNT];
+
+        transform_normalize_true(&mut TransformNormalizeParams {
+            dst: &mut expected,
+            src: &[&src[0], &src[1]],
+        });
+
+        let split_src = src
+            .each_ref()
+            .map(|s| TransformSoa::<Vec3>::from_transforms(s));
+        let mut split = TransformSoa::<Vec3>::from_transforms(&vec![Transform::IDENTITY; COUNT]);
+
+        transform_split_normalize_true(&mut TransformSoaParams {
+            dst: split.as_slices_mut(),
+            src: split_src.each_ref().map(TransformSoa::as_slices),
+        });
+
+        let padded_src = src
+            .each_ref()
+            .map(|s| TransformSoa::<Vec3A>::from_transforms(s));
+        let mut padded = TransformSoa::<Vec3A>::from_transforms(&vec![Transform::IDENTITY; COUNT]);
+
+        transform_padded_normalize_true(&mut TransformSoaParams {
+            dst: padded.as_slices_mut(),
+            src: padded_src.each_ref().map(TransformSoa::as_slices),
+        });
+
+        let interleaved_src = src
+            .each_ref()
+            .map(|s| s.iter().map(interleave_transform).collect::<Vec<_>>());
+        let mut interleaved = vec![interleave_transform(&Transform::IDENTITY); COUNT];
+
+        transform_interleaved_normalize_true(&mut TransformInterleavedParams {
+            dst: &mut interleaved,
+            src: [&interleaved_src[0], &interleaved_src[1]],
+        });
+
+        for (i, expected) in expected.iter().enumerate() {
+            assert!(split.translation[i].abs_diff_eq(expected.translation, TOLERANCE));
+            assert!(split.scale[i].abs_diff_eq(expected.scale, TOLERANCE));
+            assert!(quat_near(split.rotation[i], expected.rotation, TOLERANCE));
+
+            assert!(Vec3::from(padded.translation[i]).abs_diff_eq(expected.translation, TOLERANCE));
+            assert!(Vec3::from(padded.scale[i]).abs_diff_eq(expected.scale, TOLERANCE));
+            assert!(quat_near(padded.rotation[i], expected.rotation, TOLERANCE));
+
+            let interleaved = deinterleave_transform(&interleaved[i]);
+
+            assert!(interleaved
+                .translation
+                .abs_diff_eq(expected.translation, TOLERANCE));
+            assert!(interleaved.scale.abs_diff_eq(expected.scale, TOLERANCE));
+            assert!(quat_near(
+                interleaved.rotation,
+                expected.rotation,
+                TOLERANCE
+            ));
+        }
+    }
+
+    #[test]
+    fn transform_generic() {
+        let mut rng = test_rng();
+
+        let src = [
+            random_transform_array(&mut rng, COUNT),
+            random_transform_array(&mut rng, COUNT),
+        ];
+
+        let mut expected = vec![Transform::IDENTITY; COUNT];
+
+        transform_normalize_true(&mut TransformNormalizeParams {
+            dst: &mut expected,
+            src: &[&src[0], &src[1]],
+        });
+
+        let generic_src = src.each_ref().map(|s| {
+            s.iter()
+                .map(TransformGeneric::<f64>::from_transform)
+                .collect::<Vec<_>>()
+        });
+        let mut generic =
+            vec![TransformGeneric::<f64>::from_transform(&Transform::IDENTITY); COUNT];
+
+        transform_generic_normalize_true(&mut TransformGenericParams {
+            dst: &mut generic,
+            src: &[&generic_src[0], &generic_src[1]],
+        });
+
+        for (actual, expected) in generic.iter().zip(expected.iter()) {
+            assert!(actual
+                .translation
+                .as_vec3()
+                .abs_diff_eq(expected.translation, TOLERANCE));
+            assert!(actual
+                .scale
+                .as_vec3()
+                .abs_diff_eq(expected.scale, TOLERANCE));
+            assert!(quat_near(
+                actual.rotation.as_quat(),
+                expected.rotation,
+                TOLERANCE
+            ));
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{kernels::vector::Vec3Soa, util::CacheAlignedVec};
+use glam::{Vec3, Vec4};
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub color: Vec4,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ParticleSettings {
+    pub acceleration: Vec3,
+    pub drag: f32,
+    pub dt: f32,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self {
+            // Gravity plus a little wind. Without the wind, drag shrinks the
+            // horizontal velocities until they're denormal, which is very
+            // slow on some CPUs.
+            acceleration: Vec3::new(1.0, -9.8, 0.5),
+            drag: 0.1,
+            dt: 1.0 / 60.0,
+        }
+    }
+}
+
+// Fade out linearly over the particle's lifetime.
+fn particle_alpha(age: f32, lifetime: f32) -> f32 {
+    (1.0 - (age / lifetime)).max(0.0)
+}
+
+// Ages run to a quarter past the lifetime, so a fifth of the particles are
+// already dead and there's something for compaction to do.
+pub fn random_particle_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<Particle> {
+    (0..count)
+        .map(|_| {
+            let lifetime = rng.gen_range(1.0..5.0);
+            let age = rng.gen_range(0.0..(lifetime * 1.25));
+
+            Particle {
+                position: (rng.gen::<Vec3>() - 0.5) * 20.0,
+                velocity: (rng.gen::<Vec3>() - 0.5) * 10.0,
+                color: rng.gen::<Vec3>().extend(particle_alpha(age, lifetime)),
+                age,
+                lifetime,
+            }
+        })
+        .collect()
+}
+
+// The first `count` particles are alive. Color is split so that updating the
+// alpha doesn't touch the rest.
+#[derive(Clone)]
+pub struct ParticleSoa {
+    pub count: usize,
+    pub position: Vec3Soa,
+    pub velocity: Vec3Soa,
+    pub color: Vec3Soa,
+    pub alpha: CacheAlignedVec<f32>,
+    pub age: CacheAlignedVec<f32>,
+    pub lifetime: CacheAlignedVec<f32>,
+}
+
+impl ParticleSoa {
+    pub fn from_particles(particles: &[Particle]) -> Self {
+        let vec3s = |f: fn(&Particle) -> Vec3| {
+            Vec3Soa::from_vec3s(&particles.iter().map(f).collect::<Vec<_>>())
+        };
+
+        ParticleSoa {
+            count: particles.len(),
+            position: vec3s(|p| p.position),
+            velocity: vec3s(|p| p.velocity),
+            color: vec3s(|p| p.color.truncate()),
+            alpha: particles.iter().map(|p| p.color.w).collect(),
+            age: particles.iter().map(|p| p.age).collect(),
+            lifetime: particles.iter().map(|p| p.lifetime).collect(),
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Particle {
+        Particle {
+            position: self.position.get(i),
+            velocity: self.velocity.get(i),
+            color: self.color.get(i).extend(self.alpha[i]),
+            age: self.age[i],
+            lifetime: self.lifetime[i],
+        }
+    }
+}
+
+pub struct ParticleParams<'a, T: ?Sized> {
+    pub particles: &'a mut T,
+    pub settings: &'a ParticleSettings,
+}
+
+// Semi-implicit Euler, with drag as a per step scale of the velocity. All
+// versions use the same order of operations, so they give identical results.
+pub fn update_particle(p: &mut Particle, settings: &ParticleSettings) {
+    let drag = 1.0 - (settings.drag * settings.dt);
+
+    p.velocity = (p.velocity + (settings.acceleration * settings.dt)) * drag;
+    p.position += p.velocity * settings.dt;
+    p.age += settings.dt;
+    p.color.w = particle_alpha(p.age, p.lifetime);
+}
+
+#[inline(never)]
+pub fn particles_update_aos(params: &mut ParticleParams<[Particle]>) {
+    for p in params.particles.iter_mut() {
+        update_particle(p, params.settings);
+    }
+}
+
+#[inline(never)]
+pub fn particles_update_soa(params: &mut ParticleParams<ParticleSoa>) {
+    let s = params.settings;
+    let p = &mut params.particles;
+    let len = p.count;
+
+    let drag = 1.0 - (s.drag * s.dt);
+
+    // Reslice so the compiler can see every array has the same length.
+    let (px, py, pz) = (
+        &mut p.position.x[..len],
+        &mut p.position.y[..len],
+        &mut p.position.z[..len],
+    );
+    let (vx, vy, vz) = (
+        &mut p.velocity.x[..len],
+        &mut p.velocity.y[..len],
+        &mut p.velocity.z[..len],
+    );
+    let (alpha, age, lifetime) = (&mut p.alpha[..len], &mut p.age[..len], &p.lifetime[..len]);
+
+    for i in 0..len {
+        vx[i] = (vx[i] + (s.acceleration.x * s.dt)) * drag;
+        vy[i] = (vy[i] + (s.acceleration.y * s.dt)) * drag;
+        vz[i] = (vz[i] + (s.acceleration.z * s.dt)) * drag;
+
+        px[i] += vx[i] * s.dt;
+        py[i] += vy[i] * s.dt;
+        pz[i] += vz[i] * s.dt;
+
+        age[i] += s.dt;
+        alpha[i] = particle_alpha(age[i], lifetime[i]);
+    }
+}
+
+// Update four particles at a time with explicit vectors, rather than relying
+// on the compiler to vectorize `particles_update_soa`.
+#[inline(never)]
+pub fn particles_update_soa_x4(params: &mut ParticleParams<ParticleSoa>) {
+    const LANES: usize = 4;
+
+    let s = params.settings;
+    let p = &mut params.particles;
+    let len = p.count;
+    let chunk_len = len - (len % LANES);
+
+    let drag = Vec4::splat(1.0 - (s.drag * s.dt));
+    let dt = Vec4::splat(s.dt);
+    let acceleration_dt = (s.acceleration * s.dt).to_array().map(Vec4::splat);
+
+    let position = [
+        &mut p.position.x[..len],
+        &mut p.position.y[..len],
+        &mut p.position.z[..len],
+    ];
+    let velocity = [
+        &mut p.velocity.x[..len],
+        &mut p.velocity.y[..len],
+        &mut p.velocity.z[..len],
+    ];
+
+    for (axis, (position, velocity)) in position.into_iter().zip(velocity).enumerate() {
+        for i in (0..chunk_len).step_by(LANES) {
+            let v = (Vec4::from_slice(&velocity[i..]) + acceleration_dt[axis]) * drag;
+            let p = Vec4::from_slice(&position[i..]) + (v * dt);
+
+            v.write_to_slice(&mut velocity[i..]);
+            p.write_to_slice(&mut position[i..]);
+        }
+    }
+
+    let (alpha, age, lifetime) = (&mut p.alpha[..len], &mut p.age[..len], &p.lifetime[..len]);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        let a = Vec4::from_slice(&age[i..]) + dt;
+        let fade = (Vec4::ONE - (a / Vec4::from_slice(&lifetime[i..]))).max(Vec4::ZERO);
+
+        a.write_to_slice(&mut age[i..]);
+        fade.write_to_slice(&mut alpha[i..]);
+    }
+
+    for i in chunk_len..len {
+        let mut particle = p.get(i);
+
+        update_particle(&mut particle, s);
+
+        p.position.x[i] = particle.position.x;
+        p.position.y[i] = particle.position.y;
+        p.position.z[i] = particle.position.z;
+        p.velocity.x[i] = particle.velocity.x;
+        p.velocity.y[i] = particle.velocity.y;
+        p.velocity.z[i] = particle.velocity.z;
+        p.alpha[i] = particle.color.w;
+        p.age[i] = particle.age;
+    }
+}
+
+// Move the live particles to the front, keeping their order, and return how
+// many there are.
+#[inline(never)]
+pub fn particles_compact_aos(particles: &mut [Particle]) -> usize {
+    let mut count = 0;
+
+    for i in 0..particles.len() {
+        if particles[i].age < particles[i].lifetime {
+            particles[count] = particles[i];
+            count += 1;
+        }
+    }
+
+    count
+}
+
+#[inline(never)]
+pub fn particles_compact_soa(p: &mut ParticleSoa) {
+    let mut count = 0;
+
+    for i in 0..p.count {
+        if p.age[i] < p.lifetime[i] {
+            for v in [&mut p.position, &mut p.velocity, &mut p.color] {
+                v.x[count] = v.x[i];
+                v.y[count] = v.y[i];
+                v.z[count] = v.z[i];
+            }
+
+            for a in [&mut p.alpha, &mut p.age, &mut p.lifetime] {
+                a[count] = a[i];
+            }
+
+            count += 1;
+        }
+    }
+
+    p.count = count;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::test_rng;
+
+    const COUNT: usize = 1003;
+
+    #[test]
+    fn particles() {
+        let mut rng = test_rng();
+
+        let settings = ParticleSettings::default();
+
+        let mut aos = random_particle_array(&mut rng, COUNT);
+        let mut soa = ParticleSoa::from_particles(&aos);
+        let mut soa_x4 = soa.clone();
+
+        for _ in 0..10 {
+            particles_update_aos(&mut ParticleParams {
+                particles: aos.as_mut_slice(),
+                settings: &settings,
+            });
+
+            particles_update_soa(&mut ParticleParams {
+                particles: &mut soa,
+                settings: &settings,
+            });
+
+            particles_update_soa_x4(&mut ParticleParams {
+                particles: &mut soa_x4,
+                settings: &settings,
+            });
+        }
+
+        for (i, p) in aos.iter().enumerate() {
+            assert_eq!(soa.get(i), *p);
+            assert_eq!(soa_x4.get(i), *p);
+        }
+
+        // Compaction keeps the live particles in order.
+
+        let expected = aos
+            .iter()
+            .filter(|p| p.age < p.lifetime)
+            .copied()
+            .collect::<Vec<_>>();
+
+        assert!((expected.len() > (COUNT / 2)) && (expected.len() < COUNT));
+
+        let count = particles_compact_aos(&mut aos);
+
+        particles_compact_soa(&mut soa);
+
+        assert_eq!(&aos[..count], expected.as_slice());
+        assert_eq!(soa.count, count);
+
+        for (i, p) in expected.iter().enumerate() {
+            assert_eq!(soa.get(i), *p);
+        }
+    }
+}
@@ -0,0 +1,877 @@
+use crate::kernels::{
+    bvh::{Bvh, FlatBvh},
+    triangulate::triangle_contains,
+};
+use bevy_math::bounding::Aabb3d;
+use glam::{IVec2, Vec2};
+use rand::Rng;
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Moves are to any of the eight neighbors, but diagonals can't cut the corner
+// of a blocked cell. Costs are integers, with diagonals approximating root two.
+pub const STRAIGHT_COST: u32 = 10;
+pub const DIAGONAL_COST: u32 = 14;
+
+const DIRECTIONS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+];
+
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub open: Vec<bool>,
+}
+
+impl Grid {
+    // Cells outside the grid are blocked.
+    pub fn is_open(&self, p: IVec2) -> bool {
+        (p.x >= 0)
+            && (p.y >= 0)
+            && ((p.x as usize) < self.width)
+            && ((p.y as usize) < self.height)
+            && self.open[self.index(p)]
+    }
+
+    fn index(&self, p: IVec2) -> usize {
+        ((p.y as usize) * self.width) + (p.x as usize)
+    }
+
+    fn cell(&self, index: usize) -> IVec2 {
+        IVec2::new((index % self.width) as i32, (index / self.width) as i32)
+    }
+
+    // The move from `p` in direction `d` is allowed, including the corners.
+    fn can_move(&self, p: IVec2, d: IVec2) -> bool {
+        self.is_open(p + d)
+            && ((d.x == 0)
+                || (d.y == 0)
+                || (self.is_open(p + IVec2::new(d.x, 0)) && self.is_open(p + IVec2::new(0, d.y))))
+    }
+}
+
+// Block random rectangles until at least `coverage` of the grid is blocked.
+// Rectangles leave open areas between them, unlike blocking random cells.
+pub fn random_grid(rng: &mut impl Rng, width: usize, height: usize, coverage: f32) -> Grid {
+    let mut open = vec![true; width * height];
+    let mut blocked = 0;
+
+    while (blocked as f32) < ((width * height) as f32 * coverage) {
+        let (w, h) = (rng.gen_range(1..=16), rng.gen_range(1..=16));
+        let (x, y) = (rng.gen_range(0..width), rng.gen_range(0..height));
+
+        for y in y..(y + h).min(height) {
+            for x in x..(x + w).min(width) {
+                blocked += open[(y * width) + x] as usize;
+                open[(y * width) + x] = false;
+            }
+        }
+    }
+
+    Grid {
+        width,
+        height,
+        open,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathQuery {
+    pub start: IVec2,
+    pub goal: IVec2,
+}
+
+// Starts and goals are open, but there might not be a path between them.
+pub fn random_path_queries(rng: &mut impl Rng, grid: &Grid, count: usize) -> Vec<PathQuery> {
+    let mut random_open = || loop {
+        let p = IVec2::new(
+            rng.gen_range(0..grid.width as i32),
+            rng.gen_range(0..grid.height as i32),
+        );
+
+        if grid.is_open(p) {
+            break p;
+        }
+    };
+
+    (0..count)
+        .map(|_| PathQuery {
+            start: random_open(),
+            goal: random_open(),
+        })
+        .collect()
+}
+
+// The cost of the shortest path on an open grid, which never overestimates,
+// and is exact for the straight and diagonal lines that JPS jumps along.
+pub fn octile_distance(a: IVec2, b: IVec2) -> u32 {
+    let d = (a - b).abs();
+    let (min, max) = (d.x.min(d.y) as u32, d.x.max(d.y) as u32);
+
+    (STRAIGHT_COST * max) + ((DIAGONAL_COST - STRAIGHT_COST) * min)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A priority queue of cell indices, lowest cost first. Cells can be pushed more
+// than once, and the search skips stale entries when they're popped.
+pub trait OpenList {
+    fn new() -> Self;
+
+    fn clear(&mut self);
+
+    fn push(&mut self, cost: u32, index: u32);
+
+    fn pop(&mut self) -> Option<(u32, u32)>;
+}
+
+pub struct BinaryHeapOpenList(BinaryHeap<Reverse<(u32, u32)>>);
+
+impl OpenList for BinaryHeapOpenList {
+    fn new() -> Self {
+        BinaryHeapOpenList(BinaryHeap::new())
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn push(&mut self, cost: u32, index: u32) {
+        self.0.push(Reverse((cost, index)));
+    }
+
+    fn pop(&mut self) -> Option<(u32, u32)> {
+        self.0.pop().map(|Reverse(e)| e)
+    }
+}
+
+// One bucket per cost, scanned upwards from the last pop. Relies on the costs
+// never going down, which holds for A* with a consistent heuristic. Buckets
+// are kept between searches so their allocations are reused.
+pub struct BucketOpenList {
+    buckets: Vec<Vec<u32>>,
+    cursor: usize,
+    len: usize,
+}
+
+impl OpenList for BucketOpenList {
+    fn new() -> Self {
+        BucketOpenList {
+            buckets: Vec::new(),
+            cursor: 0,
+            len: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+
+        self.cursor = 0;
+        self.len = 0;
+    }
+
+    fn push(&mut self, cost: u32, index: u32) {
+        let cost = cost as usize;
+
+        debug_assert!(cost >= self.cursor);
+
+        if cost >= self.buckets.len() {
+            self.buckets.resize_with(cost + 1, Vec::new);
+        }
+
+        self.buckets[cost].push(index);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<(u32, u32)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        while self.buckets[self.cursor].is_empty() {
+            self.cursor += 1;
+        }
+
+        self.len -= 1;
+
+        self.buckets[self.cursor]
+            .pop()
+            .map(|index| (self.cursor as u32, index))
+    }
+}
+
+// Per search state, reused across searches. Rather than clearing the costs for
+// every search, each cell is stamped with the search that last wrote it.
+pub struct PathScratch<Q> {
+    open: Q,
+    cost: Vec<u32>,
+    parent: Vec<u32>,
+    stamp: Vec<u32>,
+    search: u32,
+}
+
+impl<Q: OpenList> PathScratch<Q> {
+    pub fn new(grid: &Grid) -> Self {
+        let len = grid.width * grid.height;
+
+        PathScratch {
+            open: Q::new(),
+            cost: vec![u32::MAX; len],
+            parent: vec![u32::MAX; len],
+            stamp: vec![0; len],
+            search: 0,
+        }
+    }
+
+    fn begin(&mut self) {
+        self.open.clear();
+        self.search += 1;
+    }
+
+    fn cost(&self, index: usize) -> u32 {
+        if self.stamp[index] == self.search {
+            self.cost[index]
+        } else {
+            u32::MAX
+        }
+    }
+
+    // Record the cost and parent if it's better than the existing cost, and
+    // queue the cell.
+    fn relax(&mut self, index: usize, parent: usize, cost: u32, heuristic: u32) {
+        if cost < self.cost(index) {
+            self.stamp[index] = self.search;
+            self.cost[index] = cost;
+            self.parent[index] = parent as u32;
+            self.open.push(cost + heuristic, index as u32);
+        }
+    }
+}
+
+// The path costs are written to `dst_array`, or `None` if there's no path.
+pub struct PathParams<'a> {
+    pub dst_array: &'a mut [Option<u32>],
+    pub queries: &'a [PathQuery],
+    pub grid: &'a Grid,
+}
+
+// Search from the start until the goal is popped, or the open list runs out.
+// Counts the cells that are expanded, not including stale entries.
+fn path_search<Q: OpenList>(
+    grid: &Grid,
+    scratch: &mut PathScratch<Q>,
+    query: &PathQuery,
+    expansions: &mut usize,
+    mut successors: impl FnMut(&Grid, &mut PathScratch<Q>, IVec2, usize, u32),
+) -> Option<u32> {
+    scratch.begin();
+
+    let start = grid.index(query.start);
+
+    scratch.relax(start, start, 0, octile_distance(query.start, query.goal));
+
+    while let Some((f, index)) = scratch.open.pop() {
+        let index = index as usize;
+        let p = grid.cell(index);
+        let g = scratch.cost(index);
+
+        if f != (g + octile_distance(p, query.goal)) {
+            continue;
+        }
+
+        if p == query.goal {
+            return Some(g);
+        }
+
+        *expansions += 1;
+
+        successors(grid, scratch, p, index, g);
+    }
+
+    None
+}
+
+// Plain A*, checking all eight neighbors of each cell. Returns the number of
+// expansions.
+#[inline(never)]
+pub fn astar<Q: OpenList>(params: &mut PathParams, scratch: &mut PathScratch<Q>) -> usize {
+    let grid = params.grid;
+    let mut expansions = 0;
+
+    for (dst, query) in params.dst_array.iter_mut().zip(params.queries) {
+        *dst = path_search(
+            grid,
+            scratch,
+            query,
+            &mut expansions,
+            |grid, scratch, p, index, g| {
+                for d in DIRECTIONS {
+                    if grid.can_move(p, d) {
+                        let n = p + d;
+                        let cost = if (d.x != 0) && (d.y != 0) {
+                            DIAGONAL_COST
+                        } else {
+                            STRAIGHT_COST
+                        };
+
+                        scratch.relax(
+                            grid.index(n),
+                            index,
+                            g + cost,
+                            octile_distance(n, query.goal),
+                        );
+                    }
+                }
+            },
+        );
+    }
+
+    expansions
+}
+
+// Harabor and Grastien, "Online Graph Pruning for Pathfinding on Grid Maps"
+// (2011), in the variant that doesn't cut corners. Instead of queueing every
+// neighbor, the search jumps along straight and diagonal lines until it finds
+// the goal, or a cell with a neighbor that can't be reached more cheaply any
+// other way. Fewer cells are expanded, but many more are scanned.
+fn jump(grid: &Grid, mut p: IVec2, d: IVec2, goal: IVec2) -> Option<IVec2> {
+    loop {
+        p += d;
+
+        if !grid.is_open(p) {
+            return None;
+        }
+
+        if p == goal {
+            return Some(p);
+        }
+
+        let open = |x, y| grid.is_open(p + IVec2::new(x, y));
+
+        if (d.x != 0) && (d.y != 0) {
+            if jump(grid, p, IVec2::new(d.x, 0), goal).is_some()
+                || jump(grid, p, IVec2::new(0, d.y), goal).is_some()
+            {
+                return Some(p);
+            }
+
+            if !open(d.x, 0) || !open(0, d.y) {
+                return None;
+            }
+        } else if d.x != 0 {
+            if (open(0, -1) && !open(-d.x, -1)) || (open(0, 1) && !open(-d.x, 1)) {
+                return Some(p);
+            }
+        } else if (open(-1, 0) && !open(-1, -d.y)) || (open(1, 0) && !open(1, -d.y)) {
+            return Some(p);
+        }
+    }
+}
+
+// The directions worth jumping in from `p`, given the direction it was
+// reached from. `None` for the start, which tries everything.
+fn jump_directions(grid: &Grid, p: IVec2, from: Option<IVec2>, mut f: impl FnMut(IVec2)) {
+    let Some(d) = from else {
+        DIRECTIONS
+            .into_iter()
+            .filter(|&d| grid.can_move(p, d))
+            .for_each(f);
+
+        return;
+    };
+
+    let open = |x, y| grid.is_open(p + IVec2::new(x, y));
+
+    if (d.x != 0) && (d.y != 0) {
+        let (horizontal, vertical) = (open(d.x, 0), open(0, d.y));
+
+        if vertical {
+            f(IVec2::new(0, d.y));
+        }
+
+        if horizontal {
+            f(IVec2::new(d.x, 0));
+        }
+
+        if horizontal && vertical {
+            f(d);
+        }
+    } else {
+        // Swap to the perpendicular axis, so horizontal and vertical moves
+        // share the same logic.
+        let side = IVec2::new(d.y.abs(), d.x.abs());
+        let (next, left, right) = (
+            open(d.x, d.y),
+            grid.is_open(p + side),
+            grid.is_open(p - side),
+        );
+
+        if next {
+            f(d);
+
+            if left {
+                f(d + side);
+            }
+
+            if right {
+                f(d - side);
+            }
+        }
+
+        if left {
+            f(side);
+        }
+
+        if right {
+            f(-side);
+        }
+    }
+}
+
+// Jump point search over the same grid and costs as `astar`, giving the same
+// path costs. Returns the number of expansions, which only counts jump points.
+#[inline(never)]
+pub fn jump_point_search<Q: OpenList>(
+    params: &mut PathParams,
+    scratch: &mut PathScratch<Q>,
+) -> usize {
+    let grid = params.grid;
+    let mut expansions = 0;
+
+    for (dst, query) in params.dst_array.iter_mut().zip(params.queries) {
+        *dst = path_search(
+            grid,
+            scratch,
+            query,
+            &mut expansions,
+            |grid, scratch, p, index, g| {
+                let parent = grid.cell(scratch.parent[index] as usize);
+                let from = (parent != p).then(|| (p - parent).signum());
+
+                jump_directions(grid, p, from, |d| {
+                    if let Some(n) = jump(grid, p, d, query.goal) {
+                        scratch.relax(
+                            grid.index(n),
+                            index,
+                            g + octile_distance(p, n),
+                            octile_distance(n, query.goal),
+                        );
+                    }
+                });
+            },
+        );
+    }
+
+    expansions
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Counter-clockwise triangles that share vertices.
+pub struct NavMesh {
+    pub vertices: Vec<Vec2>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl NavMesh {
+    pub fn triangle(&self, index: usize) -> [Vec2; 3] {
+        self.triangles[index].map(|v| self.vertices[v as usize])
+    }
+
+    pub fn contains(&self, index: usize, p: Vec2) -> bool {
+        let [a, b, c] = self.triangle(index);
+
+        triangle_contains(a, b, c, p)
+    }
+
+    // Flat boxes on the z plane, so they can go in a `Bvh`.
+    fn triangle_aabb(&self, index: usize) -> Aabb3d {
+        let [a, b, c] = self.triangle(index);
+
+        Aabb3d {
+            min: a.min(b).min(c).extend(0.0).into(),
+            max: a.max(b).max(c).extend(0.0).into(),
+        }
+    }
+
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        self.vertices
+            .iter()
+            .fold((Vec2::MAX, Vec2::MIN), |(min, max), &v| {
+                (min.min(v), max.max(v))
+            })
+    }
+}
+
+// A grid of `width` by `height` quads, each split into two triangles along a
+// random diagonal, with `hole_fraction` of the quads left out as obstacles.
+// Rows and columns have random sizes, so the triangles vary in size and
+// shape, and don't line up with a uniform grid.
+pub fn random_navmesh(
+    rng: &mut impl Rng,
+    width: usize,
+    height: usize,
+    hole_fraction: f32,
+) -> NavMesh {
+    let mut lines = |count: usize| {
+        (0..=count)
+            .scan(0.0, |x, _| {
+                let line = *x;
+
+                *x += rng.gen_range(0.25..1.75);
+
+                Some(line)
+            })
+            .collect::<Vec<f32>>()
+    };
+
+    let (xs, ys) = (lines(width), lines(height));
+
+    let vertices = ys
+        .iter()
+        .flat_map(|&y| xs.iter().map(move |&x| Vec2::new(x, y)))
+        .collect();
+
+    let mut triangles = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if rng.gen::<f32>() < hole_fraction {
+                continue;
+            }
+
+            let index = |x: usize, y: usize| ((y * (width + 1)) + x) as u32;
+
+            let [a, b, c, d] = [
+                index(x, y),
+                index(x + 1, y),
+                index(x + 1, y + 1),
+                index(x, y + 1),
+            ];
+
+            if rng.gen() {
+                triangles.extend([[a, b, c], [a, c, d]]);
+            } else {
+                triangles.extend([[a, b, d], [b, c, d]]);
+            }
+        }
+    }
+
+    NavMesh {
+        vertices,
+        triangles,
+    }
+}
+
+// Points anywhere in the bounds, so some will be in holes.
+pub fn random_navmesh_points(rng: &mut impl Rng, mesh: &NavMesh, count: usize) -> Vec<Vec2> {
+    let (min, max) = mesh.bounds();
+
+    (0..count)
+        .map(|_| min + (rng.gen::<Vec2>() * (max - min)))
+        .collect()
+}
+
+// Finds the triangle that contains a point.
+pub trait PointLocator {
+    fn build(mesh: &NavMesh) -> Self;
+
+    // The index of a triangle containing `p`, or `None` if it's not on the
+    // mesh. A point on a shared edge could be in either triangle.
+    fn locate(&self, mesh: &NavMesh, p: Vec2) -> Option<u32>;
+}
+
+// Test every triangle.
+pub struct BruteForceLocator;
+
+impl PointLocator for BruteForceLocator {
+    fn build(_: &NavMesh) -> Self {
+        BruteForceLocator
+    }
+
+    fn locate(&self, mesh: &NavMesh, p: Vec2) -> Option<u32> {
+        (0..mesh.triangles.len())
+            .find(|&t| mesh.contains(t, p))
+            .map(|t| t as u32)
+    }
+}
+
+// A `FlatBvh` over the triangle bounds. The triangles aren't reordered, so the
+// leaves go through a list of indices.
+pub struct BvhLocator {
+    bvh: FlatBvh,
+    triangles: Vec<u32>,
+}
+
+impl PointLocator for BvhLocator {
+    fn build(mesh: &NavMesh) -> Self {
+        let mut triangles = (0..mesh.triangles.len() as u32).collect::<Vec<_>>();
+
+        let bvh = FlatBvh::build_by(&mut triangles, |&t| mesh.triangle_aabb(t as usize));
+
+        BvhLocator { bvh, triangles }
+    }
+
+    fn locate(&self, mesh: &NavMesh, p: Vec2) -> Option<u32> {
+        let inside = |aabb: &Aabb3d| {
+            (p.x >= aabb.min.x) && (p.x <= aabb.max.x) && (p.y >= aabb.min.y) && (p.y <= aabb.max.y)
+        };
+
+        let mut stack = [self.bvh.root(); 64];
+        let mut stack_len = 1;
+
+        while stack_len > 0 {
+            stack_len -= 1;
+
+            let node = stack[stack_len];
+
+            if !inside(FlatBvh::node_aabb(node)) {
+                continue;
+            }
+
+            if let Some([l, r]) = self.bvh.node_children(node) {
+                stack[stack_len] = l;
+                stack[stack_len + 1] = r;
+                stack_len += 2;
+            } else {
+                for &t in &self.triangles[FlatBvh::node_range(node)] {
+                    if mesh.contains(t as usize, p) {
+                        return Some(t);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+// A uniform grid over the mesh bounds, with cells about the size of an average
+// triangle. Each cell lists the triangles whose bounds overlap it, stored as
+// one array with an offset per cell.
+pub struct GridLocator {
+    origin: Vec2,
+    cell_size: f32,
+    width: usize,
+    height: usize,
+    cell_start: Vec<u32>,
+    triangles: Vec<u32>,
+}
+
+impl GridLocator {
+    fn cell(&self, p: Vec2) -> IVec2 {
+        ((p - self.origin) / self.cell_size).floor().as_ivec2()
+    }
+
+    // The indices of the cells that the box overlaps, which must be inside the
+    // grid.
+    fn overlapping_cells(&self, aabb: &Aabb3d) -> impl Iterator<Item = usize> + '_ {
+        let (lo, hi) = (
+            self.cell(aabb.min.truncate()),
+            self.cell(aabb.max.truncate()),
+        );
+
+        (lo.y..=hi.y).flat_map(move |y| {
+            (lo.x..=hi.x).map(move |x| ((y as usize) * self.width) + (x as usize))
+        })
+    }
+}
+
+impl PointLocator for GridLocator {
+    fn build(mesh: &NavMesh) -> Self {
+        let (min, max) = mesh.bounds();
+        let size = max - min;
+
+        let cell_size = ((size.x * size.y) / (mesh.triangles.len() as f32)).sqrt();
+
+        let width = ((size.x / cell_size) as usize) + 1;
+        let height = ((size.y / cell_size) as usize) + 1;
+
+        let mut grid = GridLocator {
+            origin: min,
+            cell_size,
+            width,
+            height,
+            cell_start: Vec::new(),
+            triangles: Vec::new(),
+        };
+
+        // Sum the number of triangles up to and including each cell, then fill
+        // in the triangles while stepping each sum back to the cell's start.
+
+        let mut ends = vec![0u32; (width * height) + 1];
+
+        for t in 0..mesh.triangles.len() {
+            for cell in grid.overlapping_cells(&mesh.triangle_aabb(t)) {
+                ends[cell] += 1;
+            }
+        }
+
+        for i in 1..ends.len() {
+            ends[i] += ends[i - 1];
+        }
+
+        let mut triangles = vec![0; ends[width * height] as usize];
+
+        for t in 0..mesh.triangles.len() {
+            for cell in grid.overlapping_cells(&mesh.triangle_aabb(t)) {
+                ends[cell] -= 1;
+                triangles[ends[cell] as usize] = t as u32;
+            }
+        }
+
+        grid.cell_start = ends;
+        grid.triangles = triangles;
+
+        grid
+    }
+
+    fn locate(&self, mesh: &NavMesh, p: Vec2) -> Option<u32> {
+        let cell = self.cell(p);
+
+        if (cell.x < 0)
+            || (cell.y < 0)
+            || (cell.x as usize >= self.width)
+            || (cell.y as usize >= self.height)
+        {
+            return None;
+        }
+
+        let cell = ((cell.y as usize) * self.width) + (cell.x as usize);
+        let range = (self.cell_start[cell] as usize)..(self.cell_start[cell + 1] as usize);
+
+        self.triangles[range]
+            .iter()
+            .copied()
+            .find(|&t| mesh.contains(t as usize, p))
+    }
+}
+
+pub struct NavMeshLocateParams<'a, L> {
+    pub dst_array: &'a mut [Option<u32>],
+    pub point_array: &'a [Vec2],
+    pub mesh: &'a NavMesh,
+    pub locator: &'a L,
+}
+
+#[inline(never)]
+pub fn navmesh_locate<L: PointLocator>(params: &mut NavMeshLocateParams<L>) {
+    for (dst, &p) in params.dst_array.iter_mut().zip(params.point_array) {
+        *dst = params.locator.locate(params.mesh, p);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{run_into, test_rng};
+
+    const COUNT: usize = 100;
+
+    #[test]
+    fn pathfinding() {
+        let mut rng = test_rng();
+
+        for coverage in [0.0, 0.2, 0.4] {
+            let grid = random_grid(&mut rng, 64, 48, coverage);
+            let queries = random_path_queries(&mut rng, &grid, COUNT);
+
+            let run = |f: fn(&mut PathParams, &mut PathScratch<BinaryHeapOpenList>) -> usize| {
+                let mut dst_array = vec![None; COUNT];
+
+                let expansions = f(
+                    &mut PathParams {
+                        dst_array: &mut dst_array,
+                        queries: &queries,
+                        grid: &grid,
+                    },
+                    &mut PathScratch::new(&grid),
+                );
+
+                (dst_array, expansions)
+            };
+
+            let (expected, astar_expansions) = run(astar);
+
+            let mut bucket_array = vec![None; COUNT];
+
+            astar(
+                &mut PathParams {
+                    dst_array: &mut bucket_array,
+                    queries: &queries,
+                    grid: &grid,
+                },
+                &mut PathScratch::<BucketOpenList>::new(&grid),
+            );
+
+            assert_eq!(bucket_array, expected);
+
+            let (jps, jps_expansions) = run(jump_point_search);
+
+            assert_eq!(jps, expected);
+            assert!(jps_expansions < astar_expansions);
+
+            // Paths can't be shorter than the heuristic, and on an empty grid
+            // they're exactly the heuristic.
+
+            for (cost, query) in expected.iter().zip(&queries) {
+                let heuristic = octile_distance(query.start, query.goal);
+
+                if coverage == 0.0 {
+                    assert_eq!(*cost, Some(heuristic));
+                } else if let Some(cost) = cost {
+                    assert!(*cost >= heuristic);
+                }
+            }
+
+            assert!(expected.iter().filter(|c| c.is_some()).count() > (COUNT / 2));
+        }
+    }
+
+    #[test]
+    fn navmesh() {
+        let mut rng = test_rng();
+
+        let mesh = random_navmesh(&mut rng, 20, 30, 0.2);
+        let point_array = random_navmesh_points(&mut rng, &mesh, COUNT * 10);
+
+        fn locate_all<L: PointLocator>(mesh: &NavMesh, point_array: &[Vec2]) -> Vec<Option<u32>> {
+            run_into(vec![None; point_array.len()], |dst_array| {
+                navmesh_locate(&mut NavMeshLocateParams {
+                    dst_array,
+                    point_array,
+                    mesh,
+                    locator: &L::build(mesh),
+                })
+            })
+        }
+
+        let expected = locate_all::<BruteForceLocator>(&mesh, &point_array);
+
+        // Random points are very unlikely to land exactly on an edge, so every
+        // version should find the same triangles.
+        assert_eq!(locate_all::<BvhLocator>(&mesh, &point_array), expected);
+        assert_eq!(locate_all::<GridLocator>(&mesh, &point_array), expected);
+
+        // Roughly the hole fraction should miss.
+        let misses = expected.iter().filter(|t| t.is_none()).count();
+
+        assert!((misses > (point_array.len() / 10)) && (misses < (point_array.len() * 3 / 10)));
+
+        for (t, &p) in expected.iter().zip(&point_array) {
+            if let Some(t) = t {
+                assert!(mesh.contains(*t as usize, p));
+            }
+        }
+    }
+}
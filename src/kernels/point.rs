@@ -0,0 +1,155 @@
+use bevy_transform::components::Transform;
+use glam::{Affine3A, Mat3, Mat4, Quat, Vec3};
+
+// Transform an array of points by a single transform.
+pub struct TransformPointParams<'a> {
+    pub dst_array: &'a mut [Vec3],
+    pub src_array: &'a [Vec3],
+    pub transform: Transform,
+}
+
+#[inline(never)]
+pub fn transform_point_scalar(params: &mut TransformPointParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.transform.transform_point(params.src_array[i]);
+    }
+}
+
+// The matrix variants convert the transform once per call, which is
+// negligible next to the loop.
+
+#[inline(never)]
+pub fn transform_point_affine3a(params: &mut TransformPointParams) {
+    let t = params.transform;
+    let affine = Affine3A::from_scale_rotation_translation(t.scale, t.rotation, t.translation);
+
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = affine.transform_point3(params.src_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn transform_point_mat4(params: &mut TransformPointParams) {
+    let t = params.transform;
+    let matrix = Mat4::from_scale_rotation_translation(t.scale, t.rotation, t.translation);
+
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = (matrix * params.src_array[i].extend(1.0)).truncate();
+    }
+}
+
+// Rotate consecutive runs of `points_per_rotation` points by each rotation.
+pub struct RotatePointParams<'a> {
+    pub dst_array: &'a mut [Vec3],
+    pub src_array: &'a [Vec3],
+    pub rotation_array: &'a [Quat],
+    pub points_per_rotation: usize,
+}
+
+// Convert each rotation with `prepare` once, then apply it to its points.
+pub fn rotate_point_inner<T, R, F>(params: &mut RotatePointParams, prepare: R, f: F)
+where
+    R: Fn(Quat) -> T,
+    F: Fn(&T, Vec3) -> Vec3,
+{
+    let n = params.points_per_rotation;
+
+    for ((dst, src), &rotation) in params
+        .dst_array
+        .chunks_mut(n)
+        .zip(params.src_array.chunks(n))
+        .zip(params.rotation_array.iter())
+    {
+        let prepared = prepare(rotation);
+
+        for (dst, &src) in dst.iter_mut().zip(src.iter()) {
+            *dst = f(&prepared, src);
+        }
+    }
+}
+
+#[inline(never)]
+pub fn rotate_point_quat(params: &mut RotatePointParams) {
+    rotate_point_inner(params, |q| q, |q, p| *q * p);
+}
+
+#[inline(never)]
+pub fn rotate_point_mat3(params: &mut RotatePointParams) {
+    rotate_point_inner(params, Mat3::from_quat, |m, p| *m * p);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng, COUNT, TOLERANCE},
+        util::*,
+    };
+
+    #[test]
+    fn transform_point() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<Vec3>(&mut rng, COUNT);
+
+        // Non-uniform scale and a translation, so every part of the transform
+        // is tested.
+        let transform = Transform {
+            translation: Vec3::new(1.0, -2.0, 3.0),
+            rotation: random_quat(&mut rng),
+            scale: Vec3::new(0.5, 2.0, 3.0),
+        };
+
+        let [expected, affine3a, mat4] = [
+            transform_point_scalar as fn(&mut TransformPointParams),
+            transform_point_affine3a,
+            transform_point_mat4,
+        ]
+        .map(|f| {
+            run_into(vec![Vec3::ZERO; COUNT], |dst_array| {
+                f(&mut TransformPointParams {
+                    dst_array,
+                    src_array: &src_array,
+                    transform,
+                })
+            })
+        });
+
+        for i in 0..COUNT {
+            assert!(affine3a[i].abs_diff_eq(expected[i], TOLERANCE));
+            assert!(mat4[i].abs_diff_eq(expected[i], TOLERANCE));
+        }
+    }
+
+    #[test]
+    fn rotate_point() {
+        let mut rng = test_rng();
+
+        const POINTS_PER_ROTATION: usize = 10;
+
+        let src_array = random_array::<Vec3>(&mut rng, COUNT);
+        let rotation_array = random_quat_array(&mut rng, COUNT / POINTS_PER_ROTATION);
+
+        let [quat, mat3] = [
+            rotate_point_quat as fn(&mut RotatePointParams),
+            rotate_point_mat3,
+        ]
+        .map(|f| {
+            run_into(vec![Vec3::ZERO; COUNT], |dst_array| {
+                f(&mut RotatePointParams {
+                    dst_array,
+                    src_array: &src_array,
+                    rotation_array: &rotation_array,
+                    points_per_rotation: POINTS_PER_ROTATION,
+                })
+            })
+        });
+
+        for i in 0..COUNT {
+            let expected = rotation_array[i / POINTS_PER_ROTATION] * src_array[i];
+
+            assert!(quat[i].abs_diff_eq(expected, TOLERANCE));
+            assert!(mat3[i].abs_diff_eq(expected, TOLERANCE));
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use glam::{DQuat, Quat, Vec2, Vec2Swizzles, Vec3};
+use half::{f16, slice::HalfFloatSliceExt};
+use std::f32::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+pub struct ConvertParams<'a, S, D> {
+    pub dst_array: &'a mut [D],
+    pub src_array: &'a [S],
+}
+
+pub fn convert_inner<S: Copy, D, F>(params: &mut ConvertParams<S, D>, f: F)
+where
+    F: Fn(S) -> D,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(params.src_array[i]);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Smallest three encoding - drop the largest component and store the other
+// three in 10 bits each, plus 2 bits for the index of the dropped component.
+// The dropped component is made positive by negating the quat, so it can be
+// recovered from the unit length constraint. The stored components are in the
+// range [-1/sqrt(2), 1/sqrt(2)].
+
+const SMALLEST_THREE_BITS: u32 = 10;
+const SMALLEST_THREE_MAX: u32 = (1 << SMALLEST_THREE_BITS) - 1;
+
+pub fn smallest_three_encode_single(q: Quat) -> u32 {
+    let a = q.to_array();
+
+    let mut largest = 0;
+
+    for i in 1..4 {
+        if a[i].abs() > a[largest].abs() {
+            largest = i;
+        }
+    }
+
+    let sign = if a[largest] < 0.0 { -1.0 } else { 1.0 };
+    let scale = sign * SQRT_2 * 0.5 * (SMALLEST_THREE_MAX as f32);
+    let bias = 0.5 * (SMALLEST_THREE_MAX as f32);
+
+    let mut bits = largest as u32;
+
+    for (i, &c) in a.iter().enumerate() {
+        if i != largest {
+            let n = (c * scale + bias)
+                .round()
+                .clamp(0.0, SMALLEST_THREE_MAX as f32);
+
+            bits = (bits << SMALLEST_THREE_BITS) | (n as u32);
+        }
+    }
+
+    bits
+}
+
+pub fn smallest_three_decode_single(bits: u32) -> Quat {
+    let largest = (bits >> (3 * SMALLEST_THREE_BITS)) as usize;
+    let scale = FRAC_1_SQRT_2 * 2.0 / (SMALLEST_THREE_MAX as f32);
+
+    let mut a = [0.0f32; 4];
+    let mut shift = 3 * SMALLEST_THREE_BITS;
+
+    for (i, c) in a.iter_mut().enumerate() {
+        if i != largest {
+            shift -= SMALLEST_THREE_BITS;
+
+            let n = (bits >> shift) & SMALLEST_THREE_MAX;
+
+            *c = (n as f32) * scale - FRAC_1_SQRT_2;
+        }
+    }
+
+    let length_squared = a.iter().map(|c| c * c).sum::<f32>();
+
+    a[largest] = (1.0 - length_squared).max(0.0).sqrt();
+
+    Quat::from_array(a)
+}
+
+#[inline(never)]
+pub fn quat_encode_smallest_three(params: &mut ConvertParams<Quat, u32>) {
+    convert_inner(params, smallest_three_encode_single);
+}
+
+#[inline(never)]
+pub fn quat_decode_smallest_three(params: &mut ConvertParams<u32, Quat>) {
+    convert_inner(params, smallest_three_decode_single);
+}
+
+// Half precision quats are twice the size of smallest three, but the encoding
+// is just a conversion per component.
+
+pub type HalfQuat = [f16; 4];
+
+#[inline(never)]
+pub fn quat_encode_half(params: &mut ConvertParams<Quat, HalfQuat>) {
+    convert_inner(params, |q| q.to_array().map(f16::from_f32));
+}
+
+#[inline(never)]
+pub fn quat_decode_half(params: &mut ConvertParams<HalfQuat, Quat>) {
+    convert_inner(params, |h| Quat::from_array(h.map(f16::to_f32)));
+}
+
+// Same as `quat_decode_half`, but renormalizes the result.
+#[inline(never)]
+pub fn quat_decode_half_normalize(params: &mut ConvertParams<HalfQuat, Quat>) {
+    convert_inner(params, |h| Quat::from_array(h.map(f16::to_f32)).normalize());
+}
+
+// Return the maximum angle in radians between the decoded and original
+// rotations. The angle is calculated in `f64`, since `f32` loses precision for
+// small angles.
+pub fn quat_max_error(dst_array: &[Quat], src_array: &[Quat]) -> f32 {
+    dst_array
+        .iter()
+        .zip(src_array.iter())
+        .map(|(&l, &r)| {
+            let d = l.as_dquat().conjugate() * r.as_dquat();
+            let d = DQuat::from_xyzw(d.x, d.y, d.z, d.w.abs());
+
+            (2.0 * d.xyz().length().atan2(d.w)) as f32
+        })
+        .fold(0.0, f32::max)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Octahedral encoding of unit vectors - project onto the octahedron, unfold the
+// lower half over the upper half, and quantize the resulting square. The 16 bit
+// variant uses 8 bits per axis, and the 32 bit variant uses 16 bits per axis.
+
+pub fn octahedral_encode(v: Vec3) -> Vec2 {
+    let p = v.truncate() * (v.x.abs() + v.y.abs() + v.z.abs()).recip();
+
+    if v.z >= 0.0 {
+        p
+    } else {
+        (1.0 - p.yx().abs()) * p.signum()
+    }
+}
+
+pub fn octahedral_decode(p: Vec2) -> Vec3 {
+    let z = 1.0 - p.x.abs() - p.y.abs();
+    let t = (-z).max(0.0);
+
+    let xy = p - (Vec2::splat(t) * p.signum());
+
+    xy.extend(z).normalize()
+}
+
+pub fn octahedral_quantize<const BITS: u32>(v: Vec3) -> u32 {
+    let max = ((1u32 << BITS) - 1) as f32;
+    let p = (octahedral_encode(v) * 0.5 + 0.5) * max;
+    let n = p.round().clamp(Vec2::ZERO, Vec2::splat(max));
+
+    (n.x as u32) | ((n.y as u32) << BITS)
+}
+
+pub fn octahedral_dequantize<const BITS: u32>(bits: u32) -> Vec3 {
+    let mask = (1u32 << BITS) - 1;
+    let n = Vec2::new((bits & mask) as f32, ((bits >> BITS) & mask) as f32);
+
+    octahedral_decode(n * (2.0 / mask as f32) - 1.0)
+}
+
+#[inline(never)]
+pub fn normal_encode_oct16(params: &mut ConvertParams<Vec3, u16>) {
+    convert_inner(params, |v| octahedral_quantize::<8>(v) as u16);
+}
+
+#[inline(never)]
+pub fn normal_decode_oct16(params: &mut ConvertParams<u16, Vec3>) {
+    convert_inner(params, |b| octahedral_dequantize::<8>(b as u32));
+}
+
+#[inline(never)]
+pub fn normal_encode_oct32(params: &mut ConvertParams<Vec3, u32>) {
+    convert_inner(params, octahedral_quantize::<16>);
+}
+
+#[inline(never)]
+pub fn normal_decode_oct32(params: &mut ConvertParams<u32, Vec3>) {
+    convert_inner(params, octahedral_dequantize::<16>);
+}
+
+// Copy the normals without encoding them, as a baseline for the bandwidth.
+#[inline(never)]
+pub fn normal_copy(params: &mut ConvertParams<Vec3, Vec3>) {
+    convert_inner(params, |v| v);
+}
+
+// Return the maximum angle in radians between the decoded and original
+// normals.
+pub fn normal_max_error(dst_array: &[Vec3], src_array: &[Vec3]) -> f32 {
+    dst_array
+        .iter()
+        .zip(src_array.iter())
+        .map(|(&l, &r)| {
+            let (l, r) = (l.as_dvec3(), r.as_dvec3());
+
+            l.cross(r).length().atan2(l.dot(r)) as f32
+        })
+        .fold(0.0, f32::max)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Half float conversions. The "software" variants use `half`'s const
+// functions, which never use intrinsics. The plain variants convert one value
+// at a time and check for F16C on each call, while the slice variants check
+// once and convert several values at a time.
+
+#[inline(never)]
+pub fn f16_from_f32_software(params: &mut ConvertParams<f32, f16>) {
+    convert_inner(params, f16::from_f32_const);
+}
+
+#[inline(never)]
+pub fn f16_from_f32(params: &mut ConvertParams<f32, f16>) {
+    convert_inner(params, f16::from_f32);
+}
+
+#[inline(never)]
+pub fn f16_from_f32_slice(params: &mut ConvertParams<f32, f16>) {
+    params.dst_array.convert_from_f32_slice(params.src_array);
+}
+
+#[inline(never)]
+pub fn f32_from_f16_software(params: &mut ConvertParams<f16, f32>) {
+    convert_inner(params, f16::to_f32_const);
+}
+
+#[inline(never)]
+pub fn f32_from_f16(params: &mut ConvertParams<f16, f32>) {
+    convert_inner(params, f16::to_f32);
+}
+
+#[inline(never)]
+pub fn f32_from_f16_slice(params: &mut ConvertParams<f16, f32>) {
+    params.src_array.convert_to_f32_slice(params.dst_array);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{quat_near, run_into, test_rng, COUNT},
+        util::*,
+    };
+
+    fn round_trip<T: Copy, E: Copy + Default>(
+        src_array: &[T],
+        encode: fn(&mut ConvertParams<T, E>),
+        decode: fn(&mut ConvertParams<E, T>),
+    ) -> Vec<T> {
+        let mut encoded = vec![E::default(); src_array.len()];
+        let mut decoded = src_array.to_vec();
+
+        encode(&mut ConvertParams {
+            dst_array: &mut encoded,
+            src_array,
+        });
+
+        decode(&mut ConvertParams {
+            dst_array: &mut decoded,
+            src_array: &encoded,
+        });
+
+        decoded
+    }
+
+    #[test]
+    fn quats() {
+        let mut rng = test_rng();
+
+        let src_array = random_quat_array(&mut rng, COUNT);
+
+        let smallest_three = round_trip(
+            &src_array,
+            quat_encode_smallest_three,
+            quat_decode_smallest_three,
+        );
+        let half = round_trip(&src_array, quat_encode_half, quat_decode_half);
+        let half_normalize = round_trip(&src_array, quat_encode_half, quat_decode_half_normalize);
+
+        // 10 bits over a range of sqrt(2) gives a step of about 0.0014, and
+        // the error in the angle is up to a few steps.
+        assert!(quat_max_error(&smallest_three, &src_array) < 0.005);
+        assert!(quat_max_error(&half, &src_array) < 0.002);
+        assert!(quat_max_error(&half_normalize, &src_array) < 0.002);
+
+        for q in [
+            Quat::IDENTITY,
+            -Quat::IDENTITY,
+            Quat::from_xyzw(0.0, -1.0, 0.0, 0.0),
+        ] {
+            let decoded = smallest_three_decode_single(smallest_three_encode_single(q));
+
+            assert!(quat_near(decoded, q, 0.002));
+        }
+    }
+
+    #[test]
+    fn normals() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<Vec3>(&mut rng, COUNT)
+            .iter()
+            .map(|v| ((*v * 2.0) - 1.0).normalize())
+            .chain(Vec3::AXES.iter().flat_map(|&a| [a, -a]))
+            .collect::<Vec<_>>();
+
+        let oct16 = round_trip(&src_array, normal_encode_oct16, normal_decode_oct16);
+        let oct32 = round_trip(&src_array, normal_encode_oct32, normal_decode_oct32);
+
+        // 8 bits per axis is a step of about 0.008 over the unfolded square,
+        // which can be stretched by up to two times on the sphere.
+        assert!(normal_max_error(&oct16, &src_array) < 0.02);
+        assert!(normal_max_error(&oct32, &src_array) < 1e-4);
+
+        assert_eq!(round_trip(&src_array, normal_copy, normal_copy), src_array);
+    }
+
+    #[test]
+    fn half_floats() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT)
+            .iter()
+            .map(|x| (x - 0.5) * 1000.0)
+            .collect::<Vec<_>>();
+
+        let run = |f: fn(&mut ConvertParams<f32, f16>)| {
+            run_into(vec![f16::ZERO; COUNT], |dst_array| {
+                f(&mut ConvertParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        };
+
+        let expected = run(f16_from_f32_software);
+
+        assert_eq!(expected, run(f16_from_f32));
+        assert_eq!(expected, run(f16_from_f32_slice));
+
+        let run = |f: fn(&mut ConvertParams<f16, f32>)| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut ConvertParams {
+                    dst_array,
+                    src_array: &expected,
+                })
+            })
+        };
+
+        let expected = run(f32_from_f16_software);
+
+        assert_eq!(expected, run(f32_from_f16));
+        assert_eq!(expected, run(f32_from_f16_slice));
+    }
+}
@@ -0,0 +1,190 @@
+use glam::{Quat, Vec2, Vec3};
+use rand::{Error, Rng, RngCore, SeedableRng};
+use std::f32::consts::TAU;
+
+// Wang Yi's wyrand, with the final wyhash v4.2 constants as used by fastrand.
+// 64 bits of state and one wide multiply per output. Fast and good enough for
+// games, but not cryptographic.
+#[derive(Clone, Debug)]
+pub struct WyRand(u64);
+
+impl RngCore for WyRand {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x2d35_8dcc_aa6c_78a5);
+
+        let t = u128::from(self.0) * u128::from(self.0 ^ 0x8bb8_4b93_962e_acc9);
+
+        (t as u64) ^ ((t >> 64) as u64)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut chunks = dest.chunks_exact_mut(8);
+
+        for chunk in &mut chunks {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes());
+        }
+
+        let remainder = chunks.into_remainder();
+
+        if !remainder.is_empty() {
+            let bytes = self.next_u64().to_le_bytes();
+
+            remainder.copy_from_slice(&bytes[..remainder.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+
+        Ok(())
+    }
+}
+
+impl SeedableRng for WyRand {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        WyRand(u64::from_le_bytes(seed))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Fill `dst_array` with samples from `f`.
+#[inline(never)]
+pub fn sample_array<R: Rng, T>(rng: &mut R, dst_array: &mut [T], f: impl Fn(&mut R) -> T) {
+    for dst in dst_array {
+        *dst = f(rng);
+    }
+}
+
+// Uniform on the unit sphere, by picking a height and an angle around the
+// axis. Archimedes' hat-box theorem makes the height uniform.
+pub fn random_unit_vector_trig<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+    let z = rng.gen_range(-1.0f32..1.0);
+    let (s, c) = rng.gen_range(0.0f32..TAU).sin_cos();
+    let r = (1.0 - (z * z)).sqrt();
+
+    Vec3::new(r * c, r * s, z)
+}
+
+// Uniform on the unit sphere, by picking points in the cube until one lands in
+// the ball, then normalizing. Takes about 1.9 tries on average.
+pub fn random_unit_vector_rejection<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+    loop {
+        let v = Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        );
+
+        let length_squared = v.length_squared();
+
+        if (length_squared > 1e-6) && (length_squared <= 1.0) {
+            return v / length_squared.sqrt();
+        }
+    }
+}
+
+// Return a point in the unit disc and its squared length, by rejection from
+// the square.
+fn random_in_disc<R: Rng + ?Sized>(rng: &mut R) -> (Vec2, f32) {
+    loop {
+        let v = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        let length_squared = v.length_squared();
+
+        if length_squared < 1.0 {
+            return (v, length_squared);
+        }
+    }
+}
+
+// Uniform over rotations, like `util::random_quat`, but with Marsaglia's
+// rejection method for the 4D sphere from "Choosing a Point from the Surface
+// of a Sphere" (1972). Two points in the unit disc replace the trig.
+pub fn random_quat_marsaglia<R: Rng + ?Sized>(rng: &mut R) -> Quat {
+    let (a, s1) = random_in_disc(rng);
+
+    let (b, s2) = loop {
+        let (b, s2) = random_in_disc(rng);
+
+        if s2 > 0.0 {
+            break (b, s2);
+        }
+    };
+
+    let b = b * ((1.0 - s1) / s2).sqrt();
+
+    Quat::from_xyzw(a.x, a.y, b.x, b.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::test_rng;
+
+    #[test]
+    fn wyrand() {
+        // The first outputs of fastrand 2.3 with a seed of zero.
+        let mut rng = WyRand::from_seed([0; 8]);
+
+        assert_eq!(rng.next_u64(), 0x9a45_cd88_8d59_f0d6);
+        assert_eq!(rng.next_u64(), 0x0144_5b6a_1896_63f5);
+
+        // Bytes come from whole outputs in little endian order, with the last
+        // one truncated.
+        let mut rng = WyRand::from_seed([0; 8]);
+        let mut bytes = [0u8; 11];
+
+        rng.fill_bytes(&mut bytes);
+
+        assert_eq!(bytes[..8], 0x9a45_cd88_8d59_f0d6u64.to_le_bytes());
+        assert_eq!(bytes[8..], 0x0144_5b6a_1896_63f5u64.to_le_bytes()[..3]);
+    }
+
+    #[test]
+    fn distributions() {
+        use crate::util::random_quat;
+        use rand::rngs::StdRng;
+
+        const COUNT: usize = 10_000;
+
+        let mut rng = test_rng();
+
+        // Uniform samples should be unit length and average out to roughly
+        // zero. For quats, compare the absolute values since q and -q are the
+        // same rotation.
+
+        for f in [random_unit_vector_trig, random_unit_vector_rejection] {
+            let mut dst_array = vec![Vec3::ZERO; COUNT];
+
+            sample_array(&mut rng, &mut dst_array, f);
+
+            assert!(dst_array.iter().all(|v| v.is_normalized()));
+            assert!((dst_array.iter().sum::<Vec3>() / (COUNT as f32)).length() < 0.05);
+        }
+
+        let mean_abs = |f: fn(&mut StdRng) -> Quat, rng: &mut StdRng| {
+            let mut dst_array = vec![Quat::IDENTITY; COUNT];
+
+            sample_array(rng, &mut dst_array, f);
+
+            assert!(dst_array.iter().all(|q| q.is_normalized()));
+
+            dst_array
+                .iter()
+                .map(|q| glam::Vec4::from(*q).abs())
+                .sum::<glam::Vec4>()
+                / (COUNT as f32)
+        };
+
+        let trig = mean_abs(random_quat, &mut rng);
+        let marsaglia = mean_abs(random_quat_marsaglia, &mut rng);
+
+        assert!(trig.abs_diff_eq(marsaglia, 0.02));
+    }
+}
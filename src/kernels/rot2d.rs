@@ -0,0 +1,106 @@
+use bevy_math::Rot2;
+use glam::{Mat2, Quat, Vec2};
+
+// Rotate each point by its own rotation, as with 2D sprites. The rotation
+// types are `Rot2` (cos and sin), a quat around the Z axis, a complex number
+// stored as a `Vec2`, and a `Mat2`.
+pub struct Rot2dParams<'a, R> {
+    pub dst_array: &'a mut [Vec2],
+    pub src_array: &'a [Vec2],
+    pub rotation_array: &'a [R],
+}
+
+pub fn rot2d_inner<R, F>(params: &mut Rot2dParams<R>, f: F)
+where
+    R: Copy,
+    F: Fn(R, Vec2) -> Vec2,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(params.rotation_array[i], params.src_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn rot2d_rot2(params: &mut Rot2dParams<Rot2>) {
+    rot2d_inner(params, |r, p| r * p);
+}
+
+#[inline(never)]
+pub fn rot2d_quat(params: &mut Rot2dParams<Quat>) {
+    rot2d_inner(params, |q, p| (q * p.extend(0.0)).truncate());
+}
+
+// The rotation is a unit complex number with the cos in `x` and the sin in `y`.
+#[inline(never)]
+pub fn rot2d_complex(params: &mut Rot2dParams<Vec2>) {
+    rot2d_inner(params, |c, p| {
+        Vec2::new((c.x * p.x) - (c.y * p.y), (c.y * p.x) + (c.x * p.y))
+    });
+}
+
+#[inline(never)]
+pub fn rot2d_mat2(params: &mut Rot2dParams<Mat2>) {
+    rot2d_inner(params, |m, p| m * p);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng, COUNT},
+        util::*,
+    };
+    use core::f32::consts::TAU;
+
+    const TOLERANCE: f32 = 1e-5;
+
+    fn run<R>(f: fn(&mut Rot2dParams<R>), src_array: &[Vec2], rotation_array: &[R]) -> Vec<Vec2> {
+        run_into(vec![Vec2::ZERO; COUNT], |dst_array| {
+            f(&mut Rot2dParams {
+                dst_array,
+                src_array,
+                rotation_array,
+            })
+        })
+    }
+
+    #[test]
+    fn rot2d() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<Vec2>(&mut rng, COUNT);
+        let angle_array = random_array::<f32>(&mut rng, COUNT)
+            .iter()
+            .map(|a| a * TAU)
+            .collect::<Vec<_>>();
+
+        let rot2 = angle_array
+            .iter()
+            .map(|&a| Rot2::radians(a))
+            .collect::<Vec<_>>();
+        let quat = angle_array
+            .iter()
+            .map(|&a| Quat::from_rotation_z(a))
+            .collect::<Vec<_>>();
+        let complex = angle_array
+            .iter()
+            .map(|&a| Vec2::from_angle(a))
+            .collect::<Vec<_>>();
+        let mat2 = angle_array
+            .iter()
+            .map(|&a| Mat2::from_angle(a))
+            .collect::<Vec<_>>();
+
+        let expected = run(rot2d_rot2, &src_array, &rot2);
+
+        for actual in [
+            run(rot2d_quat, &src_array, &quat),
+            run(rot2d_complex, &src_array, &complex),
+            run(rot2d_mat2, &src_array, &mat2),
+        ] {
+            for i in 0..COUNT {
+                assert!(actual[i].abs_diff_eq(expected[i], TOLERANCE));
+            }
+        }
+    }
+}
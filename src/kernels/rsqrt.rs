@@ -0,0 +1,72 @@
+// Reciprocal square root, the core of vector and quat normalization.
+pub struct RsqrtParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub src_array: &'a [f32],
+}
+
+#[inline(never)]
+pub fn rsqrt_sqrt_recip(params: &mut RsqrtParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.src_array[i].sqrt().recip();
+    }
+}
+
+#[inline(never)]
+pub fn rsqrt_div_sqrt(params: &mut RsqrtParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = 1.0 / params.src_array[i].sqrt();
+    }
+}
+
+// The classic bit hack approximation with one Newton-Raphson iteration. The
+// relative error is below 0.2%.
+#[inline]
+pub fn rsqrt_bit_hack_single(x: f32) -> f32 {
+    let y = f32::from_bits(0x5f37_59df - (x.to_bits() >> 1));
+
+    y * (1.5 - (0.5 * x * y * y))
+}
+
+#[inline(never)]
+pub fn rsqrt_bit_hack(params: &mut RsqrtParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = rsqrt_bit_hack_single(params.src_array[i]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng, COUNT},
+        util::random_array,
+    };
+
+    #[test]
+    fn rsqrt() {
+        let mut rng = test_rng();
+
+        // Avoid values near zero, where the relative error is unstable.
+        let src_array = random_array::<f32>(&mut rng, COUNT)
+            .iter()
+            .map(|x| x + 0.01)
+            .collect::<Vec<_>>();
+
+        let run = |f: fn(&mut RsqrtParams)| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut RsqrtParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        };
+
+        let expected = run(rsqrt_sqrt_recip);
+
+        assert_eq!(run(rsqrt_div_sqrt), expected);
+
+        for (actual, expected) in run(rsqrt_bit_hack).iter().zip(expected.iter()) {
+            assert!(((actual - expected) / expected).abs() < 0.002);
+        }
+    }
+}
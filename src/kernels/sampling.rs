@@ -0,0 +1,122 @@
+use glam::Vec2;
+use rand::Rng;
+use std::f32::consts::TAU;
+
+// Bridson, "Fast Poisson Disk Sampling in Arbitrary Dimensions" (2007). Return
+// points in the rectangle from zero to `size` where no two are closer than
+// `radius`. Each active point tries `attempts` random candidates in the ring
+// between one and two radii before it's retired, and a background grid with
+// at most one point per cell limits each check to the nearby cells.
+#[inline(never)]
+pub fn poisson_disk_bridson<R: Rng + ?Sized>(
+    rng: &mut R,
+    size: Vec2,
+    radius: f32,
+    attempts: usize,
+) -> Vec<Vec2> {
+    // A cell's diagonal is the radius, so it can't hold two points.
+    let cell_size = radius / std::f32::consts::SQRT_2;
+
+    let grid_width = (size.x / cell_size).ceil() as usize;
+    let grid_height = (size.y / cell_size).ceil() as usize;
+
+    let mut grid = vec![u32::MAX; grid_width * grid_height];
+
+    let cell = |p: Vec2| {
+        let x = ((p.x / cell_size) as usize).min(grid_width - 1);
+        let y = ((p.y / cell_size) as usize).min(grid_height - 1);
+
+        (x, y)
+    };
+
+    let mut points = Vec::new();
+    let mut active = Vec::new();
+
+    let first = rng.gen::<Vec2>() * size;
+    let (x, y) = cell(first);
+
+    grid[(y * grid_width) + x] = 0;
+    points.push(first);
+    active.push(0);
+
+    let radius_squared = radius * radius;
+
+    while !active.is_empty() {
+        let a = rng.gen_range(0..active.len());
+        let center = points[active[a] as usize];
+
+        let mut found = false;
+
+        for _ in 0..attempts {
+            let angle = rng.gen::<f32>() * TAU;
+            let distance = rng.gen_range(radius..(2.0 * radius));
+
+            let candidate = center + (Vec2::from_angle(angle) * distance);
+
+            if (candidate.cmplt(Vec2::ZERO) | candidate.cmpge(size)).any() {
+                continue;
+            }
+
+            let (cx, cy) = cell(candidate);
+
+            // Points within the radius can be up to two cells away.
+            let too_close = (cy.saturating_sub(2)..(cy + 3).min(grid_height)).any(|y| {
+                (cx.saturating_sub(2)..(cx + 3).min(grid_width)).any(|x| {
+                    let i = grid[(y * grid_width) + x];
+
+                    (i != u32::MAX)
+                        && (points[i as usize].distance_squared(candidate) < radius_squared)
+                })
+            });
+
+            if !too_close {
+                grid[(cy * grid_width) + cx] = points.len() as u32;
+                active.push(points.len() as u32);
+                points.push(candidate);
+
+                found = true;
+
+                break;
+            }
+        }
+
+        if !found {
+            active.swap_remove(a);
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{test_rng, COUNT};
+
+    #[test]
+    fn poisson_disk() {
+        let mut rng = test_rng();
+
+        let size = Vec2::new(30.0, 20.0);
+        let radius = 1.0;
+
+        let points = poisson_disk_bridson(&mut rng, size, radius, 30);
+
+        for (i, p) in points.iter().enumerate() {
+            assert!(p.cmpge(Vec2::ZERO).all() && p.cmplt(size).all());
+
+            for q in &points[(i + 1)..] {
+                assert!(p.distance(*q) >= radius);
+            }
+        }
+
+        // The points should cover the rectangle, so anywhere is within two
+        // radii of a point.
+
+        for _ in 0..COUNT {
+            let p = rng.gen::<Vec2>() * size;
+
+            assert!(points.iter().any(|q| p.distance(*q) < (2.0 * radius)));
+        }
+    }
+}
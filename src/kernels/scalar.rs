@@ -0,0 +1,121 @@
+use glam::{DQuat, DVec3, Quat, Vec3};
+use std::ops::{Add, Mul, Sub};
+
+// The precision of a kernel. Each scalar type has matching glam vector and
+// quat types, so generic kernels can be instantiated for `f32` with
+// `Vec3`/`Quat` or `f64` with `DVec3`/`DQuat`.
+//
+// Quat operations are methods on the trait rather than operator bounds on
+// `Self::Quat`, as `Mul<Self::Quat>` and `Mul<Self::Vec3>` bounds together
+// make `q * q` ambiguous.
+pub trait Scalar: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    type Vec3: Copy + Add<Output = Self::Vec3> + Mul<Output = Self::Vec3>;
+    type Quat: Copy;
+
+    // Used in bench IDs, as in "precision = f64".
+    const NAME: &'static str;
+
+    fn from_f32(f: f32) -> Self;
+    fn vec3_from_f32(v: Vec3) -> Self::Vec3;
+    fn quat_from_f32(q: Quat) -> Self::Quat;
+
+    fn quat_mul(l: Self::Quat, r: Self::Quat) -> Self::Quat;
+    fn quat_mul_vec3(q: Self::Quat, v: Self::Vec3) -> Self::Vec3;
+    fn quat_normalize(q: Self::Quat) -> Self::Quat;
+    fn quat_nlerp(l: Self::Quat, r: Self::Quat, a: Self) -> Self::Quat;
+    fn quat_slerp(l: Self::Quat, r: Self::Quat, a: Self) -> Self::Quat;
+}
+
+impl Scalar for f32 {
+    type Vec3 = Vec3;
+    type Quat = Quat;
+
+    const NAME: &'static str = "f32";
+
+    #[inline]
+    fn from_f32(f: f32) -> Self {
+        f
+    }
+
+    #[inline]
+    fn vec3_from_f32(v: Vec3) -> Self::Vec3 {
+        v
+    }
+
+    #[inline]
+    fn quat_from_f32(q: Quat) -> Self::Quat {
+        q
+    }
+
+    #[inline]
+    fn quat_mul(l: Quat, r: Quat) -> Quat {
+        l * r
+    }
+
+    #[inline]
+    fn quat_mul_vec3(q: Quat, v: Vec3) -> Vec3 {
+        q * v
+    }
+
+    #[inline]
+    fn quat_normalize(q: Quat) -> Quat {
+        q.normalize()
+    }
+
+    #[inline]
+    fn quat_nlerp(l: Quat, r: Quat, a: f32) -> Quat {
+        l.lerp(r, a)
+    }
+
+    #[inline]
+    fn quat_slerp(l: Quat, r: Quat, a: f32) -> Quat {
+        l.slerp(r, a)
+    }
+}
+
+impl Scalar for f64 {
+    type Vec3 = DVec3;
+    type Quat = DQuat;
+
+    const NAME: &'static str = "f64";
+
+    #[inline]
+    fn from_f32(f: f32) -> Self {
+        f as f64
+    }
+
+    #[inline]
+    fn vec3_from_f32(v: Vec3) -> Self::Vec3 {
+        v.as_dvec3()
+    }
+
+    #[inline]
+    fn quat_from_f32(q: Quat) -> Self::Quat {
+        q.as_dquat()
+    }
+
+    #[inline]
+    fn quat_mul(l: DQuat, r: DQuat) -> DQuat {
+        l * r
+    }
+
+    #[inline]
+    fn quat_mul_vec3(q: DQuat, v: DVec3) -> DVec3 {
+        q * v
+    }
+
+    #[inline]
+    fn quat_normalize(q: DQuat) -> DQuat {
+        q.normalize()
+    }
+
+    #[inline]
+    fn quat_nlerp(l: DQuat, r: DQuat, a: f64) -> DQuat {
+        l.lerp(r, a)
+    }
+
+    #[inline]
+    fn quat_slerp(l: DQuat, r: DQuat, a: f64) -> DQuat {
+        l.slerp(r, a)
+    }
+}
@@ -0,0 +1,241 @@
+use crate::kernels::{easing::SmoothstepParams, lerp::QuatParams, point::TransformPointParams};
+use glam::{Quat, Vec3};
+use std::simd::{f32x8, num::SimdFloat, StdFloat};
+
+// Explicit `std::simd` versions of kernels that are otherwise left to the
+// auto-vectorizer. Each processes eight elements at a time, then falls back
+// to scalar code for the remainder.
+
+const LANES: usize = 8;
+
+#[inline(never)]
+pub fn smoothstep_simd(params: &mut SmoothstepParams) {
+    let (dst_chunks, dst_remainder) = params.dst_array.as_chunks_mut::<LANES>();
+    let (src_chunks, src_remainder) = params.src_array.as_chunks::<LANES>();
+
+    let two = f32x8::splat(2.0);
+    let three = f32x8::splat(3.0);
+
+    for (dst, src) in dst_chunks.iter_mut().zip(src_chunks) {
+        let t = f32x8::from_array(*src);
+
+        *dst = ((three - (two * t)) * t * t).to_array();
+    }
+
+    for (dst, &t) in dst_remainder.iter_mut().zip(src_remainder) {
+        *dst = (3.0 - (2.0 * t)) * t * t;
+    }
+}
+
+// Quats transposed to SoA form.
+struct Quatx8 {
+    x: f32x8,
+    y: f32x8,
+    z: f32x8,
+    w: f32x8,
+}
+
+impl Quatx8 {
+    #[inline]
+    fn load(quats: &[Quat; LANES]) -> Self {
+        Quatx8 {
+            x: f32x8::from_array(quats.map(|q| q.x)),
+            y: f32x8::from_array(quats.map(|q| q.y)),
+            z: f32x8::from_array(quats.map(|q| q.z)),
+            w: f32x8::from_array(quats.map(|q| q.w)),
+        }
+    }
+
+    #[inline]
+    fn store(&self, quats: &mut [Quat; LANES]) {
+        let (x, y, z, w) = (
+            self.x.to_array(),
+            self.y.to_array(),
+            self.z.to_array(),
+            self.w.to_array(),
+        );
+
+        for lane in 0..LANES {
+            quats[lane] = Quat::from_xyzw(x[lane], y[lane], z[lane], w[lane]);
+        }
+    }
+}
+
+// Same as `quat_nlerp`, including the flip to take the shortest path.
+#[inline(never)]
+pub fn quat_loop_nlerp_simd(params: &mut QuatParams) {
+    let len = params.dst.len();
+    let chunk_len = len - (len % LANES);
+
+    let alpha = f32x8::splat(params.src_alpha);
+    let inverse_alpha = f32x8::splat(1.0 - params.src_alpha);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        let l = Quatx8::load(params.src_quat[0][i..][..LANES].try_into().unwrap());
+        let r = Quatx8::load(params.src_quat[1][i..][..LANES].try_into().unwrap());
+
+        let dot = (l.x * r.x) + (l.y * r.y) + (l.z * r.z) + (l.w * r.w);
+        let r_alpha = alpha.copysign(dot);
+
+        let x = (l.x * inverse_alpha) + (r.x * r_alpha);
+        let y = (l.y * inverse_alpha) + (r.y * r_alpha);
+        let z = (l.z * inverse_alpha) + (r.z * r_alpha);
+        let w = (l.w * inverse_alpha) + (r.w * r_alpha);
+
+        let inverse_length = ((x * x) + (y * y) + (z * z) + (w * w)).sqrt().recip();
+
+        let result = Quatx8 {
+            x: x * inverse_length,
+            y: y * inverse_length,
+            z: z * inverse_length,
+            w: w * inverse_length,
+        };
+
+        result.store((&mut params.dst[i..][..LANES]).try_into().unwrap());
+    }
+
+    for i in chunk_len..len {
+        params.dst[i] = params.src_quat[0][i].lerp(params.src_quat[1][i], params.src_alpha);
+    }
+}
+
+// Same as `transform_point_scalar`.
+#[inline(never)]
+pub fn transform_point_simd(params: &mut TransformPointParams) {
+    let len = params.dst_array.len();
+    let chunk_len = len - (len % LANES);
+
+    let t = params.transform;
+
+    let (qx, qy, qz, qw) = (
+        f32x8::splat(t.rotation.x),
+        f32x8::splat(t.rotation.y),
+        f32x8::splat(t.rotation.z),
+        f32x8::splat(t.rotation.w),
+    );
+
+    let (sx, sy, sz) = (
+        f32x8::splat(t.scale.x),
+        f32x8::splat(t.scale.y),
+        f32x8::splat(t.scale.z),
+    );
+
+    let (tx, ty, tz) = (
+        f32x8::splat(t.translation.x),
+        f32x8::splat(t.translation.y),
+        f32x8::splat(t.translation.z),
+    );
+
+    let two = f32x8::splat(2.0);
+    let ww_minus_bb = (qw * qw) - ((qx * qx) + (qy * qy) + (qz * qz));
+
+    for i in (0..chunk_len).step_by(LANES) {
+        let src: &[Vec3; LANES] = params.src_array[i..][..LANES].try_into().unwrap();
+
+        let vx = f32x8::from_array(src.map(|v| v.x)) * sx;
+        let vy = f32x8::from_array(src.map(|v| v.y)) * sy;
+        let vz = f32x8::from_array(src.map(|v| v.z)) * sz;
+
+        // Same formula as glam's `Quat::mul_vec3`.
+        let dot_2 = ((vx * qx) + (vy * qy) + (vz * qz)) * two;
+        let w_2 = qw * two;
+
+        let rx = (vx * ww_minus_bb) + (qx * dot_2) + (((qy * vz) - (qz * vy)) * w_2) + tx;
+        let ry = (vy * ww_minus_bb) + (qy * dot_2) + (((qz * vx) - (qx * vz)) * w_2) + ty;
+        let rz = (vz * ww_minus_bb) + (qz * dot_2) + (((qx * vy) - (qy * vx)) * w_2) + tz;
+
+        let (rx, ry, rz) = (rx.to_array(), ry.to_array(), rz.to_array());
+
+        for lane in 0..LANES {
+            params.dst_array[i + lane] = Vec3::new(rx[lane], ry[lane], rz[lane]);
+        }
+    }
+
+    for i in chunk_len..len {
+        params.dst_array[i] = t.transform_point(params.src_array[i]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::{
+            easing::*,
+            lerp::*,
+            point::*,
+            test_util::{quat_near, run_into, test_rng, TOLERANCE},
+        },
+        util::*,
+    };
+    use bevy_transform::components::Transform;
+
+    // Not a multiple of the lane count, so the remainder is tested.
+    const COUNT: usize = 1003;
+
+    #[test]
+    fn smoothstep() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+
+        let [expected, actual] = [smoothstep_explicit, smoothstep_simd].map(|f| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut SmoothstepParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        });
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn nlerp() {
+        let mut rng = test_rng();
+
+        let l = random_quat_array(&mut rng, COUNT);
+        let r = random_quat_array(&mut rng, COUNT);
+
+        let [expected, actual] = [quat_loop_nlerp, quat_loop_nlerp_simd].map(|f| {
+            run_into(vec![Quat::IDENTITY; COUNT], |dst| {
+                f(&mut QuatParams {
+                    dst,
+                    src_quat: &[&l, &r],
+                    src_alpha: 0.3,
+                })
+            })
+        });
+
+        for i in 0..COUNT {
+            assert!(quat_near(actual[i], expected[i], TOLERANCE));
+        }
+    }
+
+    #[test]
+    fn transform_point() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<Vec3>(&mut rng, COUNT);
+        let transform = Transform {
+            translation: Vec3::new(1.0, 2.0, 3.0),
+            rotation: random_quat(&mut rng),
+            scale: Vec3::new(0.5, 1.0, 2.0),
+        };
+
+        let [expected, actual] = [transform_point_scalar, transform_point_simd].map(|f| {
+            run_into(vec![Vec3::ZERO; COUNT], |dst_array| {
+                f(&mut TransformPointParams {
+                    dst_array,
+                    src_array: &src_array,
+                    transform,
+                })
+            })
+        });
+
+        for i in 0..COUNT {
+            assert!(actual[i].abs_diff_eq(expected[i], TOLERANCE));
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::ops::{Add, Mul, Sub};
+
+// Kernels that move values towards targets over a series of frames, as used
+// for camera follow and UI smoothing. They work on any type that can be
+// lerped, which in practice means `f32` and `Vec3`.
+pub trait Smoothable:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self>
+{
+}
+
+impl<T> Smoothable for T where T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T> {}
+
+pub struct SmoothParams<'a, T> {
+    pub current_array: &'a mut [T],
+    // Only used by `smooth_damp`.
+    pub velocity_array: &'a mut [T],
+    pub target_array: &'a [T],
+    pub delta_time: f32,
+}
+
+// Lerp by a fixed fraction each frame. This is the common mistake - the
+// result depends on the frame rate.
+#[inline(never)]
+pub fn smooth_lerp<T: Smoothable>(params: &mut SmoothParams<T>, alpha: f32) {
+    for i in 0..params.current_array.len() {
+        let current = params.current_array[i];
+
+        params.current_array[i] = current + ((params.target_array[i] - current) * alpha);
+    }
+}
+
+// Exponential decay towards the target, which is the frame rate independent
+// version of `smooth_lerp`.
+#[inline(never)]
+pub fn smooth_decay<T: Smoothable>(params: &mut SmoothParams<T>, decay: f32) {
+    let factor = (-decay * params.delta_time).exp();
+
+    for i in 0..params.current_array.len() {
+        let target = params.target_array[i];
+
+        params.current_array[i] = target + ((params.current_array[i] - target) * factor);
+    }
+}
+
+// A critically damped spring, using the polynomial approximation of `exp`
+// from Game Programming Gems 4, chapter 1.10. Unlike decay this keeps a
+// velocity, so a moving target is followed without lag building up as fast.
+#[inline(never)]
+pub fn smooth_damp<T: Smoothable>(params: &mut SmoothParams<T>, smooth_time: f32) {
+    let omega = 2.0 / smooth_time;
+    let x = omega * params.delta_time;
+    let factor = 1.0 / (1.0 + x + (0.48 * x * x) + (0.235 * x * x * x));
+
+    for i in 0..params.current_array.len() {
+        let target = params.target_array[i];
+        let change = params.current_array[i] - target;
+        let temp = (params.velocity_array[i] + (change * omega)) * params.delta_time;
+
+        params.velocity_array[i] = (params.velocity_array[i] - (temp * omega)) * factor;
+        params.current_array[i] = target + ((change + temp) * factor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::{test_rng, COUNT, TOLERANCE};
+    use glam::Vec3;
+    use rand::Rng;
+
+    fn run(
+        f: impl Fn(&mut SmoothParams<Vec3>),
+        src_array: &[Vec3],
+        target_array: &[Vec3],
+        delta_time: f32,
+        frames: usize,
+    ) -> Vec<Vec3> {
+        let mut current_array = src_array.to_vec();
+        let mut velocity_array = vec![Vec3::ZERO; src_array.len()];
+
+        let mut params = SmoothParams {
+            current_array: &mut current_array,
+            velocity_array: &mut velocity_array,
+            target_array,
+            delta_time,
+        };
+
+        for _ in 0..frames {
+            f(&mut params);
+        }
+
+        current_array
+    }
+
+    #[test]
+    fn smooth() {
+        let mut rng = test_rng();
+
+        let src_array = (0..COUNT).map(|_| rng.gen::<Vec3>()).collect::<Vec<_>>();
+        let target_array = (0..COUNT).map(|_| rng.gen::<Vec3>()).collect::<Vec<_>>();
+
+        let variants = [
+            (|params: &mut _| smooth_lerp(params, 0.1)) as fn(&mut SmoothParams<Vec3>),
+            |params| smooth_decay(params, 10.0),
+            |params| smooth_damp(params, 0.2),
+        ];
+
+        // Everything converges on the target.
+        for f in variants {
+            let result = run(f, &src_array, &target_array, 1.0 / 60.0, 1000);
+
+            for i in 0..COUNT {
+                assert!(result[i].abs_diff_eq(target_array[i], TOLERANCE));
+            }
+        }
+
+        // Decay gives the same result at different frame rates. Lerp doesn't.
+        let decay_30 = run(
+            |p| smooth_decay(p, 10.0),
+            &src_array,
+            &target_array,
+            1.0 / 30.0,
+            10,
+        );
+        let decay_60 = run(
+            |p| smooth_decay(p, 10.0),
+            &src_array,
+            &target_array,
+            1.0 / 60.0,
+            20,
+        );
+        let lerp_30 = run(
+            |p| smooth_lerp(p, 0.1),
+            &src_array,
+            &target_array,
+            1.0 / 30.0,
+            10,
+        );
+        let lerp_60 = run(
+            |p| smooth_lerp(p, 0.1),
+            &src_array,
+            &target_array,
+            1.0 / 60.0,
+            20,
+        );
+
+        for i in 0..COUNT {
+            assert!(decay_30[i].abs_diff_eq(decay_60[i], TOLERANCE));
+
+            if !src_array[i].abs_diff_eq(target_array[i], 0.01) {
+                assert!(!lerp_30[i].abs_diff_eq(lerp_60[i], TOLERANCE));
+            }
+        }
+    }
+}
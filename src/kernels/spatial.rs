@@ -0,0 +1,483 @@
+use crate::{
+    kernels::{morton::morton_3d_encode_magic_single, vector::RangeQueryParams},
+    util::CacheAlignedVec,
+};
+use glam::{IVec3, Vec3};
+use rand::Rng;
+use std::collections::HashMap;
+
+// Points are scattered through a cube of this size, starting at the origin.
+pub const SPATIAL_WORLD_SIZE: f32 = 100.0;
+
+pub fn random_spatial_point_array<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+) -> CacheAlignedVec<Vec3> {
+    (0..count)
+        .map(|_| rng.gen::<Vec3>() * SPATIAL_WORLD_SIZE)
+        .collect()
+}
+
+fn cell_of(p: Vec3, cell_size: f32) -> IVec3 {
+    (p / cell_size).floor().as_ivec3()
+}
+
+// A uniform grid of cubes. The cells must be at least as large as the query
+// radius, so a query only visits the 27 cells around the query point.
+pub trait SpatialGrid {
+    fn build(point_array: &[Vec3], cell_size: f32) -> Self;
+
+    fn cell_size(&self) -> f32;
+
+    // Call `f` with the index of every point in the cell.
+    fn for_each_in_cell(&self, cell: IVec3, f: impl FnMut(u32));
+}
+
+pub fn grid_query_single<G: SpatialGrid>(
+    grid: &G,
+    point_array: &[Vec3],
+    query: Vec3,
+    radius: f32,
+) -> u32 {
+    debug_assert!(radius <= grid.cell_size());
+
+    let radius_squared = radius * radius;
+    let center = cell_of(query, grid.cell_size());
+
+    let mut count = 0;
+
+    for z in -1..=1 {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                grid.for_each_in_cell(center + IVec3::new(x, y, z), |i| {
+                    count +=
+                        (query.distance_squared(point_array[i as usize]) <= radius_squared) as u32;
+                });
+            }
+        }
+    }
+
+    count
+}
+
+// Same as `range_query_distance_squared`, but only visits nearby cells.
+#[inline(never)]
+pub fn grid_query<G: SpatialGrid>(params: &mut RangeQueryParams, grid: &G) {
+    for (dst, &query) in params.dst_array.iter_mut().zip(params.query_array) {
+        *dst = grid_query_single(grid, params.point_array, query, params.radius);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Each occupied cell has its own array of indices. Uses the standard library's
+// default hasher.
+pub struct HashMapGrid {
+    pub cell_size: f32,
+    pub cells: HashMap<IVec3, Vec<u32>>,
+}
+
+impl SpatialGrid for HashMapGrid {
+    fn build(point_array: &[Vec3], cell_size: f32) -> Self {
+        let mut cells = HashMap::<IVec3, Vec<u32>>::new();
+
+        for (i, &p) in point_array.iter().enumerate() {
+            cells
+                .entry(cell_of(p, cell_size))
+                .or_default()
+                .push(i as u32);
+        }
+
+        HashMapGrid { cell_size, cells }
+    }
+
+    fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn for_each_in_cell(&self, cell: IVec3, f: impl FnMut(u32)) {
+        if let Some(indices) = self.cells.get(&cell) {
+            indices.iter().copied().for_each(f);
+        }
+    }
+}
+
+// Every cell in the bounds of the points, including empty cells. The indices
+// are sorted by cell with a counting sort, so each cell is a range of
+// `indices`.
+pub struct DenseGrid {
+    pub cell_size: f32,
+    pub min_cell: IVec3,
+    pub dims: IVec3,
+    pub cell_start: Vec<u32>,
+    pub indices: Vec<u32>,
+}
+
+impl DenseGrid {
+    fn cell_index(&self, cell: IVec3) -> Option<usize> {
+        let c = cell - self.min_cell;
+
+        (c.cmpge(IVec3::ZERO) & c.cmplt(self.dims))
+            .all()
+            .then(|| (c.x + (self.dims.x * (c.y + (self.dims.y * c.z)))) as usize)
+    }
+}
+
+impl SpatialGrid for DenseGrid {
+    fn build(point_array: &[Vec3], cell_size: f32) -> Self {
+        let (min_cell, max_cell) =
+            point_array
+                .iter()
+                .fold((IVec3::MAX, IVec3::MIN), |(min, max), &p| {
+                    let cell = cell_of(p, cell_size);
+
+                    (min.min(cell), max.max(cell))
+                });
+
+        let mut grid = DenseGrid {
+            cell_size,
+            min_cell,
+            dims: (max_cell - min_cell) + 1,
+            cell_start: Vec::new(),
+            indices: vec![0; point_array.len()],
+        };
+
+        let cell_count = grid.dims.element_product() as usize;
+
+        // Count the points in each cell, then turn the counts into the start
+        // of each cell's range.
+
+        grid.cell_start = vec![0; cell_count + 1];
+
+        let cells = point_array
+            .iter()
+            .map(|&p| grid.cell_index(cell_of(p, cell_size)).unwrap())
+            .collect::<Vec<_>>();
+
+        for &cell in &cells {
+            grid.cell_start[cell + 1] += 1;
+        }
+
+        for i in 1..=cell_count {
+            grid.cell_start[i] += grid.cell_start[i - 1];
+        }
+
+        let mut next = grid.cell_start.clone();
+
+        for (i, &cell) in cells.iter().enumerate() {
+            grid.indices[next[cell] as usize] = i as u32;
+            next[cell] += 1;
+        }
+
+        grid
+    }
+
+    fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn for_each_in_cell(&self, cell: IVec3, f: impl FnMut(u32)) {
+        if let Some(cell) = self.cell_index(cell) {
+            let range = (self.cell_start[cell] as usize)..(self.cell_start[cell + 1] as usize);
+
+            self.indices[range].iter().copied().for_each(f);
+        }
+    }
+}
+
+// One entry per point, sorted by the Morton code of the point's cell. Queries
+// binary search for each cell, so empty cells cost nothing to store.
+pub struct SortedGrid {
+    pub cell_size: f32,
+    pub min_cell: IVec3,
+    pub keys: Vec<u32>,
+    pub indices: Vec<u32>,
+}
+
+impl SortedGrid {
+    // Morton codes have 10 bits per axis.
+    const MAX_CELL: i32 = (1 << 10) - 1;
+
+    fn cell_key(&self, cell: IVec3) -> Option<u32> {
+        let c = cell - self.min_cell;
+
+        (c.cmpge(IVec3::ZERO) & c.cmple(IVec3::splat(Self::MAX_CELL)))
+            .all()
+            .then(|| morton_3d_encode_magic_single(c.as_uvec3()))
+    }
+}
+
+impl SpatialGrid for SortedGrid {
+    fn build(point_array: &[Vec3], cell_size: f32) -> Self {
+        let min_cell = point_array
+            .iter()
+            .fold(IVec3::MAX, |min, &p| min.min(cell_of(p, cell_size)));
+
+        let mut grid = SortedGrid {
+            cell_size,
+            min_cell,
+            keys: Vec::new(),
+            indices: Vec::new(),
+        };
+
+        let mut entries = point_array
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (grid.cell_key(cell_of(p, cell_size)).unwrap(), i as u32))
+            .collect::<Vec<_>>();
+
+        entries.sort_unstable();
+
+        (grid.keys, grid.indices) = entries.into_iter().unzip();
+
+        grid
+    }
+
+    fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn for_each_in_cell(&self, cell: IVec3, f: impl FnMut(u32)) {
+        if let Some(key) = self.cell_key(cell) {
+            let start = self.keys.partition_point(|&k| k < key);
+            let end = start + self.keys[start..].partition_point(|&k| k == key);
+
+            self.indices[start..end].iter().copied().for_each(f);
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Ulrich's loose octree, where each node's bounds are expanded to twice its
+// size. An item is stored in the deepest node that's at least as large as the
+// item, chosen by the item's center, so items are never split between nodes.
+// Points have no size, so they're always stored at the deepest level.
+pub struct LooseOctree {
+    pub center: Vec3,
+    pub half_size: f32,
+    pub max_depth: u32,
+    pub nodes: Vec<LooseOctreeNode>,
+}
+
+// A child index of zero means there's no child - the root can't be a child.
+#[derive(Default)]
+pub struct LooseOctreeNode {
+    pub children: [u32; 8],
+    pub items: Vec<u32>,
+}
+
+impl LooseOctree {
+    pub const LOOSENESS: f32 = 2.0;
+
+    // Enough for seven siblings at each level of the deepest supported tree.
+    const MAX_DEPTH: u32 = 16;
+
+    // Items must be inside the cube given by `min` and `size`.
+    pub fn new(min: Vec3, size: f32, max_depth: u32) -> Self {
+        assert!(max_depth <= Self::MAX_DEPTH);
+
+        LooseOctree {
+            center: min + (size * 0.5),
+            half_size: size * 0.5,
+            max_depth,
+            nodes: vec![LooseOctreeNode::default()],
+        }
+    }
+
+    // Insert points one at a time into a tree covering the world. The deepest
+    // nodes are at least `leaf_size` across, so a query with a radius of
+    // `leaf_size` or less visits a similar area to a grid.
+    pub fn build(point_array: &[Vec3], leaf_size: f32) -> Self {
+        let max_depth = (SPATIAL_WORLD_SIZE / leaf_size).log2().floor().max(0.0) as u32;
+
+        let mut octree = LooseOctree::new(Vec3::ZERO, SPATIAL_WORLD_SIZE, max_depth);
+
+        for (i, &p) in point_array.iter().enumerate() {
+            octree.insert(i as u32, p, 0.0);
+        }
+
+        octree
+    }
+
+    pub fn insert(&mut self, item: u32, center: Vec3, radius: f32) {
+        let mut node = 0;
+        let mut node_center = self.center;
+        let mut half_size = self.half_size;
+
+        for _ in 0..self.max_depth {
+            let child_half_size = half_size * 0.5;
+
+            // With a looseness of two, an item fits in a node if its radius
+            // is no larger than the node's half size.
+            if radius > child_half_size {
+                break;
+            }
+
+            let positive = center.cmpge(node_center);
+            let octant = positive.bitmask() as usize;
+
+            node_center += Vec3::select(
+                positive,
+                Vec3::splat(child_half_size),
+                Vec3::splat(-child_half_size),
+            );
+
+            half_size = child_half_size;
+
+            node = match self.nodes[node].children[octant] {
+                0 => {
+                    let child = self.nodes.len();
+
+                    self.nodes.push(LooseOctreeNode::default());
+                    self.nodes[node].children[octant] = child as u32;
+
+                    child
+                }
+                child => child as usize,
+            };
+        }
+
+        self.nodes[node].items.push(item);
+    }
+}
+
+pub fn octree_query_single(
+    octree: &LooseOctree,
+    point_array: &[Vec3],
+    query: Vec3,
+    radius: f32,
+) -> u32 {
+    let radius_squared = radius * radius;
+
+    let mut count = 0;
+
+    let mut stack = [(0u32, Vec3::ZERO, 0.0f32); (7 * LooseOctree::MAX_DEPTH as usize) + 1];
+    let mut stack_len = 1;
+
+    stack[0] = (0, octree.center, octree.half_size);
+
+    while stack_len > 0 {
+        stack_len -= 1;
+
+        let (node, center, half_size) = stack[stack_len];
+
+        // Skip nodes whose loose bounds don't touch the query sphere.
+        let outside =
+            ((query - center).abs() - (half_size * LooseOctree::LOOSENESS)).max(Vec3::ZERO);
+
+        if outside.length_squared() > radius_squared {
+            continue;
+        }
+
+        let node = &octree.nodes[node as usize];
+
+        for &i in &node.items {
+            count += (query.distance_squared(point_array[i as usize]) <= radius_squared) as u32;
+        }
+
+        let child_half_size = half_size * 0.5;
+
+        for (octant, &child) in node.children.iter().enumerate() {
+            if child != 0 {
+                let offset = Vec3::new(
+                    if (octant & 1) != 0 { 1.0 } else { -1.0 },
+                    if (octant & 2) != 0 { 1.0 } else { -1.0 },
+                    if (octant & 4) != 0 { 1.0 } else { -1.0 },
+                );
+
+                stack[stack_len] = (child, center + (offset * child_half_size), child_half_size);
+                stack_len += 1;
+            }
+        }
+    }
+
+    count
+}
+
+// Same as `grid_query`, walking the tree from the root.
+#[inline(never)]
+pub fn octree_query(params: &mut RangeQueryParams, octree: &LooseOctree) {
+    for (dst, &query) in params.dst_array.iter_mut().zip(params.query_array) {
+        *dst = octree_query_single(octree, params.point_array, query, params.radius);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::{
+        test_util::{run_into, test_rng, COUNT},
+        vector::range_query_distance_squared,
+    };
+
+    #[test]
+    fn grids() {
+        let mut rng = test_rng();
+
+        let point_array = random_spatial_point_array(&mut rng, COUNT);
+        let query_array = random_spatial_point_array(&mut rng, 100);
+
+        let radius = 10.0;
+
+        let run = |f: &dyn Fn(&mut RangeQueryParams)| {
+            run_into(vec![0; query_array.len()], |dst_array| {
+                f(&mut RangeQueryParams {
+                    dst_array,
+                    query_array: &query_array,
+                    point_array: &point_array,
+                    radius,
+                })
+            })
+        };
+
+        let expected = run(&range_query_distance_squared);
+
+        assert!(expected.iter().sum::<u32>() > 0);
+
+        let hash_map = HashMapGrid::build(&point_array, radius);
+        let dense = DenseGrid::build(&point_array, radius);
+        let sorted = SortedGrid::build(&point_array, radius);
+
+        assert_eq!(expected, run(&|p| grid_query(p, &hash_map)));
+        assert_eq!(expected, run(&|p| grid_query(p, &dense)));
+        assert_eq!(expected, run(&|p| grid_query(p, &sorted)));
+
+        let octree = LooseOctree::build(&point_array, radius);
+
+        assert_eq!(expected, run(&|p| octree_query(p, &octree)));
+    }
+
+    #[test]
+    fn loose_octree() {
+        let mut octree = LooseOctree::new(Vec3::ZERO, 16.0, 4);
+
+        // A point goes to the deepest level, and larger items stop higher up.
+
+        for (item, radius) in [0.0, 1.0, 2.0, 4.0, 8.0].into_iter().enumerate() {
+            octree.insert(item as u32, Vec3::splat(3.0), radius);
+        }
+
+        let depth_of = |item: u32| {
+            let mut depth = 0;
+            let mut node = 0;
+
+            loop {
+                if octree.nodes[node].items.contains(&item) {
+                    return depth;
+                }
+
+                node = octree.nodes[node]
+                    .children
+                    .iter()
+                    .find(|&&c| c != 0)
+                    .copied()
+                    .unwrap() as usize;
+
+                depth += 1;
+            }
+        };
+
+        assert_eq!([0, 1, 2, 3, 4].map(depth_of), [4, 3, 2, 1, 0]);
+    }
+}
@@ -0,0 +1,469 @@
+use bevy_math::{
+    cubic_splines::{CubicCardinalSpline, CubicCurve, CubicGenerator, CubicSegment},
+    Vec3,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Evaluate a cubic Bézier segment by repeated lerping of the control points.
+#[inline]
+pub fn de_casteljau(points: &[Vec3; 4], t: f32) -> Vec3 {
+    de_casteljau_with_velocity(points, t).0
+}
+
+// The last level of de Casteljau's algorithm also gives the tangent, so the
+// velocity is almost free.
+#[inline]
+pub fn de_casteljau_with_velocity(points: &[Vec3; 4], t: f32) -> (Vec3, Vec3) {
+    let [p0, p1, p2, p3] = *points;
+
+    let a0 = p0.lerp(p1, t);
+    let a1 = p1.lerp(p2, t);
+    let a2 = p2.lerp(p3, t);
+
+    let b0 = a0.lerp(a1, t);
+    let b1 = a1.lerp(a2, t);
+
+    (b0.lerp(b1, t), 3.0 * (b1 - b0))
+}
+
+// A cubic Bézier segment converted to polynomial coefficients, so that
+// `position(t) = coeff[0] + coeff[1] t + coeff[2] t^2 + coeff[3] t^3`.
+#[derive(Clone, Copy, Debug)]
+pub struct HornerCubic {
+    pub coeff: [Vec3; 4],
+}
+
+impl HornerCubic {
+    pub fn from_bezier(points: &[Vec3; 4]) -> Self {
+        let [p0, p1, p2, p3] = *points;
+
+        HornerCubic {
+            coeff: [
+                p0,
+                3.0 * (p1 - p0),
+                3.0 * (p0 - (2.0 * p1) + p2),
+                (3.0 * (p1 - p2)) + p3 - p0,
+            ],
+        }
+    }
+
+    #[inline]
+    pub fn position(&self, t: f32) -> Vec3 {
+        let [c0, c1, c2, c3] = self.coeff;
+
+        c0 + (t * (c1 + (t * (c2 + (t * c3)))))
+    }
+
+    #[inline]
+    pub fn velocity(&self, t: f32) -> Vec3 {
+        let [_, c1, c2, c3] = self.coeff;
+
+        c1 + (t * ((2.0 * c2) + (t * (3.0 * c3))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct SplineParams<'a> {
+    pub dst_array: &'a mut [Vec3],
+    pub src_array: &'a [f32],
+}
+
+#[inline(never)]
+pub fn spline_de_casteljau(params: &mut SplineParams, points: &[Vec3; 4]) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = de_casteljau(points, params.src_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn spline_horner(params: &mut SplineParams, cubic: &HornerCubic) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = cubic.position(params.src_array[i]);
+    }
+}
+
+#[inline(never)]
+pub fn spline_bevy(params: &mut SplineParams, curve: &CubicCurve<Vec3>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = curve.position(params.src_array[i]);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct SplineVelocityParams<'a> {
+    pub dst_position_array: &'a mut [Vec3],
+    pub dst_velocity_array: &'a mut [Vec3],
+    pub src_array: &'a [f32],
+}
+
+#[inline(never)]
+pub fn spline_velocity_de_casteljau(params: &mut SplineVelocityParams, points: &[Vec3; 4]) {
+    for i in 0..params.dst_position_array.len() {
+        let (position, velocity) = de_casteljau_with_velocity(points, params.src_array[i]);
+
+        params.dst_position_array[i] = position;
+        params.dst_velocity_array[i] = velocity;
+    }
+}
+
+#[inline(never)]
+pub fn spline_velocity_horner(params: &mut SplineVelocityParams, cubic: &HornerCubic) {
+    for i in 0..params.dst_position_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_position_array[i] = cubic.position(t);
+        params.dst_velocity_array[i] = cubic.velocity(t);
+    }
+}
+
+#[inline(never)]
+pub fn spline_velocity_bevy(params: &mut SplineVelocityParams, curve: &CubicCurve<Vec3>) {
+    for i in 0..params.dst_position_array.len() {
+        let t = params.src_array[i];
+
+        params.dst_position_array[i] = curve.position(t);
+        params.dst_velocity_array[i] = curve.velocity(t);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Build the curve for a Cardinal spline through the given control points. A
+// tension of 0.5 gives a Catmull-Rom spline.
+#[inline(never)]
+pub fn cardinal_curve(tension: f32, points: &[Vec3]) -> CubicCurve<Vec3> {
+    CubicCardinalSpline::new(tension, points.iter().copied())
+        .to_curve()
+        .unwrap()
+}
+
+// Return `count` evenly spaced `t` values covering the whole of a curve with
+// the given number of segments. Needs at least two samples to cover both ends.
+pub fn uniform_samples(count: usize, segments: usize) -> Vec<f32> {
+    assert!(count >= 2);
+
+    let step = segments as f32 / (count - 1) as f32;
+
+    (0..count)
+        .map(|i| (i as f32 * step).min(segments as f32))
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Nodes and weights for 5 point Gauss-Legendre quadrature over [-1, 1].
+const GAUSS_LEGENDRE: [(f32, f32); 5] = [
+    (0.0, 0.568_888_9),
+    (-0.538_469_3, 0.478_628_67),
+    (0.538_469_3, 0.478_628_67),
+    (-0.906_179_8, 0.236_926_89),
+    (0.906_179_8, 0.236_926_89),
+];
+
+// Return the arc length of the segment between 0 and `t`.
+#[inline]
+pub fn segment_length(segment: &CubicSegment<Vec3>, t: f32) -> f32 {
+    let half = 0.5 * t;
+
+    half * GAUSS_LEGENDRE
+        .iter()
+        .map(|&(x, w)| w * segment.velocity(half * (x + 1.0)).length())
+        .sum::<f32>()
+}
+
+// Return the segment containing the given distance and the distance from the
+// start of that segment, given the cumulative distance at the start of each
+// segment plus the total length.
+#[inline]
+fn find_segment(cumulative: &[f32], distance: f32) -> (usize, f32) {
+    let i = cumulative
+        .partition_point(|&d| d <= distance)
+        .clamp(1, cumulative.len() - 1)
+        - 1;
+
+    (i, distance - cumulative[i])
+}
+
+// Returns the `t` for a distance along the curve by Newton iteration on the
+// arc length of the segment, starting from a linear guess. Only the length of
+// each segment is precomputed.
+pub struct ArcLengthNewton {
+    pub cumulative: Vec<f32>,
+    pub iterations: usize,
+}
+
+impl ArcLengthNewton {
+    pub fn new(curve: &CubicCurve<Vec3>, iterations: usize) -> Self {
+        let cumulative = std::iter::once(0.0)
+            .chain(curve.segments().iter().scan(0.0, |total, segment| {
+                *total += segment_length(segment, 1.0);
+                Some(*total)
+            }))
+            .collect();
+
+        ArcLengthNewton {
+            cumulative,
+            iterations,
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        *self.cumulative.last().unwrap()
+    }
+
+    #[inline]
+    pub fn t_at(&self, curve: &CubicCurve<Vec3>, distance: f32) -> f32 {
+        let (i, target) = find_segment(&self.cumulative, distance);
+        let segment = &curve.segments()[i];
+
+        let mut t = target / (self.cumulative[i + 1] - self.cumulative[i]);
+
+        for _ in 0..self.iterations {
+            let error = segment_length(segment, t) - target;
+            let speed = segment.velocity(t).length();
+
+            if speed > 0.0 {
+                t = (t - (error / speed)).clamp(0.0, 1.0);
+            }
+        }
+
+        i as f32 + t
+    }
+}
+
+// Returns the `t` for a distance along the curve by binary searching a table
+// of distances sampled at uniform `t`, then interpolating between entries.
+pub struct ArcLengthLut {
+    pub distances: Vec<f32>,
+    pub t_step: f32,
+}
+
+impl ArcLengthLut {
+    pub fn new(curve: &CubicCurve<Vec3>, size: usize) -> Self {
+        let t_step = curve.segments().len() as f32 / (size - 1) as f32;
+
+        let distances = curve
+            .iter_positions(size - 1)
+            .scan((0.0, None), |(total, previous), position| {
+                if let Some(previous) = *previous {
+                    *total += position.distance(previous);
+                }
+
+                *previous = Some(position);
+
+                Some(*total)
+            })
+            .collect();
+
+        ArcLengthLut { distances, t_step }
+    }
+
+    pub fn length(&self) -> f32 {
+        *self.distances.last().unwrap()
+    }
+
+    #[inline]
+    pub fn t_at(&self, distance: f32) -> f32 {
+        let (i, remainder) = find_segment(&self.distances, distance);
+        let entry_length = self.distances[i + 1] - self.distances[i];
+
+        let fraction = if entry_length > 0.0 {
+            (remainder / entry_length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        (i as f32 + fraction) * self.t_step
+    }
+}
+
+// Approximates the length of each segment by the straight line between its
+// end points, and assumes `t` is proportional to distance within a segment.
+// Cheap to build and evaluate, but the speed varies within each segment.
+pub struct ArcLengthChord {
+    pub cumulative: Vec<f32>,
+}
+
+impl ArcLengthChord {
+    pub fn new(curve: &CubicCurve<Vec3>) -> Self {
+        let cumulative = std::iter::once(0.0)
+            .chain(curve.segments().iter().scan(0.0, |total, segment| {
+                *total += segment.position(1.0).distance(segment.position(0.0));
+                Some(*total)
+            }))
+            .collect();
+
+        ArcLengthChord { cumulative }
+    }
+
+    pub fn length(&self) -> f32 {
+        *self.cumulative.last().unwrap()
+    }
+
+    #[inline]
+    pub fn t_at(&self, distance: f32) -> f32 {
+        let (i, remainder) = find_segment(&self.cumulative, distance);
+        let chord_length = self.cumulative[i + 1] - self.cumulative[i];
+
+        let fraction = if chord_length > 0.0 {
+            (remainder / chord_length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        i as f32 + fraction
+    }
+}
+
+pub struct ArcLengthParams<'a> {
+    pub dst_array: &'a mut [Vec3],
+    // Distances along the curve, from zero to the curve's length.
+    pub src_array: &'a [f32],
+}
+
+#[inline(never)]
+pub fn arc_length_newton(
+    params: &mut ArcLengthParams,
+    curve: &CubicCurve<Vec3>,
+    table: &ArcLengthNewton,
+) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = curve.position(table.t_at(curve, params.src_array[i]));
+    }
+}
+
+#[inline(never)]
+pub fn arc_length_lut(
+    params: &mut ArcLengthParams,
+    curve: &CubicCurve<Vec3>,
+    table: &ArcLengthLut,
+) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = curve.position(table.t_at(params.src_array[i]));
+    }
+}
+
+#[inline(never)]
+pub fn arc_length_chord(
+    params: &mut ArcLengthParams,
+    curve: &CubicCurve<Vec3>,
+    table: &ArcLengthChord,
+) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = curve.position(table.t_at(params.src_array[i]));
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{test_rng, COUNT, TOLERANCE},
+        util::*,
+    };
+    use bevy_math::cubic_splines::CubicBezier;
+
+    #[test]
+    fn bezier() {
+        let mut rng = test_rng();
+
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 2.0, 0.0),
+            Vec3::new(3.0, -1.0, 1.0),
+            Vec3::new(4.0, 0.0, -1.0),
+        ];
+
+        let cubic = HornerCubic::from_bezier(&points);
+        let curve = CubicBezier::new([points]).to_curve().unwrap();
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+
+        let variants: [&dyn Fn(&mut SplineVelocityParams); 3] = [
+            &|params| spline_velocity_de_casteljau(params, &points),
+            &|params| spline_velocity_horner(params, &cubic),
+            &|params| spline_velocity_bevy(params, &curve),
+        ];
+
+        let results = variants.map(|f| {
+            let mut dst_position_array = vec![Vec3::ZERO; COUNT];
+            let mut dst_velocity_array = vec![Vec3::ZERO; COUNT];
+
+            f(&mut SplineVelocityParams {
+                dst_position_array: &mut dst_position_array,
+                dst_velocity_array: &mut dst_velocity_array,
+                src_array: &src_array,
+            });
+
+            (dst_position_array, dst_velocity_array)
+        });
+
+        for (positions, velocities) in &results[1..] {
+            for i in 0..COUNT {
+                assert!(positions[i].abs_diff_eq(results[0].0[i], TOLERANCE));
+                assert!(velocities[i].abs_diff_eq(results[0].1[i], TOLERANCE));
+            }
+        }
+    }
+
+    // Cardinal splines pass through every control point, with one segment
+    // between each pair of points.
+    #[test]
+    fn cardinal() {
+        let mut rng = test_rng();
+
+        let points = random_array::<Vec3>(&mut rng, 64);
+
+        for tension in [0.0, 0.5, 1.0] {
+            let curve = cardinal_curve(tension, &points);
+
+            assert_eq!(curve.segments().len(), points.len() - 1);
+
+            for (i, point) in points.iter().enumerate() {
+                assert!(curve.position(i as f32).abs_diff_eq(*point, TOLERANCE));
+            }
+        }
+    }
+
+    // Newton iteration and a dense LUT should agree on where each distance
+    // lands. Compare positions rather than `t`, since `t` is sensitive to
+    // error where the curve is slow. The chord approximation only has to hit
+    // the control points.
+    #[test]
+    fn arc_length() {
+        let mut rng = test_rng();
+
+        let points = random_array::<Vec3>(&mut rng, 16);
+        let curve = cardinal_curve(0.5, &points);
+
+        let newton = ArcLengthNewton::new(&curve, 4);
+        let lut = ArcLengthLut::new(&curve, 64 * 1024);
+        let chord = ArcLengthChord::new(&curve);
+
+        assert!((newton.length() - lut.length()).abs() < 1e-3 * newton.length());
+
+        for i in 0..COUNT {
+            let distance = newton.length() * (i as f32 / (COUNT - 1) as f32);
+
+            let t = newton.t_at(&curve, distance);
+            let segment = (t as usize).min(curve.segments().len() - 1);
+            let u = t - segment as f32;
+            let actual = newton.cumulative[segment] + segment_length(&curve.segments()[segment], u);
+
+            assert!((actual - distance).abs() < 1e-3 * newton.length());
+
+            let expected = curve.position(t);
+            let lut_position = curve.position(lut.t_at(distance));
+
+            assert!(lut_position.distance(expected) < 1e-3 * newton.length());
+        }
+
+        for (i, &distance) in chord.cumulative.iter().enumerate() {
+            assert!((chord.t_at(distance) - i as f32).abs() < TOLERANCE);
+        }
+    }
+}
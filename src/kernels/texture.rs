@@ -0,0 +1,442 @@
+use crate::{
+    kernels::color::{linear_to_srgb_single, srgb_to_linear_single, SrgbLut},
+    util::CacheAlignedVec,
+};
+use glam::Vec3;
+use rand::Rng;
+use std::f32::consts::TAU;
+
+// Halves the size of an RGBA8 image by averaging each 2x2 block of source
+// pixels. The source width and height must be even, and the destination is
+// half the size on each axis.
+pub struct DownsampleParams<'a> {
+    pub dst_array: &'a mut [[u8; 4]],
+    pub src_array: &'a [[u8; 4]],
+    pub src_width: usize,
+}
+
+// Call `f` with the top left, top right, bottom left and bottom right source
+// pixels of each destination pixel.
+pub fn downsample_inner<F>(params: &mut DownsampleParams, f: F)
+where
+    F: Fn([[u8; 4]; 4]) -> [u8; 4],
+{
+    let src_width = params.src_width;
+    let dst_width = src_width / 2;
+
+    assert!((src_width & 1) == 0);
+    assert_eq!(params.dst_array.len() * 4, params.src_array.len());
+
+    for (y, dst_row) in params.dst_array.chunks_exact_mut(dst_width).enumerate() {
+        let top = &params.src_array[(y * 2 * src_width)..][..src_width];
+        let bottom = &params.src_array[(((y * 2) + 1) * src_width)..][..src_width];
+
+        for (x, dst) in dst_row.iter_mut().enumerate() {
+            *dst = f([
+                top[x * 2],
+                top[(x * 2) + 1],
+                bottom[x * 2],
+                bottom[(x * 2) + 1],
+            ]);
+        }
+    }
+}
+
+fn average_alpha(pixels: [[u8; 4]; 4]) -> u8 {
+    let sum = pixels.iter().map(|p| p[3] as u16).sum::<u16>();
+
+    ((sum + 2) / 4) as u8
+}
+
+// Average the encoded values, rounding to nearest. This treats sRGB as if it
+// were linear, so it darkens high contrast detail.
+pub fn downsample_box_single(pixels: [[u8; 4]; 4]) -> [u8; 4] {
+    std::array::from_fn(|c| {
+        let sum = pixels.iter().map(|p| p[c] as u16).sum::<u16>();
+
+        ((sum + 2) / 4) as u8
+    })
+}
+
+// Average in linear space using the lookup tables. Alpha is linear, so it's
+// averaged the same way as `downsample_box_single`.
+pub fn downsample_srgb_lut_single(pixels: [[u8; 4]; 4], lut: &SrgbLut) -> [u8; 4] {
+    let max = (SrgbLut::FROM_LINEAR_SIZE - 1) as f32;
+
+    let [r, g, b] = [0, 1, 2].map(|c| {
+        let sum = pixels
+            .iter()
+            .map(|p| lut.to_linear[p[c] as usize])
+            .sum::<f32>();
+
+        lut.from_linear[(((sum * 0.25).clamp(0.0, 1.0) * max) + 0.5) as usize]
+    });
+
+    [r, g, b, average_alpha(pixels)]
+}
+
+// Average in linear space using the piecewise sRGB curve.
+pub fn downsample_srgb_exact_single(pixels: [[u8; 4]; 4]) -> [u8; 4] {
+    let [r, g, b] = [0, 1, 2].map(|c| {
+        let sum = pixels
+            .iter()
+            .map(|p| srgb_to_linear_single((p[c] as f32) * (1.0 / 255.0)))
+            .sum::<f32>();
+
+        (linear_to_srgb_single(sum * 0.25) * 255.0).round() as u8
+    });
+
+    [r, g, b, average_alpha(pixels)]
+}
+
+#[inline(never)]
+pub fn downsample_box(params: &mut DownsampleParams) {
+    downsample_inner(params, downsample_box_single);
+}
+
+#[inline(never)]
+pub fn downsample_srgb_lut(params: &mut DownsampleParams, lut: &SrgbLut) {
+    downsample_inner(params, |pixels| downsample_srgb_lut_single(pixels, lut));
+}
+
+#[inline(never)]
+pub fn downsample_srgb_exact(params: &mut DownsampleParams) {
+    downsample_inner(params, downsample_srgb_exact_single);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Return smooth gradients with a little noise in each channel. Random pixels
+// would be the worst case for block compression, and unlike most textures.
+pub fn random_texture_image<R: Rng + ?Sized>(
+    rng: &mut R,
+    width: usize,
+    height: usize,
+) -> CacheAlignedVec<[u8; 4]> {
+    let waves: [[f32; 4]; 4] = std::array::from_fn(|_| {
+        [
+            rng.gen_range(0.01..0.1),
+            rng.gen_range(0.0..TAU),
+            rng.gen_range(0.01..0.1),
+            rng.gen_range(0.0..TAU),
+        ]
+    });
+
+    (0..(width * height))
+        .map(|i| {
+            let (x, y) = ((i % width) as f32, (i / width) as f32);
+
+            waves.map(|[fx, px, fy, py]| {
+                let v = 128.0
+                    + (50.0 * ((x * fx) + px).sin())
+                    + (50.0 * ((y * fy) + py).sin())
+                    + rng.gen_range(-8.0..8.0);
+
+                v.clamp(0.0, 255.0) as u8
+            })
+        })
+        .collect()
+}
+
+// Compresses an RGBA8 image to 8 bytes per 4x4 block of pixels, with blocks
+// in row order. The width and height must be multiples of four.
+pub struct BlockCompressParams<'a> {
+    pub dst_array: &'a mut [u64],
+    pub src_array: &'a [[u8; 4]],
+    pub src_width: usize,
+}
+
+pub fn block_compress_inner<F>(params: &mut BlockCompressParams, f: F)
+where
+    F: Fn(&[[u8; 4]; 16]) -> u64,
+{
+    let src_width = params.src_width;
+    let blocks_wide = src_width / 4;
+
+    assert!((src_width & 3) == 0);
+    assert_eq!(params.dst_array.len() * 16, params.src_array.len());
+
+    for (by, dst_row) in params.dst_array.chunks_exact_mut(blocks_wide).enumerate() {
+        let rows = &params.src_array[(by * 4 * src_width)..][..(4 * src_width)];
+
+        for (bx, dst) in dst_row.iter_mut().enumerate() {
+            let block = std::array::from_fn(|i| rows[((i / 4) * src_width) + (bx * 4) + (i % 4)]);
+
+            *dst = f(&block);
+        }
+    }
+}
+
+fn rgb_to_565(c: Vec3) -> u16 {
+    let r = ((c.x * (31.0 / 255.0)) + 0.5) as u16;
+    let g = ((c.y * (63.0 / 255.0)) + 0.5) as u16;
+    let b = ((c.z * (31.0 / 255.0)) + 0.5) as u16;
+
+    (r << 11) | (g << 5) | b
+}
+
+// Expand by copying the high bits into the low bits, as GPUs do.
+fn rgb_from_565(c: u16) -> [u8; 3] {
+    let r = (c >> 11) as u8;
+    let g = ((c >> 5) & 0x3f) as u8;
+    let b = (c & 0x1f) as u8;
+
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+// BC1 with the endpoints at the corners of the block's bounding box, and each
+// pixel mapped to the nearest of the four colors along the line between them.
+// This is the fastest reasonable encoder, but it ignores how the colors are
+// distributed inside the box. Alpha is ignored.
+pub fn bc1_encode_range_fit_single(block: &[[u8; 4]; 16]) -> u64 {
+    let colors = block.map(|[r, g, b, _]| Vec3::new(r as f32, g as f32, b as f32));
+
+    let min = colors.iter().fold(Vec3::splat(255.0), |m, &c| m.min(c));
+    let max = colors.iter().fold(Vec3::ZERO, |m, &c| m.max(c));
+
+    // Every channel of `max` is at least `min`, so `c0 >= c1`. If they're
+    // equal then the indices are all zero.
+    let c0 = rgb_to_565(max);
+    let c1 = rgb_to_565(min);
+
+    let mut bits = (c0 as u64) | ((c1 as u64) << 16);
+
+    if c0 == c1 {
+        return bits;
+    }
+
+    let e0 = Vec3::from_array(rgb_from_565(c0).map(|c| c as f32));
+    let e1 = Vec3::from_array(rgb_from_565(c1).map(|c| c as f32));
+
+    let direction = e1 - e0;
+    let scale = 3.0 / direction.length_squared();
+
+    // Steps along the line from `e0` to `e1`, in index order.
+    const INDICES: [u64; 4] = [0, 2, 3, 1];
+
+    for (i, &c) in colors.iter().enumerate() {
+        let step = (((c - e0).dot(direction) * scale) + 0.5).clamp(0.0, 3.0) as usize;
+
+        bits |= INDICES[step] << (32 + (i * 2));
+    }
+
+    bits
+}
+
+pub fn bc1_decode_single(bits: u64) -> [[u8; 4]; 16] {
+    let (c0, c1) = (bits as u16, (bits >> 16) as u16);
+    let (e0, e1) = (rgb_from_565(c0), rgb_from_565(c1));
+
+    let mix = |w0: u16, w1: u16, d: u16| -> [u8; 4] {
+        let [r, g, b] = std::array::from_fn(|c| {
+            (((e0[c] as u16 * w0) + (e1[c] as u16 * w1) + (d / 2)) / d) as u8
+        });
+
+        [r, g, b, 255]
+    };
+
+    // Four colors if `c0 > c1`, otherwise three and black.
+    let palette = if c0 > c1 {
+        [mix(1, 0, 1), mix(0, 1, 1), mix(2, 1, 3), mix(1, 2, 3)]
+    } else {
+        [mix(1, 0, 1), mix(0, 1, 1), mix(1, 1, 2), [0, 0, 0, 255]]
+    };
+
+    std::array::from_fn(|i| palette[((bits >> (32 + (i * 2))) & 3) as usize])
+}
+
+// BC4 with the endpoints at the block's minimum and maximum, and each value
+// mapped to the nearest of the eight values between them.
+pub fn bc4_encode_range_fit_single(values: [u8; 16]) -> u64 {
+    let min = values.iter().copied().min().unwrap();
+    let max = values.iter().copied().max().unwrap();
+
+    // `r0 > r1` selects eight values. If they're equal then the indices are
+    // all zero.
+    let mut bits = (max as u64) | ((min as u64) << 8);
+
+    if max == min {
+        return bits;
+    }
+
+    let scale = 7.0 / ((max - min) as f32);
+
+    for (i, &v) in values.iter().enumerate() {
+        let step = ((((max - v) as f32) * scale) + 0.5) as u64;
+
+        let index = match step {
+            0 => 0,
+            7 => 1,
+            s => s + 1,
+        };
+
+        bits |= index << (16 + (i * 3));
+    }
+
+    bits
+}
+
+pub fn bc4_decode_single(bits: u64) -> [u8; 16] {
+    let (r0, r1) = ((bits & 0xff) as u16, ((bits >> 8) & 0xff) as u16);
+
+    let mix = |w0: u16, w1: u16, d: u16| (((r0 * w0) + (r1 * w1) + (d / 2)) / d) as u8;
+
+    // Eight values if `r0 > r1`, otherwise six, zero and one.
+    let palette: [u8; 8] = if r0 > r1 {
+        std::array::from_fn(|i| match i {
+            0 => r0 as u8,
+            1 => r1 as u8,
+            i => mix(8 - i as u16, i as u16 - 1, 7),
+        })
+    } else {
+        std::array::from_fn(|i| match i {
+            0 => r0 as u8,
+            1 => r1 as u8,
+            6 => 0,
+            7 => 255,
+            i => mix(6 - i as u16, i as u16 - 1, 5),
+        })
+    };
+
+    std::array::from_fn(|i| palette[((bits >> (16 + (i * 3))) & 7) as usize])
+}
+
+#[inline(never)]
+pub fn bc1_encode_range_fit(params: &mut BlockCompressParams) {
+    block_compress_inner(params, bc1_encode_range_fit_single);
+}
+
+// Compresses the red channel, as for a roughness or height map.
+#[inline(never)]
+pub fn bc4_encode_range_fit(params: &mut BlockCompressParams) {
+    block_compress_inner(params, |block| {
+        bc4_encode_range_fit_single(block.map(|[r, ..]| r))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng},
+        util::random_array,
+    };
+
+    #[test]
+    fn downsample() {
+        // Averaging black and white in linear space gives a lighter gray than
+        // averaging the encoded values.
+
+        let checker = [[0, 0, 0, 0], [255, 255, 255, 255]];
+        let pixels = [checker[0], checker[1], checker[1], checker[0]];
+
+        let lut = SrgbLut::new();
+
+        assert_eq!(downsample_box_single(pixels), [128, 128, 128, 128]);
+        assert_eq!(downsample_srgb_exact_single(pixels), [188, 188, 188, 128]);
+        assert_eq!(
+            downsample_srgb_lut_single(pixels, &lut),
+            [188, 188, 188, 128]
+        );
+
+        // The lookup tables round differently, so allow one step of error.
+
+        let mut rng = test_rng();
+
+        let (width, height) = (64, 32);
+
+        let src_array = random_array::<[u8; 4]>(&mut rng, width * height);
+
+        let run = |f: &dyn Fn(&mut DownsampleParams)| {
+            run_into(vec![[0; 4]; (width / 2) * (height / 2)], |dst_array| {
+                f(&mut DownsampleParams {
+                    dst_array,
+                    src_array: &src_array,
+                    src_width: width,
+                })
+            })
+        };
+
+        let exact = run(&downsample_srgb_exact);
+        let table = run(&|p| downsample_srgb_lut(p, &lut));
+
+        for (e, t) in exact.iter().zip(&table) {
+            for c in 0..4 {
+                assert!(e[c].abs_diff(t[c]) <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn block_compress() {
+        // Solid blocks round trip exactly if the color is exact in 5:6:5.
+
+        let solid = [[0x84, 0x82, 0x08, 255]; 16];
+
+        assert_eq!(
+            bc1_decode_single(bc1_encode_range_fit_single(&solid)),
+            solid
+        );
+
+        // A block with two values round trips exactly.
+
+        let values = std::array::from_fn(|i| [10, 200][i & 1]);
+
+        assert_eq!(
+            bc4_decode_single(bc4_encode_range_fit_single(values)),
+            values
+        );
+
+        // Smooth images have a small average error.
+
+        let mut rng = test_rng();
+
+        let (width, height) = (64, 32);
+
+        let src_array = random_texture_image(&mut rng, width, height);
+
+        let run = |f: fn(&mut BlockCompressParams)| {
+            run_into(vec![0; (width / 4) * (height / 4)], |dst_array| {
+                f(&mut BlockCompressParams {
+                    dst_array,
+                    src_array: &src_array,
+                    src_width: width,
+                })
+            })
+        };
+
+        // Return the mean absolute error of each channel, comparing each
+        // decoded block with the matching source pixels.
+        let mean_error = |blocks: &[u64], decode: &dyn Fn(u64) -> [[u8; 4]; 16]| {
+            let mut sum = [0u32; 4];
+
+            for (b, &bits) in blocks.iter().enumerate() {
+                let (bx, by) = (b % (width / 4), b / (width / 4));
+
+                for (i, p) in decode(bits).iter().enumerate() {
+                    let s = src_array[(((by * 4) + (i / 4)) * width) + (bx * 4) + (i % 4)];
+
+                    for c in 0..4 {
+                        sum[c] += p[c].abs_diff(s[c]) as u32;
+                    }
+                }
+            }
+
+            sum.map(|s| (s as f32) / ((width * height) as f32))
+        };
+
+        let bc1 = mean_error(&run(bc1_encode_range_fit), &bc1_decode_single);
+
+        assert!(bc1[..3].iter().all(|&e| e < 6.0));
+
+        let bc4 = mean_error(&run(bc4_encode_range_fit), &|bits| {
+            bc4_decode_single(bits).map(|r| [r, 0, 0, 0])
+        });
+
+        assert!(bc4[0] < 1.5);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::{
+    kernels::isometry::{compose_transform, IsometryComposeParams},
+    util::CACHE_LINE_SIZE,
+};
+use bevy_transform::components::Transform;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Barrier, Mutex, RwLock,
+    },
+    thread::{self, JoinHandle},
+};
+
+// A value with a cache line to itself, so writing it doesn't invalidate any
+// other value.
+#[derive(Debug, Default)]
+#[repr(align(64))]
+pub struct CachePadded<T>(pub T);
+
+const _: () = assert!(align_of::<CachePadded<u8>>() == CACHE_LINE_SIZE);
+
+// Run `f(i)` for each `i` in `0..thread_count`, each on a new scoped thread,
+// and wait for them all to finish.
+pub fn run_on_threads(thread_count: usize, f: impl Fn(usize) + Sync) {
+    thread::scope(|s| {
+        for i in 0..thread_count {
+            let f = &f;
+
+            s.spawn(move || f(i));
+        }
+    });
+}
+
+// Threads that are spawned once, then run the same job each time `run` is
+// called. The caller and the workers meet at a barrier to start the job and
+// again when it's finished, so the cost per run is two barrier waits instead
+// of spawning and joining.
+pub struct BarrierPool {
+    shared: Arc<BarrierPoolShared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+struct BarrierPoolShared {
+    start: Barrier,
+    end: Barrier,
+    stop: AtomicBool,
+}
+
+impl BarrierPool {
+    // Spawn `thread_count` workers that each call `job` with their index.
+    pub fn new(thread_count: usize, job: impl Fn(usize) + Send + Sync + 'static) -> Self {
+        let shared = Arc::new(BarrierPoolShared {
+            start: Barrier::new(thread_count + 1),
+            end: Barrier::new(thread_count + 1),
+            stop: AtomicBool::new(false),
+        });
+
+        let job = Arc::new(job);
+
+        let threads = (0..thread_count)
+            .map(|i| {
+                let (shared, job) = (shared.clone(), job.clone());
+
+                thread::spawn(move || loop {
+                    shared.start.wait();
+
+                    if shared.stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    job(i);
+
+                    shared.end.wait();
+                })
+            })
+            .collect();
+
+        BarrierPool { shared, threads }
+    }
+
+    pub fn thread_count(&self) -> usize {
+        self.threads.len()
+    }
+
+    // Run the job once on every worker, and wait for them all to finish.
+    pub fn run(&self) {
+        self.shared.start.wait();
+        self.shared.end.wait();
+    }
+}
+
+impl Drop for BarrierPool {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        self.shared.start.wait();
+
+        for thread in self.threads.drain(..) {
+            thread.join().unwrap();
+        }
+    }
+}
+
+// Increment a counter `iterations` times. Each counter only has one writer, so
+// a relaxed load and store is enough - the atomic is just to make sharing the
+// cache line legal. If other threads are writing to the same line then every
+// store has to take the line back from them.
+#[inline(never)]
+pub fn count(counter: &AtomicU64, iterations: u64) {
+    for _ in 0..iterations {
+        counter.store(counter.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+    }
+}
+
+// Increment a counter that every thread shares. `fetch_add` is a locked
+// read-modify-write, so it stays correct, but the threads take turns owning
+// the cache line.
+#[inline(never)]
+pub fn count_shared(counter: &AtomicU64, iterations: u64) {
+    for _ in 0..iterations {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// Same as `count_shared`, but with a lock around a plain integer.
+#[inline(never)]
+pub fn count_mutex(counter: &Mutex<u64>, iterations: u64) {
+    for _ in 0..iterations {
+        *counter.lock().unwrap() += 1;
+    }
+}
+
+// A lock around a counter, so the lock benchmarks can swap implementations.
+// Locks without shared access just lock exclusively for reads.
+pub trait CounterLock: Sync {
+    const NAME: &'static str;
+
+    fn new(value: u64) -> Self;
+
+    fn read(&self) -> u64;
+
+    fn write(&self, f: impl FnOnce(&mut u64));
+}
+
+impl CounterLock for Mutex<u64> {
+    const NAME: &'static str = "std mutex";
+
+    fn new(value: u64) -> Self {
+        Mutex::new(value)
+    }
+
+    fn read(&self) -> u64 {
+        *self.lock().unwrap()
+    }
+
+    fn write(&self, f: impl FnOnce(&mut u64)) {
+        f(&mut self.lock().unwrap());
+    }
+}
+
+impl CounterLock for RwLock<u64> {
+    const NAME: &'static str = "std rwlock";
+
+    fn new(value: u64) -> Self {
+        RwLock::new(value)
+    }
+
+    fn read(&self) -> u64 {
+        *self.read().unwrap()
+    }
+
+    fn write(&self, f: impl FnOnce(&mut u64)) {
+        f(&mut self.write().unwrap());
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl CounterLock for parking_lot::Mutex<u64> {
+    const NAME: &'static str = "parking_lot mutex";
+
+    fn new(value: u64) -> Self {
+        parking_lot::Mutex::new(value)
+    }
+
+    fn read(&self) -> u64 {
+        *self.lock()
+    }
+
+    fn write(&self, f: impl FnOnce(&mut u64)) {
+        f(&mut self.lock());
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl CounterLock for parking_lot::RwLock<u64> {
+    const NAME: &'static str = "parking_lot rwlock";
+
+    fn new(value: u64) -> Self {
+        parking_lot::RwLock::new(value)
+    }
+
+    fn read(&self) -> u64 {
+        *self.read()
+    }
+
+    fn write(&self, f: impl FnOnce(&mut u64)) {
+        f(&mut self.write());
+    }
+}
+
+// Take the lock `iterations` times, incrementing the counter for
+// `write_percent` of them and reading it for the rest. The writes are spread
+// evenly - at 10% every tenth iteration writes. Returns the sum of the reads.
+#[inline(never)]
+pub fn lock_mix<L: CounterLock>(lock: &L, iterations: u64, write_percent: u64) -> u64 {
+    let mut sum = 0u64;
+
+    for i in 0..iterations {
+        if ((i * write_percent) % 100) < write_percent {
+            lock.write(|v| *v += 1);
+        } else {
+            sum = sum.wrapping_add(lock.read());
+        }
+    }
+
+    sum
+}
+
+// Spawn `count` threads that each return their index, then join them and sum
+// the results.
+#[inline(never)]
+pub fn spawn_join_threads(count: u64) -> u64 {
+    let threads = (0..count)
+        .map(|i| thread::spawn(move || i))
+        .collect::<Vec<_>>();
+
+    threads.into_iter().map(|t| t.join().unwrap()).sum()
+}
+
+// Same as `spawn_join_threads`, but with tasks on the current tokio runtime.
+#[cfg(feature = "tokio")]
+pub async fn spawn_await_tasks(count: u64) -> u64 {
+    let tasks = (0..count)
+        .map(|i| tokio::spawn(async move { i }))
+        .collect::<Vec<_>>();
+
+    let mut sum = 0;
+
+    for task in tasks {
+        sum += task.await.unwrap();
+    }
+
+    sum
+}
+
+// Send a value to a new thread and back `round_trips` times, with the other
+// thread adding one each time. Returns the final value.
+#[inline(never)]
+pub fn channel_round_trips_thread(round_trips: u64) -> u64 {
+    let (to_tx, to_rx) = mpsc::channel::<u64>();
+    let (from_tx, from_rx) = mpsc::channel();
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            for value in to_rx {
+                from_tx.send(value + 1).unwrap();
+            }
+        });
+
+        let mut value = 0;
+
+        for _ in 0..round_trips {
+            to_tx.send(value).unwrap();
+            value = from_rx.recv().unwrap();
+        }
+
+        // Close the channel so the other thread finishes.
+        drop(to_tx);
+
+        value
+    })
+}
+
+// Same as `channel_round_trips_thread`, but between tasks on the current tokio
+// runtime.
+#[cfg(feature = "tokio")]
+pub async fn channel_round_trips_task(round_trips: u64) -> u64 {
+    let (to_tx, mut to_rx) = tokio::sync::mpsc::unbounded_channel::<u64>();
+    let (from_tx, mut from_rx) = tokio::sync::mpsc::unbounded_channel();
+
+    let echo = tokio::spawn(async move {
+        while let Some(value) = to_rx.recv().await {
+            from_tx.send(value + 1).unwrap();
+        }
+    });
+
+    let mut value = 0;
+
+    for _ in 0..round_trips {
+        to_tx.send(value).unwrap();
+        value = from_rx.recv().await.unwrap();
+    }
+
+    drop(to_tx);
+    echo.await.unwrap();
+
+    value
+}
+
+// Same as `compose_transform`, but split into tasks of `task_len` elements,
+// each spawned separately into a scope on the current rayon pool.
+#[inline(never)]
+pub fn compose_transform_tasks(params: &mut IsometryComposeParams<Transform>, task_len: usize) {
+    let [l, r] = params.src_array;
+
+    rayon::scope(|s| {
+        for ((dst_array, l), r) in params
+            .dst_array
+            .chunks_mut(task_len)
+            .zip(l.chunks(task_len))
+            .zip(r.chunks(task_len))
+        {
+            s.spawn(move |_| {
+                compose_transform(&mut IsometryComposeParams {
+                    dst_array,
+                    src_array: [l, r],
+                })
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{test_rng, COUNT},
+        util::{random_transform_array, CacheAlignedVec},
+    };
+
+    #[test]
+    fn false_sharing() {
+        const THREADS: usize = 4;
+        const ITERATIONS: u64 = 1003;
+
+        let packed = (0..THREADS)
+            .map(|_| AtomicU64::new(0))
+            .collect::<CacheAlignedVec<_>>();
+
+        let padded = (0..THREADS)
+            .map(|_| CachePadded(AtomicU64::new(0)))
+            .collect::<Vec<_>>();
+
+        run_on_threads(THREADS, |i| {
+            count(&packed[i], ITERATIONS);
+            count(&padded[i].0, ITERATIONS);
+        });
+
+        assert!(packed
+            .iter()
+            .all(|c| c.load(Ordering::Relaxed) == ITERATIONS));
+        assert!(padded
+            .iter()
+            .all(|c| c.0.load(Ordering::Relaxed) == ITERATIONS));
+    }
+
+    #[test]
+    fn contention() {
+        const THREADS: usize = 4;
+        const ITERATIONS: u64 = 1003;
+
+        let shared = AtomicU64::new(0);
+        let mutex = Mutex::new(0);
+
+        run_on_threads(THREADS, |_| {
+            count_shared(&shared, ITERATIONS);
+            count_mutex(&mutex, ITERATIONS);
+        });
+
+        assert_eq!(shared.into_inner(), (THREADS as u64) * ITERATIONS);
+        assert_eq!(mutex.into_inner().unwrap(), (THREADS as u64) * ITERATIONS);
+    }
+
+    #[test]
+    fn locks() {
+        const THREADS: usize = 4;
+        const ITERATIONS: u64 = 1000;
+
+        fn check<L: CounterLock>() {
+            for write_percent in [0, 1, 10, 50, 100] {
+                let lock = L::new(0);
+
+                run_on_threads(THREADS, |_| {
+                    lock_mix(&lock, ITERATIONS, write_percent);
+                });
+
+                let writes = (THREADS as u64) * ITERATIONS * write_percent / 100;
+
+                assert_eq!(lock.read(), writes, "{} {write_percent}%", L::NAME);
+            }
+        }
+
+        check::<Mutex<u64>>();
+        check::<RwLock<u64>>();
+
+        #[cfg(feature = "parking_lot")]
+        {
+            check::<parking_lot::Mutex<u64>>();
+            check::<parking_lot::RwLock<u64>>();
+        }
+    }
+
+    #[test]
+    fn barrier_pool() {
+        const THREADS: usize = 4;
+        const RUNS: u64 = 3;
+
+        let counters = Arc::new(
+            (0..THREADS)
+                .map(|_| CachePadded(AtomicU64::new(0)))
+                .collect::<Vec<_>>(),
+        );
+
+        let pool = BarrierPool::new(THREADS, {
+            let counters = counters.clone();
+
+            move |i| count(&counters[i].0, 1)
+        });
+
+        assert_eq!(pool.thread_count(), THREADS);
+
+        for run in 1..=RUNS {
+            pool.run();
+
+            // The pool waits for the job to finish, so every counter is
+            // already up to date.
+            assert!(counters.iter().all(|c| c.0.load(Ordering::Relaxed) == run));
+        }
+
+        drop(pool);
+
+        assert_eq!(Arc::strong_count(&counters), 1);
+    }
+
+    #[test]
+    fn spawn_and_channels() {
+        assert_eq!(spawn_join_threads(4), 6);
+        assert_eq!(channel_round_trips_thread(1003), 1003);
+
+        #[cfg(feature = "tokio")]
+        {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .build()
+                .unwrap();
+
+            assert_eq!(runtime.block_on(spawn_await_tasks(4)), 6);
+            assert_eq!(runtime.block_on(channel_round_trips_task(1003)), 1003);
+        }
+    }
+
+    #[test]
+    fn compose_tasks() {
+        let mut rng = test_rng();
+
+        let transforms = [
+            random_transform_array(&mut rng, COUNT),
+            random_transform_array(&mut rng, COUNT),
+        ];
+
+        let mut expected = vec![Transform::IDENTITY; COUNT];
+
+        compose_transform(&mut IsometryComposeParams {
+            dst_array: &mut expected,
+            src_array: [&transforms[0], &transforms[1]],
+        });
+
+        for task_len in [1, 64, COUNT * 2] {
+            let mut composed = vec![Transform::IDENTITY; COUNT];
+
+            compose_transform_tasks(
+                &mut IsometryComposeParams {
+                    dst_array: &mut composed,
+                    src_array: [&transforms[0], &transforms[1]],
+                },
+                task_len,
+            );
+
+            assert_eq!(composed, expected);
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use core::f32::consts::{FRAC_2_PI, TAU};
+
+// The standard library's transcendental functions usually call the platform's
+// C library, so results and performance can vary by platform. libm is a Rust
+// port of musl that gives the same results everywhere. micromath trades
+// accuracy for speed - its sin, cos and atan2 have a maximum error of 0.002.
+
+pub struct UnaryParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub src_array: &'a [f32],
+}
+
+pub fn unary_func<F>(params: &mut UnaryParams, f: F)
+where
+    F: Fn(f32) -> f32,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(params.src_array[i]);
+    }
+}
+
+pub struct BinaryParams<'a> {
+    pub dst_array: &'a mut [f32],
+    pub src_array: [&'a [f32]; 2],
+}
+
+pub fn binary_func<F>(params: &mut BinaryParams, f: F)
+where
+    F: Fn(f32, f32) -> f32,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(params.src_array[0][i], params.src_array[1][i]);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[inline(never)]
+pub fn sin_std(params: &mut UnaryParams) {
+    unary_func(params, f32::sin);
+}
+
+#[inline(never)]
+pub fn cos_std(params: &mut UnaryParams) {
+    unary_func(params, f32::cos);
+}
+
+#[inline(never)]
+pub fn exp_std(params: &mut UnaryParams) {
+    unary_func(params, f32::exp);
+}
+
+#[inline(never)]
+pub fn atan2_std(params: &mut BinaryParams) {
+    binary_func(params, f32::atan2);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[inline(never)]
+pub fn sin_libm(params: &mut UnaryParams) {
+    unary_func(params, libm::sinf);
+}
+
+#[inline(never)]
+pub fn cos_libm(params: &mut UnaryParams) {
+    unary_func(params, libm::cosf);
+}
+
+#[inline(never)]
+pub fn exp_libm(params: &mut UnaryParams) {
+    unary_func(params, libm::expf);
+}
+
+#[inline(never)]
+pub fn atan2_libm(params: &mut BinaryParams) {
+    binary_func(params, libm::atan2f);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "micromath")]
+#[inline(never)]
+pub fn sin_micromath(params: &mut UnaryParams) {
+    unary_func(params, micromath::F32Ext::sin);
+}
+
+#[cfg(feature = "micromath")]
+#[inline(never)]
+pub fn cos_micromath(params: &mut UnaryParams) {
+    unary_func(params, micromath::F32Ext::cos);
+}
+
+#[cfg(feature = "micromath")]
+#[inline(never)]
+pub fn exp_micromath(params: &mut UnaryParams) {
+    unary_func(params, micromath::F32Ext::exp);
+}
+
+#[cfg(feature = "micromath")]
+#[inline(never)]
+pub fn atan2_micromath(params: &mut BinaryParams) {
+    binary_func(params, micromath::F32Ext::atan2);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct SinCosParams<'a> {
+    pub dst_array: &'a mut [(f32, f32)],
+    pub src_array: &'a [f32],
+}
+
+#[inline(never)]
+pub fn sin_cos_std(params: &mut SinCosParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.src_array[i].sin_cos();
+    }
+}
+
+// Minimax polynomials for sin and cos over [-PI/4, PI/4], from Cephes.
+#[inline]
+pub fn minimax_sin_cos(x: f32) -> (f32, f32) {
+    // Reduce to [-PI/4, PI/4] by subtracting the nearest multiple of PI/2.
+    // PI/2 is split into three parts so that the subtraction is exact for
+    // moderately large inputs.
+    const PI_2_A: f32 = 1.5703125;
+    const PI_2_B: f32 = 4.837_513e-4;
+    const PI_2_C: f32 = 7.549_79e-8;
+
+    let k = (x * FRAC_2_PI).round();
+    let r = ((x - (k * PI_2_A)) - (k * PI_2_B)) - (k * PI_2_C);
+    let z = r * r;
+
+    let sin = r + (r * z * (-1.666_665_5e-1 + (z * (8.332_161e-3 + (z * -1.951_529_6e-4)))));
+    let cos = 1.0 - (0.5 * z)
+        + (z * z * (4.166_664_6e-2 + (z * (-1.388_731_6e-3 + (z * 2.443_315_7e-5)))));
+
+    // Rotate the result by the quadrant.
+    match (k as i32) & 3 {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+#[inline(never)]
+pub fn sin_cos_minimax(params: &mut SinCosParams) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = minimax_sin_cos(params.src_array[i]);
+    }
+}
+
+// A lookup table of sin sampled at evenly spaced points over one period. The
+// size must be a power of two so that indices can wrap with a mask, and cos
+// reads the same table a quarter period ahead.
+pub struct SinCosLut {
+    // One more than the size so that linear interpolation can read the entry
+    // after the last interval without wrapping.
+    values: Vec<f32>,
+    scale: f32,
+    mask: usize,
+}
+
+impl SinCosLut {
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two() && size >= 4);
+
+        SinCosLut {
+            values: (0..=size)
+                .map(|i| ((i as f64 / size as f64) * core::f64::consts::TAU).sin() as f32)
+                .collect(),
+            scale: size as f32 / TAU,
+            mask: size - 1,
+        }
+    }
+
+    #[inline]
+    pub fn sample_linear(&self, x: f32) -> (f32, f32) {
+        let x = x * self.scale;
+        let floor = x.floor();
+        let a = x - floor;
+        let i = (floor as i32 as usize) & self.mask;
+        let j = (i + ((self.mask + 1) / 4)) & self.mask;
+
+        let lerp = |k: usize| self.values[k] + ((self.values[k + 1] - self.values[k]) * a);
+
+        (lerp(i), lerp(j))
+    }
+}
+
+#[inline(never)]
+pub fn sin_cos_lut_linear(params: &mut SinCosParams, lut: &SinCosLut) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = lut.sample_linear(params.src_array[i]);
+    }
+}
+
+// Return the largest absolute error of either sin or cos, compared to f64.
+pub fn sin_cos_max_error(dst_array: &[(f32, f32)], src_array: &[f32]) -> f32 {
+    dst_array
+        .iter()
+        .zip(src_array.iter())
+        .map(|(&(sin, cos), &x)| {
+            let (expected_sin, expected_cos) = (x as f64).sin_cos();
+
+            ((sin as f64 - expected_sin).abs()).max((cos as f64 - expected_cos).abs()) as f32
+        })
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng, ulps, COUNT},
+        util::*,
+    };
+
+    const MAX_ULPS: u32 = 4;
+
+    fn run_unary(f: fn(&mut UnaryParams), src_array: &[f32]) -> Vec<f32> {
+        run_into(vec![0.0; COUNT], |dst_array| {
+            f(&mut UnaryParams {
+                dst_array,
+                src_array,
+            })
+        })
+    }
+
+    fn run_binary(f: fn(&mut BinaryParams), src_array: [&[f32]; 2]) -> Vec<f32> {
+        run_into(vec![0.0; COUNT], |dst_array| {
+            f(&mut BinaryParams {
+                dst_array,
+                src_array,
+            })
+        })
+    }
+
+    // Returns the sin/cos inputs, exp inputs and atan2 inputs.
+    fn random_src_arrays() -> [Vec<f32>; 4] {
+        let mut rng = test_rng();
+
+        let mut random = |scale: f32| {
+            random_array::<f32>(&mut rng, COUNT)
+                .iter()
+                .map(|t| ((t * 2.0) - 1.0) * scale)
+                .collect::<Vec<_>>()
+        };
+
+        [random(100.0), random(80.0), random(1.0), random(1.0)]
+    }
+
+    #[test]
+    fn sin_cos() {
+        let [trig, ..] = random_src_arrays();
+
+        let run = |f: &dyn Fn(&mut SinCosParams)| {
+            let mut dst_array = vec![(0.0, 0.0); COUNT];
+
+            f(&mut SinCosParams {
+                dst_array: &mut dst_array,
+                src_array: &trig,
+            });
+
+            sin_cos_max_error(&dst_array, &trig)
+        };
+
+        assert!(run(&sin_cos_std) < 1e-6);
+        assert!(run(&sin_cos_minimax) < 1e-5);
+        assert!(run(&|params| sin_cos_lut_linear(params, &SinCosLut::new(1024))) < 1e-5);
+        assert!(run(&|params| sin_cos_lut_linear(params, &SinCosLut::new(256))) < 1e-4);
+    }
+
+    #[test]
+    fn libm_matches_std() {
+        let [trig, exp, y, x] = random_src_arrays();
+
+        for (expected, actual) in [
+            (run_unary(sin_std, &trig), run_unary(sin_libm, &trig)),
+            (run_unary(cos_std, &trig), run_unary(cos_libm, &trig)),
+            (run_unary(exp_std, &exp), run_unary(exp_libm, &exp)),
+            (
+                run_binary(atan2_std, [&y, &x]),
+                run_binary(atan2_libm, [&y, &x]),
+            ),
+        ] {
+            for (&e, &a) in expected.iter().zip(actual.iter()) {
+                assert!(ulps(e, a) <= MAX_ULPS, "expected {e}, got {a}");
+            }
+        }
+    }
+
+    // The documented maximum error is 0.002, but atan2 exceeds it slightly, so
+    // allow a little more.
+    #[cfg(feature = "micromath")]
+    #[test]
+    fn micromath_near_std() {
+        let [trig, exp, y, x] = random_src_arrays();
+
+        for (expected, actual) in [
+            (run_unary(sin_std, &trig), run_unary(sin_micromath, &trig)),
+            (run_unary(cos_std, &trig), run_unary(cos_micromath, &trig)),
+            (
+                run_binary(atan2_std, [&y, &x]),
+                run_binary(atan2_micromath, [&y, &x]),
+            ),
+        ] {
+            for (&e, &a) in expected.iter().zip(actual.iter()) {
+                assert!((e - a).abs() <= 0.005, "expected {e}, got {a}");
+            }
+        }
+
+        // exp has no documented bound, so only check the relative error is
+        // small.
+        let expected = run_unary(exp_std, &exp);
+        let actual = run_unary(exp_micromath, &exp);
+
+        for (&e, &a) in expected.iter().zip(actual.iter()) {
+            assert!(((e - a) / e).abs() <= 0.01, "expected {e}, got {a}");
+        }
+    }
+}
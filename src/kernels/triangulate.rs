@@ -0,0 +1,333 @@
+use glam::Vec2;
+use rand::Rng;
+use std::f32::consts::TAU;
+
+// An outer ring of vertices in counter-clockwise order, followed by any holes
+// in clockwise order. Each entry in `holes` is the index of a hole's first
+// vertex.
+#[derive(Clone, Debug, Default)]
+pub struct Polygon {
+    pub vertices: Vec<Vec2>,
+    pub holes: Vec<usize>,
+}
+
+impl Polygon {
+    fn ring(&self, ring: usize) -> std::ops::Range<usize> {
+        let start = if ring == 0 { 0 } else { self.holes[ring - 1] };
+        let end = self.holes.get(ring).copied().unwrap_or(self.vertices.len());
+
+        start..end
+    }
+
+    // Outer area minus the hole areas, since holes are clockwise.
+    pub fn area(&self) -> f32 {
+        (0..=self.holes.len())
+            .map(|ring| {
+                let range = self.ring(ring);
+                let ring = &self.vertices[range];
+
+                ring.iter()
+                    .zip(ring.iter().cycle().skip(1))
+                    .map(|(a, b)| a.perp_dot(*b))
+                    .sum::<f32>()
+                    * 0.5
+            })
+            .sum()
+    }
+}
+
+// Return vertices in counter-clockwise order around a circle. Each vertex is at
+// a random angle within an equal slice, so there are no large gaps.
+fn circle_ring<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+    center: Vec2,
+    radius: impl Fn(&mut R) -> f32,
+) -> Vec<Vec2> {
+    (0..count)
+        .map(|i| {
+            let angle = ((i as f32) + rng.gen::<f32>()) * TAU / (count as f32);
+
+            center + (Vec2::from_angle(angle) * radius(rng))
+        })
+        .collect()
+}
+
+// A convex polygon with vertices on a circle.
+pub fn random_convex_polygon<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Polygon {
+    Polygon {
+        vertices: circle_ring(rng, count, Vec2::ZERO, |_| 1.0),
+        holes: Vec::new(),
+    }
+}
+
+// A star shaped polygon with a random radius at each vertex, so roughly half
+// the vertices are reflex.
+pub fn random_concave_polygon<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Polygon {
+    Polygon {
+        vertices: circle_ring(rng, count, Vec2::ZERO, |rng| rng.gen_range(0.5..1.0)),
+        holes: Vec::new(),
+    }
+}
+
+// A concave polygon with half the vertices, and four circular holes with the
+// rest. The count should be at least 32 so the holes are inside.
+pub fn random_polygon_with_holes<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Polygon {
+    let hole_count = count / 8;
+
+    let mut polygon = random_concave_polygon(rng, count - (hole_count * 4));
+
+    for center in [(-0.2, -0.2), (0.2, -0.2), (-0.2, 0.2), (0.2, 0.2)] {
+        polygon.holes.push(polygon.vertices.len());
+
+        let hole = circle_ring(rng, hole_count, Vec2::from(center), |_| 0.1);
+
+        polygon.vertices.extend(hole.into_iter().rev());
+    }
+
+    polygon
+}
+
+fn is_convex(a: Vec2, b: Vec2, c: Vec2) -> bool {
+    (b - a).perp_dot(c - b) > 0.0
+}
+
+// Points on the boundary count as inside.
+pub fn triangle_contains(a: Vec2, b: Vec2, c: Vec2, p: Vec2) -> bool {
+    ((b - a).perp_dot(p - a) >= 0.0)
+        && ((c - b).perp_dot(p - b) >= 0.0)
+        && ((a - c).perp_dot(p - c) >= 0.0)
+}
+
+// Eberly, "Triangulation by Ear Clipping" (2002). Join each hole to the outer
+// ring with a pair of coincident edges, starting with the hole that's furthest
+// right. Returns the joined ring as indices into the polygon's vertices.
+fn bridge_holes(polygon: &Polygon) -> Vec<u32> {
+    let vertices = &polygon.vertices;
+    let position = |i: u32| vertices[i as usize];
+
+    let mut ring = polygon.ring(0).map(|i| i as u32).collect::<Vec<_>>();
+
+    let mut holes = (1..=polygon.holes.len())
+        .map(|h| {
+            let range = polygon.ring(h);
+
+            // The rightmost vertex of the hole.
+            range
+                .max_by(|&l, &r| vertices[l].x.total_cmp(&vertices[r].x))
+                .map(|m| (h, m as u32))
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    holes.sort_by(|l, r| position(r.1).x.total_cmp(&position(l.1).x));
+
+    for (hole, m) in holes {
+        let mp = position(m);
+
+        // Cast a ray to the right and find the closest edge it hits.
+
+        let mut closest = (f32::INFINITY, 0);
+
+        for k in 0..ring.len() {
+            let (a, b) = (position(ring[k]), position(ring[(k + 1) % ring.len()]));
+
+            // The ring is counter-clockwise, so edges crossing the ray from
+            // below are facing the hole.
+            if (a.y <= mp.y) && (b.y > mp.y) {
+                let x = a.x + ((mp.y - a.y) * (b.x - a.x) / (b.y - a.y));
+
+                if (x >= mp.x) && (x < closest.0) {
+                    closest = (x, k);
+                }
+            }
+        }
+
+        let (x, k) = closest;
+
+        assert!(x.is_finite(), "hole is outside the polygon");
+
+        let intersection = Vec2::new(x, mp.y);
+
+        // The edge's rightmost vertex is visible unless a reflex vertex is
+        // inside the triangle between it, the hole vertex and the intersection.
+        // If so, the reflex vertex with the smallest angle to the ray is.
+
+        let mut p = if position(ring[k]).x > position(ring[(k + 1) % ring.len()]).x {
+            k
+        } else {
+            (k + 1) % ring.len()
+        };
+
+        let pp = position(ring[p]);
+
+        let (ta, tb, tc) = if mp.y < pp.y {
+            (mp, intersection, pp)
+        } else {
+            (mp, pp, intersection)
+        };
+
+        let mut best_angle = f32::INFINITY;
+
+        for j in 0..ring.len() {
+            let v = position(ring[j]);
+            let prev = position(ring[(j + ring.len() - 1) % ring.len()]);
+            let next = position(ring[(j + 1) % ring.len()]);
+
+            if (j != p) && !is_convex(prev, v, next) && triangle_contains(ta, tb, tc, v) {
+                let angle = (v - mp).angle_to(Vec2::X).abs();
+
+                if angle < best_angle {
+                    best_angle = angle;
+                    p = j;
+                }
+            }
+        }
+
+        // Splice in the hole, starting and ending at its rightmost vertex, then
+        // return to the bridge vertex.
+
+        let range = polygon.ring(hole);
+        let len = range.len();
+        let start = m as usize - range.start;
+
+        let hole_ring = (0..=len).map(|i| (range.start + ((start + i) % len)) as u32);
+
+        let splice = hole_ring.chain([ring[p]]).collect::<Vec<_>>();
+
+        ring.splice((p + 1)..(p + 1), splice);
+    }
+
+    ring
+}
+
+// Repeatedly cut off a convex vertex whose triangle has no other vertices
+// inside. Only reflex vertices can be inside an ear, so `reflex_only` skips
+// testing the rest. Either way it's quadratic in the worst case.
+fn ear_clip(polygon: &Polygon, reflex_only: bool) -> Vec<[u32; 3]> {
+    let ring = bridge_holes(polygon);
+    let len = ring.len();
+
+    let position = |i: usize| polygon.vertices[ring[i] as usize];
+
+    let mut prev = (0..len).map(|i| (i + len - 1) % len).collect::<Vec<_>>();
+    let mut next = (0..len).map(|i| (i + 1) % len).collect::<Vec<_>>();
+
+    let mut reflex = (0..len)
+        .map(|i| !is_convex(position(prev[i]), position(i), position(next[i])))
+        .collect::<Vec<_>>();
+
+    // A vertex can only change from reflex to convex, so this list is
+    // filtered as it's used rather than updated.
+    let mut reflex_list = (0..len).filter(|&i| reflex[i]).collect::<Vec<_>>();
+
+    let mut removed = vec![false; len];
+    let mut triangles = Vec::with_capacity(len.saturating_sub(2));
+
+    let mut remaining = len;
+    let mut i = 0;
+    let mut misses = 0;
+
+    while remaining > 3 {
+        let (p, n) = (prev[i], next[i]);
+        let (a, b, c) = (position(p), position(i), position(n));
+
+        let candidates: &mut dyn Iterator<Item = usize> = if reflex_only {
+            &mut reflex_list.iter().copied()
+        } else {
+            &mut (0..len)
+        };
+
+        // Vertices that coincide with the triangle are the other side of a
+        // bridge, so they don't count.
+        let is_ear = !reflex[i]
+            && !candidates
+                .filter(|&j| !removed[j] && (j != p) && (j != i) && (j != n))
+                .map(position)
+                .any(|v| (v != a) && (v != b) && (v != c) && triangle_contains(a, b, c, v));
+
+        if is_ear {
+            triangles.push([ring[p], ring[i], ring[n]]);
+
+            removed[i] = true;
+            next[p] = n;
+            prev[n] = p;
+            remaining -= 1;
+
+            for k in [p, n] {
+                reflex[k] = !is_convex(position(prev[k]), position(k), position(next[k]));
+            }
+
+            if reflex_only {
+                reflex_list.retain(|&r| reflex[r] && !removed[r]);
+            }
+
+            i = p;
+            misses = 0;
+        } else {
+            i = n;
+            misses += 1;
+
+            // Rounding can leave no ears on a nearly degenerate polygon.
+            if misses > remaining {
+                break;
+            }
+        }
+    }
+
+    if remaining == 3 {
+        triangles.push([ring[prev[i]], ring[i], ring[next[i]]]);
+    }
+
+    triangles
+}
+
+#[inline(never)]
+pub fn triangulate_ear_clip_naive(polygon: &Polygon) -> Vec<[u32; 3]> {
+    ear_clip(polygon, false)
+}
+
+#[inline(never)]
+pub fn triangulate_ear_clip_reflex(polygon: &Polygon) -> Vec<[u32; 3]> {
+    ear_clip(polygon, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::test_util::test_rng;
+
+    #[test]
+    fn triangulate() {
+        let mut rng = test_rng();
+
+        for count in [32, 128, 512] {
+            for polygon in [
+                random_convex_polygon(&mut rng, count),
+                random_concave_polygon(&mut rng, count),
+                random_polygon_with_holes(&mut rng, count),
+            ] {
+                // Each hole adds two vertices to the joined ring.
+                let expected_count = polygon.vertices.len() + (polygon.holes.len() * 2) - 2;
+
+                for f in [triangulate_ear_clip_naive, triangulate_ear_clip_reflex] {
+                    let triangles = f(&polygon);
+
+                    assert_eq!(triangles.len(), expected_count);
+
+                    let areas = triangles.iter().map(|t| {
+                        let [a, b, c] = t.map(|i| polygon.vertices[i as usize]);
+
+                        (b - a).perp_dot(c - a) * 0.5
+                    });
+
+                    assert!(areas.clone().all(|area| area >= 0.0));
+
+                    let area = areas.sum::<f32>();
+
+                    assert!((area - polygon.area()).abs() < 1e-4);
+                }
+            }
+        }
+    }
+}
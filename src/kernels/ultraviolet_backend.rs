@@ -0,0 +1,216 @@
+use bevy_transform::components::Transform;
+use glam::Quat;
+use ultraviolet::{f32x8, Bivec3x8, Isometry3x8, Lerp, Rotor3x8, Slerp, Vec3x8};
+
+// ultraviolet versions of the transform and lerp kernels, which process eight
+// values at a time in SoA form. Like the nalgebra backend, isometries can't
+// represent scale so these assume the transforms have unit scale.
+//
+// The array conversions require the length to be a multiple of eight.
+
+pub const LANES: usize = 8;
+
+fn lanes<T>(values: &[T], f: impl Fn(&T) -> f32) -> f32x8 {
+    f32x8::from(std::array::from_fn::<f32, LANES, _>(
+        |lane| f(&values[lane]),
+    ))
+}
+
+// Rotors and quats have the same components, with the bivector parts reversed
+// and some signs flipped.
+pub fn rotor3x8_from_glam(quats: &[Quat]) -> Rotor3x8 {
+    Rotor3x8::new(
+        lanes(quats, |q| q.w),
+        Bivec3x8::new(
+            lanes(quats, |q| -q.z),
+            lanes(quats, |q| q.y),
+            lanes(quats, |q| -q.x),
+        ),
+    )
+}
+
+pub fn rotor3x8_to_glam(rotor: Rotor3x8) -> [Quat; LANES] {
+    let s = rotor.s.to_array();
+    let xy = rotor.bv.xy.to_array();
+    let xz = rotor.bv.xz.to_array();
+    let yz = rotor.bv.yz.to_array();
+
+    std::array::from_fn(|lane| Quat::from_xyzw(-yz[lane], xz[lane], -xy[lane], s[lane]))
+}
+
+pub fn rotor3x8_array_from_glam(quats: &[Quat]) -> Vec<Rotor3x8> {
+    quats.chunks_exact(LANES).map(rotor3x8_from_glam).collect()
+}
+
+pub fn isometry3x8_array_from_transforms(transforms: &[Transform]) -> Vec<Isometry3x8> {
+    transforms
+        .chunks_exact(LANES)
+        .map(|chunk| {
+            let rotations = chunk.iter().map(|t| t.rotation).collect::<Vec<_>>();
+
+            Isometry3x8::new(
+                Vec3x8::new(
+                    lanes(chunk, |t| t.translation.x),
+                    lanes(chunk, |t| t.translation.y),
+                    lanes(chunk, |t| t.translation.z),
+                ),
+                rotor3x8_from_glam(&rotations),
+            )
+        })
+        .collect()
+}
+
+pub struct Isometry3x8NormalizeParams<'a> {
+    pub dst: &'a mut [Isometry3x8],
+    pub src: &'a [&'a [Isometry3x8]; 2],
+}
+
+pub fn isometry3x8_normalize_inner<F>(params: &mut Isometry3x8NormalizeParams, f: F)
+where
+    F: Fn(Rotor3x8) -> Rotor3x8,
+{
+    for i in 0..params.dst.len() {
+        let (l, r) = (params.src[0][i], params.src[1][i]);
+
+        params.dst[i] = Isometry3x8::new(
+            (l.rotation * r.translation) + l.translation,
+            f(l.rotation * r.rotation),
+        );
+    }
+}
+
+#[inline(never)]
+pub fn isometry3x8_normalize_false(params: &mut Isometry3x8NormalizeParams) {
+    isometry3x8_normalize_inner(params, |r| r);
+}
+
+#[inline(never)]
+pub fn isometry3x8_normalize_true(params: &mut Isometry3x8NormalizeParams) {
+    isometry3x8_normalize_inner(params, |r| r.normalized());
+}
+
+pub struct Rotor3x8Params<'a> {
+    pub dst: &'a mut [Rotor3x8],
+    pub src_quat: &'a [&'a [Rotor3x8]; 2],
+    pub src_alpha: f32,
+}
+
+// Flip `r` where it's in the opposite hemisphere to `l`, so that the
+// interpolation takes the shortest path like glam does.
+#[inline]
+fn shortest_path(l: Rotor3x8, r: Rotor3x8) -> Rotor3x8 {
+    r * f32x8::ONE.copysign(l.dot(r))
+}
+
+#[inline(never)]
+pub fn rotor3x8_loop_nlerp(params: &mut Rotor3x8Params) {
+    let alpha = f32x8::splat(params.src_alpha);
+
+    for i in 0..params.dst.len() {
+        let (l, r) = (params.src_quat[0][i], params.src_quat[1][i]);
+
+        params.dst[i] = l.lerp(shortest_path(l, r), alpha).normalized();
+    }
+}
+
+#[inline(never)]
+pub fn rotor3x8_loop_slerp(params: &mut Rotor3x8Params) {
+    let alpha = f32x8::splat(params.src_alpha);
+
+    for i in 0..params.dst.len() {
+        let (l, r) = (params.src_quat[0][i], params.src_quat[1][i]);
+
+        params.dst[i] = l.slerp(shortest_path(l, r), alpha).normalized();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::{
+            lerp::*,
+            normalize::*,
+            test_util::{quat_near, test_rng, COUNT, TOLERANCE},
+        },
+        util::*,
+    };
+
+    #[test]
+    fn rotor_conversion() {
+        let mut rng = test_rng();
+
+        let quats = random_quat_array(&mut rng, COUNT);
+
+        for (chunk, rotor) in quats
+            .chunks_exact(LANES)
+            .zip(rotor3x8_array_from_glam(&quats))
+        {
+            assert_eq!(rotor3x8_to_glam(rotor), chunk);
+        }
+    }
+
+    #[test]
+    fn isometry3x8_normalize() {
+        let mut rng = test_rng();
+
+        let src = [
+            random_transform_array(&mut rng, COUNT),
+            random_transform_array(&mut rng, COUNT),
+        ];
+
+        let mut expected = vec![Transform::IDENTITY; COUNT];
+
+        transform_normalize_true(&mut TransformNormalizeParams {
+            dst: &mut expected,
+            src: &[&src[0], &src[1]],
+        });
+
+        let wide_src = src.each_ref().map(|s| isometry3x8_array_from_transforms(s));
+        let mut wide_dst = vec![Isometry3x8::identity(); COUNT / LANES];
+
+        isometry3x8_normalize_true(&mut Isometry3x8NormalizeParams {
+            dst: &mut wide_dst,
+            src: &[&wide_src[0], &wide_src[1]],
+        });
+
+        for (wide, expected) in wide_dst.iter().zip(expected.chunks_exact(LANES)) {
+            for (actual, expected) in rotor3x8_to_glam(wide.rotation).iter().zip(expected) {
+                assert!(quat_near(*actual, expected.rotation, TOLERANCE));
+            }
+        }
+    }
+
+    #[test]
+    fn rotor3x8_lerp() {
+        let mut rng = test_rng();
+
+        let l = random_quat_array(&mut rng, COUNT);
+        let r = random_quat_array(&mut rng, COUNT);
+
+        let wide_l = rotor3x8_array_from_glam(&l);
+        let wide_r = rotor3x8_array_from_glam(&r);
+
+        for (f, f_wide) in [
+            (
+                quat_slerp as fn(Quat, Quat, f32) -> Quat,
+                rotor3x8_loop_slerp as fn(&mut Rotor3x8Params),
+            ),
+            (quat_nlerp, rotor3x8_loop_nlerp),
+        ] {
+            let mut dst = vec![Rotor3x8::identity(); COUNT / LANES];
+
+            f_wide(&mut Rotor3x8Params {
+                dst: &mut dst,
+                src_quat: &[&wide_l, &wide_r],
+                src_alpha: 0.3,
+            });
+
+            let actual = dst.into_iter().flat_map(rotor3x8_to_glam);
+
+            for (i, actual) in actual.enumerate() {
+                assert!(quat_near(actual, f(l[i], r[i], 0.3), TOLERANCE));
+            }
+        }
+    }
+}
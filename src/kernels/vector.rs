@@ -0,0 +1,540 @@
+use crate::util::CacheAlignedVec;
+use glam::{Quat, Vec3, Vec3A};
+use rand::Rng;
+
+// The operations needed by the vector kernels, so that the same loops can run
+// over glam's `Vec3` (12 bytes, scalar), `Vec3A` (16 bytes, SIMD) and plain
+// arrays.
+pub trait Vector3: Copy {
+    fn dot(self, other: Self) -> f32;
+    fn cross(self, other: Self) -> Self;
+    fn normalize(self) -> Self;
+}
+
+impl Vector3 for Vec3 {
+    #[inline]
+    fn dot(self, other: Self) -> f32 {
+        Vec3::dot(self, other)
+    }
+
+    #[inline]
+    fn cross(self, other: Self) -> Self {
+        Vec3::cross(self, other)
+    }
+
+    #[inline]
+    fn normalize(self) -> Self {
+        Vec3::normalize(self)
+    }
+}
+
+impl Vector3 for Vec3A {
+    #[inline]
+    fn dot(self, other: Self) -> f32 {
+        Vec3A::dot(self, other)
+    }
+
+    #[inline]
+    fn cross(self, other: Self) -> Self {
+        Vec3A::cross(self, other)
+    }
+
+    #[inline]
+    fn normalize(self) -> Self {
+        Vec3A::normalize(self)
+    }
+}
+
+impl Vector3 for [f32; 3] {
+    #[inline]
+    fn dot(self, other: Self) -> f32 {
+        (self[0] * other[0]) + (self[1] * other[1]) + (self[2] * other[2])
+    }
+
+    #[inline]
+    fn cross(self, other: Self) -> Self {
+        [
+            (self[1] * other[2]) - (self[2] * other[1]),
+            (self[2] * other[0]) - (self[0] * other[2]),
+            (self[0] * other[1]) - (self[1] * other[0]),
+        ]
+    }
+
+    #[inline]
+    fn normalize(self) -> Self {
+        let inverse_length = 1.0 / self.dot(self).sqrt();
+
+        self.map(|c| c * inverse_length)
+    }
+}
+
+pub struct VectorParams<'a, V, D> {
+    pub dst_array: &'a mut [D],
+    pub src_array: [&'a [V]; 2],
+}
+
+#[inline(never)]
+pub fn vector_dot<V: Vector3>(params: &mut VectorParams<V, f32>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.src_array[0][i].dot(params.src_array[1][i]);
+    }
+}
+
+#[inline(never)]
+pub fn vector_cross<V: Vector3>(params: &mut VectorParams<V, V>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.src_array[0][i].cross(params.src_array[1][i]);
+    }
+}
+
+// Only uses the first source array.
+#[inline(never)]
+pub fn vector_normalize<V: Vector3>(params: &mut VectorParams<V, V>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.src_array[0][i].normalize();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Vector ops that take a vector and a second vector - a surface normal for
+// reflect and refract, or the target of the projection. The normals should be
+// unit length.
+
+pub fn vector_op_inner<F>(params: &mut VectorParams<Vec3, Vec3>, f: F)
+where
+    F: Fn(Vec3, Vec3) -> Vec3,
+{
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = f(params.src_array[0][i], params.src_array[1][i]);
+    }
+}
+
+#[inline(never)]
+pub fn vector_reflect(params: &mut VectorParams<Vec3, Vec3>) {
+    vector_op_inner(params, Vec3::reflect);
+}
+
+#[inline(never)]
+pub fn vector_refract(params: &mut VectorParams<Vec3, Vec3>, eta: f32) {
+    vector_op_inner(params, |v, n| v.refract(n, eta));
+}
+
+#[inline(never)]
+pub fn vector_project_onto(params: &mut VectorParams<Vec3, Vec3>) {
+    vector_op_inner(params, Vec3::project_onto);
+}
+
+#[inline(never)]
+pub fn vector_reject_from(params: &mut VectorParams<Vec3, Vec3>) {
+    vector_op_inner(params, Vec3::reject_from);
+}
+
+// Vectors stored as separate x, y and z arrays. The SoA kernels apply the same
+// glam functions, but the layout lets the compiler vectorize across elements.
+#[derive(Clone)]
+pub struct Vec3Soa {
+    pub x: CacheAlignedVec<f32>,
+    pub y: CacheAlignedVec<f32>,
+    pub z: CacheAlignedVec<f32>,
+}
+
+impl Vec3Soa {
+    pub fn from_vec3s(vectors: &[Vec3]) -> Self {
+        Vec3Soa {
+            x: vectors.iter().map(|v| v.x).collect(),
+            y: vectors.iter().map(|v| v.y).collect(),
+            z: vectors.iter().map(|v| v.z).collect(),
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Vec3 {
+        Vec3::new(self.x[i], self.y[i], self.z[i])
+    }
+}
+
+pub struct VectorSoaParams<'a> {
+    pub dst: &'a mut Vec3Soa,
+    pub src: [&'a Vec3Soa; 2],
+}
+
+pub fn vector_soa_op_inner<F>(params: &mut VectorSoaParams, f: F)
+where
+    F: Fn(Vec3, Vec3) -> Vec3,
+{
+    let [l, r] = params.src;
+    let dst = &mut params.dst;
+
+    let len = dst.x.len();
+
+    // Reslice so the compiler can see every array has the same length.
+    let (lx, ly, lz) = (&l.x[..len], &l.y[..len], &l.z[..len]);
+    let (rx, ry, rz) = (&r.x[..len], &r.y[..len], &r.z[..len]);
+    let (dx, dy, dz) = (&mut dst.x[..len], &mut dst.y[..len], &mut dst.z[..len]);
+
+    for i in 0..len {
+        let v = f(
+            Vec3::new(lx[i], ly[i], lz[i]),
+            Vec3::new(rx[i], ry[i], rz[i]),
+        );
+
+        dx[i] = v.x;
+        dy[i] = v.y;
+        dz[i] = v.z;
+    }
+}
+
+#[inline(never)]
+pub fn vector_soa_reflect(params: &mut VectorSoaParams) {
+    vector_soa_op_inner(params, Vec3::reflect);
+}
+
+#[inline(never)]
+pub fn vector_soa_refract(params: &mut VectorSoaParams, eta: f32) {
+    vector_soa_op_inner(params, |v, n| v.refract(n, eta));
+}
+
+#[inline(never)]
+pub fn vector_soa_project_onto(params: &mut VectorSoaParams) {
+    vector_soa_op_inner(params, Vec3::project_onto);
+}
+
+#[inline(never)]
+pub fn vector_soa_reject_from(params: &mut VectorSoaParams) {
+    vector_soa_op_inner(params, Vec3::reject_from);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The kinds of vector pairs for the angle kernels. The near parallel and near
+// opposite cases are where `acos` loses precision and where the rotation arc
+// has to pick an arbitrary axis.
+#[derive(Clone, Copy, Debug)]
+pub enum PairKind {
+    Random,
+    NearParallel,
+    NearOpposite,
+}
+
+impl PairKind {
+    pub const ALL: [PairKind; 3] = [
+        PairKind::Random,
+        PairKind::NearParallel,
+        PairKind::NearOpposite,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PairKind::Random => "random",
+            PairKind::NearParallel => "near parallel",
+            PairKind::NearOpposite => "near opposite",
+        }
+    }
+}
+
+fn random_unit_vec3<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+    ((rng.gen::<Vec3>() * 2.0) - 1.0).normalize_or(Vec3::X)
+}
+
+// Return two arrays of unit vectors of the given kind. The near cases are
+// jittered by between 1e-6 and 1e-2 radians.
+pub fn random_pair_arrays<R: Rng + ?Sized>(
+    rng: &mut R,
+    count: usize,
+    kind: PairKind,
+) -> [CacheAlignedVec<Vec3>; 2] {
+    let (l, r): (Vec<_>, Vec<_>) = (0..count)
+        .map(|_| {
+            let l = random_unit_vec3(rng);
+            let jitter = random_unit_vec3(rng) * 10.0f32.powf(rng.gen_range(-6.0..-2.0));
+
+            let r = match kind {
+                PairKind::Random => random_unit_vec3(rng),
+                PairKind::NearParallel => (l + jitter).normalize(),
+                PairKind::NearOpposite => (-l + jitter).normalize(),
+            };
+
+            (l, r)
+        })
+        .unzip();
+
+    [l.into_iter().collect(), r.into_iter().collect()]
+}
+
+#[inline(never)]
+pub fn vector_angle_between(params: &mut VectorParams<Vec3, f32>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] = params.src_array[0][i].angle_between(params.src_array[1][i]);
+    }
+}
+
+// The angle from the cross and dot products. This is accurate for all angles,
+// where `angle_between` uses `acos` and loses precision near zero and pi.
+#[inline(never)]
+pub fn vector_angle_atan2(params: &mut VectorParams<Vec3, f32>) {
+    for i in 0..params.dst_array.len() {
+        let (l, r) = (params.src_array[0][i], params.src_array[1][i]);
+
+        params.dst_array[i] = l.cross(r).length().atan2(l.dot(r));
+    }
+}
+
+#[inline(never)]
+pub fn vector_rotation_arc(params: &mut VectorParams<Vec3, Quat>) {
+    for i in 0..params.dst_array.len() {
+        params.dst_array[i] =
+            Quat::from_rotation_arc(params.src_array[0][i], params.src_array[1][i]);
+    }
+}
+
+// Return the maximum error in radians, compared to an `f64` reference.
+pub fn angle_max_error(dst_array: &[f32], src_array: [&[Vec3]; 2]) -> f32 {
+    dst_array
+        .iter()
+        .zip(src_array[0].iter().zip(src_array[1].iter()))
+        .map(|(&angle, (l, r))| {
+            let (l, r) = (l.as_dvec3(), r.as_dvec3());
+            let expected = l.cross(r).length().atan2(l.dot(r));
+
+            (angle as f64 - expected).abs() as f32
+        })
+        .fold(0.0, f32::max)
+}
+
+// Return the maximum distance between the rotated source vector and the
+// target vector.
+pub fn rotation_arc_max_error(dst_array: &[Quat], src_array: [&[Vec3]; 2]) -> f32 {
+    dst_array
+        .iter()
+        .zip(src_array[0].iter().zip(src_array[1].iter()))
+        .map(|(q, (l, r))| (q.as_dquat().normalize() * l.as_dvec3()).distance(r.as_dvec3()) as f32)
+        .fold(0.0, f32::max)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// For each query point, count the points within the radius. This is a brute
+// force scan, like the inner loop of a broadphase or a spatial hash cell.
+pub struct RangeQueryParams<'a> {
+    pub dst_array: &'a mut [u32],
+    pub query_array: &'a [Vec3],
+    pub point_array: &'a [Vec3],
+    pub radius: f32,
+}
+
+#[inline(never)]
+pub fn range_query_distance(params: &mut RangeQueryParams) {
+    let radius = params.radius;
+
+    for (dst, &query) in params.dst_array.iter_mut().zip(params.query_array) {
+        *dst = params
+            .point_array
+            .iter()
+            .filter(|&&point| query.distance(point) <= radius)
+            .count() as u32;
+    }
+}
+
+#[inline(never)]
+pub fn range_query_distance_squared(params: &mut RangeQueryParams) {
+    let radius_squared = params.radius * params.radius;
+
+    for (dst, &query) in params.dst_array.iter_mut().zip(params.query_array) {
+        *dst = params
+            .point_array
+            .iter()
+            .filter(|&&point| query.distance_squared(point) <= radius_squared)
+            .count() as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng, COUNT},
+        util::*,
+    };
+
+    const TOLERANCE: f32 = 1e-5;
+
+    fn run<V: Vector3, D: Copy + Default>(
+        f: fn(&mut VectorParams<V, D>),
+        src_array: [&[Vec3]; 2],
+        convert: fn(Vec3) -> V,
+    ) -> Vec<D> {
+        let src = src_array.map(|s| s.iter().copied().map(convert).collect::<Vec<_>>());
+        run_into(vec![D::default(); COUNT], |dst_array| {
+            f(&mut VectorParams {
+                dst_array,
+                src_array: [&src[0], &src[1]],
+            })
+        })
+    }
+
+    #[test]
+    fn layouts() {
+        let mut rng = test_rng();
+
+        let l = random_array::<Vec3>(&mut rng, COUNT);
+        let r = random_array::<Vec3>(&mut rng, COUNT);
+        let src_array = [&l[..], &r[..]];
+
+        let dots = [
+            run(vector_dot::<Vec3>, src_array, |v| v),
+            run(vector_dot::<Vec3A>, src_array, Vec3A::from),
+            run(vector_dot::<[f32; 3]>, src_array, <[f32; 3]>::from),
+        ];
+
+        for dot in &dots[1..] {
+            for i in 0..COUNT {
+                assert!((dot[i] - dots[0][i]).abs() < TOLERANCE);
+            }
+        }
+
+        fn to_vec3<V: Into<Vec3>>(array: Vec<V>) -> Vec<Vec3> {
+            array.into_iter().map(Into::into).collect()
+        }
+
+        let crosses = [
+            run(vector_cross::<Vec3>, src_array, |v| v),
+            to_vec3(run(vector_cross::<Vec3A>, src_array, Vec3A::from)),
+            to_vec3(run(vector_cross::<[f32; 3]>, src_array, <[f32; 3]>::from)),
+        ];
+
+        let normals = [
+            run(vector_normalize::<Vec3>, src_array, |v| v),
+            to_vec3(run(vector_normalize::<Vec3A>, src_array, Vec3A::from)),
+            to_vec3(run(
+                vector_normalize::<[f32; 3]>,
+                src_array,
+                <[f32; 3]>::from,
+            )),
+        ];
+
+        for results in [crosses, normals] {
+            for result in &results[1..] {
+                for i in 0..COUNT {
+                    assert!(result[i].abs_diff_eq(results[0][i], TOLERANCE));
+                }
+            }
+        }
+    }
+
+    type AosOp = dyn Fn(&mut VectorParams<Vec3, Vec3>);
+    type SoaOp = dyn Fn(&mut VectorSoaParams);
+
+    #[test]
+    fn ops() {
+        let mut rng = test_rng();
+
+        let v = random_array::<Vec3>(&mut rng, COUNT);
+        let n = random_array::<Vec3>(&mut rng, COUNT)
+            .iter()
+            .map(|n| (*n - 0.5).normalize())
+            .collect::<Vec<_>>();
+
+        let v_soa = Vec3Soa::from_vec3s(&v);
+        let n_soa = Vec3Soa::from_vec3s(&n);
+
+        let aos_variants: [&AosOp; 4] = [
+            &vector_reflect,
+            &|params| vector_refract(params, 0.7),
+            &vector_project_onto,
+            &vector_reject_from,
+        ];
+
+        let soa_variants: [&SoaOp; 4] = [
+            &vector_soa_reflect,
+            &|params| vector_soa_refract(params, 0.7),
+            &vector_soa_project_onto,
+            &vector_soa_reject_from,
+        ];
+
+        for (aos, soa) in aos_variants.iter().zip(soa_variants.iter()) {
+            let mut aos_dst = vec![Vec3::ZERO; COUNT];
+            let mut soa_dst = Vec3Soa::from_vec3s(&aos_dst);
+
+            aos(&mut VectorParams {
+                dst_array: &mut aos_dst,
+                src_array: [&v, &n],
+            });
+
+            soa(&mut VectorSoaParams {
+                dst: &mut soa_dst,
+                src: [&v_soa, &n_soa],
+            });
+
+            for (i, aos) in aos_dst.iter().enumerate() {
+                assert!(soa_dst.get(i).abs_diff_eq(*aos, TOLERANCE));
+            }
+        }
+    }
+
+    #[test]
+    fn angles() {
+        let mut rng = test_rng();
+
+        for kind in PairKind::ALL {
+            let src = random_pair_arrays(&mut rng, COUNT, kind);
+            let src_array = [src[0].as_slice(), src[1].as_slice()];
+
+            let mut angle_between = vec![0.0; COUNT];
+            let mut angle_atan2 = vec![0.0; COUNT];
+            let mut rotation_arc = vec![Quat::IDENTITY; COUNT];
+
+            vector_angle_between(&mut VectorParams {
+                dst_array: &mut angle_between,
+                src_array,
+            });
+
+            vector_angle_atan2(&mut VectorParams {
+                dst_array: &mut angle_atan2,
+                src_array,
+            });
+
+            vector_rotation_arc(&mut VectorParams {
+                dst_array: &mut rotation_arc,
+                src_array,
+            });
+
+            // Only the atan2 variant stays accurate near the singular cases.
+
+            let near_tolerance = match kind {
+                PairKind::Random => TOLERANCE,
+                _ => 2e-3,
+            };
+
+            assert!(angle_max_error(&angle_between, src_array) < near_tolerance);
+            assert!(angle_max_error(&angle_atan2, src_array) < 1e-6);
+            assert!(rotation_arc_max_error(&rotation_arc, src_array) < near_tolerance);
+        }
+    }
+
+    #[test]
+    fn range_query() {
+        let mut rng = test_rng();
+
+        let query_array = random_array::<Vec3>(&mut rng, 16);
+        let point_array = random_array::<Vec3>(&mut rng, COUNT);
+
+        let run = |f: fn(&mut RangeQueryParams)| {
+            run_into(vec![0; query_array.len()], |dst_array| {
+                f(&mut RangeQueryParams {
+                    dst_array,
+                    query_array: &query_array,
+                    point_array: &point_array,
+                    radius: 0.3,
+                })
+            })
+        };
+
+        let expected = run(range_query_distance);
+
+        assert!(expected.iter().any(|&c| c > 0));
+
+        // The comparisons can round differently for points right on the
+        // boundary, but that's unlikely with random points.
+        assert_eq!(expected, run(range_query_distance_squared));
+    }
+}
@@ -0,0 +1,1374 @@
+use crate::kernels::{
+    audio::{mix_remainder, MixParams},
+    color::{pack_rgba8_clamp_single, unpack_rgba8_single, SrgbLut},
+    culling::{aabb_in_frustum, sphere_in_frustum, AabbSoa, CullParams, Frustum, SphereSoa},
+    easing::{smoothstep_explicit, SmoothstepIndirectParams, SmoothstepParams},
+    quantize::ConvertParams,
+    rsqrt::RsqrtParams,
+    texture::{downsample_box_single, downsample_srgb_lut_single, DownsampleParams},
+};
+use glam::{UVec2, UVec3, Vec4};
+#[cfg(feature = "f16c")]
+use half::f16;
+use std::arch::x86_64::*;
+
+// Hand written AVX2 and AVX-512 kernels. The kernels take a token that can
+// only be created if the CPU supports the instruction set, so they're safe to
+// call. `detect` checks the CPU every time - hoist it out of loops unless the
+// check is what's being measured.
+
+#[derive(Clone, Copy, Debug)]
+pub struct Avx2(());
+
+impl Avx2 {
+    pub fn detect() -> Option<Self> {
+        (is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")).then_some(Avx2(()))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Avx512(());
+
+impl Avx512 {
+    pub fn detect() -> Option<Self> {
+        is_x86_feature_detected!("avx512f").then_some(Avx512(()))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Bmi2(());
+
+impl Bmi2 {
+    pub fn detect() -> Option<Self> {
+        is_x86_feature_detected!("bmi2").then_some(Bmi2(()))
+    }
+}
+
+#[cfg(feature = "f16c")]
+#[derive(Clone, Copy, Debug)]
+pub struct F16c(());
+
+#[cfg(feature = "f16c")]
+impl F16c {
+    pub fn detect() -> Option<Self> {
+        (is_x86_feature_detected!("avx") && is_x86_feature_detected!("f16c")).then_some(F16c(()))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[target_feature(enable = "avx2,fma")]
+fn smoothstep_avx2_inner(dst: &mut [f32], src: &[f32]) {
+    const LANES: usize = 8;
+
+    let len = dst.len().min(src.len());
+    let chunk_len = len - (len % LANES);
+
+    let two = _mm256_set1_ps(2.0);
+    let three = _mm256_set1_ps(3.0);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads and stores are in bounds.
+        unsafe {
+            let t = _mm256_loadu_ps(src.as_ptr().add(i));
+            let r = _mm256_mul_ps(_mm256_mul_ps(_mm256_fnmadd_ps(two, t, three), t), t);
+
+            _mm256_storeu_ps(dst.as_mut_ptr().add(i), r);
+        }
+    }
+
+    for i in chunk_len..len {
+        let t = src[i];
+
+        dst[i] = (3.0 - (2.0 * t)) * t * t;
+    }
+}
+
+#[target_feature(enable = "avx512f")]
+fn smoothstep_avx512_inner(dst: &mut [f32], src: &[f32]) {
+    const LANES: usize = 16;
+
+    let len = dst.len().min(src.len());
+    let chunk_len = len - (len % LANES);
+
+    let two = _mm512_set1_ps(2.0);
+    let three = _mm512_set1_ps(3.0);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads and stores are in bounds.
+        unsafe {
+            let t = _mm512_loadu_ps(src.as_ptr().add(i));
+            let r = _mm512_mul_ps(_mm512_mul_ps(_mm512_fnmadd_ps(two, t, three), t), t);
+
+            _mm512_storeu_ps(dst.as_mut_ptr().add(i), r);
+        }
+    }
+
+    for i in chunk_len..len {
+        let t = src[i];
+
+        dst[i] = (3.0 - (2.0 * t)) * t * t;
+    }
+}
+
+#[inline(never)]
+pub fn smoothstep_avx2(_: Avx2, params: &mut SmoothstepParams) {
+    // SAFETY: The token proves that AVX2 and FMA are available.
+    unsafe { smoothstep_avx2_inner(params.dst_array, params.src_array) }
+}
+
+#[inline(never)]
+pub fn smoothstep_avx512(_: Avx512, params: &mut SmoothstepParams) {
+    // SAFETY: The token proves that AVX-512 is available.
+    unsafe { smoothstep_avx512_inner(params.dst_array, params.src_array) }
+}
+
+// Pick the widest kernel the CPU supports, checking on every call.
+#[inline(never)]
+pub fn smoothstep_dispatch(params: &mut SmoothstepParams) {
+    if let Some(avx512) = Avx512::detect() {
+        smoothstep_avx512(avx512, params);
+    } else if let Some(avx2) = Avx2::detect() {
+        smoothstep_avx2(avx2, params);
+    } else {
+        smoothstep_explicit(params);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[target_feature(enable = "avx2")]
+fn memcpy_avx2_inner(dst: &mut [u8], src: &[u8]) {
+    const BYTES: usize = 32;
+
+    assert_eq!(dst.len(), src.len());
+
+    let len = dst.len();
+    let chunk_len = len - (len % BYTES);
+
+    for i in (0..chunk_len).step_by(BYTES) {
+        // SAFETY: `i + BYTES <= len`, so the loads and stores are in bounds.
+        unsafe {
+            let v = _mm256_loadu_si256(src.as_ptr().add(i) as *const __m256i);
+
+            _mm256_storeu_si256(dst.as_mut_ptr().add(i) as *mut __m256i, v);
+        }
+    }
+
+    dst[chunk_len..].copy_from_slice(&src[chunk_len..]);
+}
+
+#[target_feature(enable = "avx512f")]
+fn memcpy_avx512_inner(dst: &mut [u8], src: &[u8]) {
+    const BYTES: usize = 64;
+
+    assert_eq!(dst.len(), src.len());
+
+    let len = dst.len();
+    let chunk_len = len - (len % BYTES);
+
+    for i in (0..chunk_len).step_by(BYTES) {
+        // SAFETY: `i + BYTES <= len`, so the loads and stores are in bounds.
+        unsafe {
+            let v = _mm512_loadu_si512(src.as_ptr().add(i) as *const __m512i);
+
+            _mm512_storeu_si512(dst.as_mut_ptr().add(i) as *mut __m512i, v);
+        }
+    }
+
+    dst[chunk_len..].copy_from_slice(&src[chunk_len..]);
+}
+
+// Non-temporal stores write around the cache, so a large copy doesn't evict
+// everything else. They need aligned addresses, so the start is copied
+// normally up to the first aligned byte of the destination.
+#[target_feature(enable = "avx2")]
+fn memcpy_stream_avx2_inner(dst: &mut [u8], src: &[u8]) {
+    const BYTES: usize = 32;
+
+    assert_eq!(dst.len(), src.len());
+
+    let len = dst.len();
+    let head_len = dst.as_ptr().align_offset(BYTES).min(len);
+    let chunk_len = head_len + ((len - head_len) - ((len - head_len) % BYTES));
+
+    dst[..head_len].copy_from_slice(&src[..head_len]);
+
+    for i in (head_len..chunk_len).step_by(BYTES) {
+        // SAFETY: `i + BYTES <= len`, so the loads and stores are in bounds,
+        // and `dst + i` is aligned.
+        unsafe {
+            let v = _mm256_loadu_si256(src.as_ptr().add(i) as *const __m256i);
+
+            _mm256_stream_si256(dst.as_mut_ptr().add(i) as *mut __m256i, v);
+        }
+    }
+
+    // Streaming stores are weakly ordered, so fence them before any stores
+    // that come after the copy.
+    _mm_sfence();
+
+    dst[chunk_len..].copy_from_slice(&src[chunk_len..]);
+}
+
+#[inline(never)]
+pub fn memcpy_avx2(_: Avx2, dst: &mut [u8], src: &[u8]) {
+    // SAFETY: The token proves that AVX2 is available.
+    unsafe { memcpy_avx2_inner(dst, src) }
+}
+
+#[inline(never)]
+pub fn memcpy_stream_avx2(_: Avx2, dst: &mut [u8], src: &[u8]) {
+    // SAFETY: The token proves that AVX2 is available.
+    unsafe { memcpy_stream_avx2_inner(dst, src) }
+}
+
+#[inline(never)]
+pub fn memcpy_avx512(_: Avx512, dst: &mut [u8], src: &[u8]) {
+    // SAFETY: The token proves that AVX-512 is available.
+    unsafe { memcpy_avx512_inner(dst, src) }
+}
+
+// Pick the widest kernel the CPU supports, checking on every call.
+#[inline(never)]
+pub fn memcpy_dispatch(dst: &mut [u8], src: &[u8]) {
+    if let Some(avx512) = Avx512::detect() {
+        memcpy_avx512(avx512, dst, src);
+    } else if let Some(avx2) = Avx2::detect() {
+        memcpy_avx2(avx2, dst, src);
+    } else {
+        dst.copy_from_slice(src);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Refine an approximate reciprocal square root `y` of `x` with one
+// Newton-Raphson iteration.
+#[target_feature(enable = "sse")]
+fn rsqrt_newton(x: __m128, y: __m128) -> __m128 {
+    let xyy = _mm_mul_ps(_mm_mul_ps(x, y), y);
+
+    _mm_mul_ps(
+        y,
+        _mm_sub_ps(_mm_set1_ps(1.5), _mm_mul_ps(_mm_set1_ps(0.5), xyy)),
+    )
+}
+
+#[target_feature(enable = "sse")]
+fn rsqrt_sse_newton_inner(dst: &mut [f32], src: &[f32]) {
+    const LANES: usize = 4;
+
+    let len = dst.len().min(src.len());
+    let chunk_len = len - (len % LANES);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads and stores are in bounds.
+        unsafe {
+            let x = _mm_loadu_ps(src.as_ptr().add(i));
+
+            _mm_storeu_ps(dst.as_mut_ptr().add(i), rsqrt_newton(x, _mm_rsqrt_ps(x)));
+        }
+    }
+
+    for i in chunk_len..len {
+        let x = _mm_set_ss(src[i]);
+
+        dst[i] = _mm_cvtss_f32(rsqrt_newton(x, _mm_rsqrt_ss(x)));
+    }
+}
+
+// The SSE approximate reciprocal square root plus one Newton-Raphson
+// iteration. SSE is part of the x86_64 baseline, so this doesn't need a token.
+#[inline(never)]
+pub fn rsqrt_sse_newton(params: &mut RsqrtParams) {
+    // SAFETY: SSE is part of the x86_64 baseline.
+    unsafe { rsqrt_sse_newton_inner(params.dst_array, params.src_array) }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Pack four colors at a time. The saturating packs clamp to [0, 255], and the
+// conversion rounds to nearest even rather than away from zero, so ties can
+// differ by one from the scalar kernels.
+#[target_feature(enable = "sse2")]
+fn pack_rgba8_sse2_inner(dst: &mut [u32], src: &[Vec4]) {
+    const LANES: usize = 4;
+
+    let len = dst.len().min(src.len());
+    let chunk_len = len - (len % LANES);
+
+    let scale = _mm_set1_ps(255.0);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads and stores are in bounds.
+        // `Vec4` is four floats.
+        unsafe {
+            let p = src.as_ptr().add(i).cast::<f32>();
+
+            let c0 = _mm_cvtps_epi32(_mm_mul_ps(_mm_loadu_ps(p), scale));
+            let c1 = _mm_cvtps_epi32(_mm_mul_ps(_mm_loadu_ps(p.add(4)), scale));
+            let c2 = _mm_cvtps_epi32(_mm_mul_ps(_mm_loadu_ps(p.add(8)), scale));
+            let c3 = _mm_cvtps_epi32(_mm_mul_ps(_mm_loadu_ps(p.add(12)), scale));
+
+            let c = _mm_packus_epi16(_mm_packs_epi32(c0, c1), _mm_packs_epi32(c2, c3));
+
+            _mm_storeu_si128(dst.as_mut_ptr().add(i).cast(), c);
+        }
+    }
+
+    for i in chunk_len..len {
+        dst[i] = pack_rgba8_clamp_single(src[i]);
+    }
+}
+
+#[target_feature(enable = "sse2")]
+fn unpack_rgba8_sse2_inner(dst: &mut [Vec4], src: &[u32]) {
+    const LANES: usize = 4;
+
+    let len = dst.len().min(src.len());
+    let chunk_len = len - (len % LANES);
+
+    let scale = _mm_set1_ps(1.0 / 255.0);
+    let zero = _mm_setzero_si128();
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads and stores are in bounds.
+        // `Vec4` is four floats.
+        unsafe {
+            let c = _mm_loadu_si128(src.as_ptr().add(i).cast());
+
+            let c01 = _mm_unpacklo_epi8(c, zero);
+            let c23 = _mm_unpackhi_epi8(c, zero);
+
+            let p = dst.as_mut_ptr().add(i).cast::<f32>();
+
+            for (j, c) in [
+                _mm_unpacklo_epi16(c01, zero),
+                _mm_unpackhi_epi16(c01, zero),
+                _mm_unpacklo_epi16(c23, zero),
+                _mm_unpackhi_epi16(c23, zero),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                _mm_storeu_ps(p.add(j * 4), _mm_mul_ps(_mm_cvtepi32_ps(c), scale));
+            }
+        }
+    }
+
+    for i in chunk_len..len {
+        dst[i] = unpack_rgba8_single(src[i]);
+    }
+}
+
+// SSE2 is part of the x86_64 baseline, so these don't need a token.
+
+#[inline(never)]
+pub fn pack_rgba8_sse2(params: &mut ConvertParams<Vec4, u32>) {
+    // SAFETY: SSE2 is part of the x86_64 baseline.
+    unsafe { pack_rgba8_sse2_inner(params.dst_array, params.src_array) }
+}
+
+#[inline(never)]
+pub fn unpack_rgba8_sse2(params: &mut ConvertParams<u32, Vec4>) {
+    // SAFETY: SSE2 is part of the x86_64 baseline.
+    unsafe { unpack_rgba8_sse2_inner(params.dst_array, params.src_array) }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Eight volume versions of `cull_spheres_x4` and `cull_aabbs_x4`. FMA would
+// round differently from the scalar kernels, so it's not used.
+
+#[target_feature(enable = "avx2")]
+fn cull_spheres_avx2_inner(dst: &mut [bool], spheres: &SphereSoa, frustum: &Frustum) {
+    const LANES: usize = 8;
+
+    let len = dst.len();
+    let chunk_len = len - (len % LANES);
+
+    let (x, y, z) = (
+        &spheres.center.x[..len],
+        &spheres.center.y[..len],
+        &spheres.center.z[..len],
+    );
+
+    let radius = &spheres.radius[..len];
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads are in bounds.
+        let mask = unsafe {
+            let x = _mm256_loadu_ps(x.as_ptr().add(i));
+            let y = _mm256_loadu_ps(y.as_ptr().add(i));
+            let z = _mm256_loadu_ps(z.as_ptr().add(i));
+            let neg_radius =
+                _mm256_sub_ps(_mm256_setzero_ps(), _mm256_loadu_ps(radius.as_ptr().add(i)));
+
+            let mut mask = 0xff;
+
+            for p in frustum.planes {
+                let distance = _mm256_add_ps(
+                    _mm256_add_ps(
+                        _mm256_add_ps(
+                            _mm256_mul_ps(_mm256_set1_ps(p.x), x),
+                            _mm256_mul_ps(_mm256_set1_ps(p.y), y),
+                        ),
+                        _mm256_mul_ps(_mm256_set1_ps(p.z), z),
+                    ),
+                    _mm256_set1_ps(p.w),
+                );
+
+                mask &= _mm256_movemask_ps(_mm256_cmp_ps::<_CMP_GE_OQ>(distance, neg_radius));
+
+                if mask == 0 {
+                    break;
+                }
+            }
+
+            mask
+        };
+
+        for lane in 0..LANES {
+            dst[i + lane] = (mask & (1 << lane)) != 0;
+        }
+    }
+
+    for (i, visible) in dst.iter_mut().enumerate().skip(chunk_len) {
+        *visible = sphere_in_frustum(frustum, &spheres.get(i));
+    }
+}
+
+#[target_feature(enable = "avx2")]
+fn cull_aabbs_avx2_inner(dst: &mut [bool], aabbs: &AabbSoa, frustum: &Frustum) {
+    const LANES: usize = 8;
+
+    let len = dst.len();
+    let chunk_len = len - (len % LANES);
+
+    let min = [
+        &aabbs.min.x[..len],
+        &aabbs.min.y[..len],
+        &aabbs.min.z[..len],
+    ];
+    let max = [
+        &aabbs.max.x[..len],
+        &aabbs.max.y[..len],
+        &aabbs.max.z[..len],
+    ];
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads are in bounds.
+        let mask = unsafe {
+            let min = [0, 1, 2].map(|axis| _mm256_loadu_ps(min[axis].as_ptr().add(i)));
+            let max = [0, 1, 2].map(|axis| _mm256_loadu_ps(max[axis].as_ptr().add(i)));
+
+            let mut mask = 0xff;
+
+            for p in frustum.planes {
+                let corner = |axis: usize| {
+                    if p[axis] > 0.0 {
+                        max[axis]
+                    } else {
+                        min[axis]
+                    }
+                };
+
+                let distance = _mm256_add_ps(
+                    _mm256_add_ps(
+                        _mm256_add_ps(
+                            _mm256_mul_ps(_mm256_set1_ps(p.x), corner(0)),
+                            _mm256_mul_ps(_mm256_set1_ps(p.y), corner(1)),
+                        ),
+                        _mm256_mul_ps(_mm256_set1_ps(p.z), corner(2)),
+                    ),
+                    _mm256_set1_ps(p.w),
+                );
+
+                mask &=
+                    _mm256_movemask_ps(_mm256_cmp_ps::<_CMP_GE_OQ>(distance, _mm256_setzero_ps()));
+
+                if mask == 0 {
+                    break;
+                }
+            }
+
+            mask
+        };
+
+        for lane in 0..LANES {
+            dst[i + lane] = (mask & (1 << lane)) != 0;
+        }
+    }
+
+    for (i, visible) in dst.iter_mut().enumerate().skip(chunk_len) {
+        *visible = aabb_in_frustum(frustum, &aabbs.get(i));
+    }
+}
+
+#[inline(never)]
+pub fn cull_spheres_avx2(_: Avx2, params: &mut CullParams<SphereSoa>) {
+    // SAFETY: The token proves that AVX2 is available.
+    unsafe { cull_spheres_avx2_inner(params.dst_array, params.volumes, params.frustum) }
+}
+
+#[inline(never)]
+pub fn cull_aabbs_avx2(_: Avx2, params: &mut CullParams<AabbSoa>) {
+    // SAFETY: The token proves that AVX2 is available.
+    unsafe { cull_aabbs_avx2_inner(params.dst_array, params.volumes, params.frustum) }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Morton codes with the BMI2 bit deposit and extract instructions. These are
+// one instruction each on Intel and AMD Zen 3 onwards, but microcoded and much
+// slower on earlier AMD CPUs.
+
+const MORTON_2D_MASKS: [u32; 2] = [0x55555555, 0xaaaaaaaa];
+const MORTON_3D_MASKS: [u32; 3] = [0x09249249, 0x12492492, 0x24924924];
+
+#[target_feature(enable = "bmi2")]
+fn morton_2d_encode_bmi2_inner(dst: &mut [u32], src: &[UVec2]) {
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        let [x, y] = MORTON_2D_MASKS;
+
+        *d = _pdep_u32(s.x, x) | _pdep_u32(s.y, y);
+    }
+}
+
+#[target_feature(enable = "bmi2")]
+fn morton_2d_decode_bmi2_inner(dst: &mut [UVec2], src: &[u32]) {
+    for (d, &s) in dst.iter_mut().zip(src.iter()) {
+        let [x, y] = MORTON_2D_MASKS;
+
+        *d = UVec2::new(_pext_u32(s, x), _pext_u32(s, y));
+    }
+}
+
+#[target_feature(enable = "bmi2")]
+fn morton_3d_encode_bmi2_inner(dst: &mut [u32], src: &[UVec3]) {
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        let [x, y, z] = MORTON_3D_MASKS;
+
+        *d = _pdep_u32(s.x, x) | _pdep_u32(s.y, y) | _pdep_u32(s.z, z);
+    }
+}
+
+#[target_feature(enable = "bmi2")]
+fn morton_3d_decode_bmi2_inner(dst: &mut [UVec3], src: &[u32]) {
+    for (d, &s) in dst.iter_mut().zip(src.iter()) {
+        let [x, y, z] = MORTON_3D_MASKS;
+
+        *d = UVec3::new(_pext_u32(s, x), _pext_u32(s, y), _pext_u32(s, z));
+    }
+}
+
+#[inline(never)]
+pub fn morton_2d_encode_bmi2(_: Bmi2, params: &mut ConvertParams<UVec2, u32>) {
+    // SAFETY: The token proves that BMI2 is available.
+    unsafe { morton_2d_encode_bmi2_inner(params.dst_array, params.src_array) }
+}
+
+#[inline(never)]
+pub fn morton_2d_decode_bmi2(_: Bmi2, params: &mut ConvertParams<u32, UVec2>) {
+    // SAFETY: The token proves that BMI2 is available.
+    unsafe { morton_2d_decode_bmi2_inner(params.dst_array, params.src_array) }
+}
+
+#[inline(never)]
+pub fn morton_3d_encode_bmi2(_: Bmi2, params: &mut ConvertParams<UVec3, u32>) {
+    // SAFETY: The token proves that BMI2 is available.
+    unsafe { morton_3d_encode_bmi2_inner(params.dst_array, params.src_array) }
+}
+
+#[inline(never)]
+pub fn morton_3d_decode_bmi2(_: Bmi2, params: &mut ConvertParams<u32, UVec3>) {
+    // SAFETY: The token proves that BMI2 is available.
+    unsafe { morton_3d_decode_bmi2_inner(params.dst_array, params.src_array) }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// 2x2 downsampling of RGBA8 images. Each iteration loads a run of pixels from
+// two source rows, adds the rows, then adds neighbouring pixels.
+
+#[target_feature(enable = "avx2")]
+fn downsample_box_avx2_inner(dst: &mut [[u8; 4]], src: &[[u8; 4]], src_width: usize) {
+    // Destination pixels per iteration.
+    const LANES: usize = 4;
+
+    let dst_width = src_width / 2;
+    let chunk_len = dst_width - (dst_width % LANES);
+
+    let two = _mm256_set1_epi16(2);
+
+    for (y, dst_row) in dst.chunks_exact_mut(dst_width).enumerate() {
+        let top = &src[(y * 2 * src_width)..][..src_width];
+        let bottom = &src[(((y * 2) + 1) * src_width)..][..src_width];
+
+        for x in (0..chunk_len).step_by(LANES) {
+            // SAFETY: `x + LANES <= dst_width`, so the source loads of
+            // `LANES * 2` pixels and destination stores of `LANES` pixels are
+            // in bounds.
+            let (left, right) = unsafe {
+                let t = _mm256_loadu_si256(top.as_ptr().add(x * 2).cast());
+                let b = _mm256_loadu_si256(bottom.as_ptr().add(x * 2).cast());
+
+                // Widen to 16 bits. Each half has two pixels in each 128-bit
+                // lane.
+                (
+                    _mm256_add_epi16(
+                        _mm256_cvtepu8_epi16(_mm256_castsi256_si128(t)),
+                        _mm256_cvtepu8_epi16(_mm256_castsi256_si128(b)),
+                    ),
+                    _mm256_add_epi16(
+                        _mm256_cvtepu8_epi16(_mm256_extracti128_si256::<1>(t)),
+                        _mm256_cvtepu8_epi16(_mm256_extracti128_si256::<1>(b)),
+                    ),
+                )
+            };
+
+            // Add each pixel to its neighbour by swapping the 64-bit halves of
+            // each lane, then round.
+            let left = _mm256_add_epi16(left, _mm256_shuffle_epi32::<0b01_00_11_10>(left));
+            let right = _mm256_add_epi16(right, _mm256_shuffle_epi32::<0b01_00_11_10>(right));
+
+            let left = _mm256_srli_epi16::<2>(_mm256_add_epi16(left, two));
+            let right = _mm256_srli_epi16::<2>(_mm256_add_epi16(right, two));
+
+            // Gather the four results into order, then narrow to 8 bits.
+            let sums = _mm256_permute4x64_epi64::<0b11_01_10_00>(
+                _mm256_blend_epi32::<0b1100_1100>(left, right),
+            );
+
+            let packed = _mm256_packus_epi16(sums, sums);
+            let packed = _mm256_permute4x64_epi64::<0b00_00_10_00>(packed);
+
+            // SAFETY: See above.
+            unsafe {
+                _mm_storeu_si128(
+                    dst_row.as_mut_ptr().add(x).cast(),
+                    _mm256_castsi256_si128(packed),
+                );
+            }
+        }
+
+        for (x, d) in dst_row.iter_mut().enumerate().skip(chunk_len) {
+            *d = downsample_box_single([
+                top[x * 2],
+                top[(x * 2) + 1],
+                bottom[x * 2],
+                bottom[(x * 2) + 1],
+            ]);
+        }
+    }
+}
+
+// Look up the linear value of eight 8-bit sRGB channels.
+#[target_feature(enable = "avx2")]
+fn srgb_gather_to_linear_avx2(to_linear: &[f32; 256], index: __m256i) -> __m256 {
+    // SAFETY: The indices are bytes, so they're inside the 256 entry table.
+    unsafe { _mm256_i32gather_ps::<4>(to_linear.as_ptr(), index) }
+}
+
+#[target_feature(enable = "avx2")]
+fn downsample_srgb_lut_avx2_inner(
+    dst: &mut [[u8; 4]],
+    src: &[[u8; 4]],
+    src_width: usize,
+    lut: &SrgbLut,
+) {
+    // Destination pixels per iteration.
+    const LANES: usize = 2;
+
+    assert!(lut.from_linear.len() >= (SrgbLut::FROM_LINEAR_SIZE + 3));
+
+    let dst_width = src_width / 2;
+    let chunk_len = dst_width - (dst_width % LANES);
+
+    let max = _mm256_set1_ps((SrgbLut::FROM_LINEAR_SIZE - 1) as f32);
+    let quarter = _mm256_set1_ps(0.25);
+    let half = _mm256_set1_ps(0.5);
+    let byte_mask = _mm256_set1_epi32(0xff);
+    let two = _mm256_set1_epi32(2);
+    let alpha_mask = _mm256_setr_epi32(0, 0, 0, -1, 0, 0, 0, -1);
+
+    for (y, dst_row) in dst.chunks_exact_mut(dst_width).enumerate() {
+        let top = &src[(y * 2 * src_width)..][..src_width];
+        let bottom = &src[(((y * 2) + 1) * src_width)..][..src_width];
+
+        for x in (0..chunk_len).step_by(LANES) {
+            // SAFETY: `x + LANES <= dst_width`, so the source loads of
+            // `LANES * 2` pixels are in bounds.
+            let (t, b) = unsafe {
+                (
+                    _mm_loadu_si128(top.as_ptr().add(x * 2).cast()),
+                    _mm_loadu_si128(bottom.as_ptr().add(x * 2).cast()),
+                )
+            };
+
+            // Widen to one channel per 32-bit lane. The first two source
+            // pixels of each row, then the second two.
+            let t0 = _mm256_cvtepu8_epi32(t);
+            let t1 = _mm256_cvtepu8_epi32(_mm_unpackhi_epi64(t, t));
+            let b0 = _mm256_cvtepu8_epi32(b);
+            let b1 = _mm256_cvtepu8_epi32(_mm_unpackhi_epi64(b, b));
+
+            let first = _mm256_add_ps(
+                srgb_gather_to_linear_avx2(&lut.to_linear, t0),
+                srgb_gather_to_linear_avx2(&lut.to_linear, b0),
+            );
+
+            let second = _mm256_add_ps(
+                srgb_gather_to_linear_avx2(&lut.to_linear, t1),
+                srgb_gather_to_linear_avx2(&lut.to_linear, b1),
+            );
+
+            // Add neighbouring pixels so each 128-bit lane has the sum for one
+            // destination pixel.
+            let linear = _mm256_add_ps(
+                _mm256_permute2f128_ps::<0x20>(first, second),
+                _mm256_permute2f128_ps::<0x31>(first, second),
+            );
+
+            let linear = _mm256_min_ps(
+                _mm256_max_ps(_mm256_mul_ps(linear, quarter), _mm256_setzero_ps()),
+                _mm256_set1_ps(1.0),
+            );
+
+            // Matches the scalar rounding, so FMA isn't used.
+            let index = _mm256_cvttps_epi32(_mm256_add_ps(_mm256_mul_ps(linear, max), half));
+
+            // SAFETY: The indices are at most `FROM_LINEAR_SIZE - 1`, and the
+            // table is padded so the 32-bit reads are in bounds.
+            let srgb =
+                unsafe { _mm256_i32gather_epi32::<1>(lut.from_linear.as_ptr().cast(), index) };
+
+            let srgb = _mm256_and_si256(srgb, byte_mask);
+
+            // Alpha is averaged in integers, the same as the box filter.
+            let alpha = {
+                let first = _mm256_add_epi32(t0, b0);
+                let second = _mm256_add_epi32(t1, b1);
+
+                let sum = _mm256_add_epi32(
+                    _mm256_permute2x128_si256::<0x20>(first, second),
+                    _mm256_permute2x128_si256::<0x31>(first, second),
+                );
+
+                _mm256_srli_epi32::<2>(_mm256_add_epi32(sum, two))
+            };
+
+            let pixels = _mm256_blendv_epi8(srgb, alpha, alpha_mask);
+
+            let packed = _mm256_packus_epi32(pixels, pixels);
+            let packed = _mm256_packus_epi16(packed, packed);
+
+            dst_row[x] = (_mm256_extract_epi32::<0>(packed) as u32).to_le_bytes();
+            dst_row[x + 1] = (_mm256_extract_epi32::<4>(packed) as u32).to_le_bytes();
+        }
+
+        for (x, d) in dst_row.iter_mut().enumerate().skip(chunk_len) {
+            *d = downsample_srgb_lut_single(
+                [
+                    top[x * 2],
+                    top[(x * 2) + 1],
+                    bottom[x * 2],
+                    bottom[(x * 2) + 1],
+                ],
+                lut,
+            );
+        }
+    }
+}
+
+#[inline(never)]
+pub fn downsample_box_avx2(_: Avx2, params: &mut DownsampleParams) {
+    assert!((params.src_width & 1) == 0);
+    assert_eq!(params.dst_array.len() * 4, params.src_array.len());
+
+    // SAFETY: The token proves that AVX2 is available.
+    unsafe { downsample_box_avx2_inner(params.dst_array, params.src_array, params.src_width) }
+}
+
+#[inline(never)]
+pub fn downsample_srgb_lut_avx2(_: Avx2, params: &mut DownsampleParams, lut: &SrgbLut) {
+    assert!((params.src_width & 1) == 0);
+    assert_eq!(params.dst_array.len() * 4, params.src_array.len());
+
+    // SAFETY: The token proves that AVX2 is available.
+    unsafe {
+        downsample_srgb_lut_avx2_inner(params.dst_array, params.src_array, params.src_width, lut)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// `f16` has the same layout as `u16`, so the kernels load and store the bits
+// directly.
+
+#[cfg(feature = "f16c")]
+#[target_feature(enable = "avx,f16c")]
+fn f16_from_f32_f16c_inner(dst: &mut [f16], src: &[f32]) {
+    const LANES: usize = 8;
+
+    let len = dst.len().min(src.len());
+    let chunk_len = len - (len % LANES);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads and stores are in bounds.
+        unsafe {
+            let x = _mm256_loadu_ps(src.as_ptr().add(i));
+            let h = _mm256_cvtps_ph::<_MM_FROUND_TO_NEAREST_INT>(x);
+
+            _mm_storeu_si128(dst.as_mut_ptr().add(i).cast(), h);
+        }
+    }
+
+    for i in chunk_len..len {
+        dst[i] = f16::from_f32_const(src[i]);
+    }
+}
+
+#[cfg(feature = "f16c")]
+#[target_feature(enable = "avx,f16c")]
+fn f32_from_f16_f16c_inner(dst: &mut [f32], src: &[f16]) {
+    const LANES: usize = 8;
+
+    let len = dst.len().min(src.len());
+    let chunk_len = len - (len % LANES);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads and stores are in bounds.
+        unsafe {
+            let h = _mm_loadu_si128(src.as_ptr().add(i).cast());
+
+            _mm256_storeu_ps(dst.as_mut_ptr().add(i), _mm256_cvtph_ps(h));
+        }
+    }
+
+    for i in chunk_len..len {
+        dst[i] = src[i].to_f32_const();
+    }
+}
+
+#[cfg(feature = "f16c")]
+#[inline(never)]
+pub fn f16_from_f32_f16c(_: F16c, params: &mut ConvertParams<f32, f16>) {
+    // SAFETY: The token proves that AVX and F16C are available.
+    unsafe { f16_from_f32_f16c_inner(params.dst_array, params.src_array) }
+}
+
+#[cfg(feature = "f16c")]
+#[inline(never)]
+pub fn f32_from_f16_f16c(_: F16c, params: &mut ConvertParams<f16, f32>) {
+    // SAFETY: The token proves that AVX and F16C are available.
+    unsafe { f32_from_f16_f16c_inner(params.dst_array, params.src_array) }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Four frames per vector. FMA would round differently from the scalar kernel,
+// so it's not used.
+
+#[target_feature(enable = "avx2")]
+fn mix_avx2_inner(dst: &mut [f32], src: &[f32], l: f32, r: f32) {
+    const LANES: usize = 8;
+
+    let len = dst.len().min(src.len());
+    let chunk_len = len - (len % LANES);
+
+    let gain = _mm256_setr_ps(l, r, l, r, l, r, l, r);
+
+    for i in (0..chunk_len).step_by(LANES) {
+        // SAFETY: `i + LANES <= len`, so the loads and stores are in bounds.
+        unsafe {
+            let d = _mm256_loadu_ps(dst.as_ptr().add(i));
+            let s = _mm256_loadu_ps(src.as_ptr().add(i));
+
+            _mm256_storeu_ps(
+                dst.as_mut_ptr().add(i),
+                _mm256_add_ps(d, _mm256_mul_ps(s, gain)),
+            );
+        }
+    }
+
+    mix_remainder(&mut dst[chunk_len..len], &src[chunk_len..len], l, r);
+}
+
+#[inline(never)]
+pub fn mix_avx2(_: Avx2, params: &mut MixParams) {
+    params.dst_array.fill(0.0);
+
+    for (src, &[l, r]) in params.src_arrays.iter().zip(params.gains) {
+        // SAFETY: The token proves that AVX2 is available.
+        unsafe { mix_avx2_inner(params.dst_array, src, l, r) }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Versions of `smoothstep_indirect_explicit` and `gather_sum` that prefetch the
+// element `distance` indices ahead. Prefetches are hints that never fault, so
+// any address is allowed. SSE is part of the x86_64 baseline, so these don't
+// need a token.
+
+#[target_feature(enable = "sse")]
+fn prefetch<T>(p: *const T) {
+    _mm_prefetch::<_MM_HINT_T0>(p as *const i8);
+}
+
+#[inline(never)]
+pub fn smoothstep_indirect_prefetch(params: &mut SmoothstepIndirectParams, distance: usize) {
+    for i in 0..params.dst_array.len() {
+        if let Some(&ahead) = params.index_array.get(i + distance) {
+            // SAFETY: SSE is part of the x86_64 baseline.
+            unsafe { prefetch(params.src_array.as_ptr().wrapping_add(ahead)) }
+        }
+
+        let t = params.src_array[params.index_array[i]];
+
+        params.dst_array[i] = (3.0 - (2.0 * t)) * t * t;
+    }
+}
+
+#[inline(never)]
+pub fn gather_sum_prefetch(src: &[u32], index_array: &[usize], distance: usize) -> u32 {
+    let mut sum = 0u32;
+
+    for (i, &index) in index_array.iter().enumerate() {
+        if let Some(&ahead) = index_array.get(i + distance) {
+            // SAFETY: SSE is part of the x86_64 baseline.
+            unsafe { prefetch(src.as_ptr().wrapping_add(ahead)) }
+        }
+
+        sum = sum.wrapping_add(src[index]);
+    }
+
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        kernels::test_util::{run_into, test_rng},
+        util::*,
+    };
+
+    // Not a multiple of any vector width, so the remainder is tested.
+    const COUNT: usize = 1003;
+
+    #[test]
+    fn smoothstep() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+
+        let run = |f: &dyn Fn(&mut SmoothstepParams)| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut SmoothstepParams {
+                    dst_array,
+                    src_array: &src_array,
+                })
+            })
+        };
+
+        let expected = run(&smoothstep_explicit);
+
+        // FMA rounds differently, so allow some error.
+        let check = |actual: Vec<f32>| {
+            for i in 0..COUNT {
+                assert!((actual[i] - expected[i]).abs() < 1e-6);
+            }
+        };
+
+        check(run(&smoothstep_dispatch));
+
+        if let Some(avx2) = Avx2::detect() {
+            check(run(&|params| smoothstep_avx2(avx2, params)));
+        }
+
+        if let Some(avx512) = Avx512::detect() {
+            check(run(&|params| smoothstep_avx512(avx512, params)));
+        }
+    }
+
+    #[test]
+    fn rsqrt() {
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT)
+            .iter()
+            .map(|x| x + 0.01)
+            .collect::<Vec<_>>();
+
+        let mut dst_array = vec![0.0; COUNT];
+
+        rsqrt_sse_newton(&mut RsqrtParams {
+            dst_array: &mut dst_array,
+            src_array: &src_array,
+        });
+
+        // One iteration refines the roughly 12 bit estimate to about 22 bits.
+        for (actual, x) in dst_array.iter().zip(src_array.iter()) {
+            let expected = x.sqrt().recip();
+
+            assert!(((actual - expected) / expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn memcpy() {
+        let mut rng = test_rng();
+
+        let src = random_array::<u8>(&mut rng, COUNT);
+
+        let run = |f: &dyn Fn(&mut [u8], &[u8])| {
+            let mut dst = vec![0u8; COUNT];
+
+            f(&mut dst, &src);
+
+            assert_eq!(dst, &src[..]);
+        };
+
+        run(&memcpy_dispatch);
+
+        if let Some(avx2) = Avx2::detect() {
+            run(&|dst, src| memcpy_avx2(avx2, dst, src));
+            run(&|dst, src| memcpy_stream_avx2(avx2, dst, src));
+
+            // Misalign the destination so the streaming version copies a
+            // head before the aligned part.
+            for offset in 1..4 {
+                let mut dst = vec![0u8; COUNT + offset];
+
+                memcpy_stream_avx2(avx2, &mut dst[offset..], &src);
+
+                assert_eq!(&dst[offset..], &src[..]);
+            }
+        }
+
+        if let Some(avx512) = Avx512::detect() {
+            run(&|dst, src| memcpy_avx512(avx512, dst, src));
+        }
+    }
+
+    #[test]
+    fn culling() {
+        use crate::kernels::culling::*;
+
+        let Some(avx2) = Avx2::detect() else {
+            return;
+        };
+
+        let mut rng = test_rng();
+
+        let frustum = culling_frustum();
+        let spheres = random_culling_sphere_array(&mut rng, COUNT);
+        let aabbs = random_culling_aabb_array(&mut rng, COUNT);
+
+        let mut expected = vec![false; COUNT];
+        let mut actual = vec![false; COUNT];
+
+        cull_spheres_scalar(&mut CullParams {
+            dst_array: &mut expected,
+            volumes: &spheres,
+            frustum: &frustum,
+        });
+
+        cull_spheres_avx2(
+            avx2,
+            &mut CullParams {
+                dst_array: &mut actual,
+                volumes: &SphereSoa::from_spheres(&spheres),
+                frustum: &frustum,
+            },
+        );
+
+        assert_eq!(expected, actual);
+
+        cull_aabbs_scalar(&mut CullParams {
+            dst_array: &mut expected,
+            volumes: &aabbs,
+            frustum: &frustum,
+        });
+
+        cull_aabbs_avx2(
+            avx2,
+            &mut CullParams {
+                dst_array: &mut actual,
+                volumes: &AabbSoa::from_aabbs(&aabbs),
+                frustum: &frustum,
+            },
+        );
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn morton() {
+        use crate::kernels::morton::*;
+
+        let Some(bmi2) = Bmi2::detect() else {
+            return;
+        };
+
+        let mut rng = test_rng();
+
+        let points_2d = random_morton_2d_array(&mut rng, COUNT);
+        let points_3d = random_morton_3d_array(&mut rng, COUNT);
+
+        let mut codes = vec![0; COUNT];
+
+        morton_2d_encode_bmi2(
+            bmi2,
+            &mut ConvertParams {
+                dst_array: &mut codes,
+                src_array: &points_2d,
+            },
+        );
+
+        for (&code, &p) in codes.iter().zip(points_2d.iter()) {
+            assert_eq!(code, morton_2d_encode_magic_single(p));
+        }
+
+        let mut decoded_2d = vec![UVec2::ZERO; COUNT];
+
+        morton_2d_decode_bmi2(
+            bmi2,
+            &mut ConvertParams {
+                dst_array: &mut decoded_2d,
+                src_array: &codes,
+            },
+        );
+
+        assert_eq!(decoded_2d, points_2d.as_slice());
+
+        morton_3d_encode_bmi2(
+            bmi2,
+            &mut ConvertParams {
+                dst_array: &mut codes,
+                src_array: &points_3d,
+            },
+        );
+
+        for (&code, &p) in codes.iter().zip(points_3d.iter()) {
+            assert_eq!(code, morton_3d_encode_magic_single(p));
+        }
+
+        let mut decoded_3d = vec![UVec3::ZERO; COUNT];
+
+        morton_3d_decode_bmi2(
+            bmi2,
+            &mut ConvertParams {
+                dst_array: &mut decoded_3d,
+                src_array: &codes,
+            },
+        );
+
+        assert_eq!(decoded_3d, points_3d.as_slice());
+    }
+
+    #[cfg(feature = "f16c")]
+    #[test]
+    fn f16c() {
+        use crate::kernels::quantize::*;
+
+        let Some(f16c) = F16c::detect() else {
+            return;
+        };
+
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT)
+            .iter()
+            .map(|x| (x - 0.5) * 1000.0)
+            .collect::<Vec<_>>();
+
+        let mut expected = vec![f16::ZERO; COUNT];
+        let mut actual = vec![f16::ZERO; COUNT];
+
+        f16_from_f32_software(&mut ConvertParams {
+            dst_array: &mut expected,
+            src_array: &src_array,
+        });
+
+        f16_from_f32_f16c(
+            f16c,
+            &mut ConvertParams {
+                dst_array: &mut actual,
+                src_array: &src_array,
+            },
+        );
+
+        assert_eq!(expected, actual);
+
+        let mut expected_f32 = vec![0.0; COUNT];
+        let mut actual_f32 = vec![0.0; COUNT];
+
+        f32_from_f16_software(&mut ConvertParams {
+            dst_array: &mut expected_f32,
+            src_array: &expected,
+        });
+
+        f32_from_f16_f16c(
+            f16c,
+            &mut ConvertParams {
+                dst_array: &mut actual_f32,
+                src_array: &expected,
+            },
+        );
+
+        assert_eq!(expected_f32, actual_f32);
+    }
+
+    #[test]
+    fn rgba8() {
+        use crate::kernels::color::*;
+
+        let mut rng = test_rng();
+
+        let src_array = random_array::<Vec4>(&mut rng, COUNT)
+            .iter()
+            .map(|c| (*c * 1.2) - 0.1)
+            .collect::<Vec<_>>();
+
+        let mut packed = vec![0; COUNT];
+        let mut expected_packed = vec![0; COUNT];
+
+        pack_rgba8_sse2(&mut ConvertParams {
+            dst_array: &mut packed,
+            src_array: &src_array,
+        });
+
+        pack_rgba8_clamp(&mut ConvertParams {
+            dst_array: &mut expected_packed,
+            src_array: &src_array,
+        });
+
+        for (actual, expected) in packed.iter().zip(expected_packed.iter()) {
+            for (a, e) in actual.to_le_bytes().iter().zip(expected.to_le_bytes()) {
+                assert!(a.abs_diff(e) <= 1);
+            }
+        }
+
+        let mut unpacked = vec![Vec4::ZERO; COUNT];
+        let mut expected_unpacked = vec![Vec4::ZERO; COUNT];
+
+        unpack_rgba8_sse2(&mut ConvertParams {
+            dst_array: &mut unpacked,
+            src_array: &packed,
+        });
+
+        unpack_rgba8(&mut ConvertParams {
+            dst_array: &mut expected_unpacked,
+            src_array: &packed,
+        });
+
+        assert_eq!(unpacked, expected_unpacked);
+    }
+
+    #[test]
+    fn downsample() {
+        use crate::kernels::texture::*;
+
+        let Some(avx2) = Avx2::detect() else {
+            return;
+        };
+
+        let mut rng = test_rng();
+
+        // An odd destination width, so the remainder is tested.
+        let (width, height) = (2 * 13, 2 * 5);
+
+        let src_array = random_array::<[u8; 4]>(&mut rng, width * height);
+        let lut = SrgbLut::new();
+
+        let run = |f: &dyn Fn(&mut DownsampleParams)| {
+            run_into(vec![[0; 4]; (width / 2) * (height / 2)], |dst_array| {
+                f(&mut DownsampleParams {
+                    dst_array,
+                    src_array: &src_array,
+                    src_width: width,
+                })
+            })
+        };
+
+        assert_eq!(run(&downsample_box), run(&|p| downsample_box_avx2(avx2, p)));
+
+        assert_eq!(
+            run(&|p| downsample_srgb_lut(p, &lut)),
+            run(&|p| downsample_srgb_lut_avx2(avx2, p, &lut))
+        );
+    }
+
+    #[test]
+    fn mix() {
+        use crate::kernels::audio::*;
+
+        let Some(avx2) = Avx2::detect() else {
+            return;
+        };
+
+        let mut rng = test_rng();
+
+        let streams = (0..5)
+            .map(|_| random_audio_stream(&mut rng, COUNT))
+            .collect::<Vec<_>>();
+
+        let src_arrays = streams.iter().map(|s| s.as_slice()).collect::<Vec<_>>();
+        let gains = random_voice_gains(&mut rng, src_arrays.len());
+
+        let run = |f: &dyn Fn(&mut MixParams)| {
+            run_into(vec![1.0; COUNT * CHANNELS], |dst_array| {
+                f(&mut MixParams {
+                    dst_array,
+                    src_arrays: &src_arrays,
+                    gains: &gains,
+                })
+            })
+        };
+
+        assert_eq!(run(&|p| mix_avx2(avx2, p)), run(&mix_scalar));
+    }
+
+    #[test]
+    fn prefetch() {
+        use crate::kernels::{easing::*, memory::gather_sum};
+
+        let mut rng = test_rng();
+
+        let src_array = random_array::<f32>(&mut rng, COUNT);
+        let index_array = random_array::<usize>(&mut rng, COUNT)
+            .iter()
+            .map(|i| i % COUNT)
+            .collect::<Vec<_>>();
+
+        let run = |f: &dyn Fn(&mut SmoothstepIndirectParams)| {
+            run_into(vec![0.0; COUNT], |dst_array| {
+                f(&mut SmoothstepIndirectParams {
+                    dst_array,
+                    src_array: &src_array,
+                    index_array: &index_array,
+                })
+            })
+        };
+
+        let expected = run(&smoothstep_indirect_explicit);
+
+        let src = random_array::<u32>(&mut rng, COUNT);
+
+        for distance in [0, 1, 16, COUNT * 2] {
+            assert_eq!(
+                run(&|p| smoothstep_indirect_prefetch(p, distance)),
+                expected
+            );
+
+            assert_eq!(
+                gather_sum_prefetch(&src, &index_array, distance),
+                gather_sum(&src, &index_array)
+            );
+        }
+    }
+}
@@ -1 +1,7 @@
+#![cfg_attr(misc_benches_nightly, feature(portable_simd))]
+
+pub mod kernels;
+#[cfg(target_os = "linux")]
+pub mod measure;
+pub mod sysreport;
 pub mod util;
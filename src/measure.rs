@@ -0,0 +1,247 @@
+use criterion::{
+    measurement::{Measurement, ValueFormatter},
+    Criterion, Throughput,
+};
+use perf_event_open_sys::{bindings, perf_event_open};
+use std::{
+    env,
+    fs::File,
+    io::{self, Read},
+    os::fd::FromRawFd,
+};
+
+/// Hardware counters that can be measured with `PerfMeasurement`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PerfCounter {
+    Instructions,
+    L1dMisses,
+    BranchMisses,
+    DtlbMisses,
+}
+
+impl PerfCounter {
+    pub const ALL: [PerfCounter; 4] = [
+        PerfCounter::Instructions,
+        PerfCounter::L1dMisses,
+        PerfCounter::BranchMisses,
+        PerfCounter::DtlbMisses,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PerfCounter::Instructions => "instructions",
+            PerfCounter::L1dMisses => "l1d_misses",
+            PerfCounter::BranchMisses => "branch_misses",
+            PerfCounter::DtlbMisses => "dtlb_misses",
+        }
+    }
+
+    fn type_and_config(self) -> (u32, u64) {
+        match self {
+            PerfCounter::Instructions => (
+                bindings::perf_type_id_PERF_TYPE_HARDWARE,
+                bindings::perf_hw_id_PERF_COUNT_HW_INSTRUCTIONS as u64,
+            ),
+            PerfCounter::L1dMisses => (
+                bindings::perf_type_id_PERF_TYPE_HW_CACHE,
+                (bindings::perf_hw_cache_id_PERF_COUNT_HW_CACHE_L1D as u64)
+                    | ((bindings::perf_hw_cache_op_id_PERF_COUNT_HW_CACHE_OP_READ as u64) << 8)
+                    | ((bindings::perf_hw_cache_op_result_id_PERF_COUNT_HW_CACHE_RESULT_MISS
+                        as u64)
+                        << 16),
+            ),
+            PerfCounter::BranchMisses => (
+                bindings::perf_type_id_PERF_TYPE_HARDWARE,
+                bindings::perf_hw_id_PERF_COUNT_HW_BRANCH_MISSES as u64,
+            ),
+            PerfCounter::DtlbMisses => (
+                bindings::perf_type_id_PERF_TYPE_HW_CACHE,
+                (bindings::perf_hw_cache_id_PERF_COUNT_HW_CACHE_DTLB as u64)
+                    | ((bindings::perf_hw_cache_op_id_PERF_COUNT_HW_CACHE_OP_READ as u64) << 8)
+                    | ((bindings::perf_hw_cache_op_result_id_PERF_COUNT_HW_CACHE_RESULT_MISS
+                        as u64)
+                        << 16),
+            ),
+        }
+    }
+}
+
+/// A Criterion measurement that counts hardware events on the current thread
+/// instead of wall time.
+///
+/// User space only, so this works with the default `perf_event_paranoid`
+/// setting of 2.
+pub struct PerfMeasurement {
+    file: File,
+    formatter: PerfFormatter,
+}
+
+impl PerfMeasurement {
+    pub fn new(counter: PerfCounter) -> io::Result<Self> {
+        let (type_, config) = counter.type_and_config();
+
+        let mut attrs = bindings::perf_event_attr {
+            type_,
+            size: size_of::<bindings::perf_event_attr>() as u32,
+            config,
+            ..Default::default()
+        };
+
+        attrs.set_exclude_kernel(1);
+        attrs.set_exclude_hv(1);
+
+        // SAFETY: `attrs` is a valid, fully initialized `perf_event_attr`.
+        let fd = unsafe { perf_event_open(&mut attrs, 0, -1, -1, 0) };
+
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(PerfMeasurement {
+            // SAFETY: `fd` is a freshly opened descriptor that nothing else owns.
+            file: unsafe { File::from_raw_fd(fd) },
+            formatter: PerfFormatter { counter },
+        })
+    }
+
+    fn read(&self) -> u64 {
+        let mut bytes = [0u8; 8];
+
+        (&self.file)
+            .read_exact(&mut bytes)
+            .expect("failed to read perf counter");
+
+        u64::from_ne_bytes(bytes)
+    }
+}
+
+impl Measurement for PerfMeasurement {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> Self::Intermediate {
+        self.read()
+    }
+
+    fn end(&self, i: Self::Intermediate) -> Self::Value {
+        self.read() - i
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &self.formatter
+    }
+}
+
+struct PerfFormatter {
+    counter: PerfCounter,
+}
+
+impl ValueFormatter for PerfFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        match self.counter {
+            PerfCounter::Instructions => "instructions",
+            PerfCounter::L1dMisses => "L1D misses",
+            PerfCounter::BranchMisses => "branch misses",
+            PerfCounter::DtlbMisses => "dTLB misses",
+        }
+    }
+
+    // Report events per element or byte, which is easier to compare across
+    // groups than the raw count.
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let (count, per_byte) = match *throughput {
+            Throughput::Bytes(bytes) | Throughput::BytesDecimal(bytes) => (bytes, true),
+            Throughput::Elements(elements) => (elements, false),
+        };
+
+        for value in values {
+            *value /= count as f64;
+        }
+
+        match (self.counter, per_byte) {
+            (PerfCounter::Instructions, false) => "instructions/elem",
+            (PerfCounter::Instructions, true) => "instructions/byte",
+            (PerfCounter::L1dMisses, false) => "L1D misses/elem",
+            (PerfCounter::L1dMisses, true) => "L1D misses/byte",
+            (PerfCounter::BranchMisses, false) => "branch misses/elem",
+            (PerfCounter::BranchMisses, true) => "branch misses/byte",
+            (PerfCounter::DtlbMisses, false) => "dTLB misses/elem",
+            (PerfCounter::DtlbMisses, true) => "dTLB misses/byte",
+        }
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        self.counter.name()
+    }
+}
+
+/// Return the counters selected by the `MISC_BENCHES_PERF` environment
+/// variable. This is either "1" for all counters, or a comma separated list of
+/// counter names like "instructions,branch_misses".
+pub fn perf_counters_from_env() -> Vec<PerfCounter> {
+    let Ok(value) = env::var("MISC_BENCHES_PERF") else {
+        return Vec::new();
+    };
+
+    if value == "1" || value == "all" {
+        return PerfCounter::ALL.to_vec();
+    }
+
+    value
+        .split(',')
+        .filter_map(|name| {
+            let counter = PerfCounter::ALL
+                .into_iter()
+                .find(|counter| counter.name() == name.trim());
+
+            if counter.is_none() {
+                eprintln!("MISC_BENCHES_PERF: unknown counter \"{name}\"");
+            }
+
+            counter
+        })
+        .collect()
+}
+
+/// Call `f` with a Criterion instance for each counter selected by
+/// `MISC_BENCHES_PERF`, along with a group name that includes the counter so
+/// the results don't overwrite the wall time results.
+///
+/// Counters that can't be opened (e.g. in a VM without a PMU) are skipped with
+/// a warning.
+pub fn for_each_perf_counter<F>(group_name: &str, mut f: F)
+where
+    F: FnMut(&mut Criterion<PerfMeasurement>, &str),
+{
+    for counter in perf_counters_from_env() {
+        match PerfMeasurement::new(counter) {
+            Ok(measurement) => {
+                let mut c = Criterion::default()
+                    .with_measurement(measurement)
+                    .configure_from_args();
+
+                f(&mut c, &format!("{group_name} ({})", counter.name()));
+            }
+            Err(error) => {
+                eprintln!("skipping {group_name} ({}): {error}", counter.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Mutex, PoisonError,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+
+/// A description of the machine and build that produced a set of results.
+/// Fields are `None` if the information isn't available on this platform.
+#[derive(Clone, Debug, Serialize)]
+pub struct SystemReport {
+    pub os: OsReport,
+    pub cpu: CpuReport,
+    pub memory_bytes: u64,
+    pub frequency: FrequencySample,
+    pub build: BuildReport,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OsReport {
+    pub name: Option<String>,
+    pub long_version: Option<String>,
+    pub kernel_version: Option<String>,
+    pub arch: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CpuReport {
+    pub brand: Option<String>,
+    pub vendor: Option<String>,
+    pub physical_cores: Option<usize>,
+    pub logical_cores: usize,
+    pub caches: Vec<CacheReport>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CacheReport {
+    pub level: u32,
+    /// "Data", "Instruction" or "Unified".
+    pub kind: String,
+    pub size_bytes: u64,
+    pub line_size_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BuildReport {
+    pub rustc_version: &'static str,
+    pub target: &'static str,
+    pub profile: &'static str,
+    /// "scalar" if glam was built with `scalar-math` or `glam-scalar`,
+    /// otherwise "simd".
+    pub glam_backend: &'static str,
+}
+
+impl SystemReport {
+    pub fn collect() -> Self {
+        let sys = System::new_with_specifics(
+            RefreshKind::new()
+                .with_cpu(CpuRefreshKind::new())
+                .with_memory(MemoryRefreshKind::new().with_ram()),
+        );
+
+        let first_cpu = sys.cpus().first();
+
+        SystemReport {
+            os: OsReport {
+                name: System::name(),
+                long_version: System::long_os_version(),
+                kernel_version: System::kernel_version(),
+                arch: System::cpu_arch(),
+            },
+            cpu: CpuReport {
+                brand: first_cpu.map(|cpu| cpu.brand().trim().to_string()),
+                vendor: first_cpu.map(|cpu| cpu.vendor_id().trim().to_string()),
+                physical_cores: sys.physical_core_count(),
+                logical_cores: sys.cpus().len(),
+                caches: cache_reports(),
+            },
+            memory_bytes: sys.total_memory(),
+            frequency: FrequencySample::collect(),
+            build: BuildReport {
+                rustc_version: env!("MISC_BENCHES_RUSTC_VERSION"),
+                target: env!("MISC_BENCHES_TARGET"),
+                profile: env!("MISC_BENCHES_PROFILE"),
+                glam_backend: if cfg!(any(feature = "scalar-math", feature = "glam-scalar")) {
+                    "scalar"
+                } else {
+                    "simd"
+                },
+            },
+        }
+    }
+
+    /// Write the report as `system.json` in the Criterion output directory,
+    /// returning the path.
+    pub fn write_to_criterion_dir(&self) -> io::Result<PathBuf> {
+        let dir = criterion_output_dir();
+
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("system.json");
+
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(path)
+    }
+}
+
+/// A snapshot of each core's clock speed and the power management settings
+/// that affect it.
+#[derive(Clone, Debug, Serialize)]
+pub struct FrequencySample {
+    pub core_mhz: Vec<u64>,
+    pub governor: Option<String>,
+    pub boost: Option<bool>,
+}
+
+impl FrequencySample {
+    pub fn collect() -> Self {
+        let sys = System::new_with_specifics(
+            RefreshKind::new().with_cpu(CpuRefreshKind::new().with_frequency()),
+        );
+
+        FrequencySample {
+            core_mhz: sys.cpus().iter().map(|cpu| cpu.frequency()).collect(),
+            governor: scaling_governor(),
+            boost: boost_enabled(),
+        }
+    }
+
+    // The fastest core's frequency. While a benchmark runs this is usually
+    // the core it's running on, so it tracks throttling without being thrown
+    // off by idle cores.
+    fn peak_mhz(&self) -> u64 {
+        self.core_mhz.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// If the peak core frequency varies by more than this fraction over a group
+/// then the group's results are suspect.
+pub const FREQUENCY_VARIATION_THRESHOLD: f64 = 0.05;
+
+/// How often `FrequencyCapture` samples the CPU frequency while a group runs.
+pub const FREQUENCY_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Frequency samples taken while a benchmark group ran.
+#[derive(Clone, Debug, Serialize)]
+pub struct GroupFrequencyReport {
+    pub group: String,
+    pub sample_count: usize,
+    /// The lowest and highest peak core frequency across the samples.
+    pub min_peak_mhz: u64,
+    pub max_peak_mhz: u64,
+    /// `(max_peak_mhz - min_peak_mhz) / max_peak_mhz`.
+    pub max_variation: f64,
+    pub governor: Option<String>,
+    pub boost: Option<bool>,
+    /// Reasons the group's results may be unreliable. Empty if there are none.
+    pub warnings: Vec<String>,
+}
+
+impl GroupFrequencyReport {
+    pub fn new(group: &str, samples: &[FrequencySample]) -> Self {
+        let min_peak_mhz = samples
+            .iter()
+            .map(FrequencySample::peak_mhz)
+            .min()
+            .unwrap_or(0);
+        let max_peak_mhz = samples
+            .iter()
+            .map(FrequencySample::peak_mhz)
+            .max()
+            .unwrap_or(0);
+
+        let max_variation = if max_peak_mhz > 0 {
+            (max_peak_mhz - min_peak_mhz) as f64 / max_peak_mhz as f64
+        } else {
+            0.0
+        };
+
+        let mut warnings = Vec::new();
+
+        if max_variation > FREQUENCY_VARIATION_THRESHOLD {
+            warnings.push(format!(
+                "peak core frequency varied by {:.1}% (threshold {:.1}%)",
+                max_variation * 100.0,
+                FREQUENCY_VARIATION_THRESHOLD * 100.0
+            ));
+        }
+
+        let first = samples.first();
+
+        if let Some(first) = first {
+            if samples.iter().any(|s| s.governor != first.governor) {
+                warnings.push("scaling governor changed".to_string());
+            }
+
+            if samples.iter().any(|s| s.boost != first.boost) {
+                warnings.push("boost state changed".to_string());
+            }
+        }
+
+        GroupFrequencyReport {
+            group: group.to_string(),
+            sample_count: samples.len(),
+            min_peak_mhz,
+            max_peak_mhz,
+            max_variation,
+            governor: first.and_then(|f| f.governor.clone()),
+            boost: first.and_then(|f| f.boost),
+            warnings,
+        }
+    }
+}
+
+// Reports from this process, by group. A group can be run more than once -
+// `smoothstep` and `smoothstep_indirect` share a group, for example - so each
+// group's file holds all its reports.
+static GROUP_FREQUENCY_REPORTS: Mutex<BTreeMap<String, Vec<GroupFrequencyReport>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Add the report to the group's reports from this process, and write them
+/// to `frequency/<group>.json` in the Criterion output directory. Returns the
+/// path.
+pub fn write_group_frequency_report(report: GroupFrequencyReport) -> io::Result<PathBuf> {
+    let dir = criterion_output_dir().join("frequency");
+
+    fs::create_dir_all(&dir)?;
+
+    let path = dir.join(format!("{}.json", filename_safe(&report.group)));
+
+    let mut all_reports = GROUP_FREQUENCY_REPORTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    let reports = all_reports.entry(report.group.clone()).or_default();
+
+    reports.push(report);
+
+    fs::write(&path, serde_json::to_string_pretty(reports)?)?;
+
+    Ok(path)
+}
+
+/// Samples the CPU frequency on a background thread from when it's created
+/// until it's dropped, then writes a `GroupFrequencyReport`. Create this
+/// before the benchmark group so that it's dropped after the group finishes.
+pub struct FrequencyCapture {
+    group: String,
+    stop: Option<mpsc::Sender<()>>,
+    sampler: Option<JoinHandle<Vec<FrequencySample>>>,
+}
+
+impl FrequencyCapture {
+    pub fn start(group: &str) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        // Only sample while the group is running. A sample taken before the
+        // group starts would see idle clocks and flag the normal ramp up.
+        let sampler = thread::spawn(move || {
+            let mut samples = Vec::new();
+
+            while let Err(RecvTimeoutError::Timeout) =
+                stopped.recv_timeout(FREQUENCY_SAMPLE_INTERVAL)
+            {
+                samples.push(FrequencySample::collect());
+            }
+
+            samples
+        });
+
+        FrequencyCapture {
+            group: group.to_string(),
+            stop: Some(stop),
+            sampler: Some(sampler),
+        }
+    }
+}
+
+impl Drop for FrequencyCapture {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+
+        let mut samples = self
+            .sampler
+            .take()
+            .and_then(|sampler| sampler.join().ok())
+            .unwrap_or_default();
+
+        // Groups that finish within one interval get a single sample.
+        if samples.is_empty() {
+            samples.push(FrequencySample::collect());
+        }
+
+        let report = GroupFrequencyReport::new(&self.group, &samples);
+
+        for warning in &report.warnings {
+            eprintln!("warning: {}: {warning}", self.group);
+        }
+
+        if let Err(error) = write_group_frequency_report(report) {
+            eprintln!(
+                "failed to write frequency report for {}: {error}",
+                self.group
+            );
+        }
+    }
+}
+
+/// Return the directory Criterion writes its results to. This mirrors the
+/// logic in Criterion itself.
+pub fn criterion_output_dir() -> PathBuf {
+    if let Some(home) = env::var_os("CRITERION_HOME") {
+        PathBuf::from(home)
+    } else if let Some(target) = env::var_os("CARGO_TARGET_DIR") {
+        Path::new(&target).join("criterion")
+    } else {
+        PathBuf::from("target/criterion")
+    }
+}
+
+// Replace the characters that Criterion considers unsafe in file names.
+fn filename_safe(group: &str) -> String {
+    group.replace(
+        &['?', '"', '/', '\\', '*', '<', '>', ':', '|', '^'][..],
+        "_",
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn cache_reports() -> Vec<CacheReport> {
+    let Ok(entries) = fs::read_dir("/sys/devices/system/cpu/cpu0/cache") else {
+        return Vec::new();
+    };
+
+    let mut caches = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("index"))
+        .filter_map(|entry| {
+            let read = |name: &str| {
+                fs::read_to_string(entry.path().join(name))
+                    .ok()
+                    .map(|s| s.trim().to_string())
+            };
+
+            Some(CacheReport {
+                level: read("level")?.parse().ok()?,
+                kind: read("type")?,
+                size_bytes: parse_cache_size(&read("size")?)?,
+                line_size_bytes: read("coherency_line_size").and_then(|s| s.parse().ok()),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    caches.sort_by(|l, r| (l.level, &l.kind).cmp(&(r.level, &r.kind)));
+
+    caches
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cache_reports() -> Vec<CacheReport> {
+    Vec::new()
+}
+
+// Parse sizes in the sysfs format, e.g. "48K" or "32M".
+#[cfg(target_os = "linux")]
+fn parse_cache_size(s: &str) -> Option<u64> {
+    let (digits, scale) = match s.as_bytes().last()? {
+        b'K' => (&s[..s.len() - 1], 1024),
+        b'M' => (&s[..s.len() - 1], 1024 * 1024),
+        b'G' => (&s[..s.len() - 1], 1024 * 1024 * 1024),
+        _ => (s, 1),
+    };
+
+    digits.parse::<u64>().ok().map(|n| n * scale)
+}
+
+#[cfg(target_os = "linux")]
+fn scaling_governor() -> Option<String> {
+    fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn scaling_governor() -> Option<String> {
+    None
+}
+
+// The boost control depends on the driver - acpi-cpufreq and amd-pstate use
+// "cpufreq/boost", while intel_pstate uses the inverted "no_turbo".
+#[cfg(target_os = "linux")]
+fn boost_enabled() -> Option<bool> {
+    let read = |path: &str| fs::read_to_string(path).ok().map(|s| s.trim() == "1");
+
+    read("/sys/devices/system/cpu/cpufreq/boost")
+        .or_else(|| read("/sys/devices/system/cpu/intel_pstate/no_turbo").map(|no_turbo| !no_turbo))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn boost_enabled() -> Option<bool> {
+    None
+}
@@ -1,5 +1,15 @@
 use bevy_transform::components::Transform;
+use criterion::{measurement::Measurement, BenchmarkGroup};
+use glam::Quat;
 use rand::{distributions::Standard, prelude::Distribution, Rng};
+use std::{
+    alloc::{self, Layout},
+    f32::consts::TAU,
+    fmt::Display,
+    iter::repeat_with,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+};
 
 // Return how many values of T can comfortably fit in L1 on reasonably modern x86.
 pub const fn l1_sized_count<T>() -> usize {
@@ -11,7 +21,372 @@ pub const fn l2_sized_count<T>() -> usize {
     (512 * 1024) / size_of::<T>()
 }
 
-pub fn random_transform_array(rng: &mut impl Rng, count: usize) -> Vec<Transform> {
+// Return how many values of T are comfortably larger than the last level cache
+// on most desktop CPUs, so that the working set comes from RAM.
+pub const fn ram_sized_count<T>() -> usize {
+    (256 * 1024 * 1024) / size_of::<T>()
+}
+
+// Criterion only reports time and throughput, so print a metric for the
+// variant - like its max error - before benchmarking it.
+pub fn bench_function_with_metric<M: Measurement, O>(
+    group: &mut BenchmarkGroup<M>,
+    group_name: &str,
+    name: &str,
+    metric: impl Display,
+    mut f: impl FnMut() -> O,
+) {
+    println!("{group_name}: {name}, {metric}");
+
+    group.bench_function(name, |b| b.iter(&mut f));
+}
+
+// Alignment used for all benchmark input and output arrays, so that results
+// don't depend on where the allocator happened to put them.
+pub const CACHE_LINE_SIZE: usize = 64;
+
+pub type CacheAlignedVec<T> = AlignedVec<T, CACHE_LINE_SIZE>;
+
+// A fixed size array on the heap, with the start of the array aligned to at
+// least ALIGN bytes.
+pub struct AlignedVec<T, const ALIGN: usize> {
+    ptr: NonNull<T>,
+    len: usize,
+}
+
+// SAFETY: AlignedVec owns its elements, same as Vec.
+unsafe impl<T: Send, const ALIGN: usize> Send for AlignedVec<T, ALIGN> {}
+// SAFETY: AlignedVec owns its elements, same as Vec.
+unsafe impl<T: Sync, const ALIGN: usize> Sync for AlignedVec<T, ALIGN> {}
+
+impl<T, const ALIGN: usize> AlignedVec<T, ALIGN> {
+    fn layout(len: usize) -> Layout {
+        Layout::array::<T>(len)
+            .and_then(|layout| layout.align_to(ALIGN))
+            .expect("invalid AlignedVec layout")
+    }
+
+    pub fn from_elem(value: T, len: usize) -> Self
+    where
+        T: Clone,
+    {
+        std::iter::repeat_n(value, len).collect()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T, const ALIGN: usize> FromIterator<T> for AlignedVec<T, ALIGN> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = iter.into_iter().collect::<Vec<T>>();
+        let len = vec.len();
+        let layout = Self::layout(len);
+
+        let ptr = if layout.size() == 0 {
+            // Zero sized allocations aren't allowed, so make a dangling pointer
+            // with the right alignment.
+            NonNull::new(layout.align() as *mut T).unwrap()
+        } else {
+            // SAFETY: The layout has a non-zero size.
+            NonNull::new(unsafe { alloc::alloc(layout) } as *mut T)
+                .unwrap_or_else(|| alloc::handle_alloc_error(layout))
+        };
+
+        // SAFETY: The destination has room for `len` elements, and the
+        // elements are moved rather than copied since the Vec's length is
+        // set to zero.
+        unsafe {
+            ptr::copy_nonoverlapping(vec.as_ptr(), ptr.as_ptr(), len);
+            vec.set_len(0);
+        }
+
+        AlignedVec { ptr, len }
+    }
+}
+
+impl<T: Clone, const ALIGN: usize> Clone for AlignedVec<T, ALIGN> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T, const ALIGN: usize> Deref for AlignedVec<T, ALIGN> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // SAFETY: The pointer is aligned and valid for `len` initialized elements.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T, const ALIGN: usize> DerefMut for AlignedVec<T, ALIGN> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        // SAFETY: The pointer is aligned and valid for `len` initialized elements.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T, const ALIGN: usize> Drop for AlignedVec<T, ALIGN> {
+    fn drop(&mut self) {
+        let layout = Self::layout(self.len);
+
+        // SAFETY: The elements are initialized and the allocation was made with
+        // the same layout.
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len));
+
+            if layout.size() != 0 {
+                alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout);
+            }
+        }
+    }
+}
+
+// How the pages behind a `PageVec` are allocated.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PageKind {
+    // Regular pages, with transparent huge pages disabled for the range.
+    Small,
+    // Transparent huge pages requested with `madvise`. The kernel may still
+    // fall back to regular pages if it can't find contiguous memory.
+    Transparent,
+    // Explicit huge pages from `MAP_HUGETLB`. Fails unless enough have been
+    // reserved through `/proc/sys/vm/nr_hugepages`.
+    Explicit,
+}
+
+#[cfg(target_os = "linux")]
+impl PageKind {
+    pub const ALL: [PageKind; 3] = [PageKind::Small, PageKind::Transparent, PageKind::Explicit];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PageKind::Small => "4K",
+            PageKind::Transparent => "transparent huge",
+            PageKind::Explicit => "explicit huge",
+        }
+    }
+}
+
+// The huge page size on x86-64, and the default on most other Linux targets.
+#[cfg(target_os = "linux")]
+pub const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+// A fixed size array mapped directly from the OS, so the page size can be
+// chosen. Every element is written on creation, so the pages are already
+// faulted in when a benchmark uses them.
+#[cfg(target_os = "linux")]
+pub struct PageVec<T> {
+    ptr: NonNull<T>,
+    len: usize,
+    map_len: usize,
+}
+
+#[cfg(target_os = "linux")]
+impl<T: Copy> PageVec<T> {
+    pub fn from_elem(value: T, len: usize, kind: PageKind) -> std::io::Result<Self> {
+        Self::from_elem_inner(value, len, kind, None)
+    }
+
+    // Same as `from_elem`, but the pages are only allowed to come from the
+    // given NUMA node.
+    pub fn from_elem_on_node(
+        value: T,
+        len: usize,
+        kind: PageKind,
+        node: usize,
+    ) -> std::io::Result<Self> {
+        Self::from_elem_inner(value, len, kind, Some(node))
+    }
+
+    fn from_elem_inner(
+        value: T,
+        len: usize,
+        kind: PageKind,
+        node: Option<usize>,
+    ) -> std::io::Result<Self> {
+        let map_len = (len * size_of::<T>())
+            .max(1)
+            .next_multiple_of(HUGE_PAGE_SIZE);
+
+        let flags = match kind {
+            PageKind::Explicit => libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_HUGETLB,
+            _ => libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+        };
+
+        // SAFETY: An anonymous mapping without an address hint can't overlap
+        // anything that already exists.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // Start empty, so the mapping is released if anything below fails.
+        let mut vec = PageVec {
+            ptr: NonNull::new(ptr as *mut T).unwrap(),
+            len: 0,
+            map_len,
+        };
+
+        let advice = match kind {
+            PageKind::Small => Some(libc::MADV_NOHUGEPAGE),
+            PageKind::Transparent => Some(libc::MADV_HUGEPAGE),
+            PageKind::Explicit => None,
+        };
+
+        if let Some(advice) = advice {
+            // SAFETY: The range is exactly the mapping from above.
+            if unsafe { libc::madvise(ptr, map_len, advice) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        if let Some(node) = node {
+            const MASK_BITS: usize = libc::c_ulong::BITS as usize;
+
+            let mut mask = vec![0 as libc::c_ulong; (node / MASK_BITS) + 1];
+
+            mask[node / MASK_BITS] |= 1 << (node % MASK_BITS);
+
+            // SAFETY: The range is exactly the mapping from above, and the mask
+            // has room for the number of bits given. The kernel drops the last
+            // bit, so pass one more than we have, as libnuma does.
+            let result = unsafe {
+                libc::syscall(
+                    libc::SYS_mbind,
+                    ptr,
+                    map_len,
+                    libc::MPOL_BIND,
+                    mask.as_ptr(),
+                    (mask.len() * MASK_BITS) + 1,
+                    0,
+                )
+            };
+
+            if result != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        for i in 0..len {
+            // SAFETY: The mapping is page aligned and has room for `len`
+            // elements.
+            unsafe { vec.ptr.as_ptr().add(i).write(value) };
+        }
+
+        vec.len = len;
+
+        Ok(vec)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl<T> Deref for PageVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // SAFETY: The pointer is aligned and valid for `len` initialized elements.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl<T> DerefMut for PageVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        // SAFETY: The pointer is aligned and valid for `len` initialized elements.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl<T> Drop for PageVec<T> {
+    fn drop(&mut self) {
+        // SAFETY: The range is exactly the mapping made in `from_elem`, and
+        // the elements are `Copy` so don't need dropping.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.map_len);
+        }
+    }
+}
+
+// Saves the current thread's CPU affinity, and restores it when dropped.
+#[cfg(target_os = "linux")]
+pub struct AffinityGuard(libc::cpu_set_t);
+
+#[cfg(target_os = "linux")]
+impl AffinityGuard {
+    pub fn save() -> Option<Self> {
+        // SAFETY: An all zero `cpu_set_t` is an empty set.
+        let mut set = unsafe { std::mem::zeroed::<libc::cpu_set_t>() };
+
+        // SAFETY: The size matches the set.
+        let result = unsafe { libc::sched_getaffinity(0, size_of::<libc::cpu_set_t>(), &mut set) };
+
+        (result == 0).then_some(AffinityGuard(set))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for AffinityGuard {
+    fn drop(&mut self) {
+        // SAFETY: The size matches the set.
+        unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &self.0) };
+    }
+}
+
+// Return the NUMA nodes that have memory. Empty if the topology isn't
+// available.
+#[cfg(target_os = "linux")]
+pub fn numa_nodes_with_memory() -> Vec<usize> {
+    std::fs::read_to_string("/sys/devices/system/node/has_memory")
+        .map(|s| parse_cpu_list(&s))
+        .unwrap_or_default()
+}
+
+// Return the logical cores on the given NUMA node.
+#[cfg(target_os = "linux")]
+pub fn numa_node_cores(node: usize) -> Vec<usize> {
+    std::fs::read_to_string(format!("/sys/devices/system/node/node{node}/cpulist"))
+        .map(|s| parse_cpu_list(&s))
+        .unwrap_or_default()
+}
+
+// Return how much of this process's anonymous memory is currently backed by
+// transparent huge pages, or None if the kernel doesn't report it.
+#[cfg(target_os = "linux")]
+pub fn transparent_huge_page_bytes() -> Option<usize> {
+    let rollup = std::fs::read_to_string("/proc/self/smaps_rollup").ok()?;
+
+    let kb = rollup
+        .lines()
+        .find_map(|line| line.strip_prefix("AnonHugePages:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<usize>()
+        .ok()?;
+
+    Some(kb * 1024)
+}
+
+pub fn random_transform_array(rng: &mut impl Rng, count: usize) -> CacheAlignedVec<Transform> {
     Standard
         .sample_iter(rng)
         .map(Transform::from_rotation)
@@ -19,9 +394,238 @@ pub fn random_transform_array(rng: &mut impl Rng, count: usize) -> Vec<Transform
         .collect()
 }
 
-pub fn random_array<T>(rng: &mut impl Rng, count: usize) -> Vec<T>
+pub fn random_array<T>(rng: &mut impl Rng, count: usize) -> CacheAlignedVec<T>
 where
     Standard: Distribution<T>,
 {
     Standard.sample_iter(rng).take(count).collect()
 }
+
+// Return a quat sampled uniformly from the space of rotations.
+pub fn random_quat<R: Rng + ?Sized>(rng: &mut R) -> Quat {
+    let r0 = rng.gen_range(0.0f32..TAU);
+    let r1 = rng.gen_range(0.0f32..TAU);
+    let r2 = rng.gen_range(0.0f32..1.0f32);
+
+    let (s0, c0) = r0.sin_cos();
+    let (s1, c1) = r1.sin_cos();
+
+    let t0 = (1.0 - r2).sqrt();
+    let t1 = r2.sqrt();
+
+    Quat::from_xyzw(t0 * s0, t0 * c0, t1 * s1, t1 * c1)
+}
+
+pub fn random_quat_array<R: Rng + ?Sized>(rng: &mut R, count: usize) -> CacheAlignedVec<Quat> {
+    repeat_with(|| random_quat(rng)).take(count).collect()
+}
+
+// Pin the current thread to the given logical core. Returns false if the core
+// doesn't exist or the platform doesn't support pinning.
+pub fn pin_to_core(core_id: usize) -> bool {
+    core_affinity::set_for_current(core_affinity::CoreId { id: core_id })
+}
+
+// Return true if the given environment variable is set to anything other than
+// "0" or nothing, for benchmarks that only run when asked.
+pub fn opt_in_from_env(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|value| !matches!(value.as_str(), "" | "0"))
+}
+
+// Return the comma separated fractions in the given environment variable, or
+// `default` if it's not set. Values that aren't in [0, 1] are skipped with a
+// warning.
+pub fn fractions_from_env(name: &str, default: &[f64]) -> Vec<f64> {
+    let Ok(value) = std::env::var(name) else {
+        return default.to_vec();
+    };
+
+    value
+        .split(',')
+        .filter_map(|fraction| {
+            let parsed = fraction
+                .trim()
+                .parse()
+                .ok()
+                .filter(|f| (0.0..=1.0).contains(f));
+
+            if parsed.is_none() {
+                eprintln!("{name}: invalid fraction \"{fraction}\" - expected 0 to 1");
+            }
+
+            parsed
+        })
+        .collect()
+}
+
+// If the `MISC_BENCHES_PIN` environment variable is set then pin the current
+// thread. The value is "1" to choose a core automatically - see
+// `preferred_pin_core` - or "core=N" for a specific core. "0" turns pinning
+// off.
+pub fn pin_from_env() {
+    let Ok(value) = std::env::var("MISC_BENCHES_PIN") else {
+        return;
+    };
+
+    let core_id = match value.as_str() {
+        "0" | "" => return,
+        "1" => preferred_pin_core(),
+        _ => value
+            .strip_prefix("core=")
+            .and_then(|core_id| core_id.parse().ok()),
+    };
+
+    match core_id {
+        Some(core_id) if pin_to_core(core_id) => println!("pinned to core {core_id}"),
+        _ => {
+            eprintln!("MISC_BENCHES_PIN: failed to pin with \"{value}\" - expected 0, 1 or core=N")
+        }
+    }
+}
+
+// Return the core that's least likely to be disturbed by other work. This is
+// the first core isolated with `isolcpus` if there is one. Otherwise it's the
+// first thread of the last physical core, since the OS tends to favor low
+// numbered cores for interrupts and housekeeping.
+pub fn preferred_pin_core() -> Option<usize> {
+    if let Some(&core_id) = isolated_cores().first() {
+        return Some(core_id);
+    }
+
+    let core_ids = core_affinity::get_core_ids()?;
+
+    core_ids
+        .iter()
+        .rev()
+        .map(|core_id| core_id.id)
+        .find(|&core_id| is_first_thread(core_id))
+}
+
+// Split the logical cores into the first thread of each physical core, and the
+// remaining SMT siblings. If the topology isn't available then every core
+// counts as physical.
+pub fn physical_and_smt_cores() -> (Vec<usize>, Vec<usize>) {
+    core_affinity::get_core_ids()
+        .unwrap_or_default()
+        .iter()
+        .map(|core_id| core_id.id)
+        .partition(|&core_id| is_first_thread(core_id))
+}
+
+fn is_first_thread(core_id: usize) -> bool {
+    thread_siblings(core_id)
+        .first()
+        .is_none_or(|&first| first == core_id)
+}
+
+// Return the cores that are isolated from the scheduler with `isolcpus`.
+pub fn isolated_cores() -> Vec<usize> {
+    std::fs::read_to_string("/sys/devices/system/cpu/isolated")
+        .map(|s| parse_cpu_list(&s))
+        .unwrap_or_default()
+}
+
+// Return the logical cores that share a physical core with the given core,
+// including itself. Empty if the topology isn't available.
+pub fn thread_siblings(core_id: usize) -> Vec<usize> {
+    std::fs::read_to_string(format!(
+        "/sys/devices/system/cpu/cpu{core_id}/topology/thread_siblings_list"
+    ))
+    .map(|s| parse_cpu_list(&s))
+    .unwrap_or_default()
+}
+
+// Parse a Linux cpu list like "0-3,8,10-11".
+pub fn parse_cpu_list(s: &str) -> Vec<usize> {
+    s.trim()
+        .split(',')
+        .filter(|range| !range.is_empty())
+        .filter_map(|range| {
+            let (first, last) = range.split_once('-').unwrap_or((range, range));
+
+            Some(first.parse::<usize>().ok()?..=last.parse::<usize>().ok()?)
+        })
+        .flatten()
+        .collect()
+}
+
+const FLUSH_LINE_SIZE: usize = 64;
+
+// Evict the memory covered by the slice from all levels of the cache. This
+// uses clflushopt (or clflush if not available) on x86, and DC CIVAC on
+// aarch64. On other architectures it does nothing.
+pub fn flush_cache<T>(slice: &[T]) {
+    // An empty slice can have a dangling pointer, which mustn't be flushed.
+    if size_of_val(slice) == 0 {
+        return;
+    }
+
+    let start = slice.as_ptr() as usize & !(FLUSH_LINE_SIZE - 1);
+    let end = slice.as_ptr() as usize + size_of_val(slice);
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::{
+            arch::{
+                asm,
+                x86_64::{__cpuid_count, _mm_mfence},
+            },
+            sync::OnceLock,
+        };
+
+        static HAS_CLFLUSHOPT: OnceLock<bool> = OnceLock::new();
+
+        // `is_x86_feature_detected` doesn't support clflushopt, so check
+        // CPUID leaf 7 directly.
+        let has_clflushopt =
+            *HAS_CLFLUSHOPT.get_or_init(|| (__cpuid_count(7, 0).ebx & (1 << 23)) != 0);
+
+        for line in (start..end).step_by(FLUSH_LINE_SIZE) {
+            // SAFETY: The line is within or overlaps the slice, so it's mapped.
+            unsafe {
+                if has_clflushopt {
+                    asm!("clflushopt [{}]", in(reg) line, options(nostack, preserves_flags));
+                } else {
+                    asm!("clflush [{}]", in(reg) line, options(nostack, preserves_flags));
+                }
+            }
+        }
+
+        // SAFETY: mfence is always available on x86_64.
+        unsafe { _mm_mfence() };
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        use std::arch::asm;
+
+        for line in (start..end).step_by(FLUSH_LINE_SIZE) {
+            // SAFETY: The line is within or overlaps the slice, so it's mapped.
+            unsafe { asm!("dc civac, {}", in(reg) line, options(nostack, preserves_flags)) };
+        }
+
+        // SAFETY: Barriers have no preconditions.
+        unsafe { asm!("dsb sy", options(nostack, preserves_flags)) };
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        let _ = (start, end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn flush_cache() {
+        let v = (0..1003).collect::<Vec<u32>>();
+
+        super::flush_cache(&v);
+        super::flush_cache(&v[1..2]);
+        super::flush_cache::<u32>(&[]);
+        super::flush_cache(&Vec::<u64>::new());
+        super::flush_cache(&[(); 4]);
+
+        assert_eq!(v.iter().sum::<u32>(), 1003 * 1002 / 2);
+    }
+}